@@ -7,6 +7,54 @@ import 'frb_generated.dart';
 import 'package:flutter_rust_bridge/flutter_rust_bridge_for_generated.dart';
 import 'third_party/localp2p_ffi/bridge.dart';
 
+/// 聊天记录（用于 FRB）
+class P2PBridgeChatMessage {
+  final String messageId;
+
+  /// 会话对方的 Peer ID
+  final String peerId;
+
+  /// 发送者的 Peer ID
+  final String from;
+  final String content;
+
+  /// Unix 时间戳（毫秒）
+  final PlatformInt64 timestamp;
+
+  /// 是否为本地发出的消息
+  final bool outgoing;
+
+  const P2PBridgeChatMessage({
+    required this.messageId,
+    required this.peerId,
+    required this.from,
+    required this.content,
+    required this.timestamp,
+    required this.outgoing,
+  });
+
+  @override
+  int get hashCode =>
+      messageId.hashCode ^
+      peerId.hashCode ^
+      from.hashCode ^
+      content.hashCode ^
+      timestamp.hashCode ^
+      outgoing.hashCode;
+
+  @override
+  bool operator ==(Object other) =>
+      identical(this, other) ||
+      other is P2PBridgeChatMessage &&
+          runtimeType == other.runtimeType &&
+          messageId == other.messageId &&
+          peerId == other.peerId &&
+          from == other.from &&
+          content == other.content &&
+          timestamp == other.timestamp &&
+          outgoing == other.outgoing;
+}
+
 /// P2P 事件（用于 FRB）
 class P2PBridgeEvent {
   /// 事件类型
@@ -14,11 +62,21 @@ class P2PBridgeEvent {
   /// 2 = NodeExpired
   /// 3 = NodeVerified
   /// 4 = NodeOffline
-  /// 5 = UserInfoReceived
-  /// 6 = MessageReceived
+  /// 5 = UserInfoReceived (`presence` 为 online / away / busy / do_not_disturb / invisible)
+  /// 6 = MessageReceived (`silent` 为 true 时本地处于请勿打扰状态，不应发出通知)
   /// 7 = MessageSent
   /// 8 = PeerTyping
   /// 9 = Log (Rust 日志)
+  /// 10 = MessageStatusChanged (pending / sent / delivered / read)
+  /// 11 = DeliveryFailed (离线消息超过有效期仍未送达)
+  /// 12 = MessageSendFailed (单次发送失败，消息会自动重试)
+  /// 13 = UserInfoRequestFailed
+  /// 14 = RoomMessage (聊天室消息)
+  /// 15 = RoomUpdated (聊天室创建或成员变更)
+  /// 16 = RichMessageReceived (图片 / 文件附件 / 编辑 / 删除 / 表情回应，`message` 为完整消息 JSON)
+  /// 17 = PeerBanned (节点多次发送超限或格式错误的数据，已断开并封禁)
+  /// 18 = UserInfoUpdated (节点修改了昵称、状态等用户信息)
+  /// 19 = AvatarReceived (节点头像已获取并写入缓存，`path` 为缓存文件路径)
   final int eventType;
 
   /// 事件数据 (JSON 字符串)
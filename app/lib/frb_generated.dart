@@ -66,7 +66,7 @@ class P2PBridge
   String get codegenVersion => '2.11.1';
 
   @override
-  int get rustContentHash => -238510421;
+  int get rustContentHash => -138862931;
 
   static const kDefaultExternalLibraryLoaderConfig =
       ExternalLibraryLoaderConfig(
@@ -77,6 +77,8 @@ class P2PBridge
 }
 
 abstract class P2PBridgeApi extends BaseApi {
+  bool localp2PFfiBridgeP2PAddStaticPeer({required String address});
+
   void localp2PFfiBridgeP2PBroadcastMessage({
     required List<String> targetPeerIds,
     required String message,
@@ -84,15 +86,43 @@ abstract class P2PBridgeApi extends BaseApi {
 
   void localp2PFfiBridgeP2PCleanup();
 
+  String localp2PFfiBridgeP2PCreateRoom({
+    required String name,
+    required List<String> memberPeerIds,
+  });
+
+  void localp2PFfiBridgeP2PEnableRendezvous({
+    required String server,
+    required String team,
+  });
+
+  Uint8List? localp2PFfiBridgeP2PGetAvatar({required String peerId});
+
+  String? localp2PFfiBridgeP2PGetAvatarPath({required String peerId});
+
+  List<P2PBridgeChatMessage> localp2PFfiBridgeP2PGetChatHistory({
+    required String peerId,
+    String? beforeMessageId,
+    required int limit,
+  });
+
   String localp2PFfiBridgeP2PGetDeviceName();
 
   String localp2PFfiBridgeP2PGetLocalPeerId();
 
+  List<String> localp2PFfiBridgeP2PGetStaticPeers();
+
   List<P2PBridgeNodeInfo> localp2PFfiBridgeP2PGetVerifiedNodes();
 
   void localp2PFfiBridgeP2PInit({
     required String deviceName,
     required String identityPath,
+    required String networkKey,
+  });
+
+  void localp2PFfiBridgeP2PInviteToRoom({
+    required String roomId,
+    required List<String> memberPeerIds,
   });
 
   bool localp2PFfiBridgeP2PIsDiscoveryThreadAlive();
@@ -101,8 +131,14 @@ abstract class P2PBridgeApi extends BaseApi {
 
   bool localp2PFfiBridgeP2PIsRunning();
 
+  void localp2PFfiBridgeP2PLeaveRoom({required String roomId});
+
+  void localp2PFfiBridgeP2PMarkConversationRead({required String peerId});
+
   List<P2PBridgeEvent> localp2PFfiBridgeP2PPollEvents();
 
+  bool localp2PFfiBridgeP2PRemoveStaticPeer({required String address});
+
   void localp2PFfiBridgeP2PRestartDiscovery();
 
   void localp2PFfiBridgeP2PSendMessage({
@@ -110,8 +146,25 @@ abstract class P2PBridgeApi extends BaseApi {
     required String message,
   });
 
+  String localp2PFfiBridgeP2PSendRoomMessage({
+    required String roomId,
+    required String message,
+  });
+
+  String localp2PFfiBridgeP2PSetAvatar({required List<int> data});
+
   Stream<P2PBridgeEvent> localp2PFfiBridgeP2PSetEventStream();
 
+  void localp2PFfiBridgeP2PSetPresence({
+    required String presence,
+    required String message,
+  });
+
+  void localp2PFfiBridgeP2PSetUserInfo({
+    required String nickname,
+    required String status,
+  });
+
   void localp2PFfiBridgeP2PStart();
 
   void localp2PFfiBridgeP2PStop();
@@ -145,6 +198,32 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     required super.portManager,
   });
 
+  @override
+  bool localp2PFfiBridgeP2PAddStaticPeer({required String address}) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(address, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 1)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PAddStaticPeerConstMeta,
+        argValues: [address],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PAddStaticPeerConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_add_static_peer",
+        argNames: ["address"],
+      );
+
   @override
   void localp2PFfiBridgeP2PBroadcastMessage({
     required List<String> targetPeerIds,
@@ -156,7 +235,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
           final serializer = SseSerializer(generalizedFrbRustBinding);
           sse_encode_list_String(targetPeerIds, serializer);
           sse_encode_String(message, serializer);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 1)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 2)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
@@ -181,7 +260,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 2)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 3)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
@@ -197,13 +276,154 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
   TaskConstMeta get kLocalp2PFfiBridgeP2PCleanupConstMeta =>
       const TaskConstMeta(debugName: "p2p_cleanup", argNames: []);
 
+  @override
+  String localp2PFfiBridgeP2PCreateRoom({
+    required String name,
+    required List<String> memberPeerIds,
+  }) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(name, serializer);
+          sse_encode_list_String(memberPeerIds, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 4)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_String,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PCreateRoomConstMeta,
+        argValues: [name, memberPeerIds],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PCreateRoomConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_create_room",
+        argNames: ["name", "memberPeerIds"],
+      );
+
+  @override
+  void localp2PFfiBridgeP2PEnableRendezvous({
+    required String server,
+    required String team,
+  }) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(server, serializer);
+          sse_encode_String(team, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 5)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_unit,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PEnableRendezvousConstMeta,
+        argValues: [server, team],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PEnableRendezvousConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_enable_rendezvous",
+        argNames: ["server", "team"],
+      );
+
+  @override
+  Uint8List? localp2PFfiBridgeP2PGetAvatar({required String peerId}) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(peerId, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 6)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_opt_list_prim_u_8_strict,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PGetAvatarConstMeta,
+        argValues: [peerId],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PGetAvatarConstMeta =>
+      const TaskConstMeta(debugName: "p2p_get_avatar", argNames: ["peerId"]);
+
+  @override
+  String? localp2PFfiBridgeP2PGetAvatarPath({required String peerId}) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(peerId, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 7)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_opt_String,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PGetAvatarPathConstMeta,
+        argValues: [peerId],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PGetAvatarPathConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_get_avatar_path",
+        argNames: ["peerId"],
+      );
+
+  @override
+  List<P2PBridgeChatMessage> localp2PFfiBridgeP2PGetChatHistory({
+    required String peerId,
+    String? beforeMessageId,
+    required int limit,
+  }) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(peerId, serializer);
+          sse_encode_opt_String(beforeMessageId, serializer);
+          sse_encode_u_32(limit, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 8)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_list_p_2_p_bridge_chat_message,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PGetChatHistoryConstMeta,
+        argValues: [peerId, beforeMessageId, limit],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PGetChatHistoryConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_get_chat_history",
+        argNames: ["peerId", "beforeMessageId", "limit"],
+      );
+
   @override
   String localp2PFfiBridgeP2PGetDeviceName() {
     return handler.executeSync(
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 3)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 9)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_String,
@@ -225,7 +445,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 4)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 10)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_String,
@@ -241,13 +461,35 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
   TaskConstMeta get kLocalp2PFfiBridgeP2PGetLocalPeerIdConstMeta =>
       const TaskConstMeta(debugName: "p2p_get_local_peer_id", argNames: []);
 
+  @override
+  List<String> localp2PFfiBridgeP2PGetStaticPeers() {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 11)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_list_String,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PGetStaticPeersConstMeta,
+        argValues: [],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PGetStaticPeersConstMeta =>
+      const TaskConstMeta(debugName: "p2p_get_static_peers", argNames: []);
+
   @override
   List<P2PBridgeNodeInfo> localp2PFfiBridgeP2PGetVerifiedNodes() {
     return handler.executeSync(
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 5)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 12)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_list_p_2_p_bridge_node_info,
@@ -267,6 +509,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
   void localp2PFfiBridgeP2PInit({
     required String deviceName,
     required String identityPath,
+    required String networkKey,
   }) {
     return handler.executeSync(
       SyncTask(
@@ -274,14 +517,15 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
           final serializer = SseSerializer(generalizedFrbRustBinding);
           sse_encode_String(deviceName, serializer);
           sse_encode_String(identityPath, serializer);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 6)!;
+          sse_encode_String(networkKey, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 13)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
           decodeErrorData: sse_decode_String,
         ),
         constMeta: kLocalp2PFfiBridgeP2PInitConstMeta,
-        argValues: [deviceName, identityPath],
+        argValues: [deviceName, identityPath, networkKey],
         apiImpl: this,
       ),
     );
@@ -289,16 +533,46 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
 
   TaskConstMeta get kLocalp2PFfiBridgeP2PInitConstMeta => const TaskConstMeta(
     debugName: "p2p_init",
-    argNames: ["deviceName", "identityPath"],
+    argNames: ["deviceName", "identityPath", "networkKey"],
   );
 
+  @override
+  void localp2PFfiBridgeP2PInviteToRoom({
+    required String roomId,
+    required List<String> memberPeerIds,
+  }) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(roomId, serializer);
+          sse_encode_list_String(memberPeerIds, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 14)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_unit,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PInviteToRoomConstMeta,
+        argValues: [roomId, memberPeerIds],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PInviteToRoomConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_invite_to_room",
+        argNames: ["roomId", "memberPeerIds"],
+      );
+
   @override
   bool localp2PFfiBridgeP2PIsDiscoveryThreadAlive() {
     return handler.executeSync(
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 7)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 15)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_bool,
@@ -323,7 +597,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 8)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 16)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_bool,
@@ -345,7 +619,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 9)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 17)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_bool,
@@ -361,13 +635,62 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
   TaskConstMeta get kLocalp2PFfiBridgeP2PIsRunningConstMeta =>
       const TaskConstMeta(debugName: "p2p_is_running", argNames: []);
 
+  @override
+  void localp2PFfiBridgeP2PLeaveRoom({required String roomId}) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(roomId, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 18)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_unit,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PLeaveRoomConstMeta,
+        argValues: [roomId],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PLeaveRoomConstMeta =>
+      const TaskConstMeta(debugName: "p2p_leave_room", argNames: ["roomId"]);
+
+  @override
+  void localp2PFfiBridgeP2PMarkConversationRead({required String peerId}) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(peerId, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 19)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_unit,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PMarkConversationReadConstMeta,
+        argValues: [peerId],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PMarkConversationReadConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_mark_conversation_read",
+        argNames: ["peerId"],
+      );
+
   @override
   List<P2PBridgeEvent> localp2PFfiBridgeP2PPollEvents() {
     return handler.executeSync(
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 10)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 20)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_list_p_2_p_bridge_event,
@@ -383,13 +706,39 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
   TaskConstMeta get kLocalp2PFfiBridgeP2PPollEventsConstMeta =>
       const TaskConstMeta(debugName: "p2p_poll_events", argNames: []);
 
+  @override
+  bool localp2PFfiBridgeP2PRemoveStaticPeer({required String address}) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(address, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 21)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PRemoveStaticPeerConstMeta,
+        argValues: [address],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PRemoveStaticPeerConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_remove_static_peer",
+        argNames: ["address"],
+      );
+
   @override
   void localp2PFfiBridgeP2PRestartDiscovery() {
     return handler.executeSync(
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 11)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 22)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
@@ -416,7 +765,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
           final serializer = SseSerializer(generalizedFrbRustBinding);
           sse_encode_String(targetPeerId, serializer);
           sse_encode_String(message, serializer);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 12)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 23)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
@@ -435,6 +784,59 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
         argNames: ["targetPeerId", "message"],
       );
 
+  @override
+  String localp2PFfiBridgeP2PSendRoomMessage({
+    required String roomId,
+    required String message,
+  }) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(roomId, serializer);
+          sse_encode_String(message, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 24)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_String,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PSendRoomMessageConstMeta,
+        argValues: [roomId, message],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PSendRoomMessageConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_send_room_message",
+        argNames: ["roomId", "message"],
+      );
+
+  @override
+  String localp2PFfiBridgeP2PSetAvatar({required List<int> data}) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_list_prim_u_8_loose(data, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 25)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_String,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PSetAvatarConstMeta,
+        argValues: [data],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PSetAvatarConstMeta =>
+      const TaskConstMeta(debugName: "p2p_set_avatar", argNames: ["data"]);
+
   @override
   Stream<P2PBridgeEvent> localp2PFfiBridgeP2PSetEventStream() {
     final streamSink = RustStreamSink<P2PBridgeEvent>();
@@ -443,7 +845,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
           sse_encode_StreamSink_p_2_p_bridge_event_Sse(streamSink, serializer);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 13)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 26)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
@@ -463,13 +865,73 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
         argNames: ["streamSink"],
       );
 
+  @override
+  void localp2PFfiBridgeP2PSetPresence({
+    required String presence,
+    required String message,
+  }) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(presence, serializer);
+          sse_encode_String(message, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 27)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_unit,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PSetPresenceConstMeta,
+        argValues: [presence, message],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PSetPresenceConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_set_presence",
+        argNames: ["presence", "message"],
+      );
+
+  @override
+  void localp2PFfiBridgeP2PSetUserInfo({
+    required String nickname,
+    required String status,
+  }) {
+    return handler.executeSync(
+      SyncTask(
+        callFfi: () {
+          final serializer = SseSerializer(generalizedFrbRustBinding);
+          sse_encode_String(nickname, serializer);
+          sse_encode_String(status, serializer);
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 28)!;
+        },
+        codec: SseCodec(
+          decodeSuccessData: sse_decode_unit,
+          decodeErrorData: sse_decode_String,
+        ),
+        constMeta: kLocalp2PFfiBridgeP2PSetUserInfoConstMeta,
+        argValues: [nickname, status],
+        apiImpl: this,
+      ),
+    );
+  }
+
+  TaskConstMeta get kLocalp2PFfiBridgeP2PSetUserInfoConstMeta =>
+      const TaskConstMeta(
+        debugName: "p2p_set_user_info",
+        argNames: ["nickname", "status"],
+      );
+
   @override
   void localp2PFfiBridgeP2PStart() {
     return handler.executeSync(
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 14)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 29)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
@@ -491,7 +953,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
       SyncTask(
         callFfi: () {
           final serializer = SseSerializer(generalizedFrbRustBinding);
-          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 15)!;
+          return pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 30)!;
         },
         codec: SseCodec(
           decodeSuccessData: sse_decode_unit,
@@ -523,7 +985,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
           pdeCallFfi(
             generalizedFrbRustBinding,
             serializer,
-            funcId: 16,
+            funcId: 31,
             port: port_,
           );
         },
@@ -561,7 +1023,7 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
           pdeCallFfi(
             generalizedFrbRustBinding,
             serializer,
-            funcId: 17,
+            funcId: 32,
             port: port_,
           );
         },
@@ -640,12 +1102,28 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     return raw as int;
   }
 
+  @protected
+  PlatformInt64 dco_decode_i_64(dynamic raw) {
+    // Codec=Dco (DartCObject based), see doc to use other codecs
+    return dcoDecodeI64(raw);
+  }
+
   @protected
   List<String> dco_decode_list_String(dynamic raw) {
     // Codec=Dco (DartCObject based), see doc to use other codecs
     return (raw as List<dynamic>).map(dco_decode_String).toList();
   }
 
+  @protected
+  List<P2PBridgeChatMessage> dco_decode_list_p_2_p_bridge_chat_message(
+    dynamic raw,
+  ) {
+    // Codec=Dco (DartCObject based), see doc to use other codecs
+    return (raw as List<dynamic>)
+        .map(dco_decode_p_2_p_bridge_chat_message)
+        .toList();
+  }
+
   @protected
   List<P2PBridgeEvent> dco_decode_list_p_2_p_bridge_event(dynamic raw) {
     // Codec=Dco (DartCObject based), see doc to use other codecs
@@ -660,6 +1138,12 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
         .toList();
   }
 
+  @protected
+  List<int> dco_decode_list_prim_u_8_loose(dynamic raw) {
+    // Codec=Dco (DartCObject based), see doc to use other codecs
+    return raw as List<int>;
+  }
+
   @protected
   Uint8List dco_decode_list_prim_u_8_strict(dynamic raw) {
     // Codec=Dco (DartCObject based), see doc to use other codecs
@@ -672,6 +1156,28 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     return raw == null ? null : dco_decode_String(raw);
   }
 
+  @protected
+  Uint8List? dco_decode_opt_list_prim_u_8_strict(dynamic raw) {
+    // Codec=Dco (DartCObject based), see doc to use other codecs
+    return raw == null ? null : dco_decode_list_prim_u_8_strict(raw);
+  }
+
+  @protected
+  P2PBridgeChatMessage dco_decode_p_2_p_bridge_chat_message(dynamic raw) {
+    // Codec=Dco (DartCObject based), see doc to use other codecs
+    final arr = raw as List<dynamic>;
+    if (arr.length != 6)
+      throw Exception('unexpected arr length: expect 6 but see ${arr.length}');
+    return P2PBridgeChatMessage(
+      messageId: dco_decode_String(arr[0]),
+      peerId: dco_decode_String(arr[1]),
+      from: dco_decode_String(arr[2]),
+      content: dco_decode_String(arr[3]),
+      timestamp: dco_decode_i_64(arr[4]),
+      outgoing: dco_decode_bool(arr[5]),
+    );
+  }
+
   @protected
   P2PBridgeEvent dco_decode_p_2_p_bridge_event(dynamic raw) {
     // Codec=Dco (DartCObject based), see doc to use other codecs
@@ -700,6 +1206,12 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     );
   }
 
+  @protected
+  int dco_decode_u_32(dynamic raw) {
+    // Codec=Dco (DartCObject based), see doc to use other codecs
+    return raw as int;
+  }
+
   @protected
   int dco_decode_u_8(dynamic raw) {
     // Codec=Dco (DartCObject based), see doc to use other codecs
@@ -776,6 +1288,12 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     return deserializer.buffer.getInt32();
   }
 
+  @protected
+  PlatformInt64 sse_decode_i_64(SseDeserializer deserializer) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    return deserializer.buffer.getPlatformInt64();
+  }
+
   @protected
   List<String> sse_decode_list_String(SseDeserializer deserializer) {
     // Codec=Sse (Serialization based), see doc to use other codecs
@@ -788,6 +1306,20 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     return ans_;
   }
 
+  @protected
+  List<P2PBridgeChatMessage> sse_decode_list_p_2_p_bridge_chat_message(
+    SseDeserializer deserializer,
+  ) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+
+    var len_ = sse_decode_i_32(deserializer);
+    var ans_ = <P2PBridgeChatMessage>[];
+    for (var idx_ = 0; idx_ < len_; ++idx_) {
+      ans_.add(sse_decode_p_2_p_bridge_chat_message(deserializer));
+    }
+    return ans_;
+  }
+
   @protected
   List<P2PBridgeEvent> sse_decode_list_p_2_p_bridge_event(
     SseDeserializer deserializer,
@@ -816,6 +1348,13 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     return ans_;
   }
 
+  @protected
+  List<int> sse_decode_list_prim_u_8_loose(SseDeserializer deserializer) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    var len_ = sse_decode_i_32(deserializer);
+    return deserializer.buffer.getUint8List(len_);
+  }
+
   @protected
   Uint8List sse_decode_list_prim_u_8_strict(SseDeserializer deserializer) {
     // Codec=Sse (Serialization based), see doc to use other codecs
@@ -834,6 +1373,38 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     }
   }
 
+  @protected
+  Uint8List? sse_decode_opt_list_prim_u_8_strict(SseDeserializer deserializer) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+
+    if (sse_decode_bool(deserializer)) {
+      return (sse_decode_list_prim_u_8_strict(deserializer));
+    } else {
+      return null;
+    }
+  }
+
+  @protected
+  P2PBridgeChatMessage sse_decode_p_2_p_bridge_chat_message(
+    SseDeserializer deserializer,
+  ) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    var var_messageId = sse_decode_String(deserializer);
+    var var_peerId = sse_decode_String(deserializer);
+    var var_from = sse_decode_String(deserializer);
+    var var_content = sse_decode_String(deserializer);
+    var var_timestamp = sse_decode_i_64(deserializer);
+    var var_outgoing = sse_decode_bool(deserializer);
+    return P2PBridgeChatMessage(
+      messageId: var_messageId,
+      peerId: var_peerId,
+      from: var_from,
+      content: var_content,
+      timestamp: var_timestamp,
+      outgoing: var_outgoing,
+    );
+  }
+
   @protected
   P2PBridgeEvent sse_decode_p_2_p_bridge_event(SseDeserializer deserializer) {
     // Codec=Sse (Serialization based), see doc to use other codecs
@@ -863,6 +1434,12 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     );
   }
 
+  @protected
+  int sse_decode_u_32(SseDeserializer deserializer) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    return deserializer.buffer.getUint32();
+  }
+
   @protected
   int sse_decode_u_8(SseDeserializer deserializer) {
     // Codec=Sse (Serialization based), see doc to use other codecs
@@ -950,6 +1527,12 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     serializer.buffer.putInt32(self);
   }
 
+  @protected
+  void sse_encode_i_64(PlatformInt64 self, SseSerializer serializer) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    serializer.buffer.putPlatformInt64(self);
+  }
+
   @protected
   void sse_encode_list_String(List<String> self, SseSerializer serializer) {
     // Codec=Sse (Serialization based), see doc to use other codecs
@@ -959,6 +1542,18 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     }
   }
 
+  @protected
+  void sse_encode_list_p_2_p_bridge_chat_message(
+    List<P2PBridgeChatMessage> self,
+    SseSerializer serializer,
+  ) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    sse_encode_i_32(self.length, serializer);
+    for (final item in self) {
+      sse_encode_p_2_p_bridge_chat_message(item, serializer);
+    }
+  }
+
   @protected
   void sse_encode_list_p_2_p_bridge_event(
     List<P2PBridgeEvent> self,
@@ -983,6 +1578,18 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     }
   }
 
+  @protected
+  void sse_encode_list_prim_u_8_loose(
+    List<int> self,
+    SseSerializer serializer,
+  ) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    sse_encode_i_32(self.length, serializer);
+    serializer.buffer.putUint8List(
+      self is Uint8List ? self : Uint8List.fromList(self),
+    );
+  }
+
   @protected
   void sse_encode_list_prim_u_8_strict(
     Uint8List self,
@@ -1003,6 +1610,33 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     }
   }
 
+  @protected
+  void sse_encode_opt_list_prim_u_8_strict(
+    Uint8List? self,
+    SseSerializer serializer,
+  ) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+
+    sse_encode_bool(self != null, serializer);
+    if (self != null) {
+      sse_encode_list_prim_u_8_strict(self, serializer);
+    }
+  }
+
+  @protected
+  void sse_encode_p_2_p_bridge_chat_message(
+    P2PBridgeChatMessage self,
+    SseSerializer serializer,
+  ) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    sse_encode_String(self.messageId, serializer);
+    sse_encode_String(self.peerId, serializer);
+    sse_encode_String(self.from, serializer);
+    sse_encode_String(self.content, serializer);
+    sse_encode_i_64(self.timestamp, serializer);
+    sse_encode_bool(self.outgoing, serializer);
+  }
+
   @protected
   void sse_encode_p_2_p_bridge_event(
     P2PBridgeEvent self,
@@ -1027,6 +1661,12 @@ class P2PBridgeApiImpl extends P2PBridgeApiImplPlatform
     sse_encode_opt_String(self.avatarUrl, serializer);
   }
 
+  @protected
+  void sse_encode_u_32(int self, SseSerializer serializer) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    serializer.buffer.putUint32(self);
+  }
+
   @protected
   void sse_encode_u_8(int self, SseSerializer serializer) {
     // Codec=Sse (Serialization based), see doc to use other codecs
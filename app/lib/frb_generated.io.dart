@@ -52,27 +52,47 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
   @protected
   int dco_decode_i_32(dynamic raw);
 
+  @protected
+  PlatformInt64 dco_decode_i_64(dynamic raw);
+
   @protected
   List<String> dco_decode_list_String(dynamic raw);
 
+  @protected
+  List<P2PBridgeChatMessage> dco_decode_list_p_2_p_bridge_chat_message(
+    dynamic raw,
+  );
+
   @protected
   List<P2PBridgeEvent> dco_decode_list_p_2_p_bridge_event(dynamic raw);
 
   @protected
   List<P2PBridgeNodeInfo> dco_decode_list_p_2_p_bridge_node_info(dynamic raw);
 
+  @protected
+  List<int> dco_decode_list_prim_u_8_loose(dynamic raw);
+
   @protected
   Uint8List dco_decode_list_prim_u_8_strict(dynamic raw);
 
   @protected
   String? dco_decode_opt_String(dynamic raw);
 
+  @protected
+  Uint8List? dco_decode_opt_list_prim_u_8_strict(dynamic raw);
+
+  @protected
+  P2PBridgeChatMessage dco_decode_p_2_p_bridge_chat_message(dynamic raw);
+
   @protected
   P2PBridgeEvent dco_decode_p_2_p_bridge_event(dynamic raw);
 
   @protected
   P2PBridgeNodeInfo dco_decode_p_2_p_bridge_node_info(dynamic raw);
 
+  @protected
+  int dco_decode_u_32(dynamic raw);
+
   @protected
   int dco_decode_u_8(dynamic raw);
 
@@ -111,9 +131,17 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
   @protected
   int sse_decode_i_32(SseDeserializer deserializer);
 
+  @protected
+  PlatformInt64 sse_decode_i_64(SseDeserializer deserializer);
+
   @protected
   List<String> sse_decode_list_String(SseDeserializer deserializer);
 
+  @protected
+  List<P2PBridgeChatMessage> sse_decode_list_p_2_p_bridge_chat_message(
+    SseDeserializer deserializer,
+  );
+
   @protected
   List<P2PBridgeEvent> sse_decode_list_p_2_p_bridge_event(
     SseDeserializer deserializer,
@@ -124,12 +152,23 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
     SseDeserializer deserializer,
   );
 
+  @protected
+  List<int> sse_decode_list_prim_u_8_loose(SseDeserializer deserializer);
+
   @protected
   Uint8List sse_decode_list_prim_u_8_strict(SseDeserializer deserializer);
 
   @protected
   String? sse_decode_opt_String(SseDeserializer deserializer);
 
+  @protected
+  Uint8List? sse_decode_opt_list_prim_u_8_strict(SseDeserializer deserializer);
+
+  @protected
+  P2PBridgeChatMessage sse_decode_p_2_p_bridge_chat_message(
+    SseDeserializer deserializer,
+  );
+
   @protected
   P2PBridgeEvent sse_decode_p_2_p_bridge_event(SseDeserializer deserializer);
 
@@ -138,6 +177,9 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
     SseDeserializer deserializer,
   );
 
+  @protected
+  int sse_decode_u_32(SseDeserializer deserializer);
+
   @protected
   int sse_decode_u_8(SseDeserializer deserializer);
 
@@ -182,9 +224,18 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
   @protected
   void sse_encode_i_32(int self, SseSerializer serializer);
 
+  @protected
+  void sse_encode_i_64(PlatformInt64 self, SseSerializer serializer);
+
   @protected
   void sse_encode_list_String(List<String> self, SseSerializer serializer);
 
+  @protected
+  void sse_encode_list_p_2_p_bridge_chat_message(
+    List<P2PBridgeChatMessage> self,
+    SseSerializer serializer,
+  );
+
   @protected
   void sse_encode_list_p_2_p_bridge_event(
     List<P2PBridgeEvent> self,
@@ -197,6 +248,9 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
     SseSerializer serializer,
   );
 
+  @protected
+  void sse_encode_list_prim_u_8_loose(List<int> self, SseSerializer serializer);
+
   @protected
   void sse_encode_list_prim_u_8_strict(
     Uint8List self,
@@ -206,6 +260,18 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
   @protected
   void sse_encode_opt_String(String? self, SseSerializer serializer);
 
+  @protected
+  void sse_encode_opt_list_prim_u_8_strict(
+    Uint8List? self,
+    SseSerializer serializer,
+  );
+
+  @protected
+  void sse_encode_p_2_p_bridge_chat_message(
+    P2PBridgeChatMessage self,
+    SseSerializer serializer,
+  );
+
   @protected
   void sse_encode_p_2_p_bridge_event(
     P2PBridgeEvent self,
@@ -218,6 +284,9 @@ abstract class P2PBridgeApiImplPlatform extends BaseApiImpl<P2PBridgeWire> {
     SseSerializer serializer,
   );
 
+  @protected
+  void sse_encode_u_32(int self, SseSerializer serializer);
+
   @protected
   void sse_encode_u_8(int self, SseSerializer serializer);
 
@@ -28,6 +28,10 @@ class P2PInitConfig {
   /// 心跳失败阈值（可选，默认 3）
   final int? maxFailures;
 
+  /// 私有网络密钥（可选，swarm.key 内容或 64 位十六进制）
+  /// 只有使用相同密钥的设备才能互相发现和连接
+  final String? networkKey;
+
   P2PInitConfig({
     required this.deviceName,
     this.identityPath,
@@ -35,6 +39,7 @@ class P2PInitConfig {
     this.protocolVersion,
     this.heartbeatIntervalSecs,
     this.maxFailures,
+    this.networkKey,
   });
 }
 
@@ -146,6 +151,7 @@ class P2PManager {
       RustLib.instance.api.localp2PFfiBridgeP2PInit(
         deviceName: config.deviceName,
         identityPath: config.identityPath ?? '',
+        networkKey: config.networkKey ?? '',
       );
       _initialized = true;
       _log.rustReturn('init', result: 'initialized=$_initialized');
@@ -7,7 +7,7 @@ import '../../bridge.dart';
 import '../../frb_generated.dart';
 import 'package:flutter_rust_bridge/flutter_rust_bridge_for_generated.dart';
 
-// These function are ignored because they are on traits that is not defined in current crate (put an empty `#[frb]` on it to unignore): `clone`, `clone`, `fmt`, `fmt`
+// These function are ignored because they are on traits that is not defined in current crate (put an empty `#[frb]` on it to unignore): `clone`, `clone`, `clone`, `fmt`, `fmt`, `fmt`
 
 /// 检查 P2P 是否已初始化
 bool p2PIsInitialized() =>
@@ -35,11 +35,16 @@ void p2PRestartDiscovery() =>
 /// # Arguments
 /// * `device_name` - 本设备的显示名称
 /// * `identity_path` - 密钥对保存路径（空字符串表示不持久化）
-void p2PInit({required String deviceName, required String identityPath}) =>
-    P2PBridge.instance.api.localp2PFfiBridgeP2PInit(
-      deviceName: deviceName,
-      identityPath: identityPath,
-    );
+/// * `network_key` - 私有网络密钥（`swarm.key` 内容或 64 位十六进制，空字符串表示不使用私有网络）
+void p2PInit({
+  required String deviceName,
+  required String identityPath,
+  required String networkKey,
+}) => P2PBridge.instance.api.localp2PFfiBridgeP2PInit(
+  deviceName: deviceName,
+  identityPath: identityPath,
+  networkKey: networkKey,
+);
 
 /// 启动 P2P 服务
 void p2PStart() => P2PBridge.instance.api.localp2PFfiBridgeP2PStart();
@@ -62,6 +67,78 @@ String p2PGetDeviceName() =>
 List<P2PBridgeNodeInfo> p2PGetVerifiedNodes() =>
     P2PBridge.instance.api.localp2PFfiBridgeP2PGetVerifiedNodes();
 
+/// 修改本地用户信息，并推送给所有已连接的已验证节点
+///
+/// # Arguments
+/// * `nickname` - 昵称（空字符串表示不设置）
+/// * `status` - 状态（空字符串表示不设置）
+void p2PSetUserInfo({required String nickname, required String status}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PSetUserInfo(
+      nickname: nickname,
+      status: status,
+    );
+
+/// 设置在线状态，并推送给所有已连接的已验证节点
+///
+/// 请勿打扰状态下不再产生 PeerTyping 事件，消息事件的 `silent` 为 true。
+///
+/// # Arguments
+/// * `presence` - online / away / busy / do_not_disturb / invisible
+/// * `message` - 状态说明（空字符串表示不设置）
+void p2PSetPresence({required String presence, required String message}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PSetPresence(
+      presence: presence,
+      message: message,
+    );
+
+/// 设置本地头像，并推送给所有已连接的已验证节点
+///
+/// # Arguments
+/// * `data` - 图片数据（PNG / JPEG / GIF / WebP，不超过 256KB；空数据表示清除头像）
+///
+/// # Returns
+/// 头像哈希（清除头像时为空字符串）
+String p2PSetAvatar({required List<int> data}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PSetAvatar(data: data);
+
+/// 手动添加节点地址（用于 mDNS 被屏蔽的网络）
+///
+/// 地址为 multiaddr，例如 `/ip4/192.168.1.20/tcp/4001`，会保存到静态节点列表，
+/// 启动时自动拨号，断开后自动重拨。
+///
+/// # Returns
+/// 是否新添加（地址已存在时为 false）
+bool p2PAddStaticPeer({required String address}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PAddStaticPeer(address: address);
+
+/// 移除静态节点（已建立的连接不会断开）
+bool p2PRemoveStaticPeer({required String address}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PRemoveStaticPeer(
+      address: address,
+    );
+
+/// 获取所有静态节点地址
+List<String> p2PGetStaticPeers() =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PGetStaticPeers();
+
+/// 启用会合点发现（用于团队成员分布在多个子网的情况）
+///
+/// `server` 为会合服务器地址（必须带 `/p2p/<peer_id>`），`team` 为团队名称，
+/// 同一团队的节点在服务器上互相可见。重启节点后需要重新调用。
+void p2PEnableRendezvous({required String server, required String team}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PEnableRendezvous(
+      server: server,
+      team: team,
+    );
+
+/// 获取节点头像的缓存文件路径（尚未获取或没有头像时返回 None）
+String? p2PGetAvatarPath({required String peerId}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PGetAvatarPath(peerId: peerId);
+
+/// 获取节点的头像数据（尚未获取或没有头像时返回 None）
+Uint8List? p2PGetAvatar({required String peerId}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PGetAvatar(peerId: peerId);
+
 /// 发送消息给指定节点
 ///
 /// # Arguments
@@ -86,6 +163,72 @@ void p2PBroadcastMessage({
   message: message,
 );
 
+/// 分页获取与指定节点的聊天记录（按时间正序）
+///
+/// # Arguments
+/// * `peer_id` - 会话对方的 Peer ID
+/// * `before_message_id` - 从该消息之前开始加载（`None` 表示最新的消息）
+/// * `limit` - 最多返回的条数
+List<P2PBridgeChatMessage> p2PGetChatHistory({
+  required String peerId,
+  String? beforeMessageId,
+  required int limit,
+}) => P2PBridge.instance.api.localp2PFfiBridgeP2PGetChatHistory(
+  peerId: peerId,
+  beforeMessageId: beforeMessageId,
+  limit: limit,
+);
+
+/// 将与指定节点的会话标记为已读
+///
+/// Flutter 端在用户打开会话时调用，Rust 会向对方发送已读回执。
+///
+/// # Arguments
+/// * `peer_id` - 会话对方的 Peer ID
+void p2PMarkConversationRead({required String peerId}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PMarkConversationRead(
+      peerId: peerId,
+    );
+
+/// 创建聊天室并通知所有成员
+///
+/// # Arguments
+/// * `name` - 聊天室名称
+/// * `member_peer_ids` - 其他成员的 Peer ID（本地节点自动加入）
+///
+/// # Returns
+/// 聊天室 ID
+String p2PCreateRoom({
+  required String name,
+  required List<String> memberPeerIds,
+}) => P2PBridge.instance.api.localp2PFfiBridgeP2PCreateRoom(
+  name: name,
+  memberPeerIds: memberPeerIds,
+);
+
+/// 邀请新成员加入聊天室
+void p2PInviteToRoom({
+  required String roomId,
+  required List<String> memberPeerIds,
+}) => P2PBridge.instance.api.localp2PFfiBridgeP2PInviteToRoom(
+  roomId: roomId,
+  memberPeerIds: memberPeerIds,
+);
+
+/// 退出聊天室
+void p2PLeaveRoom({required String roomId}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PLeaveRoom(roomId: roomId);
+
+/// 在聊天室中发送文本消息
+///
+/// # Returns
+/// 消息 ID（用于匹配 MessageStatusChanged 事件）
+String p2PSendRoomMessage({required String roomId, required String message}) =>
+    P2PBridge.instance.api.localp2PFfiBridgeP2PSendRoomMessage(
+      roomId: roomId,
+      message: message,
+    );
+
 /// 设置事件流接收器（用于 Stream 模式）
 ///
 /// 调用此函数后，Rust 会将事件推送到 Stream，Flutter 端可以订阅这个 Stream
@@ -25,11 +25,21 @@ pub struct P2PBridgeEvent {
     /// 2 = NodeExpired
     /// 3 = NodeVerified
     /// 4 = NodeOffline
-    /// 5 = UserInfoReceived
-    /// 6 = MessageReceived
+    /// 5 = UserInfoReceived (`presence` 为 online / away / busy / do_not_disturb / invisible)
+    /// 6 = MessageReceived (`silent` 为 true 时本地处于请勿打扰状态，不应发出通知)
     /// 7 = MessageSent
     /// 8 = PeerTyping
     /// 9 = Log (Rust 日志)
+    /// 10 = MessageStatusChanged (pending / sent / delivered / read)
+    /// 11 = DeliveryFailed (离线消息超过有效期仍未送达)
+    /// 12 = MessageSendFailed (单次发送失败，消息会自动重试)
+    /// 13 = UserInfoRequestFailed
+    /// 14 = RoomMessage (聊天室消息)
+    /// 15 = RoomUpdated (聊天室创建或成员变更)
+    /// 16 = RichMessageReceived (图片 / 文件附件 / 编辑 / 删除 / 表情回应，`message` 为完整消息 JSON)
+    /// 17 = PeerBanned (节点多次发送超限或格式错误的数据，已断开并封禁)
+    /// 18 = UserInfoUpdated (节点修改了昵称、状态等用户信息)
+    /// 19 = AvatarReceived (节点头像已获取并写入缓存，`path` 为缓存文件路径)
     pub event_type: i32,
     /// 事件数据 (JSON 字符串)
     pub data: String,
@@ -38,6 +48,21 @@ pub struct P2PBridgeEvent {
 /// 类型别名，用于兼容 lib.rs 中的引用
 pub type P2PEvent = P2PBridgeEvent;
 
+/// 聊天记录（用于 FRB）
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct P2PBridgeChatMessage {
+    pub message_id: String,
+    /// 会话对方的 Peer ID
+    pub peer_id: String,
+    /// 发送者的 Peer ID
+    pub from: String,
+    pub content: String,
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+    /// 是否为本地发出的消息
+    pub outgoing: bool,
+}
+
 /// 节点信息（用于 FRB）
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct P2PBridgeNodeInfo {
@@ -116,9 +141,10 @@ pub fn p2p_restart_discovery() -> Result<(), String> {
 /// # Arguments
 /// * `device_name` - 本设备的显示名称
 /// * `identity_path` - 密钥对保存路径（空字符串表示不持久化）
+/// * `network_key` - 私有网络密钥（`swarm.key` 内容或 64 位十六进制，空字符串表示不使用私有网络）
 #[frb(sync)]
-pub fn p2p_init(device_name: String, identity_path: String) -> Result<(), String> {
-    crate::internal_init(device_name, identity_path)
+pub fn p2p_init(device_name: String, identity_path: String, network_key: String) -> Result<(), String> {
+    crate::internal_init(device_name, identity_path, network_key)
 }
 
 /// 启动 P2P 服务
@@ -175,6 +201,85 @@ pub fn p2p_get_verified_nodes() -> Result<Vec<P2PBridgeNodeInfo>, String> {
     }).collect())
 }
 
+/// 修改本地用户信息，并推送给所有已连接的已验证节点
+///
+/// # Arguments
+/// * `nickname` - 昵称（空字符串表示不设置）
+/// * `status` - 状态（空字符串表示不设置）
+#[frb(sync)]
+pub fn p2p_set_user_info(nickname: String, status: String) -> Result<(), String> {
+    crate::internal_set_user_info_sync(nickname, status)
+}
+
+/// 设置在线状态，并推送给所有已连接的已验证节点
+///
+/// 请勿打扰状态下不再产生 PeerTyping 事件，消息事件的 `silent` 为 true。
+///
+/// # Arguments
+/// * `presence` - online / away / busy / do_not_disturb / invisible
+/// * `message` - 状态说明（空字符串表示不设置）
+#[frb(sync)]
+pub fn p2p_set_presence(presence: String, message: String) -> Result<(), String> {
+    crate::internal_set_presence_sync(presence, message)
+}
+
+/// 设置本地头像，并推送给所有已连接的已验证节点
+///
+/// # Arguments
+/// * `data` - 图片数据（PNG / JPEG / GIF / WebP，不超过 256KB；空数据表示清除头像）
+///
+/// # Returns
+/// 头像哈希（清除头像时为空字符串）
+#[frb(sync)]
+pub fn p2p_set_avatar(data: Vec<u8>) -> Result<String, String> {
+    crate::internal_set_avatar_sync(data)
+}
+
+/// 手动添加节点地址（用于 mDNS 被屏蔽的网络）
+///
+/// 地址为 multiaddr，例如 `/ip4/192.168.1.20/tcp/4001`，会保存到静态节点列表，
+/// 启动时自动拨号，断开后自动重拨。
+///
+/// # Returns
+/// 是否新添加（地址已存在时为 false）
+#[frb(sync)]
+pub fn p2p_add_static_peer(address: String) -> Result<bool, String> {
+    crate::internal_add_static_peer_sync(address)
+}
+
+/// 移除静态节点（已建立的连接不会断开）
+#[frb(sync)]
+pub fn p2p_remove_static_peer(address: String) -> Result<bool, String> {
+    crate::internal_remove_static_peer_sync(address)
+}
+
+/// 获取所有静态节点地址
+#[frb(sync)]
+pub fn p2p_get_static_peers() -> Result<Vec<String>, String> {
+    crate::internal_get_static_peers_sync()
+}
+
+/// 启用会合点发现（用于团队成员分布在多个子网的情况）
+///
+/// `server` 为会合服务器地址（必须带 `/p2p/<peer_id>`），`team` 为团队名称，
+/// 同一团队的节点在服务器上互相可见。重启节点后需要重新调用。
+#[frb(sync)]
+pub fn p2p_enable_rendezvous(server: String, team: String) -> Result<(), String> {
+    crate::internal_enable_rendezvous_sync(server, team)
+}
+
+/// 获取节点头像的缓存文件路径（尚未获取或没有头像时返回 None）
+#[frb(sync)]
+pub fn p2p_get_avatar_path(peer_id: String) -> Result<Option<String>, String> {
+    crate::internal_get_avatar_path_sync(peer_id)
+}
+
+/// 获取节点的头像数据（尚未获取或没有头像时返回 None）
+#[frb(sync)]
+pub fn p2p_get_avatar(peer_id: String) -> Result<Option<Vec<u8>>, String> {
+    crate::internal_get_avatar_sync(peer_id)
+}
+
 // /// 获取所有节点的用户信息（包括昵称、状态等）
 // pub async fn p2p_list_user_info() -> Result<Vec<P2PBridgeNodeInfo>, String> {
 //     crate::internal_list_user_info().await
@@ -210,6 +315,66 @@ pub fn p2p_broadcast_message(
     crate::internal_broadcast_message_sync(target_peer_ids, message)
 }
 
+/// 分页获取与指定节点的聊天记录（按时间正序）
+///
+/// # Arguments
+/// * `peer_id` - 会话对方的 Peer ID
+/// * `before_message_id` - 从该消息之前开始加载（`None` 表示最新的消息）
+/// * `limit` - 最多返回的条数
+#[frb(sync)]
+pub fn p2p_get_chat_history(
+    peer_id: String,
+    before_message_id: Option<String>,
+    limit: u32,
+) -> Result<Vec<P2PBridgeChatMessage>, String> {
+    crate::internal_get_chat_history(peer_id, before_message_id, limit)
+}
+
+/// 将与指定节点的会话标记为已读
+///
+/// Flutter 端在用户打开会话时调用，Rust 会向对方发送已读回执。
+///
+/// # Arguments
+/// * `peer_id` - 会话对方的 Peer ID
+#[frb(sync)]
+pub fn p2p_mark_conversation_read(peer_id: String) -> Result<(), String> {
+    crate::internal_mark_conversation_read_sync(peer_id)
+}
+
+/// 创建聊天室并通知所有成员
+///
+/// # Arguments
+/// * `name` - 聊天室名称
+/// * `member_peer_ids` - 其他成员的 Peer ID（本地节点自动加入）
+///
+/// # Returns
+/// 聊天室 ID
+#[frb(sync)]
+pub fn p2p_create_room(name: String, member_peer_ids: Vec<String>) -> Result<String, String> {
+    crate::internal_create_room_sync(name, member_peer_ids)
+}
+
+/// 邀请新成员加入聊天室
+#[frb(sync)]
+pub fn p2p_invite_to_room(room_id: String, member_peer_ids: Vec<String>) -> Result<(), String> {
+    crate::internal_invite_to_room_sync(room_id, member_peer_ids)
+}
+
+/// 退出聊天室
+#[frb(sync)]
+pub fn p2p_leave_room(room_id: String) -> Result<(), String> {
+    crate::internal_leave_room_sync(room_id)
+}
+
+/// 在聊天室中发送文本消息
+///
+/// # Returns
+/// 消息 ID（用于匹配 MessageStatusChanged 事件）
+#[frb(sync)]
+pub fn p2p_send_room_message(room_id: String, message: String) -> Result<String, String> {
+    crate::internal_send_room_message_sync(room_id, message)
+}
+
 // ============================================================================
 // 事件功能
 // ============================================================================
@@ -8,6 +8,7 @@
 #![allow(clippy::missing_safety_doc)]
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 
@@ -15,31 +16,32 @@ use once_cell::sync::Lazy;
 use tokio::runtime::Runtime;
 use mdns::{
     ManagedDiscovery, NodeManager, NodeManagerConfig,
-    HealthCheckConfig, UserInfo, ChatExtension, ChatMessage,
-    IdentityManager,
+    HealthCheckConfig, UserInfo, ChatExtension,
+    IdentityManager, ChatStore, FileChatStore, MemoryChatStore, NetworkKey, AvatarCache, StaticPeerStore, RendezvousConfig,
 };
 
-mod types;
-mod error;
 pub mod bridge;
 mod frb_generated; /* AUTO INJECTED BY flutter_rust_bridge. This line may not be accurate, and you can change it according to your needs. */
 
-use types::*;
-
 // ============================================================================
 // 全局运行时和状态管理
 // ============================================================================
 
-/// 全局 Tokio 运行时
-static mut RUNTIME: Option<Runtime> = None;
+/// 全局 Tokio 运行时（清理时释放，之后可以重新初始化）
+static RUNTIME: Mutex<Option<Arc<Runtime>>> = Mutex::new(None);
 
-/// 获取 Tokio 运行时的引用（供 bridge 模块使用）
-pub fn get_runtime() -> Option<&'static Runtime> {
-    unsafe { RUNTIME.as_ref() }
+/// 获取 Tokio 运行时（供 bridge 模块使用）
+pub fn get_runtime() -> Option<Arc<Runtime>> {
+    RUNTIME.lock().unwrap().clone()
 }
 
 /// 全局 P2P 实例
-static mut P2P_INSTANCE: Option<Arc<Mutex<P2PInstance>>> = None;
+static P2P_INSTANCE: Mutex<Option<Arc<Mutex<P2PInstance>>>> = Mutex::new(None);
+
+/// 获取全局 P2P 实例，未初始化时为 None
+fn p2p_instance() -> Option<Arc<Mutex<P2PInstance>>> {
+    P2P_INSTANCE.lock().unwrap().clone()
+}
 
 /// 全局用户信息缓存（用于查询用户信息）
 /// 使用 RwLock 允许多读单写
@@ -52,13 +54,12 @@ struct GlobalDiscoveryResources {
     discovery: Option<ManagedDiscovery>,
     chat_event_rx: Option<tokio::sync::mpsc::UnboundedReceiver<mdns::chat::ChatEvent>>,
     command_rx: Option<tokio::sync::mpsc::UnboundedReceiver<P2PCommand>>,
-    event_tx: Option<tokio::sync::mpsc::UnboundedSender<P2PEvent>>,
 }
 
-static mut DISCOVERY_RESOURCES: Option<GlobalDiscoveryResources> = None;
+static DISCOVERY_RESOURCES: Mutex<Option<GlobalDiscoveryResources>> = Mutex::new(None);
 
 /// P2P 服务运行标志（用于 internal_is_running 检查）
-static mut P2P_IS_RUNNING: bool = false;
+static P2P_IS_RUNNING: AtomicBool = AtomicBool::new(false);
 
 /// 最后一次收到 discovery 事件的时间
 static LAST_EVENT_TIME: Mutex<Option<std::time::Instant>> = Mutex::new(None);
@@ -84,25 +85,20 @@ struct P2PInstance {
     device_name: String,
     /// 身份密钥对（用于保持 Peer ID 稳定）
     identity: Option<libp2p::identity::Keypair>,
+    /// 聊天记录存储（重启 discovery 时复用）
+    chat_store: Arc<dyn ChatStore>,
+    /// 私有网络密钥（重启 discovery 时复用）
+    network_key: Option<NetworkKey>,
+    /// 头像缓存目录（未指定密钥文件路径时为 None，头像只保存在内存中）
+    avatar_dir: Option<std::path::PathBuf>,
+    /// 静态节点列表文件（未指定密钥文件路径时为 None，列表只保存在内存中）
+    static_peers_path: Option<std::path::PathBuf>,
     /// 命令通道，用于向 discovery 线程发送命令
     command_tx: tokio::sync::mpsc::UnboundedSender<P2PCommand>,
     /// Discovery 线程句柄
     discovery_thread: Option<thread::JoinHandle<()>>,
 }
 
-/// P2P 内部事件
-#[derive(Debug)]
-enum P2PEvent {
-    NodeDiscovered { peer_id: String, addr: String },
-    NodeExpired { peer_id: String },
-    NodeVerified { peer_id: String, display_name: String },
-    NodeOffline { peer_id: String },
-    UserInfoReceived { peer_id: String, user_info: UserInfoJson },
-    MessageReceived { from: String, message: ChatMessageJson },
-    MessageSent { to: String, message_id: String },
-    PeerTyping { from: String, is_typing: bool },
-}
-
 /// P2P 命令（用于与 discovery 线程通信）
 #[derive(Debug)]
 enum P2PCommand {
@@ -116,6 +112,59 @@ enum P2PCommand {
         message: String,
         response_tx: tokio::sync::oneshot::Sender<Result<String, String>>,
     },
+    MarkRead {
+        peer_id: String,
+        response_tx: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    CreateRoom {
+        name: String,
+        member_peer_ids: Vec<String>,
+        response_tx: tokio::sync::oneshot::Sender<Result<String, String>>,
+    },
+    InviteToRoom {
+        room_id: String,
+        member_peer_ids: Vec<String>,
+        response_tx: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    LeaveRoom {
+        room_id: String,
+        response_tx: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    SendRoomMessage {
+        room_id: String,
+        message: String,
+        response_tx: tokio::sync::oneshot::Sender<Result<String, String>>,
+    },
+    SetAvatar {
+        data: Option<Vec<u8>>,
+        response_tx: tokio::sync::oneshot::Sender<Result<String, String>>,
+    },
+    SetPresence {
+        presence: mdns::Presence,
+        message: Option<String>,
+        response_tx: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    AddStaticPeer {
+        address: String,
+        response_tx: tokio::sync::oneshot::Sender<Result<bool, String>>,
+    },
+    RemoveStaticPeer {
+        address: String,
+        response_tx: tokio::sync::oneshot::Sender<Result<bool, String>>,
+    },
+    GetStaticPeers {
+        response_tx: tokio::sync::oneshot::Sender<Result<Vec<String>, String>>,
+    },
+    EnableRendezvous {
+        server: String,
+        team: String,
+        response_tx: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    SetUserInfo {
+        nickname: Option<String>,
+        status: Option<String>,
+        response_tx: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
     Ping {
         response_tx: tokio::sync::oneshot::Sender<Result<(), String>>,
     },
@@ -137,7 +186,7 @@ use bridge::InternalNodeInfo;
 // ============================================================================
 
 /// 内部初始化函数（供 FRB 调用）
-pub fn internal_init(device_name: String, identity_path: String) -> Result<(), String> {
+pub fn internal_init(device_name: String, identity_path: String, network_key: String) -> Result<(), String> {
     // 初始化日志
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::INFO)
@@ -145,20 +194,19 @@ pub fn internal_init(device_name: String, identity_path: String) -> Result<(), S
         .ok();
 
     // 创建 Tokio 运行时
-    unsafe {
-        if RUNTIME.is_some() {
+    let runtime = {
+        let mut runtime = RUNTIME.lock().unwrap();
+        if runtime.is_some() {
             return Err("Already initialized".to_string());
         }
 
-        RUNTIME = Some(
+        runtime.insert(Arc::new(
             tokio::runtime::Builder::new_multi_thread()
                 .enable_all()
                 .build()
                 .expect("Failed to create runtime")
-        );
-    }
-
-    let runtime = unsafe { RUNTIME.as_ref().unwrap() };
+        )).clone()
+    };
 
     // 在运行时中初始化核心组件
     let result = runtime.block_on(async {
@@ -185,13 +233,14 @@ pub fn internal_init(device_name: String, identity_path: String) -> Result<(), S
 
         // 创建用户信息
         let user_info = UserInfo::new(device_name.clone())
-            .with_status("在线".to_string());
+            .with_presence(mdns::Presence::Online);
 
         // 创建节点管理器配置
         let config = NodeManagerConfig::new()
             .with_protocol_version("/localp2p/1.0.0".to_string())
             .with_agent_prefix(Some("localp2p-rust/".to_string()))
-            .with_device_name(device_name.clone());
+            .with_device_name(device_name.clone())
+            .with_quic(true);
 
         let node_manager = Arc::new(NodeManager::new(config));
 
@@ -210,6 +259,17 @@ pub fn internal_init(device_name: String, identity_path: String) -> Result<(), S
         // 保存 identity 的克隆，用于后续保存到 P2PInstance
         let identity_for_instance = identity.clone();
 
+        // 解析私有网络密钥（空字符串表示不使用私有网络）
+        let network_key = if network_key.trim().is_empty() {
+            None
+        } else {
+            Some(
+                network_key
+                    .parse::<NetworkKey>()
+                    .map_err(|e| format!("Invalid network key: {}", e))?,
+            )
+        };
+
         // 创建发现器，传入密钥对（如果有）
         let discovery_result = ManagedDiscovery::new(
             node_manager.clone(),
@@ -217,6 +277,7 @@ pub fn internal_init(device_name: String, identity_path: String) -> Result<(), S
             health_config,
             user_info,
             identity,
+            network_key,
         ).await;
 
         let mut discovery = match discovery_result {
@@ -228,11 +289,32 @@ pub fn internal_init(device_name: String, identity_path: String) -> Result<(), S
 
         let local_peer_id = discovery.local_peer_id().to_string();
 
+        // 打开聊天记录存储（与密钥文件放在同一目录，未指定路径时仅保存在内存中）
+        let chat_store = open_chat_store(&identity_path).await;
+
         // 启用聊天功能
-        if let Err(e) = discovery.enable_chat().await {
+        if let Err(e) = discovery.enable_chat_with_store(chat_store.clone()).await {
             tracing::error!("Failed to enable chat: {:?}", e);
         }
 
+        // 头像缓存与密钥文件放在同一目录
+        let avatar_dir = (!identity_path.is_empty()).then(|| {
+            std::path::Path::new(&identity_path)
+                .parent()
+                .map(|p| p.join("avatars"))
+                .unwrap_or_else(|| std::path::PathBuf::from("avatars"))
+        });
+        discovery.set_avatar_cache(open_avatar_cache(avatar_dir.as_deref()));
+
+        // 静态节点列表与密钥文件放在同一目录，启动时自动拨号
+        let static_peers_path = (!identity_path.is_empty()).then(|| {
+            std::path::Path::new(&identity_path)
+                .parent()
+                .map(|p| p.join("static_peers.json"))
+                .unwrap_or_else(|| std::path::PathBuf::from("static_peers.json"))
+        });
+        discovery.set_static_peer_store(open_static_peer_store(static_peers_path.as_deref()));
+
         // 创建命令通道
         let (command_tx, command_rx) = tokio::sync::mpsc::unbounded_channel();
 
@@ -245,21 +327,22 @@ pub fn internal_init(device_name: String, identity_path: String) -> Result<(), S
             local_peer_id: local_peer_id.clone(),
             device_name: device_name.clone(),
             identity: identity_for_instance, // 保存 identity 以保持 Peer ID 稳定
+            chat_store,
+            network_key,
+            avatar_dir,
+            static_peers_path,
             command_tx,
             discovery_thread: None,
         };
 
         // 保存到全局变量
-        unsafe {
-            DISCOVERY_RESOURCES = Some(GlobalDiscoveryResources {
-                discovery: Some(discovery),
-                chat_event_rx,
-                command_rx: Some(command_rx),
-                event_tx: None, // 不使用旧的 event_tx
-            });
+        *DISCOVERY_RESOURCES.lock().unwrap() = Some(GlobalDiscoveryResources {
+            discovery: Some(discovery),
+            chat_event_rx,
+            command_rx: Some(command_rx),
+        });
 
-            P2P_INSTANCE = Some(Arc::new(Mutex::new(instance)));
-        }
+        *P2P_INSTANCE.lock().unwrap() = Some(Arc::new(Mutex::new(instance)));
 
         tracing::info!("✓ P2P 初始化成功");
         tracing::info!("  设备名称: {}", device_name);
@@ -277,243 +360,568 @@ pub fn internal_init(device_name: String, identity_path: String) -> Result<(), S
     result
 }
 
+/// 打开聊天记录存储
+///
+/// 指定了密钥文件路径时，聊天记录保存在同目录下的 `chat_history` 中；否则使用内存存储。
+async fn open_chat_store(identity_path: &str) -> Arc<dyn ChatStore> {
+    if identity_path.is_empty() {
+        return Arc::new(MemoryChatStore::new());
+    }
+
+    let dir = std::path::Path::new(identity_path)
+        .parent()
+        .map(|p| p.join("chat_history"))
+        .unwrap_or_else(|| std::path::PathBuf::from("chat_history"));
+    match FileChatStore::open(&dir).await {
+        Ok(store) => {
+            tracing::info!("聊天记录目录: {}", dir.display());
+            Arc::new(store)
+        }
+        Err(e) => {
+            tracing::warn!("打开聊天记录存储失败，将仅保存在内存中: {}", e);
+            Arc::new(MemoryChatStore::new())
+        }
+    }
+}
+
+/// 打开静态节点列表，未指定路径或打开失败时使用内存列表
+fn open_static_peer_store(path: Option<&std::path::Path>) -> StaticPeerStore {
+    let Some(path) = path else {
+        return StaticPeerStore::in_memory();
+    };
+    match StaticPeerStore::open(path) {
+        Ok(store) => {
+            tracing::info!("静态节点列表: {}（{} 个节点）", path.display(), store.addresses().len());
+            store
+        }
+        Err(e) => {
+            tracing::warn!("打开静态节点列表失败，将仅保存在内存中: {}", e);
+            StaticPeerStore::in_memory()
+        }
+    }
+}
+
+/// 打开头像缓存，未指定目录或打开失败时使用内存缓存
+fn open_avatar_cache(dir: Option<&std::path::Path>) -> AvatarCache {
+    let Some(dir) = dir else {
+        return AvatarCache::in_memory();
+    };
+    match AvatarCache::open(dir) {
+        Ok(cache) => {
+            tracing::info!("头像缓存目录: {}", dir.display());
+            cache
+        }
+        Err(e) => {
+            tracing::warn!("打开头像缓存失败，将仅保存在内存中: {}", e);
+            AvatarCache::in_memory()
+        }
+    }
+}
+
 /// 内部启动函数（供 FRB 调用）
 pub fn internal_start() -> Result<(), String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = p2p_instance().ok_or("Not initialized")?;
 
-        let runtime = RUNTIME.as_ref().ok_or("No runtime")?;
-
-        // 启动 discovery 事件循环
-        if let Some(resources) = DISCOVERY_RESOURCES.as_mut() {
-            let discovery = resources.discovery.take();
-            let chat_event_rx = resources.chat_event_rx.take();
-            let command_rx = resources.command_rx.take();
-
-            if let (Some(discovery), Some(chat_event_rx), Some(command_rx)) = (discovery, chat_event_rx, command_rx) {
-                // 启动 discovery 线程
-                let handle = std::thread::spawn(move || {
-                    runtime.block_on(async move {
-                        tracing::info!("FRB Discovery 线程启动");
-                        send_log_to_flutter("INFO", "ffi", "Discovery 线程启动".to_string());
-                        let mut discovery = discovery;
-                        let mut command_rx = command_rx;
-                        let mut chat_event_rx = chat_event_rx;
-
-                        loop {
-                            tokio::select! {
-                                // 处理 discovery 事件
-                                result = discovery.run() => {
-                                    match result {
-                                        Ok(event) => {
-                                            // 更新最后事件时间（任何 discovery 事件都算）
-                                            update_last_event_time();
-
-                                            use mdns::managed_discovery::DiscoveryEvent;
-                                            match event {
-                                                DiscoveryEvent::Discovered(peer_id, addr) => {
-                                                    send_log_to_flutter(
-                                                        "INFO",
-                                                        "discovery",
-                                                        format!("发现节点: {} @ {}", peer_id, addr)
-                                                    );
-                                                    let event = bridge::P2PEvent {
-                                                        event_type: 1,
-                                                        data: format!(r#"{{"peer_id":"{}","addr":"{}"}}"#, peer_id, addr),
-                                                    };
-                                                    // 同时发送到 Stream 和队列（兼容模式）
-                                                    send_event_to_stream(event.clone());
-                                                    let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
-                                                    queue.push(event);
-                                                }
-                                                DiscoveryEvent::Verified(peer_id) => {
-                                                    send_log_to_flutter(
-                                                        "INFO",
-                                                        "discovery",
-                                                        format!("验证节点: {}", peer_id)
-                                                    );
-                                                    let display_name = peer_id.to_string();
-                                                    let event = bridge::P2PEvent {
-                                                        event_type: 3,
-                                                        data: format!(r#"{{"peer_id":"{}","display_name":"{}"}}"#, peer_id, display_name),
-                                                    };
-                                                    // 同时发送到 Stream 和队列（兼容模式）
-                                                    send_event_to_stream(event.clone());
-                                                    let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
-                                                    queue.push(event);
-                                                }
-                                                DiscoveryEvent::NodeOffline(peer_id) => {
-                                                    send_log_to_flutter(
-                                                        "WARN",
-                                                        "discovery",
-                                                        format!("节点离线: {}", peer_id)
-                                                    );
-                                                    let event = bridge::P2PEvent {
-                                                        event_type: 4,
-                                                        data: format!(r#"{{"peer_id":"{}"}}"#, peer_id),
-                                                    };
-                                                    // 同时发送到 Stream 和队列（兼容模式）
-                                                    send_event_to_stream(event.clone());
-                                                    let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
-                                                    queue.push(event);
-
-                                                    // 从用户信息缓存中移除
-                                                    if let Some(cache) = GLOBAL_USER_INFO.lock().ok() {
-                                                        let mut cache = cache.write().unwrap();
-                                                        cache.remove(&peer_id.to_string());
-                                                    }
-                                                }
-                                                DiscoveryEvent::UserInfoReceived(peer_id, user_info) => {
-                                                    // 更新全局用户信息缓存
-                                                    if let Some(cache) = GLOBAL_USER_INFO.lock().ok() {
-                                                        let mut cache = cache.write().unwrap();
-                                                        cache.insert(peer_id.to_string(), user_info.clone());
-                                                    }
-
-                                                    // 发送用户信息事件到 Flutter
-                                                    let event = bridge::P2PEvent {
-                                                        event_type: 5, // UserInfoReceived
-                                                        data: format!(r#"{{"peer_id":"{}","device_name":"{}","nickname":"{}","status":"{}","avatar_url":"{}"}}"#,
-                                                            peer_id,
-                                                            user_info.device_name,
-                                                            user_info.nickname.as_ref().unwrap_or(&String::new()),
-                                                            user_info.status.as_ref().unwrap_or(&String::new()),
-                                                            user_info.avatar_url.as_ref().unwrap_or(&String::new()),
-                                                        ),
-                                                    };
-                                                    // 同时发送到 Stream 和队列（兼容模式）
-                                                    send_event_to_stream(event.clone());
-                                                    let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
-                                                    queue.push(event);
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                        Err(e) => {
-                                            let error_msg = format!("Discovery error: {:?}", e);
-                                            tracing::error!("{}", error_msg);
-                                            send_log_to_flutter("ERROR", "discovery", error_msg);
-                                            break;
-                                        }
-                                    }
-                                }
+    let runtime = get_runtime().ok_or("No runtime")?;
 
-                                // 处理命令
-                                Some(command) = command_rx.recv() => {
-                                    match command {
-                                        P2PCommand::SendMessage { target_peer_id, message, response_tx } => {
-                                            let peer_id: libp2p::PeerId = match target_peer_id.parse() {
-                                                Ok(id) => id,
-                                                Err(e) => {
-                                                    let _ = response_tx.send(Err(format!("Invalid peer_id: {:?}", e)));
-                                                    continue;
-                                                }
-                                            };
+    // 启动 discovery 事件循环
+    if let Some(resources) = DISCOVERY_RESOURCES.lock().unwrap().as_mut() {
+        let discovery = resources.discovery.take();
+        let chat_event_rx = resources.chat_event_rx.take();
+        let command_rx = resources.command_rx.take();
 
-                                            let chat_msg = mdns::ChatMessage::text(message);
-                                            let result = discovery.send_message(peer_id, chat_msg).await;
-                                            let _ = response_tx.send(result.map(|_| "OK".to_string()).map_err(|e| format!("{:?}", e)));
-                                        }
-                                        P2PCommand::BroadcastMessage { target_peer_ids, message, response_tx } => {
-                                            let mut peer_ids = Vec::new();
-                                            let mut parse_error = None;
-
-                                            for target in &target_peer_ids {
-                                                match target.parse::<libp2p::PeerId>() {
-                                                    Ok(id) => peer_ids.push(id),
-                                                    Err(e) => {
-                                                        parse_error = Some(format!("Invalid peer_id: {:?} - {:?}", target, e));
-                                                        break;
-                                                    }
+        if let (Some(discovery), Some(chat_event_rx), Some(command_rx)) = (discovery, chat_event_rx, command_rx) {
+            // 启动 discovery 线程
+            let handle = std::thread::spawn(move || {
+                runtime.block_on(async move {
+                    tracing::info!("FRB Discovery 线程启动");
+                    send_log_to_flutter("INFO", "ffi", "Discovery 线程启动".to_string());
+                    let mut discovery = discovery;
+                    let mut command_rx = command_rx;
+                    let mut chat_event_rx = chat_event_rx;
+
+                    loop {
+                        tokio::select! {
+                            // 处理 discovery 事件
+                            result = discovery.run() => {
+                                match result {
+                                    Ok(event) => {
+                                        // 更新最后事件时间（任何 discovery 事件都算）
+                                        update_last_event_time();
+
+                                        use mdns::managed_discovery::DiscoveryEvent;
+                                        match event {
+                                            DiscoveryEvent::Discovered(peer_id, addr) => {
+                                                send_log_to_flutter(
+                                                    "INFO",
+                                                    "discovery",
+                                                    format!("发现节点: {} @ {}", peer_id, addr)
+                                                );
+                                                let event = bridge::P2PEvent {
+                                                    event_type: 1,
+                                                    data: format!(r#"{{"peer_id":"{}","addr":"{}"}}"#, peer_id, addr),
+                                                };
+                                                // 同时发送到 Stream 和队列（兼容模式）
+                                                send_event_to_stream(event.clone());
+                                                let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                                queue.push(event);
+                                            }
+                                            DiscoveryEvent::Verified(peer_id) => {
+                                                send_log_to_flutter(
+                                                    "INFO",
+                                                    "discovery",
+                                                    format!("验证节点: {}", peer_id)
+                                                );
+                                                let display_name = peer_id.to_string();
+                                                let transport = discovery.node_manager().get_node(&peer_id).await
+                                                    .and_then(|node| node.transport)
+                                                    .map(|t| t.as_str())
+                                                    .unwrap_or("");
+                                                let event = bridge::P2PEvent {
+                                                    event_type: 3,
+                                                    data: format!(r#"{{"peer_id":"{}","display_name":"{}","transport":"{}"}}"#, peer_id, display_name, transport),
+                                                };
+                                                // 同时发送到 Stream 和队列（兼容模式）
+                                                send_event_to_stream(event.clone());
+                                                let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                                queue.push(event);
+                                            }
+                                            DiscoveryEvent::NodeOffline(peer_id) => {
+                                                send_log_to_flutter(
+                                                    "WARN",
+                                                    "discovery",
+                                                    format!("节点离线: {}", peer_id)
+                                                );
+                                                let event = bridge::P2PEvent {
+                                                    event_type: 4,
+                                                    data: format!(r#"{{"peer_id":"{}"}}"#, peer_id),
+                                                };
+                                                // 同时发送到 Stream 和队列（兼容模式）
+                                                send_event_to_stream(event.clone());
+                                                let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                                queue.push(event);
+
+                                                // 从用户信息缓存中移除
+                                                if let Ok(cache) = GLOBAL_USER_INFO.lock() {
+                                                    let mut cache = cache.write().unwrap();
+                                                    cache.remove(&peer_id.to_string());
                                                 }
                                             }
+                                            DiscoveryEvent::UserInfoReceived(peer_id, user_info) => {
+                                                // 更新全局用户信息缓存
+                                                if let Ok(cache) = GLOBAL_USER_INFO.lock() {
+                                                    let mut cache = cache.write().unwrap();
+                                                    cache.insert(peer_id.to_string(), user_info.clone());
+                                                }
 
-                                            if let Some(err) = parse_error {
-                                                let _ = response_tx.send(Err(err));
-                                            } else {
-                                                let chat_msg = mdns::ChatMessage::text(message);
-                                                let result = discovery.broadcast_message(peer_ids, chat_msg).await;
-                                                let _ = response_tx.send(result.map(|_| "OK".to_string()).map_err(|e| format!("{:?}", e)));
+                                                // 发送用户信息事件到 Flutter
+                                                let event = bridge::P2PEvent {
+                                                    event_type: 5, // UserInfoReceived
+                                                    data: format!(r#"{{"peer_id":"{}","device_name":"{}","nickname":"{}","status":"{}","avatar_url":"{}","avatar_hash":"{}","presence":"{}","last_active":{}}}"#,
+                                                        peer_id,
+                                                        user_info.device_name,
+                                                        user_info.nickname.as_ref().unwrap_or(&String::new()),
+                                                        user_info.status.as_ref().unwrap_or(&String::new()),
+                                                        user_info.avatar_url.as_ref().unwrap_or(&String::new()),
+                                                        user_info.avatar_hash.as_ref().unwrap_or(&String::new()),
+                                                        user_info.presence().as_str(),
+                                                        user_info.last_active.unwrap_or_default(),
+                                                    ),
+                                                };
+                                                // 同时发送到 Stream 和队列（兼容模式）
+                                                send_event_to_stream(event.clone());
+                                                let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                                queue.push(event);
                                             }
-                                        }
-                                        P2PCommand::Ping { response_tx } => {
-                                            // Ping 命令用于健康检查
-                                            let _ = response_tx.send(Ok(()));
-                                        }
-                                        P2PCommand::Stop => {
-                                            tracing::info!("Received stop command");
-                                            break;
-                                        }
-                                    }
-                                }
+                                            DiscoveryEvent::UserInfoUpdated(peer_id, user_info) => {
+                                                if let Ok(cache) = GLOBAL_USER_INFO.lock() {
+                                                    let mut cache = cache.write().unwrap();
+                                                    cache.insert(peer_id.to_string(), user_info.clone());
+                                                }
 
-                                // 处理 chat 事件
-                                Some(chat_event) = chat_event_rx.recv() => {
-                                    use mdns::chat::ChatEvent;
-                                    match chat_event {
-                                        ChatEvent::MessageReceived { from, message } => {
-                                            if let mdns::chat::ChatMessage::Text(text) = message {
                                                 let event = bridge::P2PEvent {
-                                                    event_type: 6,
-                                                    data: format!(r#"{{"from":"{}","content":"{}","timestamp":{}}}"#, from, text.content, text.timestamp),
+                                                    event_type: 18, // UserInfoUpdated
+                                                    data: serde_json::json!({
+                                                        "peer_id": peer_id.to_string(),
+                                                        "device_name": user_info.device_name,
+                                                        "nickname": user_info.nickname.clone().unwrap_or_default(),
+                                                        "status": user_info.status.clone().unwrap_or_default(),
+                                                        "avatar_url": user_info.avatar_url.clone().unwrap_or_default(),
+                                                        "avatar_hash": user_info.avatar_hash.clone().unwrap_or_default(),
+                                                        "presence": user_info.presence().as_str(),
+                                                        "last_active": user_info.last_active.unwrap_or_default(),
+                                                    }).to_string(),
+                                                };
+                                                // 同时发送到 Stream 和队列（兼容模式）
+                                                send_event_to_stream(event.clone());
+                                                let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                                queue.push(event);
+                                            }
+                                            DiscoveryEvent::AvatarReceived(peer_id, hash) => {
+                                                let path = discovery
+                                                    .avatar_cache()
+                                                    .path(&hash)
+                                                    .map(|p| p.to_string_lossy().into_owned())
+                                                    .unwrap_or_default();
+                                                let event = bridge::P2PEvent {
+                                                    event_type: 19, // AvatarReceived
+                                                    data: serde_json::json!({
+                                                        "peer_id": peer_id.to_string(),
+                                                        "hash": hash,
+                                                        "path": path,
+                                                    }).to_string(),
+                                                };
+                                                // 同时发送到 Stream 和队列（兼容模式）
+                                                send_event_to_stream(event.clone());
+                                                let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                                queue.push(event);
+                                            }
+                                            DiscoveryEvent::UserInfoRequestFailed(peer_id, reason) => {
+                                                send_log_to_flutter(
+                                                    "WARN",
+                                                    "discovery",
+                                                    format!("获取用户信息失败: {} - {}", peer_id, reason)
+                                                );
+                                                let event = bridge::P2PEvent {
+                                                    event_type: 13,
+                                                    data: serde_json::json!({
+                                                        "peer_id": peer_id.to_string(),
+                                                        "reason": reason,
+                                                    }).to_string(),
+                                                };
+                                                // 同时发送到 Stream 和队列（兼容模式）
+                                                send_event_to_stream(event.clone());
+                                                let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                                queue.push(event);
+                                            }
+                                            DiscoveryEvent::PeerBanned(peer_id, reason) => {
+                                                send_log_to_flutter(
+                                                    "WARN",
+                                                    "discovery",
+                                                    format!("节点因违反协议被封禁: {} - {}", peer_id, reason)
+                                                );
+                                                let event = bridge::P2PEvent {
+                                                    event_type: 17,
+                                                    data: serde_json::json!({
+                                                        "peer_id": peer_id.to_string(),
+                                                        "reason": reason,
+                                                    }).to_string(),
                                                 };
                                                 // 同时发送到 Stream 和队列（兼容模式）
                                                 send_event_to_stream(event.clone());
                                                 let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
                                                 queue.push(event);
                                             }
+                                            _ => {}
                                         }
-                                        ChatEvent::MessageSent { to, message_id } => {
-                                            let event = bridge::P2PEvent {
-                                                event_type: 7,
-                                                data: format!(r#"{{"to":"{}","message_id":"{}"}}"#, to, message_id),
-                                            };
-                                            // 同时发送到 Stream 和队列（兼容模式）
-                                            send_event_to_stream(event.clone());
-                                            let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
-                                            queue.push(event);
+                                    }
+                                    Err(e) => {
+                                        let error_msg = format!("Discovery error: {:?}", e);
+                                        tracing::error!("{}", error_msg);
+                                        send_log_to_flutter("ERROR", "discovery", error_msg);
+                                        break;
+                                    }
+                                }
+                            }
+
+                            // 处理命令
+                            Some(command) = command_rx.recv() => {
+                                match command {
+                                    P2PCommand::SendMessage { target_peer_id, message, response_tx } => {
+                                        let peer_id: libp2p::PeerId = match target_peer_id.parse() {
+                                            Ok(id) => id,
+                                            Err(e) => {
+                                                let _ = response_tx.send(Err(format!("Invalid peer_id: {:?}", e)));
+                                                continue;
+                                            }
+                                        };
+
+                                        let chat_msg = mdns::ChatMessage::text(message);
+                                        let result = discovery.send_message(peer_id, chat_msg).await;
+                                        let _ = response_tx.send(result.map(|_| "OK".to_string()).map_err(|e| format!("{:?}", e)));
+                                    }
+                                    P2PCommand::BroadcastMessage { target_peer_ids, message, response_tx } => {
+                                        let mut peer_ids = Vec::new();
+                                        let mut parse_error = None;
+
+                                        for target in &target_peer_ids {
+                                            match target.parse::<libp2p::PeerId>() {
+                                                Ok(id) => peer_ids.push(id),
+                                                Err(e) => {
+                                                    parse_error = Some(format!("Invalid peer_id: {:?} - {:?}", target, e));
+                                                    break;
+                                                }
+                                            }
                                         }
-                                        ChatEvent::PeerTyping { from, is_typing } => {
-                                            let event = bridge::P2PEvent {
-                                                event_type: 8,
-                                                data: format!(r#"{{"from":"{}","is_typing":{}}}"#, from, is_typing),
-                                            };
-                                            // 同时发送到 Stream 和队列（兼容模式）
-                                            send_event_to_stream(event.clone());
-                                            let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
-                                            queue.push(event);
+
+                                        if let Some(err) = parse_error {
+                                            let _ = response_tx.send(Err(err));
+                                        } else {
+                                            let chat_msg = mdns::ChatMessage::text(message);
+                                            let result = discovery.broadcast_message(peer_ids, chat_msg).await;
+                                            let _ = response_tx.send(result.map(|_| "OK".to_string()).map_err(|e| format!("{:?}", e)));
                                         }
-                                        _ => {}
                                     }
+                                    P2PCommand::MarkRead { peer_id, response_tx } => {
+                                        let result = match peer_id.parse::<libp2p::PeerId>() {
+                                            Ok(peer) => discovery
+                                                .mark_conversation_read(peer)
+                                                .await
+                                                .map_err(|e| format!("{:?}", e)),
+                                            Err(e) => Err(format!("Invalid peer_id: {:?}", e)),
+                                        };
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::CreateRoom { name, member_peer_ids, response_tx } => {
+                                        let result = match parse_peer_ids(&member_peer_ids) {
+                                            Ok(members) => discovery
+                                                .create_room(name, members)
+                                                .await
+                                                .map(|room| room.id)
+                                                .map_err(|e| format!("{:?}", e)),
+                                            Err(e) => Err(e),
+                                        };
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::InviteToRoom { room_id, member_peer_ids, response_tx } => {
+                                        let result = match parse_peer_ids(&member_peer_ids) {
+                                            Ok(members) => discovery
+                                                .invite_to_room(&room_id, members)
+                                                .await
+                                                .map(|_| ())
+                                                .map_err(|e| format!("{:?}", e)),
+                                            Err(e) => Err(e),
+                                        };
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::LeaveRoom { room_id, response_tx } => {
+                                        let result = discovery
+                                            .leave_room(&room_id)
+                                            .await
+                                            .map_err(|e| format!("{:?}", e));
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::SendRoomMessage { room_id, message, response_tx } => {
+                                        let chat_msg = mdns::ChatMessage::room_text(room_id.clone(), message);
+                                        let message_id = chat_msg.id().unwrap_or_default().to_string();
+                                        let result = discovery
+                                            .send_room_message(&room_id, chat_msg)
+                                            .await
+                                            .map(|_| message_id)
+                                            .map_err(|e| format!("{:?}", e));
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::SetAvatar { data, response_tx } => {
+                                        let result = discovery
+                                            .set_local_avatar(data)
+                                            .await
+                                            .map(Option::unwrap_or_default)
+                                            .map_err(|e| e.to_string());
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::SetPresence { presence, message, response_tx } => {
+                                        let result = discovery
+                                            .set_presence(presence, message)
+                                            .await
+                                            .map_err(|e| e.to_string());
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::AddStaticPeer { address, response_tx } => {
+                                        let result = match address.trim().parse() {
+                                            Ok(addr) => discovery.add_static_peer(addr).await.map_err(|e| e.to_string()),
+                                            Err(e) => Err(format!("地址无效: {}", e)),
+                                        };
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::RemoveStaticPeer { address, response_tx } => {
+                                        let result = match address.trim().parse() {
+                                            Ok(addr) => discovery.remove_static_peer(&addr).map_err(|e| e.to_string()),
+                                            Err(e) => Err(format!("地址无效: {}", e)),
+                                        };
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::GetStaticPeers { response_tx } => {
+                                        let peers = discovery.static_peers().iter().map(|addr| addr.to_string()).collect();
+                                        let _ = response_tx.send(Ok(peers));
+                                    }
+                                    P2PCommand::EnableRendezvous { server, team, response_tx } => {
+                                        let result = match server.trim().parse() {
+                                            Ok(addr) => discovery
+                                                .enable_rendezvous(RendezvousConfig::new(addr, &team))
+                                                .map_err(|e| e.to_string()),
+                                            Err(e) => Err(format!("地址无效: {}", e)),
+                                        };
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::SetUserInfo { nickname, status, response_tx } => {
+                                        let mut info = discovery.local_user_info().clone();
+                                        info.nickname = nickname;
+                                        info.status = status;
+                                        let result = discovery
+                                            .set_local_user_info(info)
+                                            .await
+                                            .map_err(|e| e.to_string());
+                                        let _ = response_tx.send(result);
+                                    }
+                                    P2PCommand::Ping { response_tx } => {
+                                        // Ping 命令用于健康检查
+                                        let _ = response_tx.send(Ok(()));
+                                    }
+                                    P2PCommand::Stop => {
+                                        tracing::info!("Received stop command");
+                                        break;
+                                    }
+                                }
+                            }
+
+                            // 处理 chat 事件
+                            Some(chat_event) = chat_event_rx.recv() => {
+                                use mdns::chat::ChatEvent;
+                                match chat_event {
+                                    ChatEvent::MessageReceived { from, message: mdns::chat::ChatMessage::Text(text) } => {
+                                        // 请勿打扰时标记为静默，由 Flutter 跳过通知
+                                        let event = bridge::P2PEvent {
+                                            event_type: 6,
+                                            data: format!(r#"{{"from":"{}","content":"{}","timestamp":{},"silent":{}}}"#, from, text.content, text.timestamp, discovery.is_do_not_disturb()),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    ChatEvent::MessageSent { to, message_id } => {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 7,
+                                            data: format!(r#"{{"to":"{}","message_id":"{}"}}"#, to, message_id),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    ChatEvent::PeerTyping { from, is_typing } => {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 8,
+                                            data: format!(r#"{{"from":"{}","is_typing":{}}}"#, from, is_typing),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    ChatEvent::MessageStatusChanged { peer_id, message_id, status } => {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 10,
+                                            data: format!(r#"{{"peer_id":"{}","message_id":"{}","status":"{}"}}"#, peer_id, message_id, status.as_str()),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    ChatEvent::MessageSendFailed { peer, message_id, reason } => {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 12,
+                                            data: serde_json::json!({
+                                                "peer_id": peer.to_string(),
+                                                "message_id": message_id,
+                                                "reason": reason,
+                                            }).to_string(),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    ChatEvent::DeliveryFailed { peer_id, message_id, reason } => {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 11,
+                                            data: serde_json::json!({
+                                                "peer_id": peer_id.to_string(),
+                                                "message_id": message_id,
+                                                "reason": reason,
+                                            }).to_string(),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    ChatEvent::RoomMessage { room_id, from, message: mdns::chat::ChatMessage::Text(text) } => {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 14,
+                                            data: serde_json::json!({
+                                                "room_id": room_id,
+                                                "from": from.to_string(),
+                                                "message_id": text.id,
+                                                "content": text.content,
+                                                "timestamp": text.timestamp,
+                                                "silent": discovery.is_do_not_disturb(),
+                                            }).to_string(),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    // 图片、文件附件以及编辑 / 删除 / 表情回应，携带完整消息 JSON
+                                    ChatEvent::MessageReceived { from, message } | ChatEvent::RoomMessage { from, message, .. }
+                                        if message.is_content() || message.is_update() =>
+                                    {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 16,
+                                            data: serde_json::json!({
+                                                "from": from.to_string(),
+                                                "room_id": message.room_id(),
+                                                "message": message,
+                                                "silent": discovery.is_do_not_disturb(),
+                                            }).to_string(),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    ChatEvent::RoomUpdated { room } => {
+                                        let event = bridge::P2PEvent {
+                                            event_type: 15,
+                                            data: serde_json::json!({
+                                                "room_id": room.id,
+                                                "name": room.name,
+                                                "creator": room.creator,
+                                                "members": room.members,
+                                            }).to_string(),
+                                        };
+                                        // 同时发送到 Stream 和队列（兼容模式）
+                                        send_event_to_stream(event.clone());
+                                        let mut queue = FRB_EVENT_QUEUE.lock().unwrap();
+                                        queue.push(event);
+                                    }
+                                    _ => {}
                                 }
                             }
                         }
+                    }
 
-                        tracing::info!("FRB Discovery 线程结束");
-                        send_log_to_flutter("WARN", "ffi", "Discovery 线程结束".to_string());
-                    });
+                    tracing::info!("FRB Discovery 线程结束");
+                    send_log_to_flutter("WARN", "ffi", "Discovery 线程结束".to_string());
                 });
+            });
 
-                // 保存线程句柄
-                if let Some(instance) = P2P_INSTANCE.as_mut() {
-                    let mut inst = instance.lock().unwrap();
-                    inst.discovery_thread = Some(handle);
-                }
-            } else {
-                return Err("Discovery resources not complete".to_string());
-            }
+            // 保存线程句柄
+            instance.lock().unwrap().discovery_thread = Some(handle);
         } else {
-            return Err("Discovery resources not available".to_string());
+            return Err("Discovery resources not complete".to_string());
         }
+    } else {
+        return Err("Discovery resources not available".to_string());
+    }
 
-        // 设置运行标志
-        P2P_IS_RUNNING = true;
-        send_log_to_flutter("INFO", "ffi", "P2P 服务已启动".to_string());
+    // 设置运行标志
+    P2P_IS_RUNNING.store(true, Ordering::SeqCst);
+    send_log_to_flutter("INFO", "ffi", "P2P 服务已启动".to_string());
 
-        Ok(())
-    }
+    Ok(())
 }
 
 /// 获取事件接收器（供 FRB stream 使用）
@@ -522,7 +930,7 @@ pub async fn get_event_receiver() -> Result<tokio::sync::mpsc::UnboundedReceiver
     *GLOBAL_EVENT_TX.lock().unwrap() = Some(tx);
 
     // 启动事件转发任务
-    if let Some(resources) = unsafe { DISCOVERY_RESOURCES.as_mut() } {
+    if let Some(resources) = DISCOVERY_RESOURCES.lock().unwrap().as_mut() {
         let discovery = resources.discovery.take();
         let chat_event_rx = resources.chat_event_rx.take();
         let command_rx = resources.command_rx.take();
@@ -563,6 +971,24 @@ pub async fn get_event_receiver() -> Result<tokio::sync::mpsc::UnboundedReceiver
                                                     data: format!(r#"{{"peer_id":"{}"}}"#, peer_id),
                                                 });
                                             }
+                                            DiscoveryEvent::UserInfoRequestFailed(peer_id, reason) => {
+                                                let _ = tx.send(bridge::P2PEvent {
+                                                    event_type: 13,
+                                                    data: serde_json::json!({
+                                                        "peer_id": peer_id.to_string(),
+                                                        "reason": reason,
+                                                    }).to_string(),
+                                                });
+                                            }
+                                            DiscoveryEvent::PeerBanned(peer_id, reason) => {
+                                                let _ = tx.send(bridge::P2PEvent {
+                                                    event_type: 17,
+                                                    data: serde_json::json!({
+                                                        "peer_id": peer_id.to_string(),
+                                                        "reason": reason,
+                                                    }).to_string(),
+                                                });
+                                            }
                                             _ => {}
                                         }
                                     }
@@ -601,6 +1027,15 @@ pub async fn get_event_receiver() -> Result<tokio::sync::mpsc::UnboundedReceiver
                                                 event_type: 6,
                                                 data: format!(r#"{{"from":"{}","content":"{}","timestamp":{}}}"#, from, text.content, text.timestamp),
                                             });
+                                        } else if message.is_content() || message.is_update() {
+                                            let _ = tx.send(bridge::P2PEvent {
+                                                event_type: 16,
+                                                data: serde_json::json!({
+                                                    "from": from.to_string(),
+                                                    "room_id": message.room_id(),
+                                                    "message": message,
+                                                }).to_string(),
+                                            });
                                         }
                                     }
                                     ChatEvent::MessageSent { to, message_id } => {
@@ -615,6 +1050,32 @@ pub async fn get_event_receiver() -> Result<tokio::sync::mpsc::UnboundedReceiver
                                             data: format!(r#"{{"from":"{}","is_typing":{}}}"#, from, is_typing),
                                         });
                                     }
+                                    ChatEvent::MessageStatusChanged { peer_id, message_id, status } => {
+                                        let _ = tx.send(bridge::P2PEvent {
+                                            event_type: 10,
+                                            data: format!(r#"{{"peer_id":"{}","message_id":"{}","status":"{}"}}"#, peer_id, message_id, status.as_str()),
+                                        });
+                                    }
+                                    ChatEvent::MessageSendFailed { peer, message_id, reason } => {
+                                        let _ = tx.send(bridge::P2PEvent {
+                                            event_type: 12,
+                                            data: serde_json::json!({
+                                                "peer_id": peer.to_string(),
+                                                "message_id": message_id,
+                                                "reason": reason,
+                                            }).to_string(),
+                                        });
+                                    }
+                                    ChatEvent::DeliveryFailed { peer_id, message_id, reason } => {
+                                        let _ = tx.send(bridge::P2PEvent {
+                                            event_type: 11,
+                                            data: serde_json::json!({
+                                                "peer_id": peer_id.to_string(),
+                                                "message_id": message_id,
+                                                "reason": reason,
+                                            }).to_string(),
+                                        });
+                                    }
                                     _ => {}
                                 }
                             }
@@ -630,39 +1091,29 @@ pub async fn get_event_receiver() -> Result<tokio::sync::mpsc::UnboundedReceiver
 
 /// 内部停止函数
 pub fn internal_stop() -> Result<(), String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = P2P_INSTANCE.lock().unwrap().take().ok_or("Not initialized")?;
 
-        // 发送停止命令
-        if let Some(instance) = P2P_INSTANCE.as_ref() {
-            let inst = instance.lock().unwrap();
-            let _ = inst.command_tx.send(P2PCommand::Stop);
-        }
+    // 发送停止命令
+    let _ = instance.lock().unwrap().command_tx.send(P2PCommand::Stop);
 
-        // 清除运行标志
-        P2P_IS_RUNNING = false;
-        send_log_to_flutter("INFO", "ffi", "P2P 服务已停止".to_string());
+    // 清除运行标志
+    P2P_IS_RUNNING.store(false, Ordering::SeqCst);
+    send_log_to_flutter("INFO", "ffi", "P2P 服务已停止".to_string());
 
-        P2P_INSTANCE = None;
-        Ok(())
-    }
+    Ok(())
 }
 
 /// 内部清理函数
 pub fn internal_cleanup() {
-    unsafe {
-        P2P_INSTANCE = None;
-        P2P_IS_RUNNING = false;
-        RUNTIME = None;
-        DISCOVERY_RESOURCES = None;
-
-        // 清空用户信息缓存
-        if let Some(cache) = GLOBAL_USER_INFO.lock().ok() {
-            let mut cache = cache.write().unwrap();
-            cache.clear();
-        }
+    *P2P_INSTANCE.lock().unwrap() = None;
+    P2P_IS_RUNNING.store(false, Ordering::SeqCst);
+    *RUNTIME.lock().unwrap() = None;
+    *DISCOVERY_RESOURCES.lock().unwrap() = None;
+
+    // 清空用户信息缓存
+    if let Ok(cache) = GLOBAL_USER_INFO.lock() {
+        let mut cache = cache.write().unwrap();
+        cache.clear();
     }
 
     // 清空全局事件通道
@@ -682,12 +1133,12 @@ pub fn internal_cleanup() {
 
 /// 检查 P2P 是否已初始化
 pub fn internal_is_initialized() -> bool {
-    unsafe { P2P_INSTANCE.is_some() }
+    P2P_INSTANCE.lock().unwrap().is_some()
 }
 
 /// 检查 P2P 服务是否正在运行
 pub fn internal_is_running() -> bool {
-    unsafe { P2P_IS_RUNNING }
+    P2P_IS_RUNNING.load(Ordering::SeqCst)
 }
 
 /// 检查 discovery 线程是否真的活着
@@ -698,71 +1149,68 @@ pub fn internal_is_running() -> bool {
 pub fn internal_is_discovery_thread_alive() -> bool {
     send_log_to_flutter("INFO", "ffi", "开始 Discovery 健康检查...".to_string());
 
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            send_log_to_flutter("WARN", "ffi", "P2P 实例不存在，无法检查健康状态".to_string());
-            return false;
-        }
+    let Some(instance) = p2p_instance() else {
+        send_log_to_flutter("WARN", "ffi", "P2P 实例不存在，无法检查健康状态".to_string());
+        return false;
+    };
 
-        let instance = P2P_INSTANCE.as_ref().unwrap();
-        let inst = instance.lock().unwrap();
+    let inst = instance.lock().unwrap();
 
-        // 发送 Ping 命令
-        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-        let ping_command = P2PCommand::Ping { response_tx };
+    // 发送 Ping 命令
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    let ping_command = P2PCommand::Ping { response_tx };
 
-        if let Err(_) = inst.command_tx.send(ping_command) {
-            send_log_to_flutter("ERROR", "ffi", "Ping 发送失败，线程已死".to_string());
-            return false;
-        }
-        drop(inst);
-
-        let runtime = RUNTIME.as_ref();
-        if let Some(rt) = runtime {
-            let ping_result = rt.block_on(async {
-                tokio::time::timeout(
-                    std::time::Duration::from_millis(100),
-                    response_rx
-                ).await
-            });
+    if inst.command_tx.send(ping_command).is_err() {
+        send_log_to_flutter("ERROR", "ffi", "Ping 发送失败，线程已死".to_string());
+        return false;
+    }
+    drop(inst);
+
+    let runtime = get_runtime();
+    if let Some(rt) = runtime {
+        let ping_result = rt.block_on(async {
+            tokio::time::timeout(
+                std::time::Duration::from_millis(100),
+                response_rx
+            ).await
+        });
 
-            let ping_alive = ping_result.is_ok() && ping_result.unwrap().is_ok();
-
-            // 检查最后事件时间
-            let event_alive = if let Some(elapsed) = time_since_last_event() {
-                // 如果超过 10 秒没有事件，认为 discovery 已停止工作
-                // 降低阈值以更快检测到从后台恢复时的 mDNS 问题
-                let is_alive = elapsed.as_secs() < 10;
-                if !is_alive {
-                    send_log_to_flutter(
-                        "WARN",
-                        "ffi",
-                        format!("距离上次事件已 {:.1} 秒，超过阈值 10 秒，discovery 可能已停止", elapsed.as_secs_f64())
-                    );
-                }
+        let ping_alive = ping_result.is_ok() && ping_result.unwrap().is_ok();
+
+        // 检查最后事件时间
+        let event_alive = if let Some(elapsed) = time_since_last_event() {
+            // 如果超过 10 秒没有事件，认为 discovery 已停止工作
+            // 降低阈值以更快检测到从后台恢复时的 mDNS 问题
+            let is_alive = elapsed.as_secs() < 10;
+            if !is_alive {
                 send_log_to_flutter(
-                    "INFO",
+                    "WARN",
                     "ffi",
-                    format!("距离上次事件 {:.1} 秒", elapsed.as_secs_f64())
+                    format!("距离上次事件已 {:.1} 秒，超过阈值 10 秒，discovery 可能已停止", elapsed.as_secs_f64())
                 );
-                is_alive
-            } else {
-                send_log_to_flutter("INFO", "ffi", "尚未收到过 discovery 事件".to_string());
-                true // 如果还没有收到过事件，暂时认为健康
-            };
-
-            let alive = ping_alive && event_alive;
+            }
             send_log_to_flutter(
                 "INFO",
                 "ffi",
-                format!("Discovery 健康检查结果: Ping={} 事件时间={} => {}", ping_alive, event_alive, alive)
+                format!("距离上次事件 {:.1} 秒", elapsed.as_secs_f64())
             );
-
-            alive
+            is_alive
         } else {
-            send_log_to_flutter("ERROR", "ffi", "运行时不存在".to_string());
-            false
-        }
+            send_log_to_flutter("INFO", "ffi", "尚未收到过 discovery 事件".to_string());
+            true // 如果还没有收到过事件，暂时认为健康
+        };
+
+        let alive = ping_alive && event_alive;
+        send_log_to_flutter(
+            "INFO",
+            "ffi",
+            format!("Discovery 健康检查结果: Ping={} 事件时间={} => {}", ping_alive, event_alive, alive)
+        );
+
+        alive
+    } else {
+        send_log_to_flutter("ERROR", "ffi", "运行时不存在".to_string());
+        false
     }
 }
 
@@ -773,123 +1221,114 @@ pub fn internal_is_discovery_thread_alive() -> bool {
 pub fn internal_restart_discovery() -> Result<(), String> {
     send_log_to_flutter("INFO", "ffi", "开始重启 Discovery 服务".to_string());
 
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = p2p_instance().ok_or("Not initialized")?;
 
-        let runtime = RUNTIME.as_ref().ok_or("No runtime")?;
+    let runtime = get_runtime().ok_or("No runtime")?;
 
-        // 发送停止命令给 discovery 线程
-        if let Some(instance) = P2P_INSTANCE.as_ref() {
-            let inst = instance.lock().unwrap();
-            let _ = inst.command_tx.send(P2PCommand::Stop);
-            drop(inst);
-        }
+    // 发送停止命令给 discovery 线程
+    let _ = instance.lock().unwrap().command_tx.send(P2PCommand::Stop);
 
-        // 清除运行标志（但不删除 P2P_INSTANCE）
-        P2P_IS_RUNNING = false;
-        send_log_to_flutter("INFO", "ffi", "已停止旧 Discovery 服务".to_string());
+    // 清除运行标志（但不删除 P2P_INSTANCE）
+    P2P_IS_RUNNING.store(false, Ordering::SeqCst);
+    send_log_to_flutter("INFO", "ffi", "已停止旧 Discovery 服务".to_string());
 
-        // 等待一小段时间确保线程退出
-        std::thread::sleep(std::time::Duration::from_millis(300));
+    // 等待一小段时间确保线程退出
+    std::thread::sleep(std::time::Duration::from_millis(300));
 
-        // 记录 identity 使用情况
-        let has_identity = {
-            let inst = P2P_INSTANCE.as_ref().unwrap().lock().unwrap();
-            inst.identity.is_some()
-        };
+    // 记录 identity 使用情况
+    let has_identity = {
+        let inst = instance.lock().unwrap();
+        inst.identity.is_some()
+    };
 
-        if has_identity {
-            send_log_to_flutter("INFO", "ffi", "重启时使用保存的密钥对，Peer ID 将保持不变".to_string());
-        } else {
-            send_log_to_flutter("INFO", "ffi", "重启时生成新密钥对，Peer ID 将变化".to_string());
-        }
+    if has_identity {
+        send_log_to_flutter("INFO", "ffi", "重启时使用保存的密钥对，Peer ID 将保持不变".to_string());
+    } else {
+        send_log_to_flutter("INFO", "ffi", "重启时生成新密钥对，Peer ID 将变化".to_string());
+    }
 
-        // 重新创建 discovery 资源
-        let (node_manager, device_name, local_peer_id, identity) = {
-            let inst = P2P_INSTANCE.as_ref().unwrap().lock().unwrap();
-            (
-                inst.node_manager.clone(),
-                inst.device_name.clone(),
-                inst.local_peer_id.clone(),
-                inst.identity.clone(), // 获取保存的 identity
-            )
+    // 重新创建 discovery 资源
+    let (node_manager, device_name, identity, chat_store, network_key, avatar_dir, static_peers_path) = {
+        let inst = instance.lock().unwrap();
+        (
+            inst.node_manager.clone(),
+            inst.device_name.clone(),
+            inst.identity.clone(), // 获取保存的 identity
+            inst.chat_store.clone(),
+            inst.network_key,
+            inst.avatar_dir.clone(),
+            inst.static_peers_path.clone(),
+        )
+    };
+
+    // 在运行时中创建新的 discovery
+    let result = runtime.block_on(async {
+        // 创建用户信息
+        let user_info = UserInfo::new(device_name.clone())
+            .with_presence(mdns::Presence::Online);
+
+        // 创建健康检查配置
+        let health_config = HealthCheckConfig {
+            heartbeat_interval: std::time::Duration::from_secs(10),
+            max_failures: 3,
         };
 
-        // 在运行时中创建新的 discovery
-        let result = runtime.block_on(async {
-            // 创建用户信息
-            let user_info = UserInfo::new(device_name.clone())
-                .with_status("在线".to_string());
-
-            // 创建健康检查配置
-            let health_config = HealthCheckConfig {
-                heartbeat_interval: std::time::Duration::from_secs(10),
-                max_failures: 3,
-            };
-
-            // 解析监听地址
-            let listen_addresses = vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()];
-
-            // 创建新的 discovery，使用保存的 identity 以保持 Peer ID 稳定
-            let discovery_result = ManagedDiscovery::new(
-                node_manager.clone(),
-                listen_addresses,
-                health_config,
-                user_info,
-                identity, // 使用保存的密钥对
-            ).await;
-
-            match discovery_result {
-                Ok(mut discovery) => {
-                    // 启用聊天功能
-                    if let Err(e) = discovery.enable_chat().await {
-                        tracing::error!("Failed to enable chat: {:?}", e);
-                    }
+        // 解析监听地址
+        let listen_addresses = vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()];
 
-                    // 获取 chat 事件接收器
-                    let chat_event_rx = discovery.take_chat_events();
+        // 创建新的 discovery，使用保存的 identity 以保持 Peer ID 稳定
+        let discovery_result = ManagedDiscovery::new(
+            node_manager.clone(),
+            listen_addresses,
+            health_config,
+            user_info,
+            identity, // 使用保存的密钥对
+            network_key,
+        ).await;
 
-                    // 创建新的命令通道
-                    let (command_tx, command_rx) = tokio::sync::mpsc::unbounded_channel();
+        match discovery_result {
+            Ok(mut discovery) => {
+                // 启用聊天功能（复用原有的聊天记录存储）
+                if let Err(e) = discovery.enable_chat_with_store(chat_store).await {
+                    tracing::error!("Failed to enable chat: {:?}", e);
+                }
+                discovery.set_avatar_cache(open_avatar_cache(avatar_dir.as_deref()));
+                discovery.set_static_peer_store(open_static_peer_store(static_peers_path.as_deref()));
 
-                    // 创建新的事件通道
-                    let (event_tx, _) = tokio::sync::mpsc::unbounded_channel();
+                // 获取 chat 事件接收器
+                let chat_event_rx = discovery.take_chat_events();
 
-                    // 更新 P2P_INSTANCE 中的 command_tx
-                    if let Some(instance) = P2P_INSTANCE.as_ref() {
-                        let mut inst = instance.lock().unwrap();
-                        inst.command_tx = command_tx.clone();
-                    }
+                // 创建新的命令通道
+                let (command_tx, command_rx) = tokio::sync::mpsc::unbounded_channel();
 
-                    Ok((discovery, chat_event_rx, command_rx, event_tx, command_tx))
-                }
-                Err(e) => {
-                    Err(format!("Failed to create discovery: {:?}", e))
-                }
+                // 更新 P2P_INSTANCE 中的 command_tx
+                instance.lock().unwrap().command_tx = command_tx;
+
+                Ok((discovery, chat_event_rx, command_rx))
             }
-        });
+            Err(e) => {
+                Err(format!("Failed to create discovery: {:?}", e))
+            }
+        }
+    });
 
-        let (discovery, chat_event_rx, command_rx, event_tx, command_tx) = result?;
+    let (discovery, chat_event_rx, command_rx) = result?;
 
-        // 将新资源放入 DISCOVERY_RESOURCES
-        DISCOVERY_RESOURCES = Some(GlobalDiscoveryResources {
-            discovery: Some(discovery),
-            chat_event_rx,
-            command_rx: Some(command_rx),
-            event_tx: Some(event_tx),
-        });
+    // 将新资源放入 DISCOVERY_RESOURCES
+    *DISCOVERY_RESOURCES.lock().unwrap() = Some(GlobalDiscoveryResources {
+        discovery: Some(discovery),
+        chat_event_rx,
+        command_rx: Some(command_rx),
+    });
 
-        // 等待一小段时间
-        std::thread::sleep(std::time::Duration::from_millis(100));
+    // 等待一小段时间
+    std::thread::sleep(std::time::Duration::from_millis(100));
 
-        // 调用 internal_start 启动 discovery 线程
-        internal_start()?;
-        send_log_to_flutter("INFO", "ffi", "Discovery 服务重启成功".to_string());
+    // 调用 internal_start 启动 discovery 线程
+    internal_start()?;
+    send_log_to_flutter("INFO", "ffi", "Discovery 服务重启成功".to_string());
 
-        Ok(())
-    }
+    Ok(())
 }
 
 // ============================================================================
@@ -898,26 +1337,18 @@ pub fn internal_restart_discovery() -> Result<(), String> {
 
 /// 获取本地 Peer ID
 pub async fn internal_get_local_peer_id() -> Result<String, String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = p2p_instance().ok_or("Not initialized")?;
 
-        let instance = P2P_INSTANCE.as_ref().unwrap().lock().unwrap();
-        Ok(instance.local_peer_id.clone())
-    }
+    let instance = instance.lock().unwrap();
+    Ok(instance.local_peer_id.clone())
 }
 
 /// 获取设备名称
 pub async fn internal_get_device_name() -> Result<String, String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = p2p_instance().ok_or("Not initialized")?;
 
-        let instance = P2P_INSTANCE.as_ref().unwrap().lock().unwrap();
-        Ok(instance.device_name.clone())
-    }
+    let instance = instance.lock().unwrap();
+    Ok(instance.device_name.clone())
 }
 
 /// 获取已验证的节点列表
@@ -925,55 +1356,51 @@ pub async fn internal_get_device_name() -> Result<String, String> {
 /// 注意：这是一个同步函数，因为需要在内部使用 block_on
 /// 调用此函数需要 tokio::task::spawn_blocking
 pub fn internal_get_nodes_sync() -> Result<Vec<InternalNodeInfo>, String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = p2p_instance().ok_or("Not initialized")?;
 
-        let runtime = RUNTIME.as_ref().ok_or("No runtime")?;
-        let node_manager = {
-            let instance = P2P_INSTANCE.as_ref().unwrap().lock().unwrap();
-            instance.node_manager.clone()
-        };
+    let runtime = get_runtime().ok_or("No runtime")?;
+    let node_manager = {
+        let instance = instance.lock().unwrap();
+        instance.node_manager.clone()
+    };
 
-        // 在正确的运行时上执行异步操作
-        let nodes = runtime.block_on(async {
-            node_manager.list_nodes().await
-        });
+    // 在正确的运行时上执行异步操作
+    let nodes = runtime.block_on(async {
+        node_manager.list_nodes().await
+    });
 
-        // 同时从用户信息缓存中获取详细信息
-        let user_info_cache: HashMap<String, mdns::UserInfo> = GLOBAL_USER_INFO
-            .lock()
-            .unwrap()
-            .read()
-            .unwrap()
-            .clone();
-
-        Ok(nodes.into_iter().map(|node| {
-            let peer_id = node.peer_id.to_string();
-            if let Some(user_info) = user_info_cache.get(&peer_id) {
-                // 使用缓存的用户信息
-                InternalNodeInfo {
-                    peer_id: peer_id.clone(),
-                    display_name: user_info.display_name(),
-                    device_name: user_info.device_name.clone(),
-                    nickname: user_info.nickname.clone(),
-                    status: user_info.status.clone(),
-                    avatar_url: user_info.avatar_url.clone(),
-                }
-            } else {
-                // 使用基本信息
-                InternalNodeInfo {
-                    peer_id: peer_id.clone(),
-                    display_name: node.display_name(),
-                    device_name: node.name.unwrap_or_default(),
-                    nickname: None,
-                    status: None,
-                    avatar_url: None,
-                }
+    // 同时从用户信息缓存中获取详细信息
+    let user_info_cache: HashMap<String, mdns::UserInfo> = GLOBAL_USER_INFO
+        .lock()
+        .unwrap()
+        .read()
+        .unwrap()
+        .clone();
+
+    Ok(nodes.into_iter().map(|node| {
+        let peer_id = node.peer_id.to_string();
+        if let Some(user_info) = user_info_cache.get(&peer_id) {
+            // 使用缓存的用户信息
+            InternalNodeInfo {
+                peer_id: peer_id.clone(),
+                display_name: user_info.display_name(),
+                device_name: user_info.device_name.clone(),
+                nickname: user_info.nickname.clone(),
+                status: user_info.status.clone(),
+                avatar_url: user_info.avatar_url.clone(),
             }
-        }).collect())
-    }
+        } else {
+            // 使用基本信息
+            InternalNodeInfo {
+                peer_id: peer_id.clone(),
+                display_name: node.display_name(),
+                device_name: node.name.unwrap_or_default(),
+                nickname: None,
+                status: None,
+                avatar_url: None,
+            }
+        }
+    }).collect())
 }
 
 /// 获取已验证的节点列表（async 包装器）
@@ -987,82 +1414,277 @@ pub async fn internal_get_nodes() -> Result<Vec<InternalNodeInfo>, String> {
 
 /// 获取指定节点的用户信息
 pub async fn internal_get_user_info(peer_id: String) -> Result<Option<bridge::P2PBridgeNodeInfo>, String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    if !internal_is_initialized() {
+        return Err("Not initialized".to_string());
+    }
 
-        // 从用户信息缓存中获取
-        if let Some(cache) = GLOBAL_USER_INFO.lock().ok() {
-            let cache = cache.read().unwrap();
-            if let Some(user_info) = cache.get(&peer_id) {
-                return Ok(Some(bridge::P2PBridgeNodeInfo::from_peer_id_and_info(
-                    peer_id.clone(),
-                    user_info
-                )));
-            }
+    // 从用户信息缓存中获取
+    if let Ok(cache) = GLOBAL_USER_INFO.lock() {
+        let cache = cache.read().unwrap();
+        if let Some(user_info) = cache.get(&peer_id) {
+            return Ok(Some(bridge::P2PBridgeNodeInfo::from_peer_id_and_info(
+                peer_id.clone(),
+                user_info
+            )));
         }
-
-        Ok(None)
     }
+
+    Ok(None)
 }
 
 /// 获取所有节点的用户信息
 pub async fn internal_list_user_info() -> Result<Vec<bridge::P2PBridgeNodeInfo>, String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
-
-        // 从用户信息缓存中获取所有信息
-        if let Some(cache) = GLOBAL_USER_INFO.lock().ok() {
-            let cache = cache.read().unwrap();
-            let result = cache.iter().map(|(peer_id, user_info)| {
-                bridge::P2PBridgeNodeInfo::from_peer_id_and_info(
-                    peer_id.clone(),
-                    user_info
-                )
-            }).collect();
-            return Ok(result);
-        }
+    if !internal_is_initialized() {
+        return Err("Not initialized".to_string());
+    }
 
-        Ok(Vec::new())
+    // 从用户信息缓存中获取所有信息
+    if let Ok(cache) = GLOBAL_USER_INFO.lock() {
+        let cache = cache.read().unwrap();
+        let result = cache.iter().map(|(peer_id, user_info)| {
+            bridge::P2PBridgeNodeInfo::from_peer_id_and_info(
+                peer_id.clone(),
+                user_info
+            )
+        }).collect();
+        return Ok(result);
     }
+
+    Ok(Vec::new())
 }
 
 // ============================================================================
 // 内部消息函数
 // ============================================================================
 
+/// 分页查询与指定节点的聊天记录
+///
+/// `before_message_id` 为 `None` 时返回最近的 `limit` 条，结果按时间正序排列。
+pub fn internal_get_chat_history(
+    peer_id: String,
+    before_message_id: Option<String>,
+    limit: u32,
+) -> Result<Vec<bridge::P2PBridgeChatMessage>, String> {
+    let (runtime, chat_store, local_peer_id) = {
+        let runtime = get_runtime().ok_or("No runtime")?;
+        let instance = p2p_instance().ok_or("Not initialized")?;
+        let inst = instance.lock().map_err(|e| format!("Lock error: {:?}", e))?;
+        (runtime, inst.chat_store.clone(), inst.local_peer_id.clone())
+    };
+
+    let peer: libp2p::PeerId = peer_id
+        .parse()
+        .map_err(|e| format!("Invalid peer ID: {:?}", e))?;
+
+    let records = runtime
+        .block_on(chat_store.history_before(&peer, before_message_id.as_deref(), limit as usize))
+        .map_err(|e| e.to_string())?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| {
+            // 编辑和删除已由存储应用到目标消息上（内容为修改后的文字或“消息已删除”），
+            // 图片和文件附件以摘要文字返回
+            if !record.message.is_content() {
+                return None;
+            }
+            let from = if record.outgoing { local_peer_id.clone() } else { peer_id.clone() };
+            Some(bridge::P2PBridgeChatMessage {
+                message_id: record.message.id()?.to_string(),
+                peer_id: peer_id.clone(),
+                from,
+                content: record.message.preview(),
+                timestamp: record.message.timestamp()?,
+                outgoing: record.outgoing,
+            })
+        })
+        .collect())
+}
+
 /// 发送消息（同步版本）
 fn internal_send_message_sync(target_peer_id: String, message: String) -> Result<(), String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = p2p_instance().ok_or("Not initialized")?;
 
-        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-        let command = P2PCommand::SendMessage {
-            target_peer_id,
-            message,
-            response_tx,
-        };
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    let command = P2PCommand::SendMessage {
+        target_peer_id,
+        message,
+        response_tx,
+    };
 
-        let instance = P2P_INSTANCE.as_ref().unwrap().lock().unwrap();
-        if let Err(_) = instance.command_tx.send(command) {
-            return Err("Failed to send command".to_string());
-        }
-        drop(instance);
+    let instance = instance.lock().unwrap();
+    if instance.command_tx.send(command).is_err() {
+        return Err("Failed to send command".to_string());
+    }
+    drop(instance);
 
-        let runtime = RUNTIME.as_ref().ok_or("No runtime")?;
-        let result = runtime.block_on(async {
-            response_rx.await
-                .map_err(|e| format!("Response error: {:?}", e))
-                .and_then(|r| r)
-        });
+    let runtime = get_runtime().ok_or("No runtime")?;
+    let result = runtime.block_on(async {
+        response_rx.await
+            .map_err(|e| format!("Response error: {:?}", e))
+            .and_then(|r| r)
+    });
+
+    result.map(|_| ())
+}
+
+/// 将会话标记为已读（同步版本，发送已读回执）
+fn internal_mark_conversation_read_sync(peer_id: String) -> Result<(), String> {
+    let instance = p2p_instance().ok_or("Not initialized")?;
+
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    let command = P2PCommand::MarkRead {
+        peer_id,
+        response_tx,
+    };
+
+    let instance = instance.lock().unwrap();
+    if instance.command_tx.send(command).is_err() {
+        return Err("Failed to send command".to_string());
+    }
+    drop(instance);
+
+    let runtime = get_runtime().ok_or("No runtime")?;
+    runtime.block_on(async {
+        response_rx.await
+            .map_err(|e| format!("Response error: {:?}", e))
+            .and_then(|r| r)
+    })
+}
+
+/// 解析 Peer ID 列表
+fn parse_peer_ids(peer_ids: &[String]) -> Result<Vec<libp2p::PeerId>, String> {
+    peer_ids
+        .iter()
+        .map(|id| id.parse().map_err(|e| format!("Invalid peer_id: {:?} - {:?}", id, e)))
+        .collect()
+}
 
-        result.map(|_| ())
+/// 向 discovery 线程发送命令并等待结果（同步版本）
+fn run_command_sync<T>(
+    build: impl FnOnce(tokio::sync::oneshot::Sender<Result<T, String>>) -> P2PCommand,
+) -> Result<T, String> {
+    let instance = p2p_instance().ok_or("Not initialized")?;
+
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    let instance = instance.lock().unwrap();
+    if instance.command_tx.send(build(response_tx)).is_err() {
+        return Err("Failed to send command".to_string());
     }
+    drop(instance);
+
+    let runtime = get_runtime().ok_or("No runtime")?;
+    runtime.block_on(async {
+        response_rx.await
+            .map_err(|e| format!("Response error: {:?}", e))
+            .and_then(|r| r)
+    })
+}
+
+/// 创建聊天室（同步版本），返回聊天室 ID
+fn internal_create_room_sync(name: String, member_peer_ids: Vec<String>) -> Result<String, String> {
+    run_command_sync(|response_tx| P2PCommand::CreateRoom {
+        name,
+        member_peer_ids,
+        response_tx,
+    })
+}
+
+/// 修改本地用户信息（同步版本），空字符串表示不设置
+fn internal_set_user_info_sync(nickname: String, status: String) -> Result<(), String> {
+    let non_empty = |s: String| if s.trim().is_empty() { None } else { Some(s) };
+    run_command_sync(|response_tx| P2PCommand::SetUserInfo {
+        nickname: non_empty(nickname),
+        status: non_empty(status),
+        response_tx,
+    })
+}
+
+/// 设置在线状态（同步版本），空字符串表示没有状态说明
+fn internal_set_presence_sync(presence: String, message: String) -> Result<(), String> {
+    let presence = mdns::Presence::parse(&presence).ok_or_else(|| format!("未知的在线状态: {}", presence))?;
+    let message = (!message.trim().is_empty()).then_some(message);
+    run_command_sync(|response_tx| P2PCommand::SetPresence { presence, message, response_tx })
+}
+
+/// 设置本地头像（同步版本），空数据表示清除头像，返回头像哈希
+fn internal_set_avatar_sync(data: Vec<u8>) -> Result<String, String> {
+    let data = (!data.is_empty()).then_some(data);
+    run_command_sync(|response_tx| P2PCommand::SetAvatar { data, response_tx })
+}
+
+/// 手动添加节点地址（同步版本），已存在时返回 false
+fn internal_add_static_peer_sync(address: String) -> Result<bool, String> {
+    run_command_sync(|response_tx| P2PCommand::AddStaticPeer { address, response_tx })
+}
+
+/// 移除静态节点（同步版本），不存在时返回 false
+fn internal_remove_static_peer_sync(address: String) -> Result<bool, String> {
+    run_command_sync(|response_tx| P2PCommand::RemoveStaticPeer { address, response_tx })
+}
+
+/// 获取所有静态节点地址（同步版本）
+fn internal_get_static_peers_sync() -> Result<Vec<String>, String> {
+    run_command_sync(|response_tx| P2PCommand::GetStaticPeers { response_tx })
+}
+
+/// 启用会合点发现（同步版本）
+fn internal_enable_rendezvous_sync(server: String, team: String) -> Result<(), String> {
+    run_command_sync(|response_tx| P2PCommand::EnableRendezvous { server, team, response_tx })
+}
+
+/// 获取节点头像的缓存文件路径（同步版本），尚未获取或没有头像时返回 None
+fn internal_get_avatar_path_sync(peer_id: String) -> Result<Option<String>, String> {
+    let avatar_dir = {
+        let instance = p2p_instance().ok_or("Not initialized")?;
+        let inst = instance.lock().unwrap();
+        inst.avatar_dir.clone()
+    };
+    let Some(avatar_dir) = avatar_dir else {
+        return Ok(None);
+    };
+
+    let hash = GLOBAL_USER_INFO
+        .lock()
+        .ok()
+        .and_then(|cache| cache.read().unwrap().get(&peer_id).and_then(|info| info.avatar_hash.clone()));
+    let Some(hash) = hash else {
+        return Ok(None);
+    };
+
+    let cache = AvatarCache::open(&avatar_dir).map_err(|e| e.to_string())?;
+    Ok(cache.path(&hash).map(|p| p.to_string_lossy().into_owned()))
+}
+
+/// 获取节点的头像数据（同步版本）
+fn internal_get_avatar_sync(peer_id: String) -> Result<Option<Vec<u8>>, String> {
+    match internal_get_avatar_path_sync(peer_id)? {
+        Some(path) => std::fs::read(path).map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// 邀请成员加入聊天室（同步版本）
+fn internal_invite_to_room_sync(room_id: String, member_peer_ids: Vec<String>) -> Result<(), String> {
+    run_command_sync(|response_tx| P2PCommand::InviteToRoom {
+        room_id,
+        member_peer_ids,
+        response_tx,
+    })
+}
+
+/// 退出聊天室（同步版本）
+fn internal_leave_room_sync(room_id: String) -> Result<(), String> {
+    run_command_sync(|response_tx| P2PCommand::LeaveRoom { room_id, response_tx })
+}
+
+/// 在聊天室中发送消息（同步版本），返回消息 ID
+fn internal_send_room_message_sync(room_id: String, message: String) -> Result<String, String> {
+    run_command_sync(|response_tx| P2PCommand::SendRoomMessage {
+        room_id,
+        message,
+        response_tx,
+    })
 }
 
 /// 发送消息
@@ -1077,33 +1699,29 @@ pub async fn internal_send_message(target_peer_id: String, message: String) -> R
 
 /// 广播消息（同步版本）
 fn internal_broadcast_message_sync(target_peer_ids: Vec<String>, message: String) -> Result<(), String> {
-    unsafe {
-        if P2P_INSTANCE.is_none() {
-            return Err("Not initialized".to_string());
-        }
+    let instance = p2p_instance().ok_or("Not initialized")?;
 
-        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-        let command = P2PCommand::BroadcastMessage {
-            target_peer_ids,
-            message,
-            response_tx,
-        };
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    let command = P2PCommand::BroadcastMessage {
+        target_peer_ids,
+        message,
+        response_tx,
+    };
 
-        let instance = P2P_INSTANCE.as_ref().unwrap().lock().unwrap();
-        if let Err(_) = instance.command_tx.send(command) {
-            return Err("Failed to send command".to_string());
-        }
-        drop(instance);
+    let instance = instance.lock().unwrap();
+    if instance.command_tx.send(command).is_err() {
+        return Err("Failed to send command".to_string());
+    }
+    drop(instance);
 
-        let runtime = RUNTIME.as_ref().ok_or("No runtime")?;
-        let result = runtime.block_on(async {
-            response_rx.await
-                .map_err(|e| format!("Response error: {:?}", e))
-                .and_then(|r| r)
-        });
+    let runtime = get_runtime().ok_or("No runtime")?;
+    let result = runtime.block_on(async {
+        response_rx.await
+            .map_err(|e| format!("Response error: {:?}", e))
+            .and_then(|r| r)
+    });
 
-        result.map(|_| ())
-    }
+    result.map(|_| ())
 }
 
 /// 广播消息
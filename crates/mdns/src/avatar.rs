@@ -0,0 +1,357 @@
+//! 头像交换协议
+//!
+//! 局域网通常无法访问互联网，`avatar_url` 指向的图片往往加载不出来。
+//! 用户信息改为只携带头像内容的 SHA-256（`avatar_hash`），节点在本地缓存未命中时
+//! 才通过 `/localp2p/avatar/1.0.0` 向对方获取图片数据：
+//!
+//! - 请求为带长度前缀的 JSON（只包含哈希）
+//! - 响应为一帧原始字节，空帧表示对方没有该头像
+//! - 头像最大 256KB，只接受 PNG、JPEG、GIF 和 WebP，收到后校验哈希再写入缓存
+
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use libp2p::request_response;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::codec::{read_frame, read_message, with_deadline, write_frame, write_message, Format, ProtocolLimits};
+use crate::user_info::UserInfoError;
+
+/// 头像协议名称
+pub const AVATAR_PROTOCOL: &str = "/localp2p/avatar/1.0.0";
+
+/// 头像的最大字节数
+pub const MAX_AVATAR_SIZE: usize = 256 * 1024;
+
+/// 头像请求的最大字节数
+const MAX_AVATAR_REQUEST_SIZE: usize = 1024;
+
+/// 头像错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum AvatarError {
+    /// 头像超过大小限制
+    #[error("头像过大: {size} 字节（上限 {max} 字节）")]
+    TooLarge { size: usize, max: usize },
+
+    /// 不支持的图片格式
+    #[error("不支持的头像格式（仅支持 PNG、JPEG、GIF、WebP）")]
+    UnsupportedFormat,
+
+    /// 哈希格式无效
+    #[error("无效的头像哈希: {0}")]
+    InvalidHash(String),
+
+    /// 头像内容与哈希不一致
+    #[error("头像内容与哈希不一致")]
+    HashMismatch,
+
+    /// 读写缓存失败
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// 更新本地用户信息失败
+    #[error("更新用户信息失败: {0}")]
+    Profile(#[from] UserInfoError),
+}
+
+/// 头像图片格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvatarFormat {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+}
+
+impl AvatarFormat {
+    /// 所有支持的格式
+    pub const ALL: [AvatarFormat; 4] = [Self::Png, Self::Jpeg, Self::Gif, Self::WebP];
+
+    /// 根据文件头识别格式
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(Self::Jpeg)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(Self::WebP)
+        } else {
+            None
+        }
+    }
+
+    /// 文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+            Self::WebP => "webp",
+        }
+    }
+
+    /// MIME 类型
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::WebP => "image/webp",
+        }
+    }
+}
+
+/// 计算头像的内容哈希（SHA-256，十六进制小写）
+pub fn avatar_hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 检查哈希格式（64 个十六进制小写字符），同时保证可以安全地用作文件名
+pub fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// 检查头像大小和格式
+pub fn validate(data: &[u8]) -> Result<AvatarFormat, AvatarError> {
+    if data.len() > MAX_AVATAR_SIZE {
+        return Err(AvatarError::TooLarge { size: data.len(), max: MAX_AVATAR_SIZE });
+    }
+    AvatarFormat::detect(data).ok_or(AvatarError::UnsupportedFormat)
+}
+
+/// 头像请求
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvatarRequest {
+    /// 头像的内容哈希
+    pub hash: String,
+}
+
+/// 头像响应
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AvatarResponse {
+    /// 头像数据
+    Found(Vec<u8>),
+    /// 没有该头像
+    NotFound,
+}
+
+/// 头像协议（标记类型）
+#[derive(Debug, Clone, Default)]
+pub struct AvatarProtocol;
+
+impl AsRef<str> for AvatarProtocol {
+    fn as_ref(&self) -> &str {
+        AVATAR_PROTOCOL
+    }
+}
+
+/// 头像 Codec
+#[derive(Debug, Clone)]
+pub struct AvatarCodec {
+    read_timeout: Duration,
+}
+
+/// 按协议限制创建头像 Codec
+pub fn avatar_codec(limits: &ProtocolLimits) -> AvatarCodec {
+    AvatarCodec { read_timeout: limits.read_timeout }
+}
+
+#[async_trait]
+impl request_response::Codec for AvatarCodec {
+    type Protocol = AvatarProtocol;
+    type Request = AvatarRequest;
+    type Response = AvatarResponse;
+
+    async fn read_request<T>(&mut self, _protocol: &AvatarProtocol, io: &mut T) -> std::io::Result<AvatarRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io, Format::Json, MAX_AVATAR_REQUEST_SIZE, self.read_timeout).await
+    }
+
+    async fn read_response<T>(&mut self, _protocol: &AvatarProtocol, io: &mut T) -> std::io::Result<AvatarResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let data = with_deadline(self.read_timeout, read_frame(io, MAX_AVATAR_SIZE)).await?;
+        if data.is_empty() {
+            Ok(AvatarResponse::NotFound)
+        } else {
+            Ok(AvatarResponse::Found(data))
+        }
+    }
+
+    async fn write_request<T>(&mut self, _protocol: &AvatarProtocol, io: &mut T, req: AvatarRequest) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, Format::Json, req).await
+    }
+
+    async fn write_response<T>(&mut self, _protocol: &AvatarProtocol, io: &mut T, res: AvatarResponse) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        match res {
+            AvatarResponse::Found(data) => write_frame(io, &data).await?,
+            AvatarResponse::NotFound => write_frame(io, &[]).await?,
+        }
+        io.flush().await
+    }
+}
+
+/// 头像缓存（按内容哈希索引）
+///
+/// 指定目录时头像保存为 `<哈希>.<扩展名>` 文件，可以直接交给界面显示；否则只保存在内存中。
+#[derive(Debug, Default)]
+pub struct AvatarCache {
+    dir: Option<PathBuf>,
+    memory: HashMap<String, Vec<u8>>,
+}
+
+impl AvatarCache {
+    /// 创建内存缓存
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// 打开缓存目录（不存在时自动创建）
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, AvatarError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir: Some(dir), memory: HashMap::new() })
+    }
+
+    /// 缓存目录
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// 是否已缓存
+    pub fn contains(&self, hash: &str) -> bool {
+        self.memory.contains_key(hash) || self.path(hash).is_some()
+    }
+
+    /// 读取头像数据
+    pub fn get(&self, hash: &str) -> Option<Vec<u8>> {
+        if let Some(data) = self.memory.get(hash) {
+            return Some(data.clone());
+        }
+        std::fs::read(self.path(hash)?).ok()
+    }
+
+    /// 头像文件路径（只有目录缓存才有）
+    pub fn path(&self, hash: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        if !is_valid_hash(hash) {
+            return None;
+        }
+        AvatarFormat::ALL
+            .iter()
+            .map(|format| dir.join(format!("{}.{}", hash, format.extension())))
+            .find(|path| path.is_file())
+    }
+
+    /// 保存头像，返回内容哈希
+    pub fn insert(&mut self, data: Vec<u8>) -> Result<String, AvatarError> {
+        let hash = avatar_hash(&data);
+        self.insert_verified(&hash, data)?;
+        Ok(hash)
+    }
+
+    /// 校验头像与哈希一致后保存
+    pub fn insert_verified(&mut self, hash: &str, data: Vec<u8>) -> Result<(), AvatarError> {
+        if !is_valid_hash(hash) {
+            return Err(AvatarError::InvalidHash(hash.to_string()));
+        }
+        let format = validate(&data)?;
+        if avatar_hash(&data) != hash {
+            return Err(AvatarError::HashMismatch);
+        }
+
+        match self.dir {
+            Some(ref dir) => {
+                // 先写临时文件再重命名，避免界面读到写了一半的图片
+                let path = dir.join(format!("{}.{}", hash, format.extension()));
+                let tmp = path.with_extension("tmp");
+                std::fs::write(&tmp, &data)?;
+                std::fs::rename(&tmp, &path)?;
+            }
+            None => {
+                self.memory.insert(hash.to_string(), data);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+    use libp2p::request_response::Codec;
+
+    fn png(extra: &[u8]) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.extend_from_slice(extra);
+        data
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate(&png(b"pixels")).unwrap(), AvatarFormat::Png);
+        assert_eq!(validate(b"GIF89a....").unwrap(), AvatarFormat::Gif);
+        assert_eq!(validate(b"RIFF\0\0\0\0WEBPVP8 ").unwrap(), AvatarFormat::WebP);
+        assert!(matches!(validate(b"<svg></svg>"), Err(AvatarError::UnsupportedFormat)));
+        assert!(matches!(validate(&png(&vec![0; MAX_AVATAR_SIZE])), Err(AvatarError::TooLarge { .. })));
+
+        assert!(is_valid_hash(&avatar_hash(b"x")));
+        assert!(!is_valid_hash("../../etc/passwd"));
+    }
+
+    #[test]
+    fn test_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = AvatarCache::open(dir.path()).unwrap();
+        let data = png(b"avatar");
+
+        let hash = cache.insert(data.clone()).unwrap();
+        assert!(cache.contains(&hash));
+        assert_eq!(cache.get(&hash).unwrap(), data);
+        assert_eq!(cache.path(&hash).unwrap(), dir.path().join(format!("{}.png", hash)));
+
+        let other = png(b"other");
+        assert!(matches!(cache.insert_verified(&hash, other), Err(AvatarError::HashMismatch)));
+
+        let mut memory = AvatarCache::in_memory();
+        let hash = memory.insert(data.clone()).unwrap();
+        assert_eq!(memory.get(&hash).unwrap(), data);
+        assert!(memory.path(&hash).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_codec_round_trip() {
+        let mut codec = avatar_codec(&ProtocolLimits::default());
+        let data = png(b"avatar");
+
+        for response in [AvatarResponse::Found(data), AvatarResponse::NotFound] {
+            let mut buffer = Vec::new();
+            codec.write_response(&AvatarProtocol, &mut buffer, response.clone()).await.unwrap();
+            let decoded = codec.read_response(&AvatarProtocol, &mut Cursor::new(buffer)).await.unwrap();
+            assert_eq!(decoded, response);
+        }
+
+        // 超过大小限制的响应直接拒绝
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &vec![0; MAX_AVATAR_SIZE + 1]).await.unwrap();
+        assert!(codec.read_response(&AvatarProtocol, &mut Cursor::new(buffer)).await.is_err());
+    }
+}
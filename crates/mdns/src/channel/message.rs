@@ -0,0 +1,125 @@
+//! 频道消息类型定义
+
+use libp2p::gossipsub::IdentTopic;
+use serde::{Deserialize, Serialize};
+
+/// 频道对应的 GossipSub 主题前缀
+pub const CHANNEL_TOPIC_PREFIX: &str = "/localp2p/channel/1.0.0/";
+
+/// 频道名称最大长度
+const MAX_CHANNEL_NAME_LEN: usize = 64;
+
+/// 频道消息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChannelMessage {
+    /// 消息唯一 ID（UUID）
+    pub id: String,
+
+    /// 频道名称
+    pub channel: String,
+
+    /// 发送者的 Peer ID
+    pub sender_peer_id: String,
+
+    /// 消息内容
+    pub content: String,
+
+    /// 发送时间（Unix 时间戳，毫秒）
+    pub timestamp: i64,
+}
+
+impl ChannelMessage {
+    /// 创建新的频道消息
+    pub fn new(channel: String, sender_peer_id: String, content: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            channel,
+            sender_peer_id,
+            content,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// 编码为 JSON 字节
+    pub fn encode(&self) -> Result<Vec<u8>, ChannelError> {
+        serde_json::to_vec(self).map_err(|e| ChannelError::Serialization(e.to_string()))
+    }
+
+    /// 从 JSON 字节解码
+    pub fn decode(data: &[u8]) -> Result<Self, ChannelError> {
+        serde_json::from_slice(data).map_err(|e| ChannelError::Serialization(e.to_string()))
+    }
+}
+
+/// 校验频道名称并返回对应的 GossipSub 主题
+///
+/// 名称不能为空、不能包含空白字符，长度不超过 64 字节。
+pub fn channel_topic(channel: &str) -> Result<IdentTopic, ChannelError> {
+    if channel.is_empty()
+        || channel.len() > MAX_CHANNEL_NAME_LEN
+        || channel.chars().any(char::is_whitespace)
+    {
+        return Err(ChannelError::InvalidName(channel.to_string()));
+    }
+    Ok(IdentTopic::new(format!("{}{}", CHANNEL_TOPIC_PREFIX, channel)))
+}
+
+/// 从 GossipSub 主题中取出频道名称（不是频道主题时返回 None）
+pub fn channel_name(topic: &str) -> Option<&str> {
+    topic.strip_prefix(CHANNEL_TOPIC_PREFIX)
+}
+
+/// 频道错误类型
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ChannelError {
+    /// 频道功能未启用
+    #[error("频道功能未启用")]
+    NotEnabled,
+
+    /// 配置中未启用频道协议
+    #[error("频道协议未启用（需要 NodeManagerConfig::with_channels）")]
+    NotConfigured,
+
+    /// 频道名称无效
+    #[error("频道名称无效: {0}")]
+    InvalidName(String),
+
+    /// 未订阅该频道
+    #[error("未订阅频道: {0}")]
+    NotSubscribed(String),
+
+    /// 订阅失败
+    #[error("订阅失败: {0}")]
+    SubscribeFailed(String),
+
+    /// 发布失败
+    #[error("发布失败: {0}")]
+    PublishFailed(String),
+
+    /// 序列化错误
+    #[error("序列化错误: {0}")]
+    Serialization(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_message_roundtrip() {
+        let message = ChannelMessage::new("#build-status".to_string(), "peer".to_string(), "构建通过".to_string());
+        let decoded = ChannelMessage::decode(&message.encode().unwrap()).unwrap();
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn test_channel_topic() {
+        let topic = channel_topic("#build-status").unwrap();
+        assert_eq!(channel_name(&topic.to_string()), Some("#build-status"));
+
+        assert!(channel_topic("").is_err());
+        assert!(channel_topic("build status").is_err());
+        assert!(channel_topic(&"x".repeat(65)).is_err());
+        assert_eq!(channel_name("/other/topic"), None);
+    }
+}
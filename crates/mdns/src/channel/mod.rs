@@ -0,0 +1,35 @@
+//! 局域网频道模块
+//!
+//! 基于 GossipSub 的一对多消息频道（例如 "#build-status"），消息沿 gossip 网格转发，
+//! 不需要为每条消息向每个节点单独打开流。
+//!
+//! # 模块化设计
+//!
+//! 本模块作为 mdns crate 的扩展，通过 `ChannelExtension` trait 为 `ManagedDiscovery` 添加频道能力。
+//! 只有通过 `NodeManager::verify_node_info` 验证的节点发布的消息才会被接受和转发。
+//! GossipSub behaviour 只在 `NodeManagerConfig::with_channels(true)` 时创建。
+//!
+//! # 架构
+//!
+//! - [`message`] - 频道消息、频道名称校验和错误类型
+//! - [`traits`] - ChannelExtension trait 和事件定义
+//!
+//! # 示例
+//!
+//! ```no_run
+//! use mdns::{ManagedDiscovery, ChannelExtension};
+//!
+//! # async fn example(mut discovery: ManagedDiscovery) -> Result<(), Box<dyn std::error::Error>> {
+//! discovery.enable_channels().await?;
+//! discovery.subscribe_channel("#build-status").await?;
+//! discovery.publish_to_channel("#build-status", "main 构建通过".to_string()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod message;
+pub mod traits;
+
+// 公共 API 导出
+pub use message::{ChannelMessage, ChannelError, CHANNEL_TOPIC_PREFIX};
+pub use traits::{ChannelExtension, ChannelEvent};
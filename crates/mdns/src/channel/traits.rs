@@ -0,0 +1,98 @@
+//! 频道扩展 trait
+//!
+//! 定义扩展 ManagedDiscovery 的接口，使其支持基于 GossipSub 的频道。
+
+use super::message::{ChannelError, ChannelMessage};
+use libp2p::PeerId;
+
+/// 频道扩展 trait
+///
+/// 为 ManagedDiscovery 提供可选的一对多频道能力。
+///
+/// # 示例
+///
+/// ```no_run
+/// use mdns::{ManagedDiscovery, ChannelExtension};
+///
+/// # async fn example(mut discovery: ManagedDiscovery) -> Result<(), Box<dyn std::error::Error>> {
+/// // 启用频道功能并订阅（节点需使用 `NodeManagerConfig::with_channels(true)` 创建）
+/// discovery.enable_channels().await?;
+/// discovery.subscribe_channel("#build-status").await?;
+///
+/// // 发布消息
+/// let message_id = discovery.publish_to_channel("#build-status", "部署完成".to_string()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait::async_trait]
+pub trait ChannelExtension {
+    /// 启用频道功能
+    ///
+    /// # 错误
+    ///
+    /// - `ChannelError::NotConfigured` - 配置中未启用频道协议
+    /// - `ChannelError::SubscribeFailed` - 频道功能已经启用
+    async fn enable_channels(&mut self) -> Result<(), ChannelError>;
+
+    /// 订阅频道（重复订阅不会报错）
+    ///
+    /// # 错误
+    ///
+    /// - `ChannelError::NotEnabled` - 频道功能未启用
+    /// - `ChannelError::InvalidName` - 频道名称无效
+    /// - `ChannelError::SubscribeFailed` - 订阅失败
+    async fn subscribe_channel(&mut self, channel: &str) -> Result<(), ChannelError>;
+
+    /// 退订频道
+    ///
+    /// # 错误
+    ///
+    /// - `ChannelError::NotEnabled` - 频道功能未启用
+    /// - `ChannelError::NotSubscribed` - 未订阅该频道
+    async fn unsubscribe_channel(&mut self, channel: &str) -> Result<(), ChannelError>;
+
+    /// 向已订阅的频道发布消息，返回消息 ID
+    ///
+    /// # 错误
+    ///
+    /// - `ChannelError::NotEnabled` - 频道功能未启用
+    /// - `ChannelError::NotSubscribed` - 未订阅该频道
+    /// - `ChannelError::PublishFailed` - 发布失败（例如没有其他订阅者）
+    async fn publish_to_channel(&mut self, channel: &str, content: String) -> Result<String, ChannelError>;
+
+    /// 已订阅的频道
+    fn subscribed_channels(&self) -> Vec<String>;
+
+    /// 检查频道功能是否已启用
+    fn is_channels_enabled(&self) -> bool;
+}
+
+/// 频道事件
+#[derive(Debug, Clone)]
+pub enum ChannelEvent {
+    /// 收到频道消息（发送者已验证）
+    MessageReceived {
+        /// 频道名称
+        channel: String,
+        /// 发送者的 Peer ID
+        from: PeerId,
+        /// 收到的消息
+        message: ChannelMessage,
+    },
+
+    /// 节点订阅了频道
+    PeerSubscribed {
+        /// 频道名称
+        channel: String,
+        /// 订阅的节点
+        peer_id: PeerId,
+    },
+
+    /// 节点退订了频道
+    PeerUnsubscribed {
+        /// 频道名称
+        channel: String,
+        /// 退订的节点
+        peer_id: PeerId,
+    },
+}
@@ -1,21 +1,65 @@
 //! 聊天协议 Codec
 //!
 //! 使用 request_response 模式实现聊天消息的收发。
+//!
+//! 同时支持 1.1.0 和 1.0.0 两个协议版本，连接时由 multistream-select
+//! 按 [`ChatProtocol::supported`] 的顺序协商，优先使用新版本。
+//! 所有版本的分帧格式相同（u32 长度前缀 + 序列化数据），
+//! 向 1.0.0 节点发送时只能使用旧版认识的消息类型。
+//! 启用 CBOR 后额外提供 1.1.0 的 CBOR 变体，只在双方都启用时使用。
 
 use async_trait::async_trait;
-use futures::{AsyncRead, AsyncWrite, AsyncReadExt, AsyncWriteExt};
+use futures::{AsyncRead, AsyncWrite};
 use libp2p::request_response;
 use serde::{Deserialize, Serialize};
 
-use super::{ChatMessage, CHAT_PROTOCOL};
+use super::{ChatMessage, CHAT_PROTOCOL, CHAT_PROTOCOL_CBOR, CHAT_PROTOCOL_V1_0};
+use crate::codec::{
+    read_frame, read_message, with_deadline, write_message, Format, FramedProtocol, ProtocolLimits,
+};
+use std::time::Duration;
+
+/// 聊天协议版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChatProtocol {
+    /// `/localp2p/chat/1.0.0`
+    V1_0,
+    /// `/localp2p/chat/1.1.0`
+    #[default]
+    V1_1,
+    /// `/localp2p/chat/1.1.0/cbor`
+    V1_1Cbor,
+}
 
-/// 聊天协议（标记类型）
-#[derive(Debug, Clone, Default)]
-pub struct ChatProtocol;
+impl ChatProtocol {
+    /// 支持的协议版本（按优先级排序）
+    ///
+    /// `cbor` 为 `true` 时优先协商 CBOR 变体。
+    pub fn supported(cbor: bool) -> Vec<Self> {
+        let mut protocols = vec![Self::V1_1, Self::V1_0];
+        if cbor {
+            protocols.insert(0, Self::V1_1Cbor);
+        }
+        protocols
+    }
+}
 
 impl AsRef<str> for ChatProtocol {
     fn as_ref(&self) -> &str {
-        CHAT_PROTOCOL
+        match self {
+            Self::V1_0 => CHAT_PROTOCOL_V1_0,
+            Self::V1_1 => CHAT_PROTOCOL,
+            Self::V1_1Cbor => CHAT_PROTOCOL_CBOR,
+        }
+    }
+}
+
+impl FramedProtocol for ChatProtocol {
+    fn format(&self) -> Format {
+        match self {
+            Self::V1_0 | Self::V1_1 => Format::Json,
+            Self::V1_1Cbor => Format::Cbor,
+        }
     }
 }
 
@@ -34,13 +78,39 @@ impl ChatResponse {
     pub fn received() -> Self {
         Self { received: true }
     }
+
+    /// 创建拒绝接收的响应
+    pub fn rejected() -> Self {
+        Self { received: false }
+    }
 }
 
 /// 聊天 Codec
 ///
-/// 使用 JSON 序列化，带长度前缀的分帧协议。
-#[derive(Debug, Clone, Default)]
-pub struct ChatCodec;
+/// 带长度前缀的分帧协议，序列化格式由协商到的协议版本决定。
+#[derive(Debug, Clone)]
+pub struct ChatCodec {
+    max_message_size: usize,
+    max_response_size: usize,
+    read_timeout: Duration,
+}
+
+impl ChatCodec {
+    /// 按协议限制创建 Codec
+    pub fn new(limits: &ProtocolLimits) -> Self {
+        Self {
+            max_message_size: limits.chat_message,
+            max_response_size: limits.chat_response,
+            read_timeout: limits.read_timeout,
+        }
+    }
+}
+
+impl Default for ChatCodec {
+    fn default() -> Self {
+        Self::new(&ProtocolLimits::default())
+    }
+}
 
 #[async_trait]
 impl request_response::Codec for ChatCodec {
@@ -50,108 +120,71 @@ impl request_response::Codec for ChatCodec {
 
     async fn read_request<T>(
         &mut self,
-        _protocol: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
     ) -> std::io::Result<Self::Request>
     where
         T: AsyncRead + Unpin + Send,
     {
-        // 读取长度前缀（u32 big endian）
-        let mut len_bytes = [0u8; 4];
-        io.read_exact(&mut len_bytes).await?;
-        let len = u32::from_be_bytes(len_bytes) as usize;
-
-        // 限制最大消息大小（1MB）
-        const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
-        if len > MAX_MESSAGE_SIZE {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("消息过大: {} 字节", len),
-            ));
-        }
-
-        // 读取 JSON 数据
-        let mut buffer = vec![0u8; len];
-        io.read_exact(&mut buffer).await?;
+        let data = with_deadline(self.read_timeout, read_frame(io, self.max_message_size)).await?;
 
-        // 解析 JSON
-        serde_json::from_slice::<ChatMessage>(&buffer)
+        // 未知的消息类型解析为 ChatMessage::Unknown
+        ChatMessage::decode_as(protocol.format(), &data)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
 
     async fn read_response<T>(
         &mut self,
-        _protocol: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
     ) -> std::io::Result<Self::Response>
     where
         T: AsyncRead + Unpin + Send,
     {
-        // 读取长度前缀（u32 big endian）
-        let mut len_bytes = [0u8; 4];
-        io.read_exact(&mut len_bytes).await?;
-        let len = u32::from_be_bytes(len_bytes) as usize;
-
-        // 限制最大消息大小
-        const MAX_MESSAGE_SIZE: usize = 1024;
-        if len > MAX_MESSAGE_SIZE {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("响应过大: {} 字节", len),
-            ));
-        }
-
-        // 读取 JSON 数据
-        let mut buffer = vec![0u8; len];
-        io.read_exact(&mut buffer).await?;
-
-        // 解析 JSON
-        serde_json::from_slice::<ChatResponse>(&buffer)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        read_message(io, protocol.format(), self.max_response_size, self.read_timeout).await
     }
 
     async fn write_request<T>(
         &mut self,
-        _protocol: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
         req: Self::Request,
     ) -> std::io::Result<()>
     where
         T: AsyncWrite + Unpin + Send,
     {
-        // 序列化为 JSON
-        let data = serde_json::to_vec(&req)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-
-        // 写入长度前缀（u32 big endian）
-        let len = data.len() as u32;
-        io.write_all(&len.to_be_bytes()).await?;
+        // 对方只支持 1.0.0 时降级为旧版消息
+        let req = match protocol {
+            ChatProtocol::V1_1 | ChatProtocol::V1_1Cbor => req,
+            ChatProtocol::V1_0 => req.to_v1_0().ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "对方使用聊天协议 1.0.0，不支持此消息类型",
+                )
+            })?,
+        };
 
-        // 写入数据
-        io.write_all(&data).await?;
-        io.flush().await
+        write_message(io, protocol.format(), req).await
     }
 
     async fn write_response<T>(
         &mut self,
-        _protocol: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
         res: Self::Response,
     ) -> std::io::Result<()>
     where
         T: AsyncWrite + Unpin + Send,
     {
-        // 序列化为 JSON
-        let data = serde_json::to_vec(&res)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-
-        // 写入长度前缀（u32 big endian）
-        let len = data.len() as u32;
-        io.write_all(&len.to_be_bytes()).await?;
+        write_message(io, protocol.format(), res).await
+    }
+}
 
-        // 写入数据
-        io.write_all(&data).await?;
-        io.flush().await
+// 辅助函数，用于获取协议
+impl ChatCodec {
+    /// 获取协议（当前版本）
+    pub fn protocol(&self) -> ChatProtocol {
+        ChatProtocol::default()
     }
 }
 
@@ -159,9 +192,201 @@ impl request_response::Codec for ChatCodec {
 mod tests {
     use super::*;
 
+    use crate::chat::TextMessage;
+    use futures::io::Cursor;
+    use libp2p::request_response::Codec;
+
+    /// 1.0.0 版本节点的消息格式（与当时的代码保持一致）
+    mod v1_0 {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Debug, Serialize, Deserialize)]
+        pub enum ChatMessage {
+            Text(TextMessage),
+            TypingIndicator(TypingIndicator),
+            Ack(MessageAck),
+        }
+
+        #[derive(Debug, Serialize, Deserialize)]
+        pub struct TextMessage {
+            pub id: String,
+            pub sender_peer_id: String,
+            pub content: String,
+            pub timestamp: i64,
+            pub reply_to: Option<String>,
+        }
+
+        #[derive(Debug, Serialize, Deserialize)]
+        pub struct TypingIndicator {
+            pub sender_peer_id: String,
+            pub is_typing: bool,
+        }
+
+        #[derive(Debug, Serialize, Deserialize)]
+        pub struct MessageAck {
+            pub message_id: String,
+            pub received: bool,
+            pub timestamp: i64,
+        }
+    }
+
+    /// 按分帧格式编码
+    fn frame(data: &[u8]) -> Vec<u8> {
+        let mut framed = (data.len() as u32).to_be_bytes().to_vec();
+        framed.extend_from_slice(data);
+        framed
+    }
+
+    /// 用新版 Codec 按指定协议写出请求，返回帧内的 JSON
+    async fn write_new(protocol: ChatProtocol, message: ChatMessage) -> std::io::Result<Vec<u8>> {
+        let mut io = Cursor::new(Vec::new());
+        ChatCodec::default().write_request(&protocol, &mut io, message).await?;
+        Ok(io.into_inner()[4..].to_vec())
+    }
+
+    /// 用新版 Codec 按指定协议读取请求
+    async fn read_new(protocol: ChatProtocol, data: &[u8]) -> std::io::Result<ChatMessage> {
+        ChatCodec::default().read_request(&protocol, &mut Cursor::new(frame(data))).await
+    }
+
     #[test]
     fn test_chat_protocol() {
-        assert_eq!(ChatProtocol.as_ref(), "/localp2p/chat/1.0.0");
+        assert_eq!(ChatProtocol::V1_0.as_ref(), "/localp2p/chat/1.0.0");
+        assert_eq!(ChatProtocol::V1_1.as_ref(), "/localp2p/chat/1.1.0");
+        assert_eq!(ChatProtocol::V1_1Cbor.as_ref(), "/localp2p/chat/1.1.0/cbor");
+        assert_eq!(ChatProtocol::supported(false), [ChatProtocol::V1_1, ChatProtocol::V1_0]);
+        assert_eq!(ChatProtocol::supported(true)[0], ChatProtocol::V1_1Cbor);
+    }
+
+    #[tokio::test]
+    async fn test_message_size_limit() {
+        let limits = ProtocolLimits::new().with_chat_limits(64, 64);
+        let data = write_new(ChatProtocol::V1_1, ChatMessage::text("x".repeat(100))).await.unwrap();
+        let err = ChatCodec::new(&limits)
+            .read_request(&ChatProtocol::V1_1, &mut Cursor::new(frame(&data)))
+            .await
+            .unwrap_err();
+        assert!(crate::codec::is_protocol_violation(&err));
+    }
+
+    #[tokio::test]
+    async fn test_cbor_variant() {
+        let image = ChatMessage::image("image/png".into(), 1, 1, vec![1; 32], vec![2]).unwrap();
+        let mut io = Cursor::new(Vec::new());
+        ChatCodec::default()
+            .write_request(&ChatProtocol::V1_1Cbor, &mut io, image.clone())
+            .await
+            .unwrap();
+        let cbor_len = io.get_ref().len();
+        io.set_position(0);
+        let decoded = ChatCodec::default().read_request(&ChatProtocol::V1_1Cbor, &mut io).await.unwrap();
+        assert_eq!(decoded, image);
+        assert!(cbor_len < write_new(ChatProtocol::V1_1, image).await.unwrap().len() + 4);
+
+        let mut io = Cursor::new(Vec::new());
+        ChatCodec::default()
+            .write_response(&ChatProtocol::V1_1Cbor, &mut io, ChatResponse::received())
+            .await
+            .unwrap();
+        io.set_position(0);
+        let response = ChatCodec::default().read_response(&ChatProtocol::V1_1Cbor, &mut io).await.unwrap();
+        assert!(response.received);
+    }
+
+    #[tokio::test]
+    async fn test_new_to_new() {
+        let image = ChatMessage::image("image/png".into(), 1, 1, vec![1], vec![2]).unwrap();
+        let room = ChatMessage::room_text("room".into(), "hi".into());
+        for message in [image, room, ChatMessage::edit("id".into(), "fixed".into())] {
+            let data = write_new(ChatProtocol::V1_1, message.clone()).await.unwrap();
+            assert_eq!(read_new(ChatProtocol::V1_1, &data).await.unwrap(), message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_to_old() {
+        // 文本消息旧节点可以直接解析（新增字段被忽略）
+        let text = ChatMessage::text("hello".into());
+        let data = write_new(ChatProtocol::V1_0, text.clone()).await.unwrap();
+        match serde_json::from_slice::<v1_0::ChatMessage>(&data).unwrap() {
+            v1_0::ChatMessage::Text(old) => {
+                assert_eq!(Some(old.id.as_str()), text.id());
+                assert_eq!(old.content, "hello");
+            }
+            other => panic!("Expected Text message, got {:?}", other),
+        }
+
+        // 图片降级为同 ID 的文本摘要
+        let image = ChatMessage::image("image/png".into(), 1, 1, vec![1], vec![2]).unwrap();
+        let data = write_new(ChatProtocol::V1_0, image.clone()).await.unwrap();
+        match serde_json::from_slice::<v1_0::ChatMessage>(&data).unwrap() {
+            v1_0::ChatMessage::Text(old) => {
+                assert_eq!(Some(old.id.as_str()), image.id());
+                assert_eq!(old.content, "[图片]");
+            }
+            other => panic!("Expected Text message, got {:?}", other),
+        }
+
+        // 确认消息中的新增字段不影响旧节点
+        let mut ack = crate::chat::MessageAck::new("id".into(), true);
+        ack.read = true;
+        let data = write_new(ChatProtocol::V1_0, ChatMessage::Ack(ack)).await.unwrap();
+        assert!(matches!(
+            serde_json::from_slice::<v1_0::ChatMessage>(&data).unwrap(),
+            v1_0::ChatMessage::Ack(_)
+        ));
+
+        // 旧版没有对应类型的消息不会被发送
+        let err = write_new(ChatProtocol::V1_0, ChatMessage::delete("id".into()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+        let room = ChatMessage::room_text("room".into(), "hi".into());
+        assert!(write_new(ChatProtocol::V1_0, room).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_old_to_new() {
+        let old = v1_0::ChatMessage::Text(v1_0::TextMessage {
+            id: "id".into(),
+            sender_peer_id: "peer".into(),
+            content: "hello".into(),
+            timestamp: 1,
+            reply_to: None,
+        });
+        let data = serde_json::to_vec(&old).unwrap();
+        match read_new(ChatProtocol::V1_0, &data).await.unwrap() {
+            ChatMessage::Text(TextMessage { id, content, room_id, .. }) => {
+                assert_eq!(id, "id");
+                assert_eq!(content, "hello");
+                assert!(room_id.is_none());
+            }
+            other => panic!("Expected Text message, got {:?}", other),
+        }
+
+        let old = v1_0::ChatMessage::Ack(v1_0::MessageAck {
+            message_id: "id".into(),
+            received: true,
+            timestamp: 1,
+        });
+        let data = serde_json::to_vec(&old).unwrap();
+        match read_new(ChatProtocol::V1_0, &data).await.unwrap() {
+            ChatMessage::Ack(ack) => assert!(ack.received && !ack.read),
+            other => panic!("Expected Ack message, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_future_message_is_unknown() {
+        let data = br#"{"Poll":{"id":"1","question":"?"}}"#;
+        match read_new(ChatProtocol::V1_1, data).await.unwrap() {
+            ChatMessage::Unknown(unknown) => assert_eq!(unknown.kind, "Poll"),
+            other => panic!("Expected Unknown message, got {:?}", other),
+        }
+
+        // 格式错误的数据仍然报错
+        assert!(read_new(ChatProtocol::V1_1, b"not json").await.is_err());
+        assert!(read_new(ChatProtocol::V1_1, br#"{"A":1,"B":2}"#).await.is_err());
     }
 
     #[test]
@@ -177,11 +402,3 @@ mod tests {
         assert_eq!(codec.protocol().as_ref(), CHAT_PROTOCOL);
     }
 }
-
-// 辅助函数，用于获取协议
-impl ChatCodec {
-    /// 获取协议
-    pub fn protocol(&self) -> ChatProtocol {
-        ChatProtocol
-    }
-}
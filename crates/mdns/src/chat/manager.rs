@@ -2,17 +2,79 @@
 //!
 //! 统一管理所有聊天会话和消息收发。
 
-use super::message::{ChatMessage, ChatError};
+use super::message::{apply_update_to_history, ChatMessage, ChatError, DeliveryStatus, MessageAck};
+use super::room::{ChatRoom, RoomInvite, RoomLeave};
+use super::store::{ChatStore, MemoryChatStore, QueuedMessage, StoredMessage, StoredRoom};
 use super::traits::ChatEvent;
 use crate::node::NodeManager;
-use libp2p::PeerId;
-use std::collections::{HashMap, VecDeque};
+use libp2p::{request_response::OutboundFailure, PeerId};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock};
 
 /// 最大历史消息条数
 const MAX_HISTORY_SIZE: usize = 1000;
 
+/// 聊天配置
+#[derive(Clone)]
+pub struct ChatConfig {
+    /// 聊天记录存储
+    pub store: Arc<dyn ChatStore>,
+    /// 发件箱中消息的有效期（超过后放弃投递）
+    pub message_ttl: Duration,
+    /// 首次重试的等待时间
+    pub retry_initial_backoff: Duration,
+    /// 重试等待时间上限（每次失败翻倍，直到此上限）
+    pub retry_max_backoff: Duration,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            store: Arc::new(MemoryChatStore::new()),
+            message_ttl: Duration::from_secs(24 * 60 * 60),
+            retry_initial_backoff: Duration::from_secs(2),
+            retry_max_backoff: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl ChatConfig {
+    /// 创建默认配置（内存存储，消息有效期 24 小时）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置聊天记录存储
+    pub fn with_store(mut self, store: Arc<dyn ChatStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// 设置发件箱中消息的有效期
+    pub fn with_message_ttl(mut self, ttl: Duration) -> Self {
+        self.message_ttl = ttl;
+        self
+    }
+
+    /// 设置重试退避时间（首次等待和上限）
+    pub fn with_retry_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.retry_initial_backoff = initial;
+        self.retry_max_backoff = max;
+        self
+    }
+
+    /// 第 `attempts` 次发送失败后的等待时间
+    fn retry_delay(&self, attempts: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempts.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.retry_initial_backoff
+            .saturating_mul(factor)
+            .min(self.retry_max_backoff)
+    }
+}
+
 /// 聊天管理器
 ///
 /// 管理所有聊天会话，负责消息的发送和接收。
@@ -23,38 +85,244 @@ pub struct ChatManager {
     local_peer_id: PeerId,
     /// 聊天会话管理（PeerId -> 会话）
     sessions: RwLock<HashMap<PeerId, ChatSession>>,
+    /// 聊天室（聊天室 ID -> 聊天室及其消息历史）
+    rooms: RwLock<HashMap<String, RoomState>>,
+    /// 本地用户已退出的聊天室 ID（不再接受这些聊天室的消息和邀请）
+    left_rooms: RwLock<HashSet<String>>,
     /// 消息事件发送器
     event_tx: mpsc::UnboundedSender<ChatEvent>,
+    /// 聊天配置（存储、发件箱有效期和重试退避）
+    config: ChatConfig,
+    /// 请勿打扰（丢弃收到的正在输入提示）
+    do_not_disturb: AtomicBool,
 }
 
 impl ChatManager {
-    /// 创建新的聊天管理器
+    /// 创建新的聊天管理器（聊天记录仅保存在内存中）
     pub fn new(node_manager: Arc<NodeManager>, local_peer_id: PeerId) -> (Self, mpsc::UnboundedReceiver<ChatEvent>) {
+        Self::with_config(node_manager, local_peer_id, ChatConfig::default())
+    }
+
+    /// 使用指定的存储创建聊天管理器
+    pub fn with_store(
+        node_manager: Arc<NodeManager>,
+        local_peer_id: PeerId,
+        store: Arc<dyn ChatStore>,
+    ) -> (Self, mpsc::UnboundedReceiver<ChatEvent>) {
+        Self::with_config(node_manager, local_peer_id, ChatConfig::new().with_store(store))
+    }
+
+    /// 使用完整配置创建聊天管理器
+    pub fn with_config(
+        node_manager: Arc<NodeManager>,
+        local_peer_id: PeerId,
+        config: ChatConfig,
+    ) -> (Self, mpsc::UnboundedReceiver<ChatEvent>) {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
 
         let manager = Self {
             node_manager,
             local_peer_id,
             sessions: RwLock::new(HashMap::new()),
+            rooms: RwLock::new(HashMap::new()),
+            left_rooms: RwLock::new(HashSet::new()),
             event_tx,
+            config,
+            do_not_disturb: AtomicBool::new(false),
         };
 
         (manager, event_rx)
     }
 
+    /// 从存储中加载最近的聊天记录到会话历史，并恢复聊天室和发件箱
+    ///
+    /// 发件箱中超过有效期的消息不再投递，发送 `ChatEvent::DeliveryFailed`。
+    /// 返回加载的消息总数。
+    pub async fn load_history(&self) -> Result<usize, ChatError> {
+        let mut loaded = 0;
+        for peer_id in self.config.store.peers().await? {
+            let records = self.config.store.load_recent(&peer_id, MAX_HISTORY_SIZE).await?;
+            loaded += records.len();
+
+            let mut sessions = self.sessions.write().await;
+            let session = sessions
+                .entry(peer_id)
+                .or_insert_with(|| ChatSession::new(peer_id));
+            for record in records {
+                session.add_to_history(record.message);
+            }
+        }
+
+        tracing::info!("已加载 {} 条聊天记录", loaded);
+        self.load_rooms().await?;
+        self.load_outbox().await?;
+        Ok(loaded)
+    }
+
+    /// 从存储中恢复聊天室及其消息历史
+    async fn load_rooms(&self) -> Result<(), ChatError> {
+        for stored in self.config.store.load_rooms().await? {
+            if stored.left {
+                self.left_rooms.write().await.insert(stored.room.id);
+                continue;
+            }
+
+            let mut state = RoomState::new(stored.room);
+            for record in self.config.store.load_room_history(&state.room.id, MAX_HISTORY_SIZE).await? {
+                state.push(record.message);
+            }
+            self.rooms.write().await.insert(state.room.id.clone(), state);
+        }
+        Ok(())
+    }
+
+    /// 持久化聊天室（失败时仅记录日志）
+    async fn persist_room(&self, room: &ChatRoom, left: bool) {
+        let stored = StoredRoom { room: room.clone(), left };
+        if let Err(e) = self.config.store.save_room(&stored).await {
+            tracing::error!("保存聊天室失败: {}", e);
+        }
+    }
+
+    /// 持久化聊天室消息（失败时仅记录日志）
+    async fn persist_room_message(&self, room_id: &str, sender: PeerId, outgoing: bool, message: &ChatMessage) {
+        let record = StoredMessage::new(sender, outgoing, message.clone());
+        if let Err(e) = self.config.store.append_room_message(room_id, &record).await {
+            tracing::error!("保存聊天室消息失败: {}", e);
+        }
+    }
+
+    /// 从存储中恢复发件箱
+    async fn load_outbox(&self) -> Result<(), ChatError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut peers = Vec::new();
+        let mut expired = Vec::new();
+        {
+            let mut sessions = self.sessions.write().await;
+            for queued in self.config.store.load_outbox().await? {
+                let (Ok(peer_id), Some(message_id)) = (queued.peer_id.parse::<PeerId>(), queued.message.id()) else {
+                    continue;
+                };
+                let message_id = message_id.to_string();
+                let control = queued.message.is_room_control();
+                if !peers.contains(&peer_id) {
+                    peers.push(peer_id);
+                }
+                let session = sessions
+                    .entry(peer_id)
+                    .or_insert_with(|| ChatSession::new(peer_id));
+                if !control && session.delivery_status(&message_id).is_none() {
+                    session.track_outgoing(message_id.clone());
+                }
+                if is_expired(queued.queued_at, now, self.config.message_ttl) {
+                    session.advance_status(&message_id, DeliveryStatus::Failed);
+                    expired.push((peer_id, message_id));
+                } else {
+                    session.pending_messages.push_back(OutboxEntry::restore(queued));
+                }
+            }
+        }
+
+        // 重写发件箱，去掉过期的消息
+        for peer_id in peers {
+            self.save_outbox(&peer_id).await;
+        }
+        for (peer_id, message_id) in expired {
+            tracing::warn!("消息 {} 在重启前未能送达 {}，已超过有效期", message_id, peer_id);
+            let _ = self.event_tx.send(ChatEvent::DeliveryFailed {
+                peer_id,
+                message_id,
+                reason: "超过有效期仍未送达".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// 把指定节点当前的发件箱写入存储（失败时仅记录日志）
+    async fn save_outbox(&self, peer_id: &PeerId) {
+        let queued = self
+            .sessions
+            .read()
+            .await
+            .get(peer_id)
+            .map(|session| session.queued_messages())
+            .unwrap_or_default();
+        if let Err(e) = self.config.store.save_outbox(peer_id, &queued).await {
+            tracing::error!("保存发件箱失败: {}", e);
+        }
+    }
+
+    /// 分页查询聊天记录：获取指定消息之前的最多 `limit` 条消息（按时间正序）
+    ///
+    /// `message_id` 为 `None` 时返回最新的 `limit` 条。编辑、删除和表情回应已应用到目标消息上，
+    /// 不会作为单独的记录返回，也不占用分页数量。
+    pub async fn history_before(
+        &self,
+        peer_id: &PeerId,
+        message_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<StoredMessage>, ChatError> {
+        self.config.store.history_before(peer_id, message_id, limit).await
+    }
+
+    /// 把旧 Peer ID 的会话和聊天记录迁移到新 Peer ID（对方轮换了身份）
+    pub async fn migrate_peer(&self, from: &PeerId, to: &PeerId) -> Result<(), ChatError> {
+        self.config.store.migrate_peer(from, to).await?;
+
+        let mut sessions = self.sessions.write().await;
+        if let Some(mut session) = sessions.remove(from) {
+            session.peer_id = *to;
+            if let Some(existing) = sessions.remove(to) {
+                session.merge(existing);
+            }
+            sessions.insert(*to, session);
+        }
+        drop(sessions);
+
+        self.save_outbox(from).await;
+        self.save_outbox(to).await;
+        Ok(())
+    }
+
+    /// 获取聊天记录存储
+    pub fn store(&self) -> Arc<dyn ChatStore> {
+        self.config.store.clone()
+    }
+
+    /// 获取聊天配置
+    pub fn config(&self) -> &ChatConfig {
+        &self.config
+    }
+
+    /// 持久化消息（只保存私聊的内容消息和更新消息，失败时仅记录日志）
+    async fn persist(&self, peer_id: PeerId, outgoing: bool, message: &ChatMessage) {
+        if !message.requires_delivery() || message.room_id().is_some() {
+            return;
+        }
+        let record = StoredMessage::new(peer_id, outgoing, message.clone());
+        if let Err(e) = self.config.store.append(&record).await {
+            tracing::error!("保存聊天记录失败: {}", e);
+        }
+    }
+
     /// 发送消息给单个节点
+    ///
+    /// 需要可靠投递的消息（内容消息、更新消息和聊天室控制消息）进入发件箱，
+    /// 由调用者通过 [`take_due_messages`](Self::take_due_messages) 取出后实际发送。
+    /// 目标节点离线但有过会话，或者是聊天室成员时同样会排队，等待重新上线后重试。
     pub async fn send(&self, target: PeerId, mut message: ChatMessage) -> Result<(), ChatError> {
-        // 1. 检查节点是否已验证
+        // 1. 检查节点是否已验证（离线的已知节点和聊天室成员只接受可排队的消息）
         if !self.node_manager.is_node_verified(&target).await {
-            return Err(ChatError::NodeNotVerified(target.to_string()));
+            let known = message.requires_delivery()
+                && (message.room_id().is_some() || self.sessions.read().await.contains_key(&target));
+            if !known {
+                return Err(ChatError::NodeNotVerified(target.to_string()));
+            }
+            tracing::info!("节点 {} 当前离线，消息将在其重新上线后发送", target);
         }
 
         // 2. 设置发送者信息
-        if let ChatMessage::Text(ref mut text) = message {
-            text.sender_peer_id = self.local_peer_id.to_string();
-        } else if let ChatMessage::TypingIndicator(ref mut indicator) = message {
-            indicator.sender_peer_id = self.local_peer_id.to_string();
-        }
+        message.set_sender(self.local_peer_id.to_string());
 
         // 3. 获取或创建会话
         let mut sessions = self.sessions.write().await;
@@ -62,12 +330,32 @@ impl ChatManager {
             .entry(target)
             .or_insert_with(|| ChatSession::new(target));
 
-        // 4. 编码消息并加入待发送队列
-        let _encoded = session.encode_message(message.clone())?;
-        session.enqueue_message(message.clone());
+        // 4. 编码消息（私聊消息写入会话历史，聊天室消息记录在聊天室历史中），需要可靠投递的消息加入发件箱
+        let _encoded = if message.room_id().is_some() {
+            message.encode()?
+        } else {
+            session.encode_message(message.clone())?
+        };
+        // 聊天室控制消息不在界面上显示，不跟踪投递状态
+        let queued = if let (true, Some(id)) = (message.requires_delivery(), message.id()) {
+            session.enqueue_message(message.clone());
+            if !message.is_room_control() {
+                session.track_outgoing(id.to_string());
+            }
+            true
+        } else {
+            false
+        };
+        drop(sessions);
+
+        // 5. 持久化
+        self.persist(target, true, &message).await;
+        if queued {
+            self.save_outbox(&target).await;
+        }
 
-        // 5. 发送事件通知
-        if let Some(id) = message.id() {
+        // 6. 发送事件通知
+        if let (false, Some(id)) = (message.is_room_control(), message.id()) {
             let _ = self.event_tx.send(ChatEvent::MessageSent {
                 to: target,
                 message_id: id.to_string(),
@@ -80,11 +368,7 @@ impl ChatManager {
     /// 广播消息给多个节点（一对多）
     pub async fn broadcast(&self, targets: Vec<PeerId>, mut message: ChatMessage) -> Result<(), ChatError> {
         // 设置发送者信息（只设置一次）
-        if let ChatMessage::Text(ref mut text) = message {
-            text.sender_peer_id = self.local_peer_id.to_string();
-        } else if let ChatMessage::TypingIndicator(ref mut indicator) = message {
-            indicator.sender_peer_id = self.local_peer_id.to_string();
-        }
+        message.set_sender(self.local_peer_id.to_string());
 
         // 并发发送给所有目标
         let results = futures::future::join_all(
@@ -101,8 +385,55 @@ impl ChatManager {
         }
     }
 
+    /// 设置请勿打扰
+    ///
+    /// 开启后不再产生 `PeerTyping` 事件；消息照常接收，由界面决定是否发出通知。
+    pub fn set_do_not_disturb(&self, enabled: bool) {
+        self.do_not_disturb.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 是否处于请勿打扰状态
+    pub fn is_do_not_disturb(&self) -> bool {
+        self.do_not_disturb.load(Ordering::Relaxed)
+    }
+
     /// 处理收到的消息
-    pub async fn handle_received_message(&self, from: PeerId, message: ChatMessage) {
+    pub async fn handle_received_message(&self, from: PeerId, mut message: ChatMessage) {
+        // 以实际连接的节点为准，防止冒充他人编辑或删除消息
+        message.set_sender(from.to_string());
+        match message {
+            // 确认消息只更新投递状态，不进入会话历史
+            ChatMessage::Ack(ack) => {
+                self.handle_ack(from, &ack).await;
+                return;
+            }
+            ChatMessage::RoomCreate(room) => {
+                self.handle_room_create(from, room).await;
+                return;
+            }
+            ChatMessage::RoomInvite(invite) => {
+                self.handle_room_invite(from, invite).await;
+                return;
+            }
+            ChatMessage::RoomLeave(leave) => {
+                self.handle_room_leave(from, leave).await;
+                return;
+            }
+            // 更新版本的节点发来的消息，当前版本无法处理
+            ChatMessage::Unknown(unknown) => {
+                tracing::debug!("忽略来自 {} 的未知消息类型: {}", from, unknown.kind);
+                return;
+            }
+            ChatMessage::TypingIndicator(_) if self.is_do_not_disturb() => {
+                return;
+            }
+            _ if message.room_id().is_some() => {
+                self.handle_room_message(from, message).await;
+                return;
+            }
+            _ => {}
+        }
+
         // 保存到会话历史
         let mut sessions = self.sessions.write().await;
         let session = sessions
@@ -110,26 +441,343 @@ impl ChatManager {
             .or_insert_with(|| ChatSession::new(from));
 
         session.add_to_history(message.clone());
+        if let (true, Some(id)) = (message.is_content(), message.id()) {
+            session.note_incoming(id.to_string());
+        }
+        drop(sessions);
+
+        self.persist(from, false, &message).await;
 
         // 发送事件通知
         let event = match &message {
-            ChatMessage::Text(_) => ChatEvent::MessageReceived {
-                from,
-                message,
-            },
             ChatMessage::TypingIndicator(typing) => ChatEvent::PeerTyping {
                 from,
                 is_typing: typing.is_typing,
             },
-            ChatMessage::Ack(ack) => ChatEvent::MessageAcknowledged {
+            _ => ChatEvent::MessageReceived {
                 from,
-                message_id: ack.message_id.clone(),
+                message,
             },
         };
 
         let _ = self.event_tx.send(event);
     }
 
+    /// 创建聊天室（本地节点为创建者）
+    ///
+    /// `ChatMessage::RoomCreate` 放入其他成员的发件箱，由调用者取出后发送。
+    pub async fn create_room(&self, name: String, members: &[PeerId]) -> ChatRoom {
+        let room = ChatRoom::new(name, self.local_peer_id, members);
+        self.rooms
+            .write()
+            .await
+            .insert(room.id.clone(), RoomState::new(room.clone()));
+
+        tracing::info!("创建聊天室 {} ({} 名成员)", room.name, room.members.len());
+        self.persist_room(&room, false).await;
+        self.queue_for_members(&room.other_members(&self.local_peer_id), &ChatMessage::RoomCreate(room.clone()))
+            .await;
+        let _ = self.event_tx.send(ChatEvent::RoomUpdated { room: room.clone() });
+        room
+    }
+
+    /// 邀请新成员加入聊天室，返回更新后的聊天室
+    ///
+    /// `ChatMessage::RoomInvite` 放入所有其他成员（包括新成员）的发件箱，由调用者取出后发送。
+    pub async fn invite_to_room(&self, room_id: &str, peers: &[PeerId]) -> Result<ChatRoom, ChatError> {
+        let room = {
+            let mut rooms = self.rooms.write().await;
+            let state = rooms
+                .get_mut(room_id)
+                .ok_or_else(|| ChatError::RoomNotFound(room_id.to_string()))?;
+            state.room.add_members(peers);
+            state.room.clone()
+        };
+
+        self.persist_room(&room, false).await;
+        let invite = RoomInvite::new(room.clone(), &self.local_peer_id);
+        self.queue_for_members(&room.other_members(&self.local_peer_id), &ChatMessage::RoomInvite(invite))
+            .await;
+        let _ = self.event_tx.send(ChatEvent::RoomUpdated { room: room.clone() });
+        Ok(room)
+    }
+
+    /// 退出聊天室并删除本地消息记录，返回退出前的聊天室
+    ///
+    /// `ChatMessage::RoomLeave` 放入其他成员的发件箱，由调用者取出后发送。
+    /// 聊天室 ID 会被保留，之后不再接受该聊天室的消息和邀请。
+    pub async fn leave_room(&self, room_id: &str) -> Result<ChatRoom, ChatError> {
+        let state = self
+            .rooms
+            .write()
+            .await
+            .remove(room_id)
+            .ok_or_else(|| ChatError::RoomNotFound(room_id.to_string()))?;
+        self.left_rooms.write().await.insert(room_id.to_string());
+        self.persist_room(&state.room, true).await;
+        tracing::info!("退出聊天室 {}", state.room.name);
+
+        let leave = RoomLeave::new(room_id.to_string(), &self.local_peer_id);
+        self.queue_for_members(&state.room.other_members(&self.local_peer_id), &ChatMessage::RoomLeave(leave))
+            .await;
+        Ok(state.room)
+    }
+
+    /// 是否已退出该聊天室
+    async fn has_left_room(&self, room_id: &str) -> bool {
+        self.left_rooms.read().await.contains(room_id)
+    }
+
+    /// 获取所有聊天室
+    pub async fn rooms(&self) -> Vec<ChatRoom> {
+        self.rooms
+            .read()
+            .await
+            .values()
+            .map(|state| state.room.clone())
+            .collect()
+    }
+
+    /// 获取指定聊天室
+    pub async fn room(&self, room_id: &str) -> Option<ChatRoom> {
+        self.rooms.read().await.get(room_id).map(|state| state.room.clone())
+    }
+
+    /// 获取聊天室的消息历史
+    pub async fn room_history(&self, room_id: &str) -> Vec<ChatMessage> {
+        self.rooms
+            .read()
+            .await
+            .get(room_id)
+            .map(|state| state.history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// 在聊天室中发送消息（内容消息或更新消息）
+    ///
+    /// 消息写入聊天室历史，并放入每个其他成员的发件箱。返回成功排队的成员。
+    pub async fn send_room_message(&self, room_id: &str, message: ChatMessage) -> Result<Vec<PeerId>, ChatError> {
+        if !message.is_content() && !message.is_update() {
+            return Err(ChatError::SendFailed("聊天室只支持内容消息和更新消息".to_string()));
+        }
+        let mut message = message.with_room(room_id.to_string());
+        message.set_sender(self.local_peer_id.to_string());
+
+        let members = {
+            let mut rooms = self.rooms.write().await;
+            let state = rooms
+                .get_mut(room_id)
+                .ok_or_else(|| ChatError::RoomNotFound(room_id.to_string()))?;
+            state.push(message.clone());
+            state.room.other_members(&self.local_peer_id)
+        };
+        self.persist_room_message(room_id, self.local_peer_id, true, &message).await;
+
+        Ok(self.queue_for_members(&members, &message).await)
+    }
+
+    /// 把聊天室消息放入每个成员的发件箱，返回成功排队的成员
+    async fn queue_for_members(&self, members: &[PeerId], message: &ChatMessage) -> Vec<PeerId> {
+        let mut queued = Vec::new();
+        for member in members {
+            match self.send(*member, message.clone()).await {
+                Ok(()) => queued.push(*member),
+                Err(e) => tracing::warn!("聊天室消息无法发送给 {}: {}", member, e),
+            }
+        }
+        queued
+    }
+
+    /// 收到聊天室创建通知
+    async fn handle_room_create(&self, from: PeerId, room: ChatRoom) {
+        if room.creator != from.to_string() || !room.is_member(&self.local_peer_id) || !room.has_valid_id() {
+            tracing::warn!("忽略来自 {} 的无效聊天室创建通知: {}", from, room.id);
+            return;
+        }
+        if self.has_left_room(&room.id).await {
+            tracing::debug!("已退出聊天室 {}，忽略创建通知", room.id);
+            return;
+        }
+
+        let created = {
+            let mut rooms = self.rooms.write().await;
+            if rooms.contains_key(&room.id) {
+                false
+            } else {
+                rooms.insert(room.id.clone(), RoomState::new(room.clone()));
+                true
+            }
+        };
+        if created {
+            self.persist_room(&room, false).await;
+        }
+        tracing::info!("加入聊天室 {} (创建者 {})", room.name, from);
+        let _ = self.event_tx.send(ChatEvent::RoomUpdated { room });
+    }
+
+    /// 收到邀请（自己被邀请，或其他成员被邀请后的成员变更）
+    async fn handle_room_invite(&self, from: PeerId, invite: RoomInvite) {
+        if invite.invited_by != from.to_string()
+            || !invite.room.is_member(&self.local_peer_id)
+            || !invite.room.has_valid_id()
+        {
+            tracing::warn!("忽略来自 {} 的无效聊天室邀请: {}", from, invite.room.id);
+            return;
+        }
+        if self.has_left_room(&invite.room.id).await {
+            tracing::debug!("已退出聊天室 {}，忽略邀请", invite.room.id);
+            return;
+        }
+
+        let room = {
+            let mut rooms = self.rooms.write().await;
+            match rooms.get_mut(&invite.room.id) {
+                // 只接受现有成员发出的成员变更
+                Some(state) if !state.room.is_member(&from) => {
+                    tracing::warn!("{} 不是聊天室 {} 的成员，忽略邀请", from, invite.room.id);
+                    return;
+                }
+                // 邀请只能添加成员，名称、创建者和现有成员保持不变
+                Some(state) => {
+                    state.room.add_members(&invite.room.member_ids());
+                    state.room.clone()
+                }
+                None => {
+                    rooms.insert(invite.room.id.clone(), RoomState::new(invite.room.clone()));
+                    invite.room
+                }
+            }
+        };
+        self.persist_room(&room, false).await;
+        let _ = self.event_tx.send(ChatEvent::RoomUpdated { room });
+    }
+
+    /// 收到成员退出通知
+    async fn handle_room_leave(&self, from: PeerId, leave: RoomLeave) {
+        if leave.peer_id != from.to_string() {
+            return;
+        }
+
+        let room = {
+            let mut rooms = self.rooms.write().await;
+            let Some(state) = rooms.get_mut(&leave.room_id) else {
+                return;
+            };
+            if !state.room.remove_member(&from) {
+                return;
+            }
+            state.room.clone()
+        };
+        self.persist_room(&room, false).await;
+        tracing::info!("{} 退出聊天室 {}", from, room.name);
+        let _ = self.event_tx.send(ChatEvent::RoomUpdated { room });
+    }
+
+    /// 收到聊天室消息
+    ///
+    /// 只接受已知聊天室中成员发来的消息；未知聊天室（包括已退出的）的消息直接丢弃。
+    async fn handle_room_message(&self, from: PeerId, message: ChatMessage) {
+        let Some(room_id) = message.room_id().map(str::to_string) else {
+            return;
+        };
+
+        {
+            let mut rooms = self.rooms.write().await;
+            let Some(state) = rooms.get_mut(&room_id) else {
+                tracing::warn!("忽略来自 {} 的未知聊天室 {} 的消息", from, room_id);
+                return;
+            };
+            if !state.room.is_member(&from) {
+                tracing::warn!("{} 不是聊天室 {} 的成员，忽略消息", from, room_id);
+                return;
+            }
+            state.push(message.clone());
+        }
+        self.persist_room_message(&room_id, from, false, &message).await;
+
+        if let (true, Some(id), Some(session)) =
+            (message.is_content(), message.id(), self.sessions.write().await.get_mut(&from))
+        {
+            session.note_incoming(id.to_string());
+        }
+
+        let _ = self.event_tx.send(ChatEvent::RoomMessage {
+            room_id,
+            from,
+            message,
+        });
+    }
+
+    /// 处理对方发来的确认（送达确认或已读回执）
+    async fn handle_ack(&self, from: PeerId, ack: &MessageAck) {
+        let changed = {
+            let mut sessions = self.sessions.write().await;
+            let Some(session) = sessions.get_mut(&from) else {
+                return;
+            };
+            if ack.read {
+                let read = session.mark_read_up_to(&ack.message_id);
+                // 已读的消息必然已送达，不再重试
+                for message_id in &read {
+                    session.complete_outgoing(message_id);
+                }
+                read
+            } else if ack.received && session.advance_status(&ack.message_id, DeliveryStatus::Delivered) {
+                vec![ack.message_id.clone()]
+            } else {
+                Vec::new()
+            }
+        };
+
+        let status = if ack.read { DeliveryStatus::Read } else { DeliveryStatus::Delivered };
+        if ack.read && !changed.is_empty() {
+            self.save_outbox(&from).await;
+        }
+        for message_id in changed {
+            let _ = self.event_tx.send(ChatEvent::MessageStatusChanged {
+                peer_id: from,
+                message_id,
+                status,
+            });
+        }
+    }
+
+    /// 更新发出消息的投递状态（状态只会向前推进）
+    ///
+    /// 状态发生变化时发送 `ChatEvent::MessageStatusChanged`，返回是否变化。
+    pub async fn update_status(&self, peer_id: &PeerId, message_id: &str, status: DeliveryStatus) -> bool {
+        let changed = {
+            let mut sessions = self.sessions.write().await;
+            sessions
+                .get_mut(peer_id)
+                .is_some_and(|session| session.advance_status(message_id, status))
+        };
+
+        if changed {
+            let _ = self.event_tx.send(ChatEvent::MessageStatusChanged {
+                peer_id: *peer_id,
+                message_id: message_id.to_string(),
+                status,
+            });
+        }
+        changed
+    }
+
+    /// 获取发出消息的投递状态
+    pub async fn delivery_status(&self, peer_id: &PeerId, message_id: &str) -> Option<DeliveryStatus> {
+        let sessions = self.sessions.read().await;
+        sessions.get(peer_id).and_then(|s| s.delivery_status(message_id))
+    }
+
+    /// 将与指定节点的会话标记为已读
+    ///
+    /// 有未读消息时返回需要发送给对方的已读回执。
+    pub async fn mark_read(&self, peer_id: &PeerId) -> Option<ChatMessage> {
+        let mut sessions = self.sessions.write().await;
+        let message_id = sessions.get_mut(peer_id)?.take_unread()?;
+        Some(ChatMessage::Ack(MessageAck::read(message_id)))
+    }
+
     /// 获取会话的消息历史
     pub async fn get_history(&self, peer_id: &PeerId) -> Vec<ChatMessage> {
         let sessions = self.sessions.read().await;
@@ -155,7 +803,139 @@ impl ChatManager {
         }
     }
 
-    /// 获取待发送的编码消息（用于通过 Swarm 发送）
+    /// 取出发件箱中到期需要（重新）发送的消息，并标记为发送中
+    pub async fn take_due_messages(&self, peer_id: &PeerId) -> Vec<ChatMessage> {
+        let mut sessions = self.sessions.write().await;
+        sessions
+            .get_mut(peer_id)
+            .map(|s| s.take_due(Instant::now()))
+            .unwrap_or_default()
+    }
+
+    /// 发件箱中有到期消息的节点
+    pub async fn peers_with_due_messages(&self) -> Vec<PeerId> {
+        let now = Instant::now();
+        let sessions = self.sessions.read().await;
+        sessions
+            .values()
+            .filter(|s| s.has_due(now))
+            .map(|s| s.peer_id())
+            .collect()
+    }
+
+    /// 对方已收到消息：从发件箱移除并更新为已送达
+    pub async fn handle_delivered(&self, peer_id: &PeerId, message_id: &str) {
+        let removed = self
+            .sessions
+            .write()
+            .await
+            .get_mut(peer_id)
+            .is_some_and(|session| session.complete_outgoing(message_id));
+        if removed {
+            self.save_outbox(peer_id).await;
+        }
+        self.update_status(peer_id, message_id, DeliveryStatus::Delivered).await;
+    }
+
+    /// 发送失败：发送 `ChatEvent::MessageSendFailed`，消息留在发件箱中按退避时间稍后重试
+    pub async fn handle_send_failed(&self, peer_id: &PeerId, message_id: &str, reason: String) {
+        {
+            let mut sessions = self.sessions.write().await;
+            if let Some(session) = sessions.get_mut(peer_id) {
+                if let Some(delay) = session.retry_later(message_id, reason.clone(), &self.config, Instant::now()) {
+                    tracing::debug!("消息 {} 发送失败，{:?} 后重试", message_id, delay);
+                }
+            }
+        }
+
+        let _ = self.event_tx.send(ChatEvent::MessageSendFailed {
+            peer: *peer_id,
+            message_id: message_id.to_string(),
+            reason,
+        });
+    }
+
+    /// 发送请求失败
+    ///
+    /// 对方只支持聊天协议 1.0.0 且消息无法降级时（编码返回 `ErrorKind::Unsupported`），
+    /// 重试也不会成功，按 [`Self::handle_rejected`] 处理；其他错误按 [`Self::handle_send_failed`] 稍后重试。
+    pub async fn handle_outbound_failure(&self, peer_id: &PeerId, message_id: &str, error: &OutboundFailure) {
+        match error {
+            OutboundFailure::Io(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+                tracing::warn!("{} 不支持消息 {}: {}", peer_id, message_id, e);
+                self.fail_permanently(peer_id, message_id, e.to_string()).await;
+            }
+            _ => self.handle_send_failed(peer_id, message_id, error.to_string()).await,
+        }
+    }
+
+    /// 对方明确拒绝接收（例如不接受未受信任节点的消息）：不再重试，
+    /// 从发件箱移除并标记为失败，发送 `ChatEvent::DeliveryFailed`
+    pub async fn handle_rejected(&self, peer_id: &PeerId, message_id: &str, reason: String) {
+        tracing::warn!("{} 拒绝接收消息 {}: {}", peer_id, message_id, reason);
+        self.fail_permanently(peer_id, message_id, reason).await;
+    }
+
+    /// 消息无法送达且不再重试：从发件箱移除并标记为失败，发送 `ChatEvent::DeliveryFailed`
+    async fn fail_permanently(&self, peer_id: &PeerId, message_id: &str, reason: String) {
+        let removed = {
+            let mut sessions = self.sessions.write().await;
+            sessions.get_mut(peer_id).is_some_and(|session| {
+                session.advance_status(message_id, DeliveryStatus::Failed);
+                session.complete_outgoing(message_id)
+            })
+        };
+        if removed {
+            self.save_outbox(peer_id).await;
+        }
+
+        let _ = self.event_tx.send(ChatEvent::DeliveryFailed {
+            peer_id: *peer_id,
+            message_id: message_id.to_string(),
+            reason,
+        });
+    }
+
+    /// 节点重新上线：发件箱中的消息立即重试
+    pub async fn reset_backoff(&self, peer_id: &PeerId) {
+        if let Some(session) = self.sessions.write().await.get_mut(peer_id) {
+            session.reset_backoff(Instant::now());
+        }
+    }
+
+    /// 清理超过有效期的消息，为每条放弃的消息发送 `ChatEvent::DeliveryFailed`
+    ///
+    /// 返回放弃的消息数量。
+    pub async fn expire_outbox(&self) -> usize {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut expired = Vec::new();
+        {
+            let mut sessions = self.sessions.write().await;
+            for session in sessions.values_mut() {
+                for (message_id, reason) in session.expire(now, self.config.message_ttl) {
+                    session.advance_status(&message_id, DeliveryStatus::Failed);
+                    expired.push((session.peer_id(), message_id, reason));
+                }
+            }
+        }
+
+        let count = expired.len();
+        let mut peers: Vec<PeerId> = expired.iter().map(|(peer_id, _, _)| *peer_id).collect();
+        peers.dedup();
+        for peer_id in &peers {
+            self.save_outbox(peer_id).await;
+        }
+        for (peer_id, message_id, reason) in expired {
+            tracing::warn!("消息 {} 超过有效期仍未送达 {}: {}", message_id, peer_id, reason);
+            let _ = self.event_tx.send(ChatEvent::DeliveryFailed {
+                peer_id,
+                message_id,
+                reason,
+            });
+        }
+        count
+    }
+
     /// 获取待发送的编码消息（用于通过 Swarm 发送）
     ///
     /// 此方法会自动从待发送队列中移除消息
@@ -193,7 +973,16 @@ impl ChatManager {
 
         // 发送事件通知
         let event = match &message {
-            ChatMessage::Text(_) => ChatEvent::MessageReceived {
+            ChatMessage::Unknown(unknown) => {
+                tracing::debug!("忽略来自 {} 的未知消息类型: {}", from, unknown.kind);
+                return Ok(());
+            }
+            ChatMessage::Text(_)
+            | ChatMessage::Image(_)
+            | ChatMessage::File(_)
+            | ChatMessage::Edit(_)
+            | ChatMessage::Delete(_)
+            | ChatMessage::Reaction(_) => ChatEvent::MessageReceived {
                 from,
                 message,
             },
@@ -205,6 +994,9 @@ impl ChatManager {
                 from,
                 message_id: ack.message_id.clone(),
             },
+            ChatMessage::RoomCreate(_) | ChatMessage::RoomInvite(_) | ChatMessage::RoomLeave(_) => {
+                ChatEvent::MessageReceived { from, message }
+            }
         };
 
         let _ = self.event_tx.send(event);
@@ -220,6 +1012,84 @@ impl ChatManager {
     }
 }
 
+/// 聊天室及其消息历史
+#[derive(Debug, Clone)]
+struct RoomState {
+    room: ChatRoom,
+    history: VecDeque<ChatMessage>,
+}
+
+impl RoomState {
+    fn new(room: ChatRoom) -> Self {
+        Self {
+            room,
+            history: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, message: ChatMessage) {
+        if message.is_update() {
+            apply_update_to_history(self.history.iter_mut(), &message);
+            return;
+        }
+        self.history.push_back(message);
+        while self.history.len() > MAX_HISTORY_SIZE {
+            self.history.pop_front();
+        }
+    }
+}
+
+/// 消息是否超过有效期（`queued_at` 和 `now` 为 Unix 毫秒）
+fn is_expired(queued_at: i64, now: i64, ttl: Duration) -> bool {
+    now.saturating_sub(queued_at) >= ttl.as_millis().min(i64::MAX as u128) as i64
+}
+
+/// 发件箱中的消息
+#[derive(Debug, Clone)]
+struct OutboxEntry {
+    /// 待发送的消息
+    message: ChatMessage,
+    /// 加入发件箱的时间（Unix 毫秒，用于计算有效期，重启后仍然有效）
+    queued_at: i64,
+    /// 已尝试发送的次数
+    attempts: u32,
+    /// 下次可以发送的时间
+    next_attempt: Instant,
+    /// 是否正在等待对方响应
+    in_flight: bool,
+    /// 最后一次发送失败的原因
+    last_error: Option<String>,
+}
+
+impl OutboxEntry {
+    fn new(message: ChatMessage) -> Self {
+        Self {
+            message,
+            queued_at: chrono::Utc::now().timestamp_millis(),
+            attempts: 0,
+            next_attempt: Instant::now(),
+            in_flight: false,
+            last_error: None,
+        }
+    }
+
+    /// 从存储中恢复（重试次数从零开始，立即可以发送）
+    fn restore(queued: QueuedMessage) -> Self {
+        Self {
+            queued_at: queued.queued_at,
+            ..Self::new(queued.message)
+        }
+    }
+
+    fn message_id(&self) -> Option<&str> {
+        self.message.id()
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        !self.in_flight && self.next_attempt <= now
+    }
+}
+
 /// 聊天会话
 ///
 /// 管理与单个节点的聊天会话，包括消息历史和连接状态。
@@ -228,8 +1098,12 @@ pub struct ChatSession {
     peer_id: PeerId,
     /// 消息历史
     history: VecDeque<ChatMessage>,
-    /// 待发送的消息队列
-    pending_messages: VecDeque<ChatMessage>,
+    /// 发件箱（等待对方确认收到的消息）
+    pending_messages: VecDeque<OutboxEntry>,
+    /// 发出消息的投递状态（按发送顺序）
+    outgoing: VecDeque<(String, DeliveryStatus)>,
+    /// 最新一条尚未发送已读回执的收到消息 ID
+    last_unread: Option<String>,
 }
 
 impl ChatSession {
@@ -239,6 +1113,8 @@ impl ChatSession {
             peer_id,
             history: VecDeque::with_capacity(MAX_HISTORY_SIZE),
             pending_messages: VecDeque::new(),
+            outgoing: VecDeque::new(),
+            last_unread: None,
         }
     }
 
@@ -283,16 +1159,119 @@ impl ChatSession {
 
     /// 添加待发送消息
     pub fn enqueue_message(&mut self, message: ChatMessage) {
-        self.pending_messages.push_back(message);
+        self.pending_messages.push_back(OutboxEntry::new(message));
     }
 
     /// 获取待发送的消息
     pub fn dequeue_message(&mut self) -> Option<ChatMessage> {
-        self.pending_messages.pop_front()
+        self.pending_messages.pop_front().map(|entry| entry.message)
+    }
+
+    /// 取出到期需要发送的消息，并标记为发送中
+    ///
+    /// 各条消息作为独立的请求发送，到达顺序没有保证。聊天室控制消息送达之前，
+    /// 暂缓发送同一聊天室排在其后的消息，避免对方在得知聊天室（或成员变更）之前收到消息。
+    pub fn take_due(&mut self, now: Instant) -> Vec<ChatMessage> {
+        let mut held_rooms = HashSet::new();
+        let mut due = Vec::new();
+        for entry in self.pending_messages.iter_mut() {
+            let room_id = entry.message.room_id();
+            let held = room_id.is_some_and(|id| held_rooms.contains(id));
+            if let (true, Some(id)) = (entry.message.is_room_control(), room_id) {
+                held_rooms.insert(id.to_string());
+            }
+            if held || !entry.is_due(now) {
+                continue;
+            }
+            entry.in_flight = true;
+            entry.attempts += 1;
+            due.push(entry.message.clone());
+        }
+        due
+    }
+
+    /// 是否有到期需要发送的消息
+    pub fn has_due(&self, now: Instant) -> bool {
+        self.pending_messages.iter().any(|entry| entry.is_due(now))
+    }
+
+    /// 消息已送达，从发件箱移除
+    pub fn complete_outgoing(&mut self, message_id: &str) -> bool {
+        let before = self.pending_messages.len();
+        self.pending_messages
+            .retain(|entry| entry.message_id() != Some(message_id));
+        self.pending_messages.len() != before
+    }
+
+    /// 发送失败，按退避时间安排重试，返回等待时间
+    pub fn retry_later(
+        &mut self,
+        message_id: &str,
+        reason: String,
+        config: &ChatConfig,
+        now: Instant,
+    ) -> Option<Duration> {
+        let entry = self
+            .pending_messages
+            .iter_mut()
+            .find(|entry| entry.message_id() == Some(message_id))?;
+        let delay = config.retry_delay(entry.attempts);
+        entry.in_flight = false;
+        entry.next_attempt = now + delay;
+        entry.last_error = Some(reason);
+        Some(delay)
+    }
+
+    /// 取消退避等待，所有未在发送中的消息立即可以重试
+    pub fn reset_backoff(&mut self, now: Instant) {
+        for entry in self.pending_messages.iter_mut().filter(|e| !e.in_flight) {
+            entry.next_attempt = now;
+        }
+    }
+
+    /// 发件箱的持久化形式
+    pub fn queued_messages(&self) -> Vec<QueuedMessage> {
+        self.pending_messages
+            .iter()
+            .map(|entry| QueuedMessage {
+                peer_id: self.peer_id.to_string(),
+                message: entry.message.clone(),
+                queued_at: entry.queued_at,
+            })
+            .collect()
+    }
+
+    /// 移除超过有效期的消息（发送中的消息等待结果后再判断），返回消息 ID 和失败原因
+    ///
+    /// `now` 为 Unix 时间戳（毫秒）。
+    pub fn expire(&mut self, now: i64, ttl: Duration) -> Vec<(String, String)> {
+        let mut expired = Vec::new();
+        self.pending_messages.retain(|entry| {
+            if entry.in_flight || !is_expired(entry.queued_at, now, ttl) {
+                return true;
+            }
+            if let Some(id) = entry.message_id() {
+                let reason = entry
+                    .last_error
+                    .clone()
+                    .unwrap_or_else(|| "节点一直离线".to_string());
+                expired.push((id.to_string(), reason));
+            }
+            false
+        });
+        expired
     }
 
     /// 添加消息到历史
+    ///
+    /// 编辑、删除和表情回应就地应用到目标消息上，不单独占用一条历史。
     pub fn add_to_history(&mut self, message: ChatMessage) {
+        if message.is_update() {
+            if !apply_update_to_history(self.history.iter_mut(), &message) {
+                tracing::debug!("更新的目标消息不在历史中: {:?}", message.target_id());
+            }
+            return;
+        }
         self.history.push_back(message);
 
         // 限制历史大小
@@ -301,6 +1280,18 @@ impl ChatSession {
         }
     }
 
+    /// 合并同一节点的另一个会话（身份轮换后新旧 Peer ID 的会话），`other` 的内容排在后面
+    fn merge(&mut self, other: ChatSession) {
+        for message in other.history {
+            self.add_to_history(message);
+        }
+        self.pending_messages.extend(other.pending_messages);
+        self.outgoing.extend(other.outgoing);
+        if other.last_unread.is_some() {
+            self.last_unread = other.last_unread;
+        }
+    }
+
     /// 获取消息历史
     pub fn get_history(&self) -> Vec<ChatMessage> {
         self.history.iter().cloned().collect()
@@ -320,6 +1311,60 @@ impl ChatSession {
     pub fn pending_count(&self) -> usize {
         self.pending_messages.len()
     }
+
+    /// 开始跟踪发出消息的投递状态（初始为 `Pending`）
+    pub fn track_outgoing(&mut self, message_id: String) {
+        self.outgoing.push_back((message_id, DeliveryStatus::Pending));
+        while self.outgoing.len() > MAX_HISTORY_SIZE {
+            self.outgoing.pop_front();
+        }
+    }
+
+    /// 获取发出消息的投递状态
+    pub fn delivery_status(&self, message_id: &str) -> Option<DeliveryStatus> {
+        self.outgoing
+            .iter()
+            .find(|(id, _)| id == message_id)
+            .map(|(_, status)| *status)
+    }
+
+    /// 推进发出消息的投递状态，返回是否发生变化
+    pub fn advance_status(&mut self, message_id: &str, status: DeliveryStatus) -> bool {
+        match self.outgoing.iter_mut().find(|(id, _)| id == message_id) {
+            Some((_, current)) if *current < status => {
+                *current = status;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 将指定消息及其之前的所有发出消息标记为已读，返回状态变化的消息 ID
+    pub fn mark_read_up_to(&mut self, message_id: &str) -> Vec<String> {
+        let Some(pos) = self.outgoing.iter().position(|(id, _)| id == message_id) else {
+            return Vec::new();
+        };
+
+        self.outgoing
+            .iter_mut()
+            .take(pos + 1)
+            .filter(|(_, status)| *status < DeliveryStatus::Read)
+            .map(|(id, status)| {
+                *status = DeliveryStatus::Read;
+                id.clone()
+            })
+            .collect()
+    }
+
+    /// 记录收到的消息（等待发送已读回执）
+    pub fn note_incoming(&mut self, message_id: String) {
+        self.last_unread = Some(message_id);
+    }
+
+    /// 取出需要发送已读回执的消息 ID
+    pub fn take_unread(&mut self) -> Option<String> {
+        self.last_unread.take()
+    }
 }
 
 #[cfg(test)]
@@ -368,13 +1413,37 @@ mod tests {
         assert_eq!(history[1], msg2);
     }
 
+    #[tokio::test]
+    async fn test_chat_session_applies_updates_in_place() {
+        let peer_id = PeerId::random();
+        let mut session = ChatSession::new(peer_id);
+
+        let mut msg = ChatMessage::text("Helo".to_string());
+        msg.set_sender(peer_id.to_string());
+        let target = msg.id().unwrap().to_string();
+        session.add_to_history(msg);
+
+        let mut edit = ChatMessage::edit(target.clone(), "Hello".to_string());
+        edit.set_sender(peer_id.to_string());
+        session.add_to_history(edit);
+
+        let mut reaction = ChatMessage::reaction(target, "❤️".to_string(), true);
+        reaction.set_sender(PeerId::random().to_string());
+        session.add_to_history(reaction);
+
+        let history = session.get_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].preview(), "Hello");
+        assert_eq!(history[0].meta().unwrap().reactions.len(), 1);
+    }
+
     #[tokio::test]
     async fn test_chat_session_send() {
         let peer_id = PeerId::random();
         let mut session = ChatSession::new(peer_id);
 
         let msg = ChatMessage::text("Test".to_string());
-        let result = session.send(msg.clone()).await;
+        let result = session.encode_message(msg.clone());
 
         assert!(result.is_ok());
         let history = session.get_history();
@@ -396,4 +1465,651 @@ mod tests {
         let history = session.get_history();
         assert_eq!(history.len(), MAX_HISTORY_SIZE);
     }
+
+    #[tokio::test]
+    async fn test_delivery_status_progression() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let local_peer_id = PeerId::random();
+        let peer_id = PeerId::random();
+        node_manager
+            .add_or_update_node(crate::node::VerifiedNode::new(
+                peer_id,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+
+        let (manager, mut event_rx) = ChatManager::new(node_manager, local_peer_id);
+        let first = ChatMessage::text("one".to_string());
+        let second = ChatMessage::text("two".to_string());
+        let first_id = first.id().unwrap().to_string();
+        let second_id = second.id().unwrap().to_string();
+        manager.send(peer_id, first).await.unwrap();
+        manager.send(peer_id, second).await.unwrap();
+        while event_rx.try_recv().is_ok() {}
+
+        assert_eq!(manager.delivery_status(&peer_id, &first_id).await, Some(DeliveryStatus::Pending));
+        assert!(manager.update_status(&peer_id, &first_id, DeliveryStatus::Sent).await);
+        assert!(manager.update_status(&peer_id, &first_id, DeliveryStatus::Delivered).await);
+        // 状态不会回退
+        assert!(!manager.update_status(&peer_id, &first_id, DeliveryStatus::Sent).await);
+
+        // 已读回执确认到第二条，两条都变为已读
+        manager
+            .handle_received_message(peer_id, ChatMessage::Ack(MessageAck::read(second_id.clone())))
+            .await;
+        assert_eq!(manager.delivery_status(&peer_id, &first_id).await, Some(DeliveryStatus::Read));
+        assert_eq!(manager.delivery_status(&peer_id, &second_id).await, Some(DeliveryStatus::Read));
+
+        let mut read_events = Vec::new();
+        while let Ok(event) = event_rx.try_recv() {
+            if let ChatEvent::MessageStatusChanged { message_id, status: DeliveryStatus::Read, .. } = event {
+                read_events.push(message_id);
+            }
+        }
+        assert_eq!(read_events, vec![first_id, second_id]);
+    }
+
+    #[tokio::test]
+    async fn test_mark_read_returns_receipt_once() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let peer_id = PeerId::random();
+        let (manager, _rx) = ChatManager::new(node_manager, PeerId::random());
+
+        assert!(manager.mark_read(&peer_id).await.is_none());
+
+        let incoming = ChatMessage::text("hi".to_string());
+        let incoming_id = incoming.id().unwrap().to_string();
+        manager.handle_received_message(peer_id, incoming).await;
+
+        match manager.mark_read(&peer_id).await {
+            Some(ChatMessage::Ack(ack)) => {
+                assert!(ack.read);
+                assert_eq!(ack.message_id, incoming_id);
+            }
+            other => panic!("Expected read receipt, got {:?}", other),
+        }
+        assert!(manager.mark_read(&peer_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_outbox_retry_with_backoff() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let peer_id = PeerId::random();
+        node_manager
+            .add_or_update_node(crate::node::VerifiedNode::new(
+                peer_id,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+
+        let config = ChatConfig::new()
+            .with_retry_backoff(Duration::from_secs(3600), Duration::from_secs(7200));
+        let (manager, mut event_rx) = ChatManager::with_config(node_manager, PeerId::random(), config);
+
+        let message = ChatMessage::text("hello".to_string());
+        let message_id = message.id().unwrap().to_string();
+        manager.send(peer_id, message).await.unwrap();
+        while event_rx.try_recv().is_ok() {}
+
+        assert_eq!(manager.take_due_messages(&peer_id).await.len(), 1);
+        // 发送中的消息不会重复取出
+        assert!(manager.take_due_messages(&peer_id).await.is_empty());
+
+        // 失败后进入退避等待
+        manager.handle_send_failed(&peer_id, &message_id, "timeout".to_string()).await;
+        match event_rx.try_recv() {
+            Ok(ChatEvent::MessageSendFailed { peer, message_id: failed_id, reason }) => {
+                assert_eq!(peer, peer_id);
+                assert_eq!(failed_id, message_id);
+                assert_eq!(reason, "timeout");
+            }
+            other => panic!("Expected MessageSendFailed event, got {:?}", other),
+        }
+        assert!(manager.take_due_messages(&peer_id).await.is_empty());
+        assert!(manager.peers_with_due_messages().await.is_empty());
+
+        // 节点重新上线后立即重试
+        manager.reset_backoff(&peer_id).await;
+        assert_eq!(manager.peers_with_due_messages().await, vec![peer_id]);
+        assert_eq!(manager.take_due_messages(&peer_id).await.len(), 1);
+
+        manager.handle_delivered(&peer_id, &message_id).await;
+        assert_eq!(manager.pending_message_count(&peer_id).await, 0);
+        assert_eq!(manager.delivery_status(&peer_id, &message_id).await, Some(DeliveryStatus::Delivered));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_message_fails_without_retry() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let peer_id = PeerId::random();
+        node_manager
+            .add_or_update_node(crate::node::VerifiedNode::new(
+                peer_id,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+        let (manager, mut event_rx) = ChatManager::new(node_manager, PeerId::random());
+
+        let message = ChatMessage::text("hello".to_string());
+        let message_id = message.id().unwrap().to_string();
+        manager.send(peer_id, message).await.unwrap();
+        assert_eq!(manager.take_due_messages(&peer_id).await.len(), 1);
+        while event_rx.try_recv().is_ok() {}
+
+        manager.handle_rejected(&peer_id, &message_id, "对方拒绝接收".to_string()).await;
+        assert_eq!(manager.pending_message_count(&peer_id).await, 0);
+        assert_eq!(manager.delivery_status(&peer_id, &message_id).await, Some(DeliveryStatus::Failed));
+        assert!(matches!(
+            event_rx.try_recv(),
+            Ok(ChatEvent::DeliveryFailed { message_id: ref failed_id, .. }) if *failed_id == message_id
+        ));
+        assert!(event_rx.try_recv().is_err());
+
+        // 即使重置退避，也不会再次发送
+        manager.reset_backoff(&peer_id).await;
+        assert!(manager.peers_with_due_messages().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_message_fails_without_retry() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let peer_id = PeerId::random();
+        node_manager
+            .add_or_update_node(crate::node::VerifiedNode::new(
+                peer_id,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+        let (manager, mut event_rx) = ChatManager::new(node_manager, PeerId::random());
+
+        let target = ChatMessage::text("hello".to_string());
+        let delete = ChatMessage::delete(target.id().unwrap().to_string());
+        let delete_id = delete.id().unwrap().to_string();
+        let retried = ChatMessage::text("retry".to_string());
+        let retried_id = retried.id().unwrap().to_string();
+        manager.send(peer_id, delete).await.unwrap();
+        manager.send(peer_id, retried).await.unwrap();
+        assert_eq!(manager.take_due_messages(&peer_id).await.len(), 2);
+        while event_rx.try_recv().is_ok() {}
+
+        // 1.0.0 节点无法接收删除消息：编码失败后直接放弃
+        let unsupported = std::io::Error::new(std::io::ErrorKind::Unsupported, "不支持此消息类型");
+        manager
+            .handle_outbound_failure(&peer_id, &delete_id, &OutboundFailure::Io(unsupported))
+            .await;
+        assert_eq!(manager.delivery_status(&peer_id, &delete_id).await, Some(DeliveryStatus::Failed));
+        assert!(matches!(
+            event_rx.try_recv(),
+            Ok(ChatEvent::DeliveryFailed { message_id: ref failed_id, .. }) if *failed_id == delete_id
+        ));
+
+        // 其他错误仍然留在发件箱中重试
+        manager
+            .handle_outbound_failure(&peer_id, &retried_id, &OutboundFailure::Timeout)
+            .await;
+        assert!(matches!(event_rx.try_recv(), Ok(ChatEvent::MessageSendFailed { .. })));
+        assert_eq!(manager.pending_message_count(&peer_id).await, 1);
+
+        manager.reset_backoff(&peer_id).await;
+        let due = manager.take_due_messages(&peer_id).await;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id(), Some(retried_id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_offline_peer_queued_until_ttl() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let peer_id = PeerId::random();
+        let config = ChatConfig::new().with_message_ttl(Duration::ZERO);
+        let (manager, mut event_rx) = ChatManager::with_config(node_manager, PeerId::random(), config);
+
+        // 从未见过的节点直接拒绝
+        assert!(matches!(
+            manager.send(peer_id, ChatMessage::text("hi".to_string())).await,
+            Err(ChatError::NodeNotVerified(_))
+        ));
+
+        // 有过会话的离线节点：消息进入发件箱
+        manager.handle_received_message(peer_id, ChatMessage::text("earlier".to_string())).await;
+        let message = ChatMessage::text("later".to_string());
+        let message_id = message.id().unwrap().to_string();
+        manager.send(peer_id, message).await.unwrap();
+        assert_eq!(manager.pending_message_count(&peer_id).await, 1);
+        while event_rx.try_recv().is_ok() {}
+
+        // 超过有效期后放弃
+        assert_eq!(manager.expire_outbox().await, 1);
+        assert_eq!(manager.pending_message_count(&peer_id).await, 0);
+        assert_eq!(manager.delivery_status(&peer_id, &message_id).await, Some(DeliveryStatus::Failed));
+        match event_rx.try_recv() {
+            Ok(ChatEvent::DeliveryFailed { peer_id: failed_peer, message_id: failed_id, .. }) => {
+                assert_eq!(failed_peer, peer_id);
+                assert_eq!(failed_id, message_id);
+            }
+            other => panic!("Expected DeliveryFailed event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_until_cap() {
+        let config = ChatConfig::new()
+            .with_retry_backoff(Duration::from_secs(2), Duration::from_secs(10));
+        assert_eq!(config.retry_delay(1), Duration::from_secs(2));
+        assert_eq!(config.retry_delay(2), Duration::from_secs(4));
+        assert_eq!(config.retry_delay(3), Duration::from_secs(8));
+        assert_eq!(config.retry_delay(4), Duration::from_secs(10));
+        assert_eq!(config.retry_delay(100), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_history_persisted_and_reloaded() {
+        use crate::chat::FileChatStore;
+        use crate::node::VerifiedNode;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let local_peer_id = PeerId::random();
+        let peer_id = PeerId::random();
+
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        node_manager
+            .add_or_update_node(VerifiedNode::new(
+                peer_id,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+
+        {
+            let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+            let (manager, _rx) = ChatManager::with_store(node_manager.clone(), local_peer_id, store);
+            manager.send(peer_id, ChatMessage::text("out".to_string())).await.unwrap();
+            manager
+                .handle_received_message(peer_id, ChatMessage::text("in".to_string()))
+                .await;
+            // 输入提示不持久化
+            manager
+                .handle_received_message(
+                    peer_id,
+                    ChatMessage::TypingIndicator(crate::chat::TypingIndicator::new(peer_id.to_string(), true)),
+                )
+                .await;
+        }
+
+        // 模拟重启
+        let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+        let (manager, _rx) = ChatManager::with_store(node_manager, local_peer_id, store);
+        assert_eq!(manager.load_history().await.unwrap(), 2);
+
+        let history = manager.get_history(&peer_id).await;
+        assert_eq!(history.len(), 2);
+
+        let page = manager.history_before(&peer_id, None, 10).await.unwrap();
+        assert!(page[0].outgoing);
+        assert!(!page[1].outgoing);
+        assert_eq!(page[0].message.sender_peer_id(), Some(local_peer_id.to_string().as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_history_before_applies_edits_and_deletes() {
+        use crate::chat::FileChatStore;
+        use crate::node::VerifiedNode;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let local_peer_id = PeerId::random();
+        let peer_id = PeerId::random();
+        node_manager
+            .add_or_update_node(VerifiedNode::new(
+                peer_id,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+        let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+        let (manager, _rx) = ChatManager::with_store(node_manager, local_peer_id, store);
+
+        let sent = ChatMessage::text("原文".to_string());
+        let sent_id = sent.id().unwrap().to_string();
+        manager.send(peer_id, sent).await.unwrap();
+        let received = ChatMessage::text("稍后删除".to_string());
+        let received_id = received.id().unwrap().to_string();
+        manager.handle_received_message(peer_id, received).await;
+        let last = ChatMessage::text("最后一条".to_string());
+        let last_id = last.id().unwrap().to_string();
+        manager.handle_received_message(peer_id, last).await;
+
+        manager.send(peer_id, ChatMessage::edit(sent_id.clone(), "修改后".to_string())).await.unwrap();
+        manager.handle_received_message(peer_id, ChatMessage::delete(received_id.clone())).await;
+
+        // 更新记录不占分页名额，而是应用到目标消息上
+        let page = manager.history_before(&peer_id, None, 10).await.unwrap();
+        assert_eq!(page.len(), 3);
+        assert_eq!(page[0].message_id(), Some(sent_id.as_str()));
+        assert_eq!(page[0].message.preview(), "修改后");
+        assert!(page[0].message.meta().unwrap().edited_at.is_some());
+        assert!(page[1].message.meta().unwrap().deleted);
+        assert_eq!(page[1].message.preview(), "[消息已删除]");
+
+        // 从中间分页时同样应用更新
+        let page = manager.history_before(&peer_id, Some(&last_id), 2).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].message.preview(), "修改后");
+        assert!(page[1].message.meta().unwrap().deleted);
+
+        // 重新打开存储后直接分页（FFI 的查询方式）结果相同
+        let store = FileChatStore::open(dir.path()).await.unwrap();
+        let reloaded = store.history_before(&peer_id, None, 10).await.unwrap();
+        assert_eq!(reloaded, manager.history_before(&peer_id, None, 10).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_outbox_survives_restart() {
+        use crate::chat::FileChatStore;
+        use crate::node::VerifiedNode;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let local_peer_id = PeerId::random();
+        let peer_id = PeerId::random();
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        node_manager
+            .add_or_update_node(VerifiedNode::new(
+                peer_id,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+
+        let delivered = ChatMessage::text("delivered".to_string());
+        let queued = ChatMessage::text("queued".to_string());
+        let queued_id = queued.id().unwrap().to_string();
+        {
+            let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+            let (manager, _rx) = ChatManager::with_store(node_manager.clone(), local_peer_id, store);
+            manager.send(peer_id, delivered.clone()).await.unwrap();
+            manager.send(peer_id, queued).await.unwrap();
+            manager.handle_delivered(&peer_id, delivered.id().unwrap()).await;
+        }
+
+        // 重启后未送达的消息仍在发件箱中，立即可以重试
+        let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+        let (manager, _rx) = ChatManager::with_store(node_manager.clone(), local_peer_id, store);
+        manager.load_history().await.unwrap();
+        assert_eq!(manager.pending_message_count(&peer_id).await, 1);
+        assert_eq!(manager.delivery_status(&peer_id, &queued_id).await, Some(DeliveryStatus::Pending));
+        let due = manager.take_due_messages(&peer_id).await;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id(), Some(queued_id.as_str()));
+
+        // 重启时已超过有效期的消息直接放弃
+        let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+        let config = ChatConfig::new().with_store(store.clone()).with_message_ttl(Duration::ZERO);
+        let (manager, mut event_rx) = ChatManager::with_config(node_manager, local_peer_id, config);
+        manager.load_history().await.unwrap();
+        assert_eq!(manager.pending_message_count(&peer_id).await, 0);
+        assert_eq!(manager.delivery_status(&peer_id, &queued_id).await, Some(DeliveryStatus::Failed));
+        let mut failed = Vec::new();
+        while let Ok(event) = event_rx.try_recv() {
+            if let ChatEvent::DeliveryFailed { message_id, .. } = event {
+                failed.push(message_id);
+            }
+        }
+        assert_eq!(failed, vec![queued_id]);
+        assert!(store.load_outbox().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_room_messages_kept_out_of_direct_history() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let local_peer_id = PeerId::random();
+        let alice = PeerId::random();
+        let bob = PeerId::random();
+        for peer_id in [alice, bob] {
+            node_manager
+                .add_or_update_node(crate::node::VerifiedNode::new(
+                    peer_id,
+                    vec![],
+                    "/localp2p/1.0.0".to_string(),
+                    "localp2p-rust/1.0.0".to_string(),
+                ))
+                .await;
+        }
+
+        let (manager, mut event_rx) = ChatManager::new(node_manager, local_peer_id);
+        let room = manager.create_room("周末".to_string(), &[alice, bob]).await;
+        assert!(matches!(event_rx.try_recv(), Ok(ChatEvent::RoomUpdated { .. })));
+
+        let queued = manager
+            .send_room_message(&room.id, ChatMessage::text("大家好".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(queued, vec![alice, bob]);
+        // 创建通知和聊天室消息都在发件箱中
+        assert_eq!(manager.pending_message_count(&alice).await, 2);
+        assert!(manager.get_history(&alice).await.is_empty());
+        while event_rx.try_recv().is_ok() {}
+
+        // 成员发来的聊天室消息进入聊天室历史
+        let reply = ChatMessage::room_text(room.id.clone(), "你好".to_string());
+        manager.handle_received_message(alice, reply).await;
+        match event_rx.try_recv() {
+            Ok(ChatEvent::RoomMessage { room_id, from, .. }) => {
+                assert_eq!(room_id, room.id);
+                assert_eq!(from, alice);
+            }
+            other => panic!("Expected RoomMessage event, got {:?}", other),
+        }
+        assert_eq!(manager.room_history(&room.id).await.len(), 2);
+        assert!(manager.get_history(&alice).await.is_empty());
+
+        // 成员退出后不再接受其消息
+        manager
+            .handle_received_message(alice, ChatMessage::RoomLeave(RoomLeave::new(room.id.clone(), &alice)))
+            .await;
+        assert!(matches!(event_rx.try_recv(), Ok(ChatEvent::RoomUpdated { .. })));
+        manager
+            .handle_received_message(alice, ChatMessage::room_text(room.id.clone(), "还在吗".to_string()))
+            .await;
+        assert!(event_rx.try_recv().is_err());
+        assert_eq!(manager.room(&room.id).await.unwrap().member_ids(), vec![local_peer_id, bob]);
+    }
+
+    #[tokio::test]
+    async fn test_room_create_requires_sender_as_creator() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let local_peer_id = PeerId::random();
+        let creator = PeerId::random();
+        let (manager, mut event_rx) = ChatManager::new(node_manager, local_peer_id);
+
+        let room = ChatRoom::new("项目".to_string(), creator, &[local_peer_id]);
+        manager
+            .handle_received_message(PeerId::random(), ChatMessage::RoomCreate(room.clone()))
+            .await;
+        assert!(manager.rooms().await.is_empty());
+
+        manager.handle_received_message(creator, ChatMessage::RoomCreate(room.clone())).await;
+        assert!(matches!(event_rx.try_recv(), Ok(ChatEvent::RoomUpdated { .. })));
+        assert_eq!(manager.room(&room.id).await, Some(room));
+    }
+
+    #[tokio::test]
+    async fn test_room_messages_for_unknown_or_left_rooms_dropped() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let local_peer_id = PeerId::random();
+        let creator = PeerId::random();
+        let (manager, mut event_rx) = ChatManager::new(node_manager, local_peer_id);
+
+        // 未知聊天室的消息不会创建聊天室
+        manager
+            .handle_received_message(creator, ChatMessage::room_text("unknown".to_string(), "你好".to_string()))
+            .await;
+        assert!(manager.rooms().await.is_empty());
+        assert!(event_rx.try_recv().is_err());
+
+        let room = ChatRoom::new("项目".to_string(), creator, &[local_peer_id]);
+        manager.handle_received_message(creator, ChatMessage::RoomCreate(room.clone())).await;
+        assert!(matches!(event_rx.try_recv(), Ok(ChatEvent::RoomUpdated { .. })));
+        manager.leave_room(&room.id).await.unwrap();
+
+        // 退出后，后续的消息、创建通知和邀请都被忽略
+        manager
+            .handle_received_message(creator, ChatMessage::room_text(room.id.clone(), "还在吗".to_string()))
+            .await;
+        manager.handle_received_message(creator, ChatMessage::RoomCreate(room.clone())).await;
+        manager
+            .handle_received_message(creator, ChatMessage::RoomInvite(RoomInvite::new(room.clone(), &creator)))
+            .await;
+        assert!(manager.rooms().await.is_empty());
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_offline_member_receives_room_after_coming_online() {
+        let creator_nodes = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let creator = PeerId::random();
+        let member = PeerId::random();
+        let (creator_manager, _creator_rx) = ChatManager::new(creator_nodes.clone(), creator);
+        let (member_manager, mut member_rx) =
+            ChatManager::new(Arc::new(NodeManager::new(NodeManagerConfig::default())), member);
+
+        // 成员离线时创建聊天室并发送消息，两者都进入发件箱
+        let room = creator_manager.create_room("周末".to_string(), &[member]).await;
+        let queued = creator_manager
+            .send_room_message(&room.id, ChatMessage::text("大家好".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(queued, vec![member]);
+        assert_eq!(creator_manager.pending_message_count(&member).await, 2);
+
+        // 成员上线：创建通知送达之前，聊天室消息暂缓发送
+        creator_nodes
+            .add_or_update_node(crate::node::VerifiedNode::new(
+                member,
+                vec![],
+                "/localp2p/1.0.0".to_string(),
+                "localp2p-rust/1.0.0".to_string(),
+            ))
+            .await;
+        let due = creator_manager.take_due_messages(&member).await;
+        assert_eq!(due, vec![ChatMessage::RoomCreate(room.clone())]);
+        for message in due {
+            member_manager.handle_received_message(creator, message).await;
+        }
+        assert!(matches!(member_rx.try_recv(), Ok(ChatEvent::RoomUpdated { .. })));
+        creator_manager.handle_delivered(&member, &room.id).await;
+
+        let due = creator_manager.take_due_messages(&member).await;
+        assert_eq!(due.len(), 1);
+        for message in due {
+            member_manager.handle_received_message(creator, message).await;
+        }
+        match member_rx.try_recv() {
+            Ok(ChatEvent::RoomMessage { room_id, from, message }) => {
+                assert_eq!(room_id, room.id);
+                assert_eq!(from, creator);
+                assert_eq!(message.preview(), "大家好");
+            }
+            other => panic!("Expected RoomMessage event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_room_invite_only_adds_members() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let local_peer_id = PeerId::random();
+        let creator = PeerId::random();
+        let alice = PeerId::random();
+        let bob = PeerId::random();
+        let (manager, _rx) = ChatManager::new(node_manager, local_peer_id);
+
+        let room = ChatRoom::new("项目".to_string(), creator, &[local_peer_id, alice, bob]);
+        manager.handle_received_message(creator, ChatMessage::RoomCreate(room.clone())).await;
+
+        // 非创建者发来的邀请试图移除 bob、改名并冒充创建者
+        let carol = PeerId::random();
+        let mut forged = ChatRoom::new("改名".to_string(), alice, &[local_peer_id, carol]);
+        forged.id = room.id.clone();
+        manager
+            .handle_received_message(alice, ChatMessage::RoomInvite(RoomInvite::new(forged, &alice)))
+            .await;
+
+        let updated = manager.room(&room.id).await.unwrap();
+        assert_eq!(updated.name, room.name);
+        assert_eq!(updated.creator, room.creator);
+        assert_eq!(updated.member_ids(), vec![creator, local_peer_id, alice, bob, carol]);
+    }
+
+    #[tokio::test]
+    async fn test_rooms_survive_restart() {
+        use crate::chat::FileChatStore;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let local_peer_id = PeerId::random();
+        let alice = PeerId::random();
+
+        let (kept, left) = {
+            let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+            let (manager, _rx) = ChatManager::with_store(node_manager.clone(), local_peer_id, store);
+            let kept = manager.create_room("保留".to_string(), &[alice]).await;
+            let left = manager.create_room("退出".to_string(), &[alice]).await;
+            manager
+                .send_room_message(&kept.id, ChatMessage::text("大家好".to_string()))
+                .await
+                .unwrap();
+            manager
+                .handle_received_message(alice, ChatMessage::room_text(kept.id.clone(), "你好".to_string()))
+                .await;
+            manager.leave_room(&left.id).await.unwrap();
+            (kept, left)
+        };
+
+        // 模拟重启
+        let store = Arc::new(FileChatStore::open(dir.path()).await.unwrap());
+        let (manager, _rx) = ChatManager::with_store(node_manager, local_peer_id, store);
+        manager.load_history().await.unwrap();
+        assert_eq!(manager.room(&kept.id).await, Some(kept.clone()));
+        assert!(manager.room(&left.id).await.is_none());
+
+        let history = manager.room_history(&kept.id).await;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].preview(), "你好");
+        assert!(manager.get_history(&alice).await.is_empty());
+
+        // 已退出的聊天室在重启后仍不接受消息
+        manager
+            .handle_received_message(alice, ChatMessage::room_text(left.id.clone(), "回来吧".to_string()))
+            .await;
+        assert!(manager.room(&left.id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_do_not_disturb_drops_typing_indicators() {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        let (manager, mut event_rx) = ChatManager::new(node_manager, PeerId::random());
+        let peer = PeerId::random();
+        let typing = || ChatMessage::TypingIndicator(crate::chat::TypingIndicator::new(peer.to_string(), true));
+
+        manager.set_do_not_disturb(true);
+        manager.handle_received_message(peer, typing()).await;
+        assert!(event_rx.try_recv().is_err());
+
+        // 消息照常接收
+        manager.handle_received_message(peer, ChatMessage::text("你好".to_string())).await;
+        assert!(matches!(event_rx.try_recv(), Ok(ChatEvent::MessageReceived { .. })));
+
+        manager.set_do_not_disturb(false);
+        manager.handle_received_message(peer, typing()).await;
+        assert!(matches!(event_rx.try_recv(), Ok(ChatEvent::PeerTyping { is_typing: true, .. })));
+    }
 }
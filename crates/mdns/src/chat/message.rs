@@ -2,10 +2,27 @@
 //!
 //! 定义聊天功能使用的消息类型和序列化格式。
 
+use super::room::{ChatRoom, RoomInvite, RoomLeave};
+use crate::codec::Format;
+use crate::file_transfer::FileOffer;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-/// 聊天协议名称
-pub const CHAT_PROTOCOL: &str = "/localp2p/chat/1.0.0";
+/// 聊天协议名称（当前版本）
+pub const CHAT_PROTOCOL: &str = "/localp2p/chat/1.1.0";
+
+/// 聊天协议名称（当前版本的 CBOR 变体）
+pub const CHAT_PROTOCOL_CBOR: &str = "/localp2p/chat/1.1.0/cbor";
+
+/// 旧版聊天协议名称
+///
+/// 1.0.0 只认识文本、正在输入和确认三种消息，与旧节点通信时协商到该版本。
+pub const CHAT_PROTOCOL_V1_0: &str = "/localp2p/chat/1.0.0";
+
+/// 内联图片（原图 + 缩略图）的最大字节数
+///
+/// 图片以 base64 编码随消息发送，需要保证编码后不超过聊天协议 1MB 的消息上限。
+pub const MAX_INLINE_IMAGE_SIZE: usize = 512 * 1024;
 
 /// 聊天消息类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -16,6 +33,27 @@ pub enum ChatMessage {
     TypingIndicator(TypingIndicator),
     /// 消息确认
     Ack(MessageAck),
+    /// 创建聊天室（发送给所有初始成员）
+    RoomCreate(ChatRoom),
+    /// 邀请新成员加入聊天室
+    RoomInvite(RoomInvite),
+    /// 退出聊天室
+    RoomLeave(RoomLeave),
+    /// 内联图片
+    Image(ImageMessage),
+    /// 文件附件（引用一个文件传输提议）
+    File(FileAttachment),
+    /// 编辑已发送的消息
+    Edit(MessageEdit),
+    /// 删除已发送的消息
+    Delete(MessageDelete),
+    /// 表情回应
+    Reaction(Reaction),
+    /// 无法识别的消息（由更新版本的节点发送）
+    ///
+    /// 只在解码时产生，不能被发送。
+    #[serde(skip)]
+    Unknown(UnknownMessage),
 }
 
 impl ChatMessage {
@@ -27,15 +65,142 @@ impl ChatMessage {
             content,
             timestamp: chrono::Utc::now().timestamp_millis(),
             reply_to: None,
+            room_id: None,
+            meta: MessageMeta::default(),
+        })
+    }
+
+    /// 创建内联图片消息
+    ///
+    /// 缩略图由调用者生成；原图和缩略图合计超过 [`MAX_INLINE_IMAGE_SIZE`] 时返回错误，
+    /// 较大的图片应改用文件传输并发送 [`ChatMessage::file`]。
+    pub fn image(
+        mime_type: String,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+        thumbnail: Vec<u8>,
+    ) -> Result<Self, ChatError> {
+        let size = data.len() + thumbnail.len();
+        if size > MAX_INLINE_IMAGE_SIZE {
+            return Err(ChatError::TooLarge(size));
+        }
+        Ok(Self::Image(ImageMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            sender_peer_id: String::new(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            mime_type,
+            width,
+            height,
+            data,
+            thumbnail,
+            caption: None,
+            room_id: None,
+            meta: MessageMeta::default(),
+        }))
+    }
+
+    /// 创建引用文件传输提议的附件消息
+    pub fn file(offer: &FileOffer) -> Self {
+        Self::File(FileAttachment {
+            id: uuid::Uuid::new_v4().to_string(),
+            sender_peer_id: String::new(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            transfer_id: offer.transfer_id.clone(),
+            file_name: offer.file_name.clone(),
+            file_size: offer.file_size,
+            room_id: None,
+            meta: MessageMeta::default(),
         })
     }
 
+    /// 创建编辑消息（只有原消息的发送者可以编辑）
+    pub fn edit(target_id: String, content: String) -> Self {
+        Self::Edit(MessageEdit {
+            id: uuid::Uuid::new_v4().to_string(),
+            sender_peer_id: String::new(),
+            target_id,
+            content,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            room_id: None,
+        })
+    }
+
+    /// 创建删除消息（只有原消息的发送者可以删除）
+    pub fn delete(target_id: String) -> Self {
+        Self::Delete(MessageDelete {
+            id: uuid::Uuid::new_v4().to_string(),
+            sender_peer_id: String::new(),
+            target_id,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            room_id: None,
+        })
+    }
+
+    /// 创建表情回应（`added` 为 `false` 时撤回回应）
+    pub fn reaction(target_id: String, emoji: String, added: bool) -> Self {
+        Self::Reaction(Reaction {
+            id: uuid::Uuid::new_v4().to_string(),
+            sender_peer_id: String::new(),
+            target_id,
+            emoji,
+            added,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            room_id: None,
+        })
+    }
+
+    /// 创建聊天室内的文本消息
+    pub fn room_text(room_id: String, content: String) -> Self {
+        Self::text(content).with_room(room_id)
+    }
+
+    /// 设置所属聊天室（只对内容消息和更新消息有效）
+    pub fn with_room(mut self, room_id: String) -> Self {
+        let room = Some(room_id);
+        match &mut self {
+            Self::Text(t) => t.room_id = room,
+            Self::Image(i) => i.room_id = room,
+            Self::File(f) => f.room_id = room,
+            Self::Edit(e) => e.room_id = room,
+            Self::Delete(d) => d.room_id = room,
+            Self::Reaction(r) => r.room_id = room,
+            _ => {}
+        }
+        self
+    }
+
+    /// 所属聊天室 ID（私聊消息、正在输入提示和确认消息为 `None`）
+    pub fn room_id(&self) -> Option<&str> {
+        match self {
+            Self::Text(t) => t.room_id.as_deref(),
+            Self::Image(i) => i.room_id.as_deref(),
+            Self::File(f) => f.room_id.as_deref(),
+            Self::Edit(e) => e.room_id.as_deref(),
+            Self::Delete(d) => d.room_id.as_deref(),
+            Self::Reaction(r) => r.room_id.as_deref(),
+            Self::RoomCreate(room) => Some(&room.id),
+            Self::RoomInvite(invite) => Some(&invite.room.id),
+            Self::RoomLeave(leave) => Some(&leave.room_id),
+            _ => None,
+        }
+    }
+
     /// 获取消息 ID
     pub fn id(&self) -> Option<&str> {
         match self {
             Self::Text(t) => Some(&t.id),
+            Self::Image(i) => Some(&i.id),
+            Self::File(f) => Some(&f.id),
+            Self::Edit(e) => Some(&e.id),
+            Self::Delete(d) => Some(&d.id),
+            Self::Reaction(r) => Some(&r.id),
             Self::Ack(a) => Some(&a.message_id),
-            Self::TypingIndicator(_) => None,
+            // 每个聊天室只创建一次，创建通知直接使用聊天室 ID
+            Self::RoomCreate(room) => Some(&room.id),
+            Self::RoomInvite(invite) => Some(&invite.id),
+            Self::RoomLeave(leave) => Some(&leave.id),
+            Self::TypingIndicator(_) | Self::Unknown(_) => None,
         }
     }
 
@@ -43,8 +208,194 @@ impl ChatMessage {
     pub fn sender_peer_id(&self) -> Option<&str> {
         match self {
             Self::Text(t) => Some(&t.sender_peer_id),
+            Self::Image(i) => Some(&i.sender_peer_id),
+            Self::File(f) => Some(&f.sender_peer_id),
+            Self::Edit(e) => Some(&e.sender_peer_id),
+            Self::Delete(d) => Some(&d.sender_peer_id),
+            Self::Reaction(r) => Some(&r.sender_peer_id),
             Self::TypingIndicator(t) => Some(&t.sender_peer_id),
-            Self::Ack(_) => None,
+            Self::RoomCreate(room) => Some(&room.creator),
+            Self::RoomInvite(invite) => Some(&invite.invited_by),
+            Self::RoomLeave(leave) => Some(&leave.peer_id),
+            Self::Ack(_) | Self::Unknown(_) => None,
+        }
+    }
+
+    /// 设置发送者 Peer ID（聊天室控制消息和确认消息不受影响）
+    pub fn set_sender(&mut self, peer_id: String) {
+        match self {
+            Self::Text(t) => t.sender_peer_id = peer_id,
+            Self::Image(i) => i.sender_peer_id = peer_id,
+            Self::File(f) => f.sender_peer_id = peer_id,
+            Self::Edit(e) => e.sender_peer_id = peer_id,
+            Self::Delete(d) => d.sender_peer_id = peer_id,
+            Self::Reaction(r) => r.sender_peer_id = peer_id,
+            Self::TypingIndicator(t) => t.sender_peer_id = peer_id,
+            Self::Ack(_)
+            | Self::RoomCreate(_)
+            | Self::RoomInvite(_)
+            | Self::RoomLeave(_)
+            | Self::Unknown(_) => {}
+        }
+    }
+
+    /// 发送时间（Unix 时间戳，毫秒）
+    pub fn timestamp(&self) -> Option<i64> {
+        match self {
+            Self::Text(t) => Some(t.timestamp),
+            Self::Image(i) => Some(i.timestamp),
+            Self::File(f) => Some(f.timestamp),
+            Self::Edit(e) => Some(e.timestamp),
+            Self::Delete(d) => Some(d.timestamp),
+            Self::Reaction(r) => Some(r.timestamp),
+            Self::Ack(a) => Some(a.timestamp),
+            Self::RoomCreate(room) => Some(room.created_at),
+            Self::TypingIndicator(_)
+            | Self::RoomInvite(_)
+            | Self::RoomLeave(_)
+            | Self::Unknown(_) => None,
+        }
+    }
+
+    /// 是否为显示在会话历史中的内容消息（文本、图片、文件）
+    pub fn is_content(&self) -> bool {
+        matches!(self, Self::Text(_) | Self::Image(_) | Self::File(_))
+    }
+
+    /// 是否为修改已有消息的更新消息（编辑、删除、表情回应）
+    pub fn is_update(&self) -> bool {
+        matches!(self, Self::Edit(_) | Self::Delete(_) | Self::Reaction(_))
+    }
+
+    /// 是否为聊天室控制消息（创建、邀请、退出）
+    pub fn is_room_control(&self) -> bool {
+        matches!(self, Self::RoomCreate(_) | Self::RoomInvite(_) | Self::RoomLeave(_))
+    }
+
+    /// 是否需要可靠投递（进入发件箱，直到对方确认收到）
+    ///
+    /// 聊天室控制消息也需要可靠投递：错过创建通知或邀请的成员不知道聊天室的存在，会丢弃之后的聊天室消息。
+    pub fn requires_delivery(&self) -> bool {
+        self.is_content() || self.is_update() || self.is_room_control()
+    }
+
+    /// 更新消息所针对的消息 ID
+    pub fn target_id(&self) -> Option<&str> {
+        match self {
+            Self::Edit(e) => Some(&e.target_id),
+            Self::Delete(d) => Some(&d.target_id),
+            Self::Reaction(r) => Some(&r.target_id),
+            _ => None,
+        }
+    }
+
+    /// 内容消息的附加状态（编辑、删除、表情回应）
+    pub fn meta(&self) -> Option<&MessageMeta> {
+        match self {
+            Self::Text(t) => Some(&t.meta),
+            Self::Image(i) => Some(&i.meta),
+            Self::File(f) => Some(&f.meta),
+            _ => None,
+        }
+    }
+
+    fn meta_mut(&mut self) -> Option<&mut MessageMeta> {
+        match self {
+            Self::Text(t) => Some(&mut t.meta),
+            Self::Image(i) => Some(&mut i.meta),
+            Self::File(f) => Some(&mut f.meta),
+            _ => None,
+        }
+    }
+
+    /// 把更新消息应用到本消息上，返回是否生效
+    ///
+    /// 编辑和删除只接受原发送者的更新；编辑只对文本消息和图片说明有效。
+    /// 删除后清空内容，只保留删除标记。
+    pub fn apply_update(&mut self, update: &ChatMessage) -> bool {
+        if self.id().is_none() || update.target_id() != self.id() || !self.is_content() {
+            return false;
+        }
+
+        match update {
+            Self::Edit(edit) => {
+                if self.sender_peer_id() != Some(edit.sender_peer_id.as_str())
+                    || self.meta().is_some_and(|meta| meta.deleted)
+                {
+                    return false;
+                }
+                match self {
+                    Self::Text(t) => t.content = edit.content.clone(),
+                    Self::Image(i) => i.caption = Some(edit.content.clone()),
+                    _ => return false,
+                }
+                if let Some(meta) = self.meta_mut() {
+                    meta.edited_at = Some(edit.timestamp);
+                }
+                true
+            }
+            Self::Delete(delete) => {
+                if self.sender_peer_id() != Some(delete.sender_peer_id.as_str()) {
+                    return false;
+                }
+                match self {
+                    Self::Text(t) => t.content.clear(),
+                    Self::Image(i) => {
+                        i.data.clear();
+                        i.thumbnail.clear();
+                        i.caption = None;
+                    }
+                    _ => {}
+                }
+                if let Some(meta) = self.meta_mut() {
+                    meta.deleted = true;
+                    meta.reactions.clear();
+                }
+                true
+            }
+            Self::Reaction(reaction) => {
+                let Some(meta) = self.meta_mut() else {
+                    return false;
+                };
+                if meta.deleted {
+                    return false;
+                }
+                let peers = meta.reactions.entry(reaction.emoji.clone()).or_default();
+                let changed = if reaction.added {
+                    !peers.contains(&reaction.sender_peer_id) && {
+                        peers.push(reaction.sender_peer_id.clone());
+                        true
+                    }
+                } else {
+                    let before = peers.len();
+                    peers.retain(|peer| *peer != reaction.sender_peer_id);
+                    peers.len() != before
+                };
+                if peers.is_empty() {
+                    meta.reactions.remove(&reaction.emoji);
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// 用于列表和通知显示的简短文本
+    pub fn preview(&self) -> String {
+        if self.meta().is_some_and(|meta| meta.deleted) {
+            return "[消息已删除]".to_string();
+        }
+        match self {
+            Self::Text(t) => t.content.clone(),
+            Self::Image(i) => match &i.caption {
+                Some(caption) => format!("[图片] {}", caption),
+                None => "[图片]".to_string(),
+            },
+            Self::File(f) => format!("[文件] {} ({} 字节)", f.file_name, f.file_size),
+            Self::Edit(e) => e.content.clone(),
+            Self::Delete(_) => "[消息已删除]".to_string(),
+            Self::Reaction(r) => r.emoji.clone(),
+            _ => String::new(),
         }
     }
 
@@ -55,12 +406,73 @@ impl ChatMessage {
     }
 
     /// 从字节数组反序列化消息
+    ///
+    /// 无法识别的消息类型不会报错，而是解析为 [`ChatMessage::Unknown`]，
+    /// 这样新版本节点增加消息类型后旧节点仍能继续工作。
     pub fn decode(data: &[u8]) -> Result<Self, ChatError> {
-        serde_json::from_slice(data)
-            .map_err(|e| ChatError::Deserialization(e.to_string()))
+        Self::decode_as(Format::Json, data)
+    }
+
+    /// 按指定格式反序列化消息（规则同 [`ChatMessage::decode`]）
+    pub fn decode_as(format: Format, data: &[u8]) -> Result<Self, ChatError> {
+        match format.decode(data) {
+            Ok(WireMessage::Known(message)) => Ok(*message),
+            Ok(WireMessage::Unknown(fields)) if fields.len() == 1 => {
+                let kind = fields.into_keys().next().unwrap_or_default();
+                Ok(Self::Unknown(UnknownMessage { kind }))
+            }
+            Ok(WireMessage::Unknown(_)) => Err(ChatError::Deserialization(
+                "消息应只包含一个类型标签".to_string(),
+            )),
+            Err(e) => Err(ChatError::Deserialization(e.to_string())),
+        }
+    }
+
+    /// 转换为旧版 1.0.0 协议能够解析的消息
+    ///
+    /// 图片和文件附件降级为同 ID 的文本摘要（保证确认能对应上原消息），
+    /// 聊天室和编辑等消息在 1.0.0 中没有对应类型，返回 `None`。
+    pub fn to_v1_0(&self) -> Option<Self> {
+        let fallback = |id: &str, sender: &str, timestamp: i64| {
+            Self::Text(TextMessage {
+                id: id.to_string(),
+                sender_peer_id: sender.to_string(),
+                content: self.preview(),
+                timestamp,
+                reply_to: None,
+                room_id: None,
+                meta: MessageMeta::default(),
+            })
+        };
+        match self {
+            Self::Text(t) if t.room_id.is_none() => Some(self.clone()),
+            Self::TypingIndicator(_) | Self::Ack(_) => Some(self.clone()),
+            Self::Image(i) if i.room_id.is_none() => {
+                Some(fallback(&i.id, &i.sender_peer_id, i.timestamp))
+            }
+            Self::File(f) if f.room_id.is_none() => {
+                Some(fallback(&f.id, &f.sender_peer_id, f.timestamp))
+            }
+            _ => None,
+        }
     }
 }
 
+/// 线上格式：已知的消息类型，或者只保留类型标签的未知消息
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WireMessage {
+    Known(Box<ChatMessage>),
+    Unknown(BTreeMap<String, serde::de::IgnoredAny>),
+}
+
+/// 无法识别的消息
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownMessage {
+    /// 消息类型标签
+    pub kind: String,
+}
+
 /// 文本消息
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TextMessage {
@@ -78,6 +490,14 @@ pub struct TextMessage {
 
     /// 回复的消息 ID（可选）
     pub reply_to: Option<String>,
+
+    /// 所属聊天室 ID（私聊消息为 `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+
+    /// 编辑、删除和表情回应状态
+    #[serde(default, skip_serializing_if = "MessageMeta::is_empty")]
+    pub meta: MessageMeta,
 }
 
 impl TextMessage {
@@ -92,6 +512,206 @@ impl TextMessage {
         self.reply_to = Some(message_id);
         self
     }
+
+    /// 设置所属聊天室
+    pub fn with_room(mut self, room_id: String) -> Self {
+        self.room_id = Some(room_id);
+        self
+    }
+}
+
+/// 内容消息的附加状态
+///
+/// 由后续收到的编辑、删除和表情回应消息就地更新，不单独出现在会话历史中。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MessageMeta {
+    /// 最后编辑时间（Unix 时间戳，毫秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<i64>,
+
+    /// 是否已删除
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+
+    /// 表情回应（表情 -> 回应者的 Peer ID）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reactions: BTreeMap<String, Vec<String>>,
+}
+
+impl MessageMeta {
+    /// 是否没有任何附加状态
+    pub fn is_empty(&self) -> bool {
+        self.edited_at.is_none() && !self.deleted && self.reactions.is_empty()
+    }
+}
+
+/// 内联图片消息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImageMessage {
+    /// 消息唯一 ID（UUID）
+    pub id: String,
+
+    /// 发送者的 Peer ID
+    pub sender_peer_id: String,
+
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+
+    /// MIME 类型（如 `image/jpeg`）
+    pub mime_type: String,
+
+    /// 原图宽度（像素）
+    pub width: u32,
+
+    /// 原图高度（像素）
+    pub height: u32,
+
+    /// 原图数据（传输时为 base64）
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+
+    /// 缩略图数据（传输时为 base64）
+    #[serde(with = "base64_bytes")]
+    pub thumbnail: Vec<u8>,
+
+    /// 图片说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+
+    /// 所属聊天室 ID（私聊消息为 `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+
+    /// 编辑、删除和表情回应状态
+    #[serde(default, skip_serializing_if = "MessageMeta::is_empty")]
+    pub meta: MessageMeta,
+}
+
+/// 文件附件消息
+///
+/// 文件内容通过文件传输协议发送，消息中只引用对应提议的传输 ID。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileAttachment {
+    /// 消息唯一 ID（UUID）
+    pub id: String,
+
+    /// 发送者的 Peer ID
+    pub sender_peer_id: String,
+
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+
+    /// 文件传输 ID（对应 `FileOffer::transfer_id`）
+    pub transfer_id: String,
+
+    /// 文件名
+    pub file_name: String,
+
+    /// 文件大小（字节）
+    pub file_size: u64,
+
+    /// 所属聊天室 ID（私聊消息为 `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+
+    /// 编辑、删除和表情回应状态
+    #[serde(default, skip_serializing_if = "MessageMeta::is_empty")]
+    pub meta: MessageMeta,
+}
+
+/// 编辑消息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MessageEdit {
+    /// 本条更新的唯一 ID（UUID）
+    pub id: String,
+
+    /// 发送者的 Peer ID
+    pub sender_peer_id: String,
+
+    /// 被编辑的消息 ID
+    pub target_id: String,
+
+    /// 新内容（图片消息为新的说明）
+    pub content: String,
+
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+
+    /// 所属聊天室 ID（私聊消息为 `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+}
+
+/// 删除消息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MessageDelete {
+    /// 本条更新的唯一 ID（UUID）
+    pub id: String,
+
+    /// 发送者的 Peer ID
+    pub sender_peer_id: String,
+
+    /// 被删除的消息 ID
+    pub target_id: String,
+
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+
+    /// 所属聊天室 ID（私聊消息为 `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+}
+
+/// 表情回应
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Reaction {
+    /// 本条更新的唯一 ID（UUID）
+    pub id: String,
+
+    /// 发送者的 Peer ID
+    pub sender_peer_id: String,
+
+    /// 回应的消息 ID
+    pub target_id: String,
+
+    /// 表情
+    pub emoji: String,
+
+    /// `true` 为添加回应，`false` 为撤回
+    pub added: bool,
+
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+
+    /// 所属聊天室 ID（私聊消息为 `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+}
+
+/// 把更新消息应用到历史中最近的目标消息上，返回是否生效
+pub fn apply_update_to_history<'a, I>(history: I, update: &ChatMessage) -> bool
+where
+    I: DoubleEndedIterator<Item = &'a mut ChatMessage>,
+{
+    history
+        .rev()
+        .find(|message| message.id().is_some() && message.id() == update.target_id())
+        .is_some_and(|message| message.apply_update(update))
+}
+
+/// 二进制数据以 base64 字符串序列化
+mod base64_bytes {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
 }
 
 /// 正在输入提示
@@ -115,6 +735,8 @@ impl TypingIndicator {
 }
 
 /// 消息确认
+///
+/// `read` 为 `true` 时表示已读回执：对方已读到 `message_id` 及其之前的所有消息。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct MessageAck {
     /// 被确认的消息 ID
@@ -123,6 +745,10 @@ pub struct MessageAck {
     /// 是否已接收
     pub received: bool,
 
+    /// 是否已读（旧版本节点不发送此字段）
+    #[serde(default)]
+    pub read: bool,
+
     /// Unix 时间戳（毫秒）
     pub timestamp: i64,
 }
@@ -133,9 +759,49 @@ impl MessageAck {
         Self {
             message_id,
             received,
+            read: false,
             timestamp: chrono::Utc::now().timestamp_millis(),
         }
     }
+
+    /// 创建已读回执
+    pub fn read(message_id: String) -> Self {
+        Self {
+            read: true,
+            ..Self::new(message_id, true)
+        }
+    }
+}
+
+/// 消息投递状态
+///
+/// 状态只会向前推进：`Pending` → `Sent` → `Delivered` → `Read`，
+/// 超过有效期仍未送达时变为终止状态 `Failed`。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeliveryStatus {
+    /// 已加入待发送队列
+    Pending,
+    /// 已交给网络层发送
+    Sent,
+    /// 对方已收到
+    Delivered,
+    /// 对方已读
+    Read,
+    /// 超过有效期仍未送达，已放弃
+    Failed,
+}
+
+impl DeliveryStatus {
+    /// 状态名称（用于 FFI 等序列化场景）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Sent => "sent",
+            Self::Delivered => "delivered",
+            Self::Read => "read",
+            Self::Failed => "failed",
+        }
+    }
 }
 
 /// 聊天错误类型
@@ -172,6 +838,18 @@ pub enum ChatError {
     /// 部分失败（一对多广播时）
     #[error("部分失败: {0} 个目标失败")]
     PartialFailure(usize),
+
+    /// 聊天记录存储错误
+    #[error("存储错误: {0}")]
+    Storage(String),
+
+    /// 聊天室不存在
+    #[error("聊天室不存在: {0}")]
+    RoomNotFound(String),
+
+    /// 消息过大
+    #[error("消息过大: {0} 字节")]
+    TooLarge(usize),
 }
 
 #[cfg(test)]
@@ -215,9 +893,38 @@ mod tests {
         let ack = MessageAck::new("msg123".to_string(), true);
         assert_eq!(ack.message_id, "msg123");
         assert!(ack.received);
+        assert!(!ack.read);
         assert!(ack.timestamp > 0);
     }
 
+    #[test]
+    fn test_room_text_serialization() {
+        // 私聊消息不写出 room_id，保持与旧版本一致
+        let direct = ChatMessage::text("hi".to_string()).encode().unwrap();
+        assert!(!String::from_utf8(direct).unwrap().contains("room_id"));
+
+        let msg = ChatMessage::room_text("room1".to_string(), "hello".to_string());
+        let decoded = ChatMessage::decode(&msg.encode().unwrap()).unwrap();
+        assert_eq!(decoded.room_id(), Some("room1"));
+        assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn test_read_ack_compat() {
+        let ack = MessageAck::read("msg123".to_string());
+        assert!(ack.received && ack.read);
+
+        // 旧版本节点发送的确认没有 read 字段
+        let legacy = br#"{"Ack":{"message_id":"m1","received":true,"timestamp":1}}"#;
+        match ChatMessage::decode(legacy).unwrap() {
+            ChatMessage::Ack(ack) => assert!(!ack.read),
+            _ => panic!("Expected Ack message"),
+        }
+
+        assert!(DeliveryStatus::Read > DeliveryStatus::Delivered);
+        assert!(DeliveryStatus::Sent > DeliveryStatus::Pending);
+    }
+
     #[test]
     fn test_text_message_with_sender() {
         let msg = TextMessage {
@@ -226,6 +933,8 @@ mod tests {
             content: "Hello".to_string(),
             timestamp: 0,
             reply_to: None,
+            room_id: None,
+            meta: MessageMeta::default(),
         };
 
         let with_sender = msg.with_sender("peer456".to_string());
@@ -240,6 +949,8 @@ mod tests {
             content: "Hello".to_string(),
             timestamp: 0,
             reply_to: None,
+            room_id: None,
+            meta: MessageMeta::default(),
         };
 
         let with_reply = msg.with_reply_to("original_msg".to_string());
@@ -274,4 +985,67 @@ mod tests {
         let ack_msg = ChatMessage::Ack(ack);
         assert!(ack_msg.sender_peer_id().is_none());
     }
+
+    #[test]
+    fn test_edit_and_delete_in_place() {
+        let mut history = [ChatMessage::text("hello".to_string())];
+        history[0].set_sender("alice".to_string());
+        let target = history[0].id().unwrap().to_string();
+
+        // 只有发送者可以编辑
+        let mut forged = ChatMessage::edit(target.clone(), "hacked".to_string());
+        forged.set_sender("mallory".to_string());
+        assert!(!apply_update_to_history(history.iter_mut(), &forged));
+
+        let mut edit = ChatMessage::edit(target.clone(), "hello, world".to_string());
+        edit.set_sender("alice".to_string());
+        assert!(apply_update_to_history(history.iter_mut(), &edit));
+        assert_eq!(history[0].preview(), "hello, world");
+        assert!(history[0].meta().unwrap().edited_at.is_some());
+
+        let mut delete = ChatMessage::delete(target);
+        delete.set_sender("alice".to_string());
+        assert!(apply_update_to_history(history.iter_mut(), &delete));
+        assert!(history[0].meta().unwrap().deleted);
+        assert_eq!(history[0].preview(), "[消息已删除]");
+        // 删除后不能再编辑
+        assert!(!apply_update_to_history(history.iter_mut(), &edit));
+    }
+
+    #[test]
+    fn test_reactions() {
+        let mut message = ChatMessage::text("hi".to_string());
+        let target = message.id().unwrap().to_string();
+
+        let mut like = ChatMessage::reaction(target.clone(), "👍".to_string(), true);
+        like.set_sender("bob".to_string());
+        assert!(message.apply_update(&like));
+        // 重复回应不生效
+        assert!(!message.apply_update(&like));
+        assert_eq!(message.meta().unwrap().reactions["👍"], vec!["bob".to_string()]);
+
+        let mut unlike = ChatMessage::reaction(target, "👍".to_string(), false);
+        unlike.set_sender("bob".to_string());
+        assert!(message.apply_update(&unlike));
+        assert!(message.meta().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_image_and_file_serialization() {
+        let image = ChatMessage::image("image/png".to_string(), 2, 2, vec![1, 2, 3], vec![4]).unwrap();
+        let encoded = String::from_utf8(image.encode().unwrap()).unwrap();
+        // 二进制数据以 base64 编码，未修改的消息不写出 meta
+        assert!(encoded.contains("\"AQID\""));
+        assert!(!encoded.contains("meta"));
+        assert_eq!(ChatMessage::decode(encoded.as_bytes()).unwrap(), image);
+
+        let too_large = ChatMessage::image("image/png".to_string(), 1, 1, vec![0; MAX_INLINE_IMAGE_SIZE], vec![0]);
+        assert!(matches!(too_large, Err(ChatError::TooLarge(_))));
+
+        let offer = FileOffer::new("report.pdf".to_string(), 2048, 1024);
+        let file = ChatMessage::file(&offer);
+        assert!(file.requires_delivery() && file.is_content());
+        assert_eq!(file.preview(), "[文件] report.pdf (2048 字节)");
+        assert_eq!(ChatMessage::decode(&file.encode().unwrap()).unwrap(), file);
+    }
 }
@@ -11,6 +11,8 @@
 //! - [`message`] - 消息类型定义和序列化
 //! - [`traits`] - ChatExtension trait 定义
 //! - [`manager`] - ChatManager 实现（统一管理聊天会话）
+//! - [`store`] - ChatStore trait 及内存/文件实现（聊天记录持久化）
+//! - [`room`] - 群聊聊天室及成员管理
 //! - [`behaviour`] - ChatSession 和 Stream 管理
 //!
 //! # 示例
@@ -35,18 +37,24 @@ pub mod message;
 pub mod traits;
 pub mod manager;
 pub mod codec;
+pub mod store;
+pub mod room;
 
 // behaviour 将在后续阶段实现
 // pub mod behaviour;
 
 // 公共 API 导出
 pub use message::{
-    ChatMessage, TextMessage, TypingIndicator, MessageAck,
-    ChatError, CHAT_PROTOCOL,
+    ChatMessage, TextMessage, TypingIndicator, MessageAck, DeliveryStatus,
+    ImageMessage, FileAttachment, MessageEdit, MessageDelete, Reaction, MessageMeta,
+    UnknownMessage, apply_update_to_history, ChatError, CHAT_PROTOCOL, CHAT_PROTOCOL_CBOR,
+    CHAT_PROTOCOL_V1_0, MAX_INLINE_IMAGE_SIZE,
 };
 pub use traits::{ChatExtension, ChatEvent};
-pub use manager::{ChatManager, ChatSession};
+pub use manager::{ChatConfig, ChatManager, ChatSession};
 pub use codec::{ChatCodec, ChatProtocol, ChatRequest, ChatResponse};
+pub use store::{ChatStore, FileChatStore, MemoryChatStore, QueuedMessage, StoredMessage, StoredRoom};
+pub use room::{ChatRoom, RoomInvite, RoomLeave};
 
 // 当实现完成后，导出这些类型
 // pub use behaviour::ChatSession;
@@ -57,8 +65,9 @@ mod tests {
 
     #[test]
     fn test_chat_protocol_const() {
-        assert_eq!(CHAT_PROTOCOL, "/localp2p/chat/1.0.0");
-        assert_eq!(ChatProtocol.as_ref(), "/localp2p/chat/1.0.0");
+        assert_eq!(CHAT_PROTOCOL, "/localp2p/chat/1.1.0");
+        assert_eq!(CHAT_PROTOCOL_V1_0, "/localp2p/chat/1.0.0");
+        assert_eq!(ChatProtocol::default().as_ref(), CHAT_PROTOCOL);
     }
 
     #[test]
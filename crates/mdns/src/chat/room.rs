@@ -0,0 +1,172 @@
+//! 群聊聊天室
+//!
+//! 聊天室由创建者发起，成员列表随邀请和退出控制消息同步到所有成员。
+//! 聊天室内的消息通过各消息的 `room_id` 字段标识，仍逐个发送给每个成员。
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+
+/// 聊天室 ID 的最大长度
+const MAX_ROOM_ID_LEN: usize = 64;
+
+/// 聊天室
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatRoom {
+    /// 聊天室唯一 ID（UUID）
+    pub id: String,
+
+    /// 聊天室名称
+    pub name: String,
+
+    /// 成员的 Peer ID（包含创建者）
+    pub members: Vec<String>,
+
+    /// 创建者的 Peer ID
+    pub creator: String,
+
+    /// 创建时间（Unix 时间戳，毫秒）
+    pub created_at: i64,
+}
+
+impl ChatRoom {
+    /// 创建新的聊天室，创建者自动成为成员
+    pub fn new(name: String, creator: PeerId, members: &[PeerId]) -> Self {
+        let mut room = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            members: vec![creator.to_string()],
+            creator: creator.to_string(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+        };
+        room.add_members(members);
+        room
+    }
+
+    /// 聊天室 ID 是否有效（只含字母、数字和 `-`，可以安全地用作文件名）
+    pub fn has_valid_id(&self) -> bool {
+        is_valid_room_id(&self.id)
+    }
+
+    /// 检查节点是否为成员
+    pub fn is_member(&self, peer_id: &PeerId) -> bool {
+        let peer_id = peer_id.to_string();
+        self.members.contains(&peer_id)
+    }
+
+    /// 添加成员（忽略已存在的成员），返回新加入的成员
+    pub fn add_members(&mut self, peers: &[PeerId]) -> Vec<PeerId> {
+        let mut added = Vec::new();
+        for peer_id in peers {
+            if !self.is_member(peer_id) {
+                self.members.push(peer_id.to_string());
+                added.push(*peer_id);
+            }
+        }
+        added
+    }
+
+    /// 移除成员，返回是否移除
+    pub fn remove_member(&mut self, peer_id: &PeerId) -> bool {
+        let peer_id = peer_id.to_string();
+        let before = self.members.len();
+        self.members.retain(|member| *member != peer_id);
+        self.members.len() != before
+    }
+
+    /// 成员的 Peer ID 列表（跳过无法解析的条目）
+    pub fn member_ids(&self) -> Vec<PeerId> {
+        self.members.iter().filter_map(|m| m.parse().ok()).collect()
+    }
+
+    /// 除指定节点（通常是本地节点）之外的成员
+    pub fn other_members(&self, local_peer_id: &PeerId) -> Vec<PeerId> {
+        self.member_ids()
+            .into_iter()
+            .filter(|peer_id| peer_id != local_peer_id)
+            .collect()
+    }
+}
+
+/// 聊天室 ID 是否有效（只含字母、数字和 `-`，可以安全地用作文件名）
+pub fn is_valid_room_id(room_id: &str) -> bool {
+    !room_id.is_empty()
+        && room_id.len() <= MAX_ROOM_ID_LEN
+        && room_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// 邀请新成员
+///
+/// 发送给聊天室的所有成员（包括新成员），携带更新后的完整聊天室信息。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoomInvite {
+    /// 消息唯一 ID（UUID）
+    pub id: String,
+
+    /// 更新后的聊天室
+    pub room: ChatRoom,
+
+    /// 邀请者的 Peer ID
+    pub invited_by: String,
+}
+
+impl RoomInvite {
+    /// 创建邀请
+    pub fn new(room: ChatRoom, invited_by: &PeerId) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            room,
+            invited_by: invited_by.to_string(),
+        }
+    }
+}
+
+/// 退出聊天室
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoomLeave {
+    /// 消息唯一 ID（UUID）
+    pub id: String,
+
+    /// 聊天室 ID
+    pub room_id: String,
+
+    /// 退出成员的 Peer ID
+    pub peer_id: String,
+}
+
+impl RoomLeave {
+    /// 创建退出通知
+    pub fn new(room_id: String, peer_id: &PeerId) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            room_id,
+            peer_id: peer_id.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_room_membership() {
+        let creator = PeerId::random();
+        let alice = PeerId::random();
+        let bob = PeerId::random();
+
+        let mut room = ChatRoom::new("周末".to_string(), creator, &[alice, creator]);
+        assert_eq!(room.member_ids(), vec![creator, alice]);
+        assert_eq!(room.other_members(&creator), vec![alice]);
+
+        assert_eq!(room.add_members(&[alice, bob]), vec![bob]);
+        assert!(room.is_member(&bob));
+
+        assert!(room.remove_member(&alice));
+        assert!(!room.remove_member(&alice));
+        assert_eq!(room.member_ids(), vec![creator, bob]);
+
+        assert!(room.has_valid_id());
+        assert!(!is_valid_room_id("../../etc"));
+        assert!(!is_valid_room_id(""));
+    }
+}
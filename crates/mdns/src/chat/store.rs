@@ -0,0 +1,679 @@
+//! 聊天记录存储
+//!
+//! 定义可插拔的 `ChatStore` trait，以及内存和文件两种实现。
+//!
+//! 文件实现为每个节点维护一个追加写入的 JSON Lines 文件（`<peer_id>.jsonl`），
+//! 每行一条 [`StoredMessage`]，按写入顺序即为时间顺序。发件箱中等待投递的消息
+//! 保存在同一目录的 `outbox.json` 中，每次变化时整体替换。聊天室列表（包括已退出的
+//! 聊天室）保存在 `rooms.json` 中，聊天室消息保存在 `rooms/<聊天室 ID>.jsonl` 中。
+
+use super::message::{apply_update_to_history, ChatError, ChatMessage};
+use super::room::{is_valid_room_id, ChatRoom};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
+
+/// 持久化文件扩展名
+const STORE_EXTENSION: &str = "jsonl";
+
+/// 发件箱文件名
+const OUTBOX_FILE: &str = "outbox.json";
+
+/// 聊天室列表文件名
+const ROOMS_FILE: &str = "rooms.json";
+
+/// 聊天室消息目录
+const ROOMS_DIR: &str = "rooms";
+
+/// 存储的消息记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredMessage {
+    /// 会话对方的 Peer ID
+    pub peer_id: String,
+    /// 是否为本地发出的消息
+    pub outgoing: bool,
+    /// 消息内容
+    pub message: ChatMessage,
+}
+
+impl StoredMessage {
+    /// 创建新的存储记录
+    pub fn new(peer_id: PeerId, outgoing: bool, message: ChatMessage) -> Self {
+        Self {
+            peer_id: peer_id.to_string(),
+            outgoing,
+            message,
+        }
+    }
+
+    /// 消息 ID
+    pub fn message_id(&self) -> Option<&str> {
+        self.message.id()
+    }
+}
+
+/// 发件箱中等待投递的消息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueuedMessage {
+    /// 目标节点的 Peer ID
+    pub peer_id: String,
+    /// 待发送的消息
+    pub message: ChatMessage,
+    /// 加入发件箱的时间（Unix 时间戳，毫秒），重启后据此计算有效期
+    pub queued_at: i64,
+}
+
+/// 聊天室记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredRoom {
+    /// 聊天室
+    pub room: ChatRoom,
+    /// 本地用户是否已退出（保留记录，之后不再接受该聊天室的消息）
+    pub left: bool,
+}
+
+/// 聊天记录存储 trait
+///
+/// `ChatManager` 在发送和收到消息时写入，在启用聊天时重新加载。
+/// 内容消息和更新消息（编辑、删除、表情回应）按顺序追加，查询时更新消息会应用到目标消息上。
+#[async_trait::async_trait]
+pub trait ChatStore: Send + Sync {
+    /// 追加一条消息记录
+    async fn append(&self, record: &StoredMessage) -> Result<(), ChatError>;
+
+    /// 获取与指定节点的最近 `limit` 条消息（按时间正序）
+    async fn load_recent(&self, peer_id: &PeerId, limit: usize) -> Result<Vec<StoredMessage>, ChatError> {
+        self.history_before(peer_id, None, limit).await
+    }
+
+    /// 分页查询：获取指定消息之前的最多 `limit` 条消息（按时间正序）
+    ///
+    /// `message_id` 为 `None` 时从最新一条开始；指定的消息不存在时返回空列表。
+    async fn history_before(
+        &self,
+        peer_id: &PeerId,
+        message_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<StoredMessage>, ChatError>;
+
+    /// 列出有聊天记录的节点
+    async fn peers(&self) -> Result<Vec<PeerId>, ChatError>;
+
+    /// 把 `from` 的聊天记录迁移到 `to`（用于身份轮换）
+    ///
+    /// 迁移的记录排在 `to` 已有记录之前，`from` 的记录随后被删除。
+    async fn migrate_peer(&self, from: &PeerId, to: &PeerId) -> Result<(), ChatError>;
+
+    /// 替换指定节点的发件箱（`queued` 为空时清空）
+    async fn save_outbox(&self, peer_id: &PeerId, queued: &[QueuedMessage]) -> Result<(), ChatError>;
+
+    /// 读取所有节点的发件箱
+    async fn load_outbox(&self) -> Result<Vec<QueuedMessage>, ChatError>;
+
+    /// 保存或更新聊天室；标记为已退出时同时删除该聊天室的消息记录
+    async fn save_room(&self, room: &StoredRoom) -> Result<(), ChatError>;
+
+    /// 读取所有聊天室（包括已退出的）
+    async fn load_rooms(&self) -> Result<Vec<StoredRoom>, ChatError>;
+
+    /// 追加一条聊天室消息记录（`record.peer_id` 为发送者）
+    async fn append_room_message(&self, room_id: &str, record: &StoredMessage) -> Result<(), ChatError>;
+
+    /// 获取聊天室最近 `limit` 条消息（按时间正序，更新记录已应用）
+    async fn load_room_history(&self, room_id: &str, limit: usize) -> Result<Vec<StoredMessage>, ChatError>;
+}
+
+/// 把更新记录应用到之前的目标消息上，只保留内容消息
+fn fold_updates(records: &[StoredMessage]) -> Vec<StoredMessage> {
+    let mut folded: Vec<StoredMessage> = Vec::with_capacity(records.len());
+    for record in records {
+        if record.message.is_update() {
+            apply_update_to_history(folded.iter_mut().map(|r| &mut r.message), &record.message);
+        } else {
+            folded.push(record.clone());
+        }
+    }
+    folded
+}
+
+/// 从按时间正序排列的记录中取出指定消息之前的一页（更新记录已应用）
+fn page_before(records: &[StoredMessage], message_id: Option<&str>, limit: usize) -> Vec<StoredMessage> {
+    let records = fold_updates(records);
+    let end = match message_id {
+        Some(id) => match records.iter().position(|r| r.message_id() == Some(id)) {
+            Some(pos) => pos,
+            None => return Vec::new(),
+        },
+        None => records.len(),
+    };
+    let start = end.saturating_sub(limit);
+    records[start..end].to_vec()
+}
+
+/// 内存存储（不持久化，重启后丢失）
+#[derive(Default)]
+pub struct MemoryChatStore {
+    records: RwLock<HashMap<PeerId, Vec<StoredMessage>>>,
+    outbox: RwLock<HashMap<PeerId, Vec<QueuedMessage>>>,
+    rooms: RwLock<HashMap<String, StoredRoom>>,
+    room_records: RwLock<HashMap<String, Vec<StoredMessage>>>,
+}
+
+impl MemoryChatStore {
+    /// 创建新的内存存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl ChatStore for MemoryChatStore {
+    async fn append(&self, record: &StoredMessage) -> Result<(), ChatError> {
+        let peer_id: PeerId = record
+            .peer_id
+            .parse()
+            .map_err(|e| ChatError::Storage(format!("无效的 Peer ID: {}", e)))?;
+        self.records
+            .write()
+            .await
+            .entry(peer_id)
+            .or_default()
+            .push(record.clone());
+        Ok(())
+    }
+
+    async fn history_before(
+        &self,
+        peer_id: &PeerId,
+        message_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<StoredMessage>, ChatError> {
+        let records = self.records.read().await;
+        Ok(records
+            .get(peer_id)
+            .map(|r| page_before(r, message_id, limit))
+            .unwrap_or_default())
+    }
+
+    async fn peers(&self) -> Result<Vec<PeerId>, ChatError> {
+        Ok(self.records.read().await.keys().copied().collect())
+    }
+
+    async fn migrate_peer(&self, from: &PeerId, to: &PeerId) -> Result<(), ChatError> {
+        let mut records = self.records.write().await;
+        let Some(mut migrated) = records.remove(from) else {
+            return Ok(());
+        };
+        for record in &mut migrated {
+            record.peer_id = to.to_string();
+        }
+        let existing = records.entry(*to).or_default();
+        migrated.append(existing);
+        *existing = migrated;
+        Ok(())
+    }
+
+    async fn save_outbox(&self, peer_id: &PeerId, queued: &[QueuedMessage]) -> Result<(), ChatError> {
+        let mut outbox = self.outbox.write().await;
+        if queued.is_empty() {
+            outbox.remove(peer_id);
+        } else {
+            outbox.insert(*peer_id, queued.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn load_outbox(&self) -> Result<Vec<QueuedMessage>, ChatError> {
+        Ok(self.outbox.read().await.values().flatten().cloned().collect())
+    }
+
+    async fn save_room(&self, room: &StoredRoom) -> Result<(), ChatError> {
+        if room.left {
+            self.room_records.write().await.remove(&room.room.id);
+        }
+        self.rooms.write().await.insert(room.room.id.clone(), room.clone());
+        Ok(())
+    }
+
+    async fn load_rooms(&self) -> Result<Vec<StoredRoom>, ChatError> {
+        Ok(self.rooms.read().await.values().cloned().collect())
+    }
+
+    async fn append_room_message(&self, room_id: &str, record: &StoredMessage) -> Result<(), ChatError> {
+        self.room_records
+            .write()
+            .await
+            .entry(room_id.to_string())
+            .or_default()
+            .push(record.clone());
+        Ok(())
+    }
+
+    async fn load_room_history(&self, room_id: &str, limit: usize) -> Result<Vec<StoredMessage>, ChatError> {
+        let records = self.room_records.read().await;
+        Ok(records
+            .get(room_id)
+            .map(|r| page_before(r, None, limit))
+            .unwrap_or_default())
+    }
+}
+
+/// 文件存储（每个节点一个 JSON Lines 文件）
+pub struct FileChatStore {
+    /// 存储目录
+    dir: PathBuf,
+    /// 写入锁（保证同一时刻只有一个写入）
+    write_lock: Mutex<()>,
+}
+
+impl FileChatStore {
+    /// 创建文件存储，目录不存在时自动创建
+    pub async fn open(dir: impl Into<PathBuf>) -> Result<Self, ChatError> {
+        let dir = dir.into();
+        tokio::fs::create_dir_all(dir.join(ROOMS_DIR))
+            .await
+            .map_err(|e| ChatError::Storage(format!("无法创建目录 {}: {}", dir.display(), e)))?;
+
+        Ok(Self {
+            dir,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// 存储目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn peer_path(&self, peer_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", peer_id, STORE_EXTENSION))
+    }
+
+    /// 聊天室消息文件路径（拒绝不能安全用作文件名的 ID）
+    fn room_path(&self, room_id: &str) -> Result<PathBuf, ChatError> {
+        if !is_valid_room_id(room_id) {
+            return Err(ChatError::Storage(format!("无效的聊天室 ID: {}", room_id)));
+        }
+        Ok(self.dir.join(ROOMS_DIR).join(format!("{}.{}", room_id, STORE_EXTENSION)))
+    }
+
+    /// 读取指定节点的全部记录（跳过损坏的行）
+    async fn read_all(&self, peer_id: &PeerId) -> Result<Vec<StoredMessage>, ChatError> {
+        read_records(&self.peer_path(&peer_id.to_string())).await
+    }
+
+    /// 读取 JSON 文件（不存在或损坏时返回默认值）
+    async fn read_json<T: serde::de::DeserializeOwned + Default>(&self, name: &str) -> Result<T, ChatError> {
+        let path = self.dir.join(name);
+        let data = match tokio::fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+            Err(e) => return Err(ChatError::Storage(e.to_string())),
+        };
+        Ok(serde_json::from_slice(&data).unwrap_or_else(|e| {
+            tracing::warn!("文件损坏 ({}): {}", path.display(), e);
+            T::default()
+        }))
+    }
+
+    /// 整体替换 JSON 文件：先写临时文件再重命名，写入中断时保留旧内容
+    async fn write_json<T: Serialize>(&self, name: &str, value: &T) -> Result<(), ChatError> {
+        let data = serde_json::to_vec(value).map_err(|e| ChatError::Serialization(e.to_string()))?;
+        let tmp_path = self.dir.join(format!("{}.tmp", name));
+        tokio::fs::write(&tmp_path, data)
+            .await
+            .map_err(|e| ChatError::Storage(e.to_string()))?;
+        tokio::fs::rename(&tmp_path, self.dir.join(name))
+            .await
+            .map_err(|e| ChatError::Storage(e.to_string()))
+    }
+}
+
+/// 向 JSON Lines 文件追加一条记录
+async fn append_record(path: &Path, record: &StoredMessage) -> Result<(), ChatError> {
+    let mut line = serde_json::to_vec(record)
+        .map_err(|e| ChatError::Serialization(e.to_string()))?;
+    line.push(b'\n');
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| ChatError::Storage(e.to_string()))?;
+    file.write_all(&line)
+        .await
+        .map_err(|e| ChatError::Storage(e.to_string()))?;
+    file.flush().await.map_err(|e| ChatError::Storage(e.to_string()))
+}
+
+/// 读取 JSON Lines 文件中的全部记录（跳过损坏的行）
+async fn read_records(path: &Path) -> Result<Vec<StoredMessage>, ChatError> {
+    let data = match tokio::fs::read_to_string(path).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(ChatError::Storage(e.to_string())),
+    };
+
+    Ok(data
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<StoredMessage>(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                tracing::warn!("跳过损坏的聊天记录 ({}): {}", path.display(), e);
+                None
+            }
+        })
+        .collect())
+}
+
+#[async_trait::async_trait]
+impl ChatStore for FileChatStore {
+    async fn append(&self, record: &StoredMessage) -> Result<(), ChatError> {
+        let _guard = self.write_lock.lock().await;
+        append_record(&self.peer_path(&record.peer_id), record).await
+    }
+
+    async fn history_before(
+        &self,
+        peer_id: &PeerId,
+        message_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<StoredMessage>, ChatError> {
+        let records = self.read_all(peer_id).await?;
+        Ok(page_before(&records, message_id, limit))
+    }
+
+    async fn peers(&self) -> Result<Vec<PeerId>, ChatError> {
+        let mut entries = tokio::fs::read_dir(&self.dir)
+            .await
+            .map_err(|e| ChatError::Storage(e.to_string()))?;
+
+        let mut peers = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| ChatError::Storage(e.to_string()))?
+        {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(STORE_EXTENSION) {
+                continue;
+            }
+            if let Some(peer_id) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse().ok())
+            {
+                peers.push(peer_id);
+            }
+        }
+        Ok(peers)
+    }
+
+    async fn migrate_peer(&self, from: &PeerId, to: &PeerId) -> Result<(), ChatError> {
+        let mut migrated = self.read_all(from).await?;
+        if migrated.is_empty() {
+            return Ok(());
+        }
+        migrated.extend(self.read_all(to).await?);
+
+        let mut data = Vec::new();
+        for mut record in migrated {
+            record.peer_id = to.to_string();
+            data.extend(serde_json::to_vec(&record).map_err(|e| ChatError::Serialization(e.to_string()))?);
+            data.push(b'\n');
+        }
+
+        let _guard = self.write_lock.lock().await;
+        tokio::fs::write(self.peer_path(&to.to_string()), data)
+            .await
+            .map_err(|e| ChatError::Storage(e.to_string()))?;
+        tokio::fs::remove_file(self.peer_path(&from.to_string()))
+            .await
+            .map_err(|e| ChatError::Storage(e.to_string()))
+    }
+
+    async fn save_outbox(&self, peer_id: &PeerId, queued: &[QueuedMessage]) -> Result<(), ChatError> {
+        let _guard = self.write_lock.lock().await;
+        let peer_id = peer_id.to_string();
+        let mut outbox: Vec<QueuedMessage> = self.read_json(OUTBOX_FILE).await?;
+        outbox.retain(|entry| entry.peer_id != peer_id);
+        outbox.extend_from_slice(queued);
+        self.write_json(OUTBOX_FILE, &outbox).await
+    }
+
+    async fn load_outbox(&self) -> Result<Vec<QueuedMessage>, ChatError> {
+        self.read_json(OUTBOX_FILE).await
+    }
+
+    async fn save_room(&self, room: &StoredRoom) -> Result<(), ChatError> {
+        let path = self.room_path(&room.room.id)?;
+        let _guard = self.write_lock.lock().await;
+        let mut rooms: Vec<StoredRoom> = self.read_json(ROOMS_FILE).await?;
+        match rooms.iter_mut().find(|r| r.room.id == room.room.id) {
+            Some(existing) => *existing = room.clone(),
+            None => rooms.push(room.clone()),
+        }
+        self.write_json(ROOMS_FILE, &rooms).await?;
+
+        if room.left {
+            match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(ChatError::Storage(e.to_string()));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_rooms(&self) -> Result<Vec<StoredRoom>, ChatError> {
+        self.read_json(ROOMS_FILE).await
+    }
+
+    async fn append_room_message(&self, room_id: &str, record: &StoredMessage) -> Result<(), ChatError> {
+        let path = self.room_path(room_id)?;
+        let _guard = self.write_lock.lock().await;
+        append_record(&path, record).await
+    }
+
+    async fn load_room_history(&self, room_id: &str, limit: usize) -> Result<Vec<StoredMessage>, ChatError> {
+        let records = read_records(&self.room_path(room_id)?).await?;
+        Ok(page_before(&records, None, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn records(peer_id: PeerId, count: usize) -> Vec<StoredMessage> {
+        (0..count)
+            .map(|i| StoredMessage::new(peer_id, i % 2 == 0, ChatMessage::text(format!("msg {}", i))))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_paging() {
+        let store = MemoryChatStore::new();
+        let peer_id = PeerId::random();
+        let all = records(peer_id, 10);
+        for record in &all {
+            store.append(record).await.unwrap();
+        }
+
+        let recent = store.load_recent(&peer_id, 3).await.unwrap();
+        assert_eq!(recent, all[7..10].to_vec());
+
+        let older = store
+            .history_before(&peer_id, recent[0].message_id(), 5)
+            .await
+            .unwrap();
+        assert_eq!(older, all[2..7].to_vec());
+
+        let oldest = store
+            .history_before(&peer_id, older[0].message_id(), 5)
+            .await
+            .unwrap();
+        assert_eq!(oldest, all[0..2].to_vec());
+
+        assert!(store.history_before(&peer_id, Some("missing"), 5).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_updates_applied_when_paging() {
+        let store = MemoryChatStore::new();
+        let peer_id = PeerId::random();
+        let all = records(peer_id, 3);
+        for record in &all {
+            store.append(record).await.unwrap();
+        }
+
+        // 对方编辑了第二条消息（记录的发送者为空字符串）
+        let target = all[1].message_id().unwrap().to_string();
+        let edit = ChatMessage::edit(target.clone(), "edited".to_string());
+        store.append(&StoredMessage::new(peer_id, false, edit)).await.unwrap();
+
+        let recent = store.load_recent(&peer_id, 10).await.unwrap();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[1].message.preview(), "edited");
+        assert_eq!(recent[1].message_id(), Some(target.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_file_store_reload() {
+        let dir = TempDir::new().unwrap();
+        let peer_a = PeerId::random();
+        let peer_b = PeerId::random();
+        let all = records(peer_a, 4);
+
+        {
+            let store = FileChatStore::open(dir.path()).await.unwrap();
+            for record in &all {
+                store.append(record).await.unwrap();
+            }
+            store.append(&records(peer_b, 1)[0]).await.unwrap();
+        }
+
+        // 重新打开后记录仍在
+        let store = FileChatStore::open(dir.path()).await.unwrap();
+        let mut peers = store.peers().await.unwrap();
+        peers.sort();
+        let mut expected = vec![peer_a, peer_b];
+        expected.sort();
+        assert_eq!(peers, expected);
+
+        assert_eq!(store.load_recent(&peer_a, 10).await.unwrap(), all);
+        let page = store.history_before(&peer_a, all[2].message_id(), 1).await.unwrap();
+        assert_eq!(page, vec![all[1].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_skips_corrupted_lines() {
+        let dir = TempDir::new().unwrap();
+        let peer_id = PeerId::random();
+        let store = FileChatStore::open(dir.path()).await.unwrap();
+        let record = records(peer_id, 1).remove(0);
+        store.append(&record).await.unwrap();
+
+        // 模拟写入中断留下的半行
+        let path = dir.path().join(format!("{}.jsonl", peer_id));
+        let mut data = std::fs::read(&path).unwrap();
+        data.extend_from_slice(b"{\"peer_id\":");
+        std::fs::write(&path, data).unwrap();
+
+        assert_eq!(store.load_recent(&peer_id, 10).await.unwrap(), vec![record]);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_migrate_peer() {
+        let dir = TempDir::new().unwrap();
+        let old = PeerId::random();
+        let new = PeerId::random();
+        let store = FileChatStore::open(dir.path()).await.unwrap();
+
+        let before = records(old, 3);
+        let after = records(new, 2);
+        for record in before.iter().chain(&after) {
+            store.append(record).await.unwrap();
+        }
+
+        store.migrate_peer(&old, &new).await.unwrap();
+        assert_eq!(store.peers().await.unwrap(), vec![new]);
+
+        let history = store.load_recent(&new, 10).await.unwrap();
+        assert_eq!(history.len(), 5);
+        assert!(history.iter().all(|record| record.peer_id == new.to_string()));
+        assert_eq!(history[0].message, before[0].message);
+        assert_eq!(history[4].message, after[1].message);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_outbox() {
+        let dir = TempDir::new().unwrap();
+        let peer_a = PeerId::random();
+        let peer_b = PeerId::random();
+        let queued = |peer_id: PeerId, text: &str| QueuedMessage {
+            peer_id: peer_id.to_string(),
+            message: ChatMessage::text(text.to_string()),
+            queued_at: 1_700_000_000_000,
+        };
+
+        {
+            let store = FileChatStore::open(dir.path()).await.unwrap();
+            store.save_outbox(&peer_a, &[queued(peer_a, "a1"), queued(peer_a, "a2")]).await.unwrap();
+            store.save_outbox(&peer_b, &[queued(peer_b, "b1")]).await.unwrap();
+            // 替换而不是追加
+            store.save_outbox(&peer_a, &[queued(peer_a, "a2")]).await.unwrap();
+        }
+
+        let store = FileChatStore::open(dir.path()).await.unwrap();
+        let outbox = store.load_outbox().await.unwrap();
+        assert_eq!(outbox.len(), 2);
+        assert!(outbox.iter().any(|q| q.peer_id == peer_a.to_string() && q.message.preview() == "a2"));
+        // 发件箱文件不会被当作某个节点的聊天记录
+        assert!(store.peers().await.unwrap().is_empty());
+
+        store.save_outbox(&peer_b, &[]).await.unwrap();
+        assert_eq!(store.load_outbox().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_rooms() {
+        let dir = TempDir::new().unwrap();
+        let local = PeerId::random();
+        let peer = PeerId::random();
+        let kept = ChatRoom::new("kept".to_string(), local, &[peer]);
+        let left = ChatRoom::new("left".to_string(), local, &[peer]);
+
+        {
+            let store = FileChatStore::open(dir.path()).await.unwrap();
+            for room in [&kept, &left] {
+                store.save_room(&StoredRoom { room: room.clone(), left: false }).await.unwrap();
+                let record = StoredMessage::new(peer, false, ChatMessage::room_text(room.id.clone(), "hi".to_string()));
+                store.append_room_message(&room.id, &record).await.unwrap();
+            }
+            store.save_room(&StoredRoom { room: left.clone(), left: true }).await.unwrap();
+        }
+
+        let store = FileChatStore::open(dir.path()).await.unwrap();
+        let mut rooms = store.load_rooms().await.unwrap();
+        rooms.sort_by_key(|stored| stored.left);
+        assert_eq!(rooms, vec![
+            StoredRoom { room: kept.clone(), left: false },
+            StoredRoom { room: left.clone(), left: true },
+        ]);
+        assert_eq!(store.load_room_history(&kept.id, 10).await.unwrap().len(), 1);
+        // 退出的聊天室不再保留消息
+        assert!(store.load_room_history(&left.id, 10).await.unwrap().is_empty());
+        // 不能用作文件名的聊天室 ID 被拒绝
+        assert!(store.load_room_history("../outbox", 10).await.is_err());
+        // 聊天室文件不会被当作某个节点的聊天记录
+        assert!(store.peers().await.unwrap().is_empty());
+    }
+}
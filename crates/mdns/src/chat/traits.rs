@@ -2,8 +2,10 @@
 //!
 //! 定义扩展 ManagedDiscovery 的接口，使其支持聊天功能。
 
-use super::message::{ChatMessage, ChatError};
-use super::manager::ChatManager;
+use super::message::{ChatMessage, ChatError, DeliveryStatus};
+use super::manager::{ChatConfig, ChatManager};
+use super::room::ChatRoom;
+use super::store::ChatStore;
 use libp2p::PeerId;
 use std::sync::Arc;
 
@@ -14,16 +16,17 @@ use std::sync::Arc;
 /// # 示例
 ///
 /// ```no_run
-/// use mdns::{ManagedDiscovery, ChatExtension};
+/// use mdns::{ManagedDiscovery, ChatExtension, ChatMessage};
+/// use libp2p::PeerId;
 ///
 /// # async fn example(mut discovery: ManagedDiscovery) -> Result<(), Box<dyn std::error::Error>> {
 /// // 启用聊天功能
 /// discovery.enable_chat().await?;
 ///
 /// // 发送消息
-/// let peer_id = "12D3KooW...".parse()?;
+/// let peer_id: PeerId = "12D3KooW...".parse()?;
 /// let message = ChatMessage::text("Hello!".to_string());
-/// discovery.send_message(peer_id, message).await?;
+/// discovery.send_message(peer_id, message.clone()).await?;
 ///
 /// // 广播消息（一对多）
 /// let targets = vec![peer_id];
@@ -42,8 +45,27 @@ pub trait ChatExtension {
     /// 返回错误如果聊天功能已经启用或初始化失败。
     async fn enable_chat(&mut self) -> Result<(), ChatError>;
 
+    /// 使用指定的聊天记录存储启用聊天功能
+    ///
+    /// 启用时从存储中重新加载最近的聊天记录，之后收发的消息都会写入存储。
+    ///
+    /// # 错误
+    ///
+    /// 返回错误如果聊天功能已经启用或加载聊天记录失败。
+    async fn enable_chat_with_store(&mut self, store: Arc<dyn ChatStore>) -> Result<(), ChatError>;
+
+    /// 使用完整配置启用聊天功能（聊天记录存储、离线消息有效期和重试退避）
+    ///
+    /// # 错误
+    ///
+    /// 返回错误如果聊天功能已经启用或加载聊天记录失败。
+    async fn enable_chat_with_config(&mut self, config: ChatConfig) -> Result<(), ChatError>;
+
     /// 发送消息给指定节点
     ///
+    /// 内容消息（文本、图片、文件）和编辑、删除、表情回应先进入发件箱：目标节点离线时会保留到节点重新上线后重试，
+    /// 超过有效期仍未送达时产生 `ChatEvent::DeliveryFailed`。
+    ///
     /// # 参数
     ///
     /// * `target` - 目标节点的 Peer ID
@@ -52,7 +74,7 @@ pub trait ChatExtension {
     /// # 错误
     ///
     /// - `ChatError::NotEnabled` - 聊天功能未启用
-    /// - `ChatError::NodeNotVerified` - 目标节点未验证
+    /// - `ChatError::NodeNotVerified` - 目标节点从未验证过（离线的已知节点不会报错）
     /// - `ChatError::SendFailed` - 发送失败
     async fn send_message(&mut self, target: PeerId, message: ChatMessage) -> Result<(), ChatError>;
 
@@ -71,6 +93,52 @@ pub trait ChatExtension {
     /// - `ChatError::PartialFailure` - 部分目标发送失败
     async fn broadcast_message(&mut self, targets: Vec<PeerId>, message: ChatMessage) -> Result<(), ChatError>;
 
+    /// 将与指定节点的会话标记为已读
+    ///
+    /// 有未读消息时向对方发送已读回执（确认到最新一条收到的消息）。
+    ///
+    /// # 错误
+    ///
+    /// - `ChatError::NotEnabled` - 聊天功能未启用
+    async fn mark_conversation_read(&mut self, peer: PeerId) -> Result<(), ChatError>;
+
+    /// 创建聊天室并通知所有成员
+    ///
+    /// 本地节点作为创建者自动加入聊天室。
+    ///
+    /// # 错误
+    ///
+    /// - `ChatError::NotEnabled` - 聊天功能未启用
+    async fn create_room(&mut self, name: String, members: Vec<PeerId>) -> Result<ChatRoom, ChatError>;
+
+    /// 邀请新成员加入聊天室
+    ///
+    /// 更新后的成员列表会发送给所有成员（包括新成员）。
+    ///
+    /// # 错误
+    ///
+    /// - `ChatError::NotEnabled` - 聊天功能未启用
+    /// - `ChatError::RoomNotFound` - 聊天室不存在
+    async fn invite_to_room(&mut self, room_id: &str, members: Vec<PeerId>) -> Result<ChatRoom, ChatError>;
+
+    /// 退出聊天室并通知其他成员
+    ///
+    /// # 错误
+    ///
+    /// - `ChatError::NotEnabled` - 聊天功能未启用
+    /// - `ChatError::RoomNotFound` - 聊天室不存在
+    async fn leave_room(&mut self, room_id: &str) -> Result<(), ChatError>;
+
+    /// 在聊天室中发送消息（内容消息或编辑、删除、表情回应）
+    ///
+    /// 消息逐个发送给其他成员，离线成员的消息留在发件箱中稍后重试。
+    ///
+    /// # 错误
+    ///
+    /// - `ChatError::NotEnabled` - 聊天功能未启用
+    /// - `ChatError::RoomNotFound` - 聊天室不存在
+    async fn send_room_message(&mut self, room_id: &str, message: ChatMessage) -> Result<(), ChatError>;
+
     /// 获取聊天管理器
     ///
     /// 返回 ChatManager 的引用，如果聊天功能已启用。
@@ -111,6 +179,42 @@ pub enum ChatEvent {
         message_id: String,
     },
 
+    /// 发出消息的投递状态变化
+    MessageStatusChanged {
+        /// 会话对方的 Peer ID
+        peer_id: PeerId,
+        /// 消息 ID
+        message_id: String,
+        /// 新状态
+        status: DeliveryStatus,
+    },
+
+    /// 消息超过有效期仍未送达，放弃投递
+    DeliveryFailed {
+        /// 目标节点的 Peer ID
+        peer_id: PeerId,
+        /// 消息 ID
+        message_id: String,
+        /// 失败原因（最后一次发送错误）
+        reason: String,
+    },
+
+    /// 收到聊天室消息
+    RoomMessage {
+        /// 聊天室 ID
+        room_id: String,
+        /// 发送者的 Peer ID
+        from: PeerId,
+        /// 收到的消息
+        message: ChatMessage,
+    },
+
+    /// 聊天室创建或成员变更（包括本地创建和收到的通知）
+    RoomUpdated {
+        /// 更新后的聊天室
+        room: ChatRoom,
+    },
+
     /// 对方正在输入
     PeerTyping {
         /// 对方的 Peer ID
@@ -119,14 +223,16 @@ pub enum ChatEvent {
         is_typing: bool,
     },
 
-    /// 消息发送失败
-    MessageFailed {
+    /// 单次发送失败（网络错误、超时、对方拒绝等）
+    ///
+    /// 消息仍留在发件箱中按退避时间重试，超过有效期后才会产生 `DeliveryFailed`。
+    MessageSendFailed {
         /// 目标节点的 Peer ID
-        to: PeerId,
+        peer: PeerId,
         /// 消息 ID
         message_id: String,
-        /// 错误原因
-        error: String,
+        /// 失败原因
+        reason: String,
     },
 
     /// 会话已建立
@@ -148,15 +254,17 @@ mod tests {
 
     #[test]
     fn test_chat_event_message_received() {
+        let peer_id = PeerId::random();
+        let original = ChatMessage::text("Hello".to_string());
         let event = ChatEvent::MessageReceived {
-            from: "12D3KooWABCDEFGHIJKLMNOPQRSTUVWXYZ".parse().unwrap(),
-            message: ChatMessage::text("Hello".to_string()),
+            from: peer_id,
+            message: original.clone(),
         };
 
         match event {
             ChatEvent::MessageReceived { from, message } => {
-                assert!(from.to_string().starts_with("12D3"));
-                assert_eq!(message, ChatMessage::text("Hello".to_string()));
+                assert_eq!(from, peer_id);
+                assert_eq!(message, original);
             }
             _ => panic!("Expected MessageReceived event"),
         }
@@ -164,15 +272,16 @@ mod tests {
 
     #[test]
     fn test_chat_event_peer_typing() {
+        let peer_id = PeerId::random();
         let event = ChatEvent::PeerTyping {
-            from: "12D3KooWABCDEFGHIJKLMNOPQRSTUVWXYZ".parse().unwrap(),
+            from: peer_id,
             is_typing: true,
         };
 
         match event {
             ChatEvent::PeerTyping { from, is_typing } => {
                 assert!(is_typing);
-                assert!(from.to_string().starts_with("12D3"));
+                assert_eq!(from, peer_id);
             }
             _ => panic!("Expected PeerTyping event"),
         }
@@ -0,0 +1,426 @@
+//! 带长度前缀的通用 Codec
+//!
+//! 聊天、用户信息和文件传输协议共用的分帧格式：u32 大端长度前缀 + 数据。
+//! 帧内数据的序列化格式由协商到的协议决定，默认使用 JSON，
+//! CBOR 作为可选的协议变体，双方都启用时由 multistream-select 优先协商。
+
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::request_response;
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// 默认的最大帧大小（1MB）
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// 默认的单帧读取超时
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 协议限制
+///
+/// 约束对方发送的帧大小和读取时间，超出限制的帧视为违规，
+/// 同一节点违规达到 `max_violations` 次后断开连接并封禁 `ban_duration`。
+#[derive(Debug, Clone)]
+pub struct ProtocolLimits {
+    /// 用户信息请求的最大字节数（请求本身为空）
+    pub user_info_request: usize,
+
+    /// 用户信息响应的最大字节数
+    pub user_info_response: usize,
+
+    /// 聊天消息的最大字节数
+    pub chat_message: usize,
+
+    /// 聊天响应的最大字节数
+    pub chat_response: usize,
+
+    /// 读取单个帧的超时时间
+    pub read_timeout: Duration,
+
+    /// 封禁前允许的违规次数
+    pub max_violations: u32,
+
+    /// 封禁时长
+    pub ban_duration: Duration,
+}
+
+impl Default for ProtocolLimits {
+    fn default() -> Self {
+        Self {
+            user_info_request: 1024,
+            user_info_response: 64 * 1024,
+            chat_message: DEFAULT_MAX_FRAME_SIZE,
+            chat_response: 1024,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            max_violations: 3,
+            ban_duration: Duration::from_secs(600), // 10分钟
+        }
+    }
+}
+
+impl ProtocolLimits {
+    /// 创建默认限制
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置用户信息请求和响应的最大字节数
+    pub fn with_user_info_limits(mut self, request: usize, response: usize) -> Self {
+        self.user_info_request = request;
+        self.user_info_response = response;
+        self
+    }
+
+    /// 设置聊天消息和响应的最大字节数
+    pub fn with_chat_limits(mut self, message: usize, response: usize) -> Self {
+        self.chat_message = message;
+        self.chat_response = response;
+        self
+    }
+
+    /// 设置读取超时
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// 设置封禁前允许的违规次数
+    pub fn with_max_violations(mut self, count: u32) -> Self {
+        self.max_violations = count.max(1);
+        self
+    }
+
+    /// 设置封禁时长
+    pub fn with_ban_duration(mut self, duration: Duration) -> Self {
+        self.ban_duration = duration;
+        self
+    }
+}
+
+/// 错误是否表示对方违反协议（帧过大、格式错误或读取超时）
+///
+/// 本地序列化失败使用 `InvalidInput`，不会被误判为对方违规。
+pub fn is_protocol_violation(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::InvalidData | std::io::ErrorKind::TimedOut
+    )
+}
+
+/// 帧内数据的序列化格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// JSON（所有版本都支持）
+    #[default]
+    Json,
+    /// CBOR（体积更小，需双方启用）
+    Cbor,
+}
+
+impl Format {
+    /// 序列化
+    pub fn encode<T: Serialize>(self, value: &T) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Json => serde_json::to_vec(value)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e)),
+            Self::Cbor => cbor4ii::serde::to_vec(Vec::new(), value)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())),
+        }
+    }
+
+    /// 反序列化
+    pub fn decode<T: DeserializeOwned>(self, data: &[u8]) -> std::io::Result<T> {
+        match self {
+            Self::Json => serde_json::from_slice(data)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            Self::Cbor => cbor4ii::serde::from_slice(data)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+        }
+    }
+}
+
+/// 使用长度前缀分帧的协议
+pub trait FramedProtocol: AsRef<str> + Clone + Send + Sync + 'static {
+    /// 该协议使用的序列化格式
+    fn format(&self) -> Format;
+}
+
+/// 读取带长度前缀的数据帧
+pub async fn read_frame<T>(io: &mut T, max_size: usize) -> std::io::Result<Vec<u8>>
+where
+    T: AsyncRead + Unpin + Send,
+{
+    // 读取长度前缀（u32 big endian）
+    let mut len_bytes = [0u8; 4];
+    io.read_exact(&mut len_bytes).await?;
+    let len = u32::from_be_bytes(len_bytes) as usize;
+
+    if len > max_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("数据帧过大: {} 字节", len),
+        ));
+    }
+
+    let mut buffer = vec![0u8; len];
+    io.read_exact(&mut buffer).await?;
+    Ok(buffer)
+}
+
+/// 写入带长度前缀的数据帧（不刷新）
+pub async fn write_frame<T>(io: &mut T, data: &[u8]) -> std::io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+{
+    let len = u32::try_from(data.len()).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("数据帧过大: {} 字节", data.len()),
+        )
+    })?;
+    io.write_all(&len.to_be_bytes()).await?;
+    io.write_all(data).await
+}
+
+/// 为读取操作设置超时，超时返回 `TimedOut` 错误
+pub async fn with_deadline<F, R>(timeout: Duration, read: F) -> std::io::Result<R>
+where
+    F: Future<Output = std::io::Result<R>>,
+{
+    tokio::time::timeout(timeout, read).await.map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("读取超时（{} 秒）", timeout.as_secs()),
+        )
+    })?
+}
+
+/// 在超时时间内读取一帧并按格式解析
+pub async fn read_message<T, M>(
+    io: &mut T,
+    format: Format,
+    max_size: usize,
+    timeout: Duration,
+) -> std::io::Result<M>
+where
+    T: AsyncRead + Unpin + Send,
+    M: DeserializeOwned,
+{
+    let data = with_deadline(timeout, read_frame(io, max_size)).await?;
+    format.decode(&data)
+}
+
+/// 按格式序列化并写入一帧
+pub async fn write_message<T, M>(io: &mut T, format: Format, message: M) -> std::io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+    M: Serialize + Send,
+{
+    let data = format.encode(&message)?;
+    write_frame(io, &data).await?;
+    io.flush().await
+}
+
+/// 只用于携带类型参数，不持有数据
+type Marker<P, Req, Resp> = PhantomData<fn() -> (P, Req, Resp)>;
+
+/// 通用的长度前缀 Codec
+///
+/// 请求和响应分别限制最大帧大小，序列化格式由协议 `P` 决定。
+pub struct LengthPrefixedCodec<P, Req, Resp> {
+    max_request_size: usize,
+    max_response_size: usize,
+    read_timeout: Duration,
+    _marker: Marker<P, Req, Resp>,
+}
+
+impl<P, Req, Resp> LengthPrefixedCodec<P, Req, Resp> {
+    /// 创建 Codec 并指定请求和响应的最大帧大小
+    pub fn new(max_request_size: usize, max_response_size: usize) -> Self {
+        Self {
+            max_request_size,
+            max_response_size,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 设置读取单个帧的超时时间
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+}
+
+impl<P, Req, Resp> Default for LengthPrefixedCodec<P, Req, Resp> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FRAME_SIZE, DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl<P, Req, Resp> Clone for LengthPrefixedCodec<P, Req, Resp> {
+    fn clone(&self) -> Self {
+        Self::new(self.max_request_size, self.max_response_size)
+            .with_read_timeout(self.read_timeout)
+    }
+}
+
+impl<P, Req, Resp> std::fmt::Debug for LengthPrefixedCodec<P, Req, Resp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LengthPrefixedCodec")
+            .field("max_request_size", &self.max_request_size)
+            .field("max_response_size", &self.max_response_size)
+            .field("read_timeout", &self.read_timeout)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<P, Req, Resp> request_response::Codec for LengthPrefixedCodec<P, Req, Resp>
+where
+    P: FramedProtocol,
+    Req: Serialize + DeserializeOwned + Send + 'static,
+    Resp: Serialize + DeserializeOwned + Send + 'static,
+{
+    type Protocol = P;
+    type Request = Req;
+    type Response = Resp;
+
+    async fn read_request<T>(&mut self, protocol: &P, io: &mut T) -> std::io::Result<Req>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io, protocol.format(), self.max_request_size, self.read_timeout).await
+    }
+
+    async fn read_response<T>(&mut self, protocol: &P, io: &mut T) -> std::io::Result<Resp>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io, protocol.format(), self.max_response_size, self.read_timeout).await
+    }
+
+    async fn write_request<T>(&mut self, protocol: &P, io: &mut T, req: Req) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, protocol.format(), req).await
+    }
+
+    async fn write_response<T>(&mut self, protocol: &P, io: &mut T, res: Resp) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, protocol.format(), res).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+    use libp2p::request_response::Codec;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone)]
+    struct TestProtocol(Format);
+
+    impl AsRef<str> for TestProtocol {
+        fn as_ref(&self) -> &str {
+            "/test/1.0.0"
+        }
+    }
+
+    impl FramedProtocol for TestProtocol {
+        fn format(&self) -> Format {
+            self.0
+        }
+    }
+
+    /// 只发送一次数据然后永远挂起的读取端
+    struct Stalled(Option<[u8; 4]>);
+
+    impl AsyncRead for Stalled {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            match self.0.take() {
+                Some(data) => {
+                    buf[..data.len()].copy_from_slice(&data);
+                    std::task::Poll::Ready(Ok(data.len()))
+                }
+                None => std::task::Poll::Pending,
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Payload {
+        name: String,
+        data: Vec<u8>,
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_each_format() {
+        let mut codec = LengthPrefixedCodec::<TestProtocol, Payload, Payload>::default();
+        let payload = || Payload { name: "照片".to_string(), data: vec![7; 64] };
+
+        let mut sizes = Vec::new();
+        for format in [Format::Json, Format::Cbor] {
+            let protocol = TestProtocol(format);
+            let mut io = Cursor::new(Vec::new());
+            codec.write_request(&protocol, &mut io, payload()).await.unwrap();
+            sizes.push(io.get_ref().len());
+
+            io.set_position(0);
+            assert_eq!(codec.read_request(&protocol, &mut io).await.unwrap(), payload());
+        }
+        // CBOR 的帧比 JSON 小
+        assert!(sizes[1] < sizes[0]);
+    }
+
+    #[tokio::test]
+    async fn test_frame_size_limit() {
+        let mut codec = LengthPrefixedCodec::<TestProtocol, Payload, Payload>::new(16, 1024);
+        let protocol = TestProtocol(Format::Json);
+        let mut io = Cursor::new(Vec::new());
+        codec
+            .write_response(&protocol, &mut io, Payload { name: "a".repeat(32), data: vec![] })
+            .await
+            .unwrap();
+
+        io.set_position(0);
+        let err = codec.read_request(&protocol, &mut io).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(is_protocol_violation(&err));
+
+        io.set_position(0);
+        assert!(codec.read_response(&protocol, &mut io).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_read_deadline() {
+        let mut codec = LengthPrefixedCodec::<TestProtocol, Payload, Payload>::default()
+            .with_read_timeout(Duration::from_millis(50));
+        let protocol = TestProtocol(Format::Json);
+
+        // 声明 100 字节但只发送长度前缀，模拟迟迟不发送数据的节点
+        let mut reader = Stalled(Some(100u32.to_be_bytes()));
+
+        let err = codec.read_request(&protocol, &mut reader).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(is_protocol_violation(&err));
+    }
+
+    #[test]
+    fn test_encode_error_is_not_violation() {
+        let mut map = std::collections::HashMap::new();
+        map.insert(vec![1u8], 1);
+        let err = Format::Json.encode(&map).unwrap_err();
+        assert!(!is_protocol_violation(&err));
+    }
+}
@@ -1,6 +1,8 @@
 //! mDNS 配置模块
 
+use crate::interfaces::InterfaceFilter;
 use libp2p::Multiaddr;
+use std::time::Duration;
 
 /// mDNS 配置
 #[derive(Debug, Clone)]
@@ -11,8 +13,20 @@ pub struct MdnsConfig {
     /// 服务查询间隔
     pub query_interval: std::time::Duration,
 
+    /// mDNS 记录 TTL
+    pub ttl: Duration,
+
+    /// 是否同时在 IPv6 上运行 mDNS
+    pub enable_ipv6: bool,
+
+    /// 网卡过滤（为空时使用所有网卡）
+    pub interfaces: InterfaceFilter,
+
     /// 服务信息
     pub service_info: Option<ServiceInfo>,
+
+    /// 是否同时监听 QUIC（为每个 TCP 监听地址补充对应的 `/udp/<端口>/quic-v1` 地址）
+    pub quic: bool,
 }
 
 impl Default for MdnsConfig {
@@ -23,7 +37,11 @@ impl Default for MdnsConfig {
                 "/ip6/::/tcp/0".parse().unwrap(),
             ],
             query_interval: std::time::Duration::from_secs(5),
+            ttl: Duration::from_secs(6 * 60),
+            enable_ipv6: false,
+            interfaces: InterfaceFilter::new(),
             service_info: None,
+            quic: false,
         }
     }
 }
@@ -46,11 +64,49 @@ impl MdnsConfig {
         self
     }
 
+    /// 设置 mDNS 记录 TTL
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// 启用或禁用 IPv6 mDNS
+    pub fn with_ipv6(mut self, enabled: bool) -> Self {
+        self.enable_ipv6 = enabled;
+        self
+    }
+
+    /// 设置网卡过滤
+    pub fn with_interface_filter(mut self, filter: InterfaceFilter) -> Self {
+        self.interfaces = filter;
+        self
+    }
+
     /// 设置服务信息
     pub fn with_service_info(mut self, info: ServiceInfo) -> Self {
         self.service_info = Some(info);
         self
     }
+
+    /// 启用或禁用 QUIC
+    pub fn with_quic(mut self, enabled: bool) -> Self {
+        self.quic = enabled;
+        self
+    }
+
+    /// 添加了 QUIC 地址、尚未按网卡展开的监听地址
+    pub fn resolved_listen_addresses(&self) -> Vec<Multiaddr> {
+        if self.quic {
+            crate::transport::with_quic_listen_addresses(self.listen_addresses.clone())
+        } else {
+            self.listen_addresses.clone()
+        }
+    }
+
+    /// libp2p mDNS 参数
+    pub(crate) fn mdns_config(&self) -> libp2p::mdns::Config {
+        crate::interfaces::mdns_config(self.ttl, self.query_interval, self.enable_ipv6)
+    }
 }
 
 /// 服务信息
@@ -1,6 +1,7 @@
 //! mDNS 服务发现模块
 
 use super::{MdnsConfig, Result, MdnsError};
+use super::interfaces::{MdnsBehaviour, ResolvedInterfaces};
 use libp2p::{
     mdns,
     Swarm,
@@ -10,7 +11,7 @@ use libp2p::{
     PeerId,
 };
 use futures::StreamExt;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 /// 发现的对等节点信息
 #[derive(Debug, Clone)]
@@ -24,14 +25,22 @@ pub struct DiscoveredPeer {
 
 /// mDNS 服务发现器
 pub struct MdnsDiscovery {
-    swarm: Swarm<mdns::tokio::Behaviour>,
+    swarm: Swarm<MdnsBehaviour>,
+    /// 网卡过滤（忽略不在允许网段内的地址）
+    interfaces: ResolvedInterfaces,
     discovered_peers: HashMap<PeerId, Vec<Multiaddr>>,
+    /// 待返回的事件（一次 mDNS 响应可能包含多个节点）
+    pending_events: VecDeque<DiscoveredEvent>,
 }
 
 impl MdnsDiscovery {
     /// 创建新的服务发现器
     pub async fn new(config: MdnsConfig) -> Result<Self> {
         let local_key = Keypair::generate_ed25519();
+        let interfaces = config
+            .interfaces
+            .resolve(config.resolved_listen_addresses())
+            .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
 
         // 使用 libp2p 0.56 的 SwarmBuilder API 构建 Swarm
         // 对于 mDNS，我们需要一个基础的 transport，即使我们不直接使用它
@@ -43,55 +52,66 @@ impl MdnsDiscovery {
                 libp2p::yamux::Config::default,
             )
             .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?
+            .with_quic()
             .with_behaviour(|_key| {
-                mdns::tokio::Behaviour::new(mdns::Config::default(), _key.public().into())
+                MdnsBehaviour::new(config.mdns_config(), _key.public().into())
                     .expect("mdns behaviour creation failed")
             })
             .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?
             .with_swarm_config(|c| c.with_idle_connection_timeout(std::time::Duration::from_secs(60)))
             .build();
 
-        for addr in config.listen_addresses {
+        for addr in interfaces.listen_addresses.clone() {
             swarm.listen_on(addr)
                 .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
         }
 
         Ok(Self {
             swarm,
+            interfaces,
             discovered_peers: HashMap::new(),
+            pending_events: VecDeque::new(),
         })
     }
 
     /// 启动发现服务
     pub async fn run(&mut self) -> Result<DiscoveredEvent> {
         loop {
-            match self.swarm.select_next_some().await {
-                libp2p::swarm::SwarmEvent::Behaviour(mdns::Event::Discovered(list)) => {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Ok(event);
+            }
+
+            let libp2p::swarm::SwarmEvent::Behaviour(event) = self.swarm.select_next_some().await else {
+                continue;
+            };
+            match event.into_inner() {
+                mdns::Event::Discovered(list) => {
                     for (peer_id, addr) in list {
+                        if !self.interfaces.permits(&addr) {
+                            tracing::debug!("忽略过滤网卡外的地址: {} at {}", peer_id, addr);
+                            continue;
+                        }
                         tracing::info!("发现对等节点: {} at {}", peer_id, addr);
-                        self.discovered_peers
-                            .entry(peer_id)
-                            .or_insert_with(Vec::new)
-                            .push(addr);
-                        return Ok(DiscoveredEvent::PeerFound(DiscoveredPeer {
+                        let addrs = self.discovered_peers.entry(peer_id).or_default();
+                        addrs.push(addr);
+                        self.pending_events.push_back(DiscoveredEvent::PeerFound(DiscoveredPeer {
                             peer_id,
-                            addresses: self.discovered_peers.get(&peer_id).cloned().unwrap_or_default(),
+                            addresses: addrs.clone(),
                         }));
                     }
                 }
-                libp2p::swarm::SwarmEvent::Behaviour(mdns::Event::Expired(list)) => {
+                mdns::Event::Expired(list) => {
                     for (peer_id, addr) in list {
                         tracing::info!("对等节点过期: {} at {}", peer_id, addr);
                         if let Some(addrs) = self.discovered_peers.get_mut(&peer_id) {
                             addrs.retain(|a| a != &addr);
                             if addrs.is_empty() {
                                 self.discovered_peers.remove(&peer_id);
-                                return Ok(DiscoveredEvent::PeerExpired(peer_id));
+                                self.pending_events.push_back(DiscoveredEvent::PeerExpired(peer_id));
                             }
                         }
                     }
                 }
-                _ => {}
             }
         }
     }
@@ -0,0 +1,179 @@
+//! 文件传输协议 Codec
+//!
+//! 使用 request_response 模式传输文件。头部为带长度前缀的 JSON，
+//! 分块数据以原始字节追加在头部之后，避免 JSON 编码二进制数据的开销。
+
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use libp2p::request_response;
+
+use crate::codec::{read_frame, write_frame};
+
+use super::message::{
+    FileTransferRequest, FileTransferResponse, FILE_TRANSFER_PROTOCOL, MAX_CHUNK_SIZE,
+};
+
+/// JSON 头部的最大大小（64KB）
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// 文件传输协议（标记类型）
+#[derive(Debug, Clone, Default)]
+pub struct FileTransferProtocol;
+
+impl AsRef<str> for FileTransferProtocol {
+    fn as_ref(&self) -> &str {
+        FILE_TRANSFER_PROTOCOL
+    }
+}
+
+/// 文件传输 Codec
+#[derive(Debug, Clone, Default)]
+pub struct FileTransferCodec;
+
+#[async_trait]
+impl request_response::Codec for FileTransferCodec {
+    type Protocol = FileTransferProtocol;
+    type Request = FileTransferRequest;
+    type Response = FileTransferResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+    ) -> std::io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let header = read_frame(io, MAX_HEADER_SIZE).await?;
+        let mut request = serde_json::from_slice::<FileTransferRequest>(&header)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        // 分块请求在头部之后紧跟原始数据
+        if let FileTransferRequest::Chunk(ref mut chunk) = request {
+            chunk.data = read_frame(io, MAX_CHUNK_SIZE as usize).await?;
+        }
+
+        Ok(request)
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+    ) -> std::io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let header = read_frame(io, MAX_HEADER_SIZE).await?;
+        serde_json::from_slice::<FileTransferResponse>(&header)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+        req: Self::Request,
+    ) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let header = serde_json::to_vec(&req)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        write_frame(io, &header).await?;
+
+        if let FileTransferRequest::Chunk(ref chunk) = req {
+            write_frame(io, &chunk.data).await?;
+        }
+
+        io.flush().await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+        res: Self::Response,
+    ) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let header = serde_json::to_vec(&res)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        write_frame(io, &header).await?;
+        io.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_transfer::message::FileChunk;
+    use futures::io::Cursor;
+    use request_response::Codec;
+
+    #[tokio::test]
+    async fn test_chunk_request_roundtrip() {
+        let mut codec = FileTransferCodec;
+        let chunk = FileChunk::new("t1".to_string(), 7, vec![0xAB; 1024]);
+        let request = FileTransferRequest::Chunk(chunk.clone());
+
+        let mut buffer = Vec::new();
+        codec
+            .write_request(&FileTransferProtocol, &mut Cursor::new(&mut buffer), request)
+            .await
+            .unwrap();
+
+        let decoded = codec
+            .read_request(&FileTransferProtocol, &mut Cursor::new(buffer))
+            .await
+            .unwrap();
+
+        match decoded {
+            FileTransferRequest::Chunk(decoded) => {
+                assert_eq!(decoded, chunk);
+                assert!(decoded.verify());
+            }
+            _ => panic!("Expected Chunk request"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_roundtrip() {
+        let mut codec = FileTransferCodec;
+        let response = FileTransferResponse::ChunkAck {
+            transfer_id: "t1".to_string(),
+            index: 3,
+            verified: true,
+        };
+
+        let mut buffer = Vec::new();
+        codec
+            .write_response(&FileTransferProtocol, &mut Cursor::new(&mut buffer), response.clone())
+            .await
+            .unwrap();
+
+        let decoded = codec
+            .read_response(&FileTransferProtocol, &mut Cursor::new(buffer))
+            .await
+            .unwrap();
+        assert_eq!(decoded, response);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_header_rejected() {
+        let mut codec = FileTransferCodec;
+        let mut buffer = ((MAX_HEADER_SIZE + 1) as u32).to_be_bytes().to_vec();
+        buffer.extend_from_slice(&[0u8; 16]);
+
+        let result = codec
+            .read_request(&FileTransferProtocol, &mut Cursor::new(buffer))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_protocol_name() {
+        assert_eq!(FileTransferProtocol.as_ref(), "/localp2p/file/1.0.0");
+    }
+}
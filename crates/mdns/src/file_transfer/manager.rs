@@ -0,0 +1,1335 @@
+//! 文件传输管理器
+//!
+//! 管理所有发送和接收中的文件传输，负责分块读写、哈希校验和断点续传状态。
+//!
+//! 接收方在下载目录中写入 `<文件名>.<传输 ID>.part`，并在旁边维护 `<文件名>.<传输 ID>.part.json`
+//! 记录已校验通过的分块数，同名文件的多个传输互不影响。连接断开后重新收到同一发送方的同一文件提议时
+//! （文件名、大小和整个文件的哈希都相同），从最后一个校验通过的分块继续。
+//! 全部分块接收后校验整个文件的哈希，通过后才重命名为最终文件。
+
+use super::message::{
+    to_hex, FileChunk, FileOffer, FileTransferError, FileTransferRequest, DEFAULT_CHUNK_SIZE,
+    MAX_CHUNK_SIZE,
+};
+use super::traits::{FileTransferEvent, TransferDirection};
+use crate::node::NodeManager;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{mpsc, RwLock};
+
+/// 单个分块校验失败后的最大重发次数
+const MAX_CHUNK_RETRIES: u32 = 3;
+
+/// 未完成文件的后缀
+const PART_SUFFIX: &str = "part";
+
+/// 计算整个文件哈希时每次读取的字节数
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 传输 ID 的最大长度
+const MAX_TRANSFER_ID_LEN: usize = 64;
+
+/// 文件传输配置
+#[derive(Debug, Clone)]
+pub struct FileTransferConfig {
+    /// 接收文件的保存目录
+    pub download_dir: PathBuf,
+
+    /// 发送文件时使用的分块大小
+    pub chunk_size: u32,
+}
+
+impl Default for FileTransferConfig {
+    fn default() -> Self {
+        Self {
+            download_dir: PathBuf::from("downloads"),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl FileTransferConfig {
+    /// 创建新的配置
+    pub fn new(download_dir: PathBuf) -> Self {
+        Self {
+            download_dir,
+            ..Self::default()
+        }
+    }
+
+    /// 设置分块大小（不超过 `MAX_CHUNK_SIZE`）
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Self {
+        self.chunk_size = chunk_size.clamp(1, MAX_CHUNK_SIZE);
+        self
+    }
+}
+
+/// 传输状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferState {
+    /// 等待对方（或本地用户）接受
+    Pending,
+    /// 传输中
+    InProgress,
+    /// 连接断开，可续传
+    Interrupted,
+    /// 已完成
+    Completed,
+    /// 被拒绝
+    Rejected,
+    /// 已取消
+    Cancelled,
+    /// 失败
+    Failed,
+}
+
+impl TransferState {
+    /// 是否为终止状态
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            Self::Completed | Self::Rejected | Self::Cancelled | Self::Failed
+        )
+    }
+}
+
+/// 文件传输记录
+#[derive(Debug, Clone)]
+pub struct FileTransfer {
+    /// 文件提议
+    pub offer: FileOffer,
+    /// 对方的 Peer ID
+    pub peer_id: PeerId,
+    /// 传输方向
+    pub direction: TransferDirection,
+    /// 本地路径（发送方为源文件，接收方为最终保存位置）
+    pub path: PathBuf,
+    /// 下一个待传输的分块序号（之前的分块均已校验通过）
+    pub next_chunk: u64,
+    /// 当前状态
+    pub state: TransferState,
+    /// 当前分块的重发次数
+    retries: u32,
+}
+
+impl FileTransfer {
+    /// 传输 ID
+    pub fn transfer_id(&self) -> &str {
+        &self.offer.transfer_id
+    }
+
+    /// 已校验通过的字节数
+    pub fn transferred_bytes(&self) -> u64 {
+        self.offer.chunk_offset(self.next_chunk).min(self.offer.file_size)
+    }
+
+    /// 是否所有分块均已传输
+    pub fn is_all_chunks_done(&self) -> bool {
+        self.next_chunk >= self.offer.total_chunks()
+    }
+
+    /// 接收方的 `.part` 文件路径（包含传输 ID，每个传输独立）
+    fn part_path(&self) -> PathBuf {
+        with_suffix(&self.path, &format!("{}.{}", self.offer.transfer_id, PART_SUFFIX))
+    }
+
+    /// 接收方的续传状态文件路径
+    fn progress_path(&self) -> PathBuf {
+        with_suffix(&self.path, &format!("{}.{}.json", self.offer.transfer_id, PART_SUFFIX))
+    }
+}
+
+/// 续传状态（保存在 `.part.json` 中）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PartialProgress {
+    transfer_id: String,
+    sender_peer_id: String,
+    file_name: String,
+    file_size: u64,
+    chunk_size: u32,
+    /// 旧版本的续传状态没有哈希，不会与任何提议匹配
+    #[serde(default)]
+    file_hash: String,
+    verified_chunks: u64,
+}
+
+impl PartialProgress {
+    /// 是否与文件提议对应同一个文件
+    fn matches(&self, offer: &FileOffer) -> bool {
+        self.sender_peer_id == offer.sender_peer_id
+            && self.file_name == offer.file_name
+            && self.file_size == offer.file_size
+            && self.chunk_size == offer.chunk_size
+            && self.file_hash == offer.file_hash
+    }
+}
+
+/// 文件传输管理器
+///
+/// 管理所有文件传输，负责分块读写、校验和续传状态。
+pub struct FileTransferManager {
+    /// 节点管理器（复用，获取已验证节点）
+    node_manager: Arc<NodeManager>,
+    /// 本地 Peer ID
+    local_peer_id: PeerId,
+    /// 配置
+    config: FileTransferConfig,
+    /// 传输记录（transfer_id -> 传输）
+    transfers: RwLock<HashMap<String, FileTransfer>>,
+    /// 事件发送器
+    event_tx: mpsc::UnboundedSender<FileTransferEvent>,
+}
+
+impl FileTransferManager {
+    /// 创建新的文件传输管理器
+    pub fn new(
+        node_manager: Arc<NodeManager>,
+        local_peer_id: PeerId,
+        config: FileTransferConfig,
+    ) -> (Self, mpsc::UnboundedReceiver<FileTransferEvent>) {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+        let manager = Self {
+            node_manager,
+            local_peer_id,
+            config,
+            transfers: RwLock::new(HashMap::new()),
+            event_tx,
+        };
+
+        (manager, event_rx)
+    }
+
+    /// 获取配置
+    pub fn config(&self) -> &FileTransferConfig {
+        &self.config
+    }
+
+    /// 获取传输记录
+    pub async fn get_transfer(&self, transfer_id: &str) -> Option<FileTransfer> {
+        self.transfers.read().await.get(transfer_id).cloned()
+    }
+
+    /// 列出所有传输记录
+    pub async fn list_transfers(&self) -> Vec<FileTransfer> {
+        self.transfers.read().await.values().cloned().collect()
+    }
+
+    /// 清除已结束的传输记录
+    pub async fn clear_finished(&self) {
+        self.transfers
+            .write()
+            .await
+            .retain(|_, t| !t.state.is_finished());
+    }
+
+    // ------------------------------------------------------------------
+    // 发送方
+    // ------------------------------------------------------------------
+
+    /// 为本地文件创建发送提议
+    pub async fn create_offer(&self, target: PeerId, path: &Path) -> Result<FileOffer, FileTransferError> {
+        if !self.node_manager.is_node_verified(&target).await {
+            return Err(FileTransferError::NodeNotVerified(target.to_string()));
+        }
+
+        let metadata = tokio::fs::metadata(path).await?;
+        if !metadata.is_file() {
+            return Err(FileTransferError::Io(format!("不是文件: {}", path.display())));
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| FileTransferError::Io(format!("无效文件名: {}", path.display())))?;
+
+        let file_hash = file_hash(path).await?;
+        let mut offer = FileOffer::new(file_name, metadata.len(), self.config.chunk_size).with_file_hash(file_hash);
+        offer.sender_peer_id = self.local_peer_id.to_string();
+
+        let transfer = FileTransfer {
+            offer: offer.clone(),
+            peer_id: target,
+            direction: TransferDirection::Outgoing,
+            path: path.to_path_buf(),
+            next_chunk: 0,
+            state: TransferState::Pending,
+            retries: 0,
+        };
+        self.transfers
+            .write()
+            .await
+            .insert(offer.transfer_id.clone(), transfer);
+
+        tracing::info!(
+            "创建文件提议 {} -> {}: {} ({} 字节)",
+            offer.transfer_id, target, offer.file_name, offer.file_size
+        );
+        Ok(offer)
+    }
+
+    /// 处理对方接受文件
+    pub async fn handle_accept(&self, from: PeerId, transfer_id: &str, resume_from: u64) -> Result<(), FileTransferError> {
+        let mut transfers = self.transfers.write().await;
+        let transfer = outgoing_mut(&mut transfers, from, transfer_id)?;
+
+        if !matches!(transfer.state, TransferState::Pending | TransferState::Interrupted) {
+            return Err(FileTransferError::InvalidState(format!(
+                "{} 当前状态 {:?}",
+                transfer_id, transfer.state
+            )));
+        }
+
+        transfer.next_chunk = resume_from.min(transfer.offer.total_chunks());
+        transfer.state = TransferState::InProgress;
+        transfer.retries = 0;
+
+        tracing::info!("{} 接受文件 {}，从分块 {} 开始", from, transfer_id, transfer.next_chunk);
+        let _ = self.event_tx.send(FileTransferEvent::Accepted {
+            peer_id: from,
+            transfer_id: transfer_id.to_string(),
+            resume_from: transfer.next_chunk,
+        });
+        Ok(())
+    }
+
+    /// 处理对方拒绝文件
+    pub async fn handle_reject(&self, from: PeerId, transfer_id: &str, reason: String) -> Result<(), FileTransferError> {
+        let mut transfers = self.transfers.write().await;
+        let transfer = outgoing_mut(&mut transfers, from, transfer_id)?;
+        transfer.state = TransferState::Rejected;
+
+        tracing::info!("{} 拒绝文件 {}: {}", from, transfer_id, reason);
+        let _ = self.event_tx.send(FileTransferEvent::Rejected {
+            peer_id: from,
+            transfer_id: transfer_id.to_string(),
+            reason,
+        });
+        Ok(())
+    }
+
+    /// 生成发送方的下一个请求
+    ///
+    /// 仍有分块时返回 `Chunk`，全部分块确认后返回 `Complete`，
+    /// 传输不在进行中时返回 `None`。
+    pub async fn next_request(&self, transfer_id: &str) -> Result<Option<FileTransferRequest>, FileTransferError> {
+        let (path, offer, index) = {
+            let transfers = self.transfers.read().await;
+            let transfer = transfers
+                .get(transfer_id)
+                .ok_or_else(|| FileTransferError::TransferNotFound(transfer_id.to_string()))?;
+
+            if transfer.direction != TransferDirection::Outgoing
+                || transfer.state != TransferState::InProgress
+            {
+                return Ok(None);
+            }
+
+            if transfer.is_all_chunks_done() {
+                return Ok(Some(FileTransferRequest::Complete {
+                    transfer_id: transfer_id.to_string(),
+                }));
+            }
+
+            (transfer.path.clone(), transfer.offer.clone(), transfer.next_chunk)
+        };
+
+        // 在锁外读取文件
+        let mut file = tokio::fs::File::open(&path).await?;
+        file.seek(std::io::SeekFrom::Start(offer.chunk_offset(index))).await?;
+        let mut data = vec![0u8; offer.chunk_len(index)];
+        file.read_exact(&mut data).await?;
+
+        Ok(Some(FileTransferRequest::Chunk(FileChunk::new(
+            transfer_id.to_string(),
+            index,
+            data,
+        ))))
+    }
+
+    /// 处理分块确认
+    pub async fn handle_chunk_ack(&self, from: PeerId, transfer_id: &str, index: u64, verified: bool) -> Result<(), FileTransferError> {
+        let mut transfers = self.transfers.write().await;
+        let transfer = outgoing_mut(&mut transfers, from, transfer_id)?;
+
+        if transfer.state != TransferState::InProgress || index != transfer.next_chunk {
+            tracing::debug!("忽略过期的分块确认 {}#{}", transfer_id, index);
+            return Ok(());
+        }
+
+        if verified {
+            transfer.next_chunk += 1;
+            transfer.retries = 0;
+            let _ = self.event_tx.send(FileTransferEvent::Progress {
+                peer_id: from,
+                transfer_id: transfer_id.to_string(),
+                direction: TransferDirection::Outgoing,
+                transferred_bytes: transfer.transferred_bytes(),
+                total_bytes: transfer.offer.file_size,
+            });
+            return Ok(());
+        }
+
+        transfer.retries += 1;
+        tracing::warn!("分块 {}#{} 校验失败（第 {} 次）", transfer_id, index, transfer.retries);
+        if transfer.retries > MAX_CHUNK_RETRIES {
+            transfer.state = TransferState::Failed;
+            let error = FileTransferError::HashMismatch(index);
+            let _ = self.event_tx.send(FileTransferEvent::Failed {
+                peer_id: from,
+                transfer_id: transfer_id.to_string(),
+                direction: TransferDirection::Outgoing,
+                error: error.to_string(),
+            });
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// 处理对方的普通确认
+    ///
+    /// 所有分块发送完毕后收到的确认即为完成确认。
+    pub async fn handle_ack(&self, from: PeerId, transfer_id: &str) {
+        let mut transfers = self.transfers.write().await;
+        let Ok(transfer) = outgoing_mut(&mut transfers, from, transfer_id) else {
+            return;
+        };
+
+        if transfer.state == TransferState::InProgress && transfer.is_all_chunks_done() {
+            transfer.state = TransferState::Completed;
+            tracing::info!("✓ 文件 {} 已发送给 {}", transfer.offer.file_name, from);
+            let _ = self.event_tx.send(FileTransferEvent::Completed {
+                peer_id: from,
+                transfer_id: transfer_id.to_string(),
+                direction: TransferDirection::Outgoing,
+                path: transfer.path.clone(),
+            });
+        }
+    }
+
+    /// 取出与指定节点之间中断的发送任务，用于重新发送提议
+    pub async fn take_interrupted_offers(&self, peer_id: &PeerId) -> Vec<FileOffer> {
+        let mut transfers = self.transfers.write().await;
+        transfers
+            .values_mut()
+            .filter(|t| {
+                t.peer_id == *peer_id
+                    && t.direction == TransferDirection::Outgoing
+                    && t.state == TransferState::Interrupted
+            })
+            .map(|t| {
+                t.state = TransferState::Pending;
+                t.offer.clone()
+            })
+            .collect()
+    }
+
+    // ------------------------------------------------------------------
+    // 接收方
+    // ------------------------------------------------------------------
+
+    /// 处理收到的文件提议
+    ///
+    /// 如果该文件之前已被接受且存在未完成的部分，返回 `Some(resume_from)`，
+    /// 调用者应直接回复 `Accept` 继续传输；否则等待用户接受。
+    pub async fn handle_offer(&self, from: PeerId, mut offer: FileOffer) -> Result<Option<u64>, FileTransferError> {
+        if offer.chunk_size == 0 || offer.chunk_size > MAX_CHUNK_SIZE {
+            return Err(FileTransferError::InvalidState(format!(
+                "无效分块大小: {}",
+                offer.chunk_size
+            )));
+        }
+        if offer.file_hash.is_empty() {
+            return Err(FileTransferError::InvalidState("文件提议缺少文件哈希".to_string()));
+        }
+        // 传输 ID 是未完成文件名的一部分
+        if !is_valid_transfer_id(&offer.transfer_id) {
+            return Err(FileTransferError::InvalidState(format!("无效传输 ID: {}", offer.transfer_id)));
+        }
+
+        // 只使用文件名部分，防止路径穿越
+        let file_name = sanitize_file_name(&offer.file_name)
+            .ok_or_else(|| FileTransferError::Io(format!("无效文件名: {}", offer.file_name)))?;
+        offer.file_name = file_name;
+        offer.sender_peer_id = from.to_string();
+
+        let mut transfers = self.transfers.write().await;
+
+        // 传输 ID 由对方选择，只允许同一发送方重新提议同一文件（连接断开后恢复），
+        // 不能覆盖本地的发送任务或其他节点的传输
+        if let Some(existing) = transfers.get_mut(&offer.transfer_id) {
+            if existing.direction != TransferDirection::Incoming
+                || existing.peer_id != from
+                || !is_same_file(&existing.offer, &offer)
+            {
+                tracing::warn!("{} 的文件提议使用了已存在的传输 ID {}", from, offer.transfer_id);
+                return Err(FileTransferError::InvalidState(format!(
+                    "传输 ID 已被占用: {}",
+                    offer.transfer_id
+                )));
+            }
+            return match existing.state {
+                TransferState::InProgress | TransferState::Interrupted => {
+                    existing.state = TransferState::InProgress;
+                    tracing::info!("恢复接收 {}，从分块 {} 继续", offer.transfer_id, existing.next_chunk);
+                    Ok(Some(existing.next_chunk))
+                }
+                // 仍在等待用户接受
+                TransferState::Pending => Ok(None),
+                state => Err(FileTransferError::InvalidState(format!(
+                    "{} 当前状态 {:?}",
+                    offer.transfer_id, state
+                ))),
+            };
+        }
+
+        let path = self.config.download_dir.join(&offer.file_name);
+        let mut transfer = FileTransfer {
+            offer: offer.clone(),
+            peer_id: from,
+            direction: TransferDirection::Incoming,
+            path,
+            next_chunk: 0,
+            state: TransferState::Pending,
+            retries: 0,
+        };
+
+        // 检查磁盘上是否有同一文件的未完成部分（例如重启后重新提议），沿用为本传输的未完成文件
+        let resume_from = match find_partial(&self.config.download_dir, &offer, &transfers).await {
+            Some((progress_path, progress)) => {
+                tokio::fs::rename(progress_path.with_extension(""), transfer.part_path()).await?;
+                let _ = tokio::fs::remove_file(&progress_path).await;
+                transfer.next_chunk = progress.verified_chunks.min(offer.total_chunks());
+                transfer.state = TransferState::InProgress;
+                write_progress(&transfer).await?;
+                Some(transfer.next_chunk)
+            }
+            None => None,
+        };
+
+        transfers.insert(offer.transfer_id.clone(), transfer);
+        drop(transfers);
+
+        match resume_from {
+            Some(index) => {
+                tracing::info!("续传 {} 的文件 {}，从分块 {} 继续", from, offer.file_name, index);
+            }
+            None => {
+                tracing::info!("📁 收到 {} 的文件提议: {} ({} 字节)", from, offer.file_name, offer.file_size);
+                let _ = self.event_tx.send(FileTransferEvent::OfferReceived { from, offer });
+            }
+        }
+
+        Ok(resume_from)
+    }
+
+    /// 接受文件提议
+    ///
+    /// 返回发送方 Peer ID 和起始分块序号。
+    pub async fn accept(&self, transfer_id: &str) -> Result<(PeerId, u64), FileTransferError> {
+        let mut transfers = self.transfers.write().await;
+        let transfer = transfers
+            .get_mut(transfer_id)
+            .filter(|t| t.direction == TransferDirection::Incoming)
+            .ok_or_else(|| FileTransferError::TransferNotFound(transfer_id.to_string()))?;
+
+        if transfer.state != TransferState::Pending {
+            return Err(FileTransferError::InvalidState(format!(
+                "{} 当前状态 {:?}",
+                transfer_id, transfer.state
+            )));
+        }
+
+        tokio::fs::create_dir_all(&self.config.download_dir).await?;
+        // 新建（或截断）未完成文件
+        tokio::fs::File::create(transfer.part_path()).await?;
+        transfer.next_chunk = 0;
+        transfer.state = TransferState::InProgress;
+        write_progress(transfer).await?;
+
+        let peer_id = transfer.peer_id;
+        let _ = self.event_tx.send(FileTransferEvent::Accepted {
+            peer_id,
+            transfer_id: transfer_id.to_string(),
+            resume_from: 0,
+        });
+        Ok((peer_id, 0))
+    }
+
+    /// 拒绝文件提议
+    ///
+    /// 返回发送方 Peer ID。
+    pub async fn reject(&self, transfer_id: &str, reason: String) -> Result<PeerId, FileTransferError> {
+        let mut transfers = self.transfers.write().await;
+        let transfer = transfers
+            .get_mut(transfer_id)
+            .filter(|t| t.direction == TransferDirection::Incoming && t.state == TransferState::Pending)
+            .ok_or_else(|| FileTransferError::TransferNotFound(transfer_id.to_string()))?;
+
+        transfer.state = TransferState::Rejected;
+        let peer_id = transfer.peer_id;
+        let _ = self.event_tx.send(FileTransferEvent::Rejected {
+            peer_id,
+            transfer_id: transfer_id.to_string(),
+            reason,
+        });
+        Ok(peer_id)
+    }
+
+    /// 处理收到的分块
+    ///
+    /// 返回分块是否校验通过并已写入磁盘。重复的旧分块视为已校验。
+    pub async fn handle_chunk(&self, from: PeerId, chunk: FileChunk) -> Result<bool, FileTransferError> {
+        // 只在锁内检查状态，校验和写盘在锁外进行，避免阻塞其他传输
+        let (part_path, offset, len) = {
+            let mut transfers = self.transfers.write().await;
+            let transfer = incoming_mut(&mut transfers, from, &chunk.transfer_id)?;
+
+            if transfer.state != TransferState::InProgress {
+                return Err(FileTransferError::InvalidState(format!(
+                    "{} 当前状态 {:?}",
+                    chunk.transfer_id, transfer.state
+                )));
+            }
+
+            if chunk.index < transfer.next_chunk {
+                return Ok(true);
+            }
+            if chunk.index > transfer.next_chunk {
+                return Err(FileTransferError::InvalidState(format!(
+                    "期望分块 {}，收到 {}",
+                    transfer.next_chunk, chunk.index
+                )));
+            }
+
+            (
+                transfer.part_path(),
+                transfer.offer.chunk_offset(chunk.index),
+                transfer.offer.chunk_len(chunk.index),
+            )
+        };
+
+        if chunk.data.len() != len || !chunk.verify() {
+            tracing::warn!("分块 {}#{} 校验失败", chunk.transfer_id, chunk.index);
+            return Ok(false);
+        }
+
+        // `.part` 文件在接受时创建；不存在说明传输已被取消，不重新创建
+        let mut file = tokio::fs::OpenOptions::new().write(true).open(&part_path).await?;
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+        file.write_all(&chunk.data).await?;
+        file.flush().await?;
+
+        let mut transfers = self.transfers.write().await;
+        let transfer = incoming_mut(&mut transfers, from, &chunk.transfer_id)?;
+        // 写盘期间传输可能已被取消，或同一分块已被重复请求写入
+        if transfer.state != TransferState::InProgress || transfer.next_chunk != chunk.index {
+            return Ok(transfer.next_chunk > chunk.index);
+        }
+
+        transfer.next_chunk += 1;
+        write_progress(transfer).await?;
+
+        let _ = self.event_tx.send(FileTransferEvent::Progress {
+            peer_id: from,
+            transfer_id: chunk.transfer_id.clone(),
+            direction: TransferDirection::Incoming,
+            transferred_bytes: transfer.transferred_bytes(),
+            total_bytes: transfer.offer.file_size,
+        });
+        Ok(true)
+    }
+
+    /// 处理发送完成
+    ///
+    /// 校验所有分块均已收到后，将 `.part` 文件重命名为最终文件名，返回保存路径。
+    pub async fn handle_complete(&self, from: PeerId, transfer_id: &str) -> Result<PathBuf, FileTransferError> {
+        let (part_path, expected_hash) = {
+            let mut transfers = self.transfers.write().await;
+            let transfer = incoming_mut(&mut transfers, from, transfer_id)?;
+
+            if transfer.state == TransferState::Completed {
+                return Ok(transfer.path.clone());
+            }
+            if transfer.state != TransferState::InProgress || !transfer.is_all_chunks_done() {
+                return Err(FileTransferError::InvalidState(format!(
+                    "{} 尚未收到全部分块 ({}/{})",
+                    transfer_id,
+                    transfer.next_chunk,
+                    transfer.offer.total_chunks()
+                )));
+            }
+
+            (transfer.part_path(), transfer.offer.file_hash.clone())
+        };
+
+        // 空文件不会收到任何分块
+        if !tokio::fs::try_exists(&part_path).await? {
+            tokio::fs::File::create(&part_path).await?;
+        }
+
+        // 大文件的哈希计算较慢，在锁外进行
+        let actual_hash = file_hash(&part_path).await?;
+
+        let mut transfers = self.transfers.write().await;
+        let transfer = incoming_mut(&mut transfers, from, transfer_id)?;
+        if transfer.state == TransferState::Completed {
+            return Ok(transfer.path.clone());
+        }
+        if transfer.state != TransferState::InProgress {
+            return Err(FileTransferError::InvalidState(format!(
+                "{} 当前状态 {:?}",
+                transfer_id, transfer.state
+            )));
+        }
+
+        if actual_hash != expected_hash {
+            transfer.state = TransferState::Failed;
+            let _ = tokio::fs::remove_file(&part_path).await;
+            let _ = tokio::fs::remove_file(transfer.progress_path()).await;
+
+            let error = FileTransferError::FileHashMismatch(transfer.offer.file_name.clone());
+            tracing::error!("来自 {} 的文件 {} 校验失败，已丢弃", from, transfer.offer.file_name);
+            let _ = self.event_tx.send(FileTransferEvent::Failed {
+                peer_id: from,
+                transfer_id: transfer_id.to_string(),
+                direction: TransferDirection::Incoming,
+                error: error.to_string(),
+            });
+            return Err(error);
+        }
+
+        let final_path = unique_path(&transfer.path).await;
+        tokio::fs::rename(&part_path, &final_path).await?;
+        let _ = tokio::fs::remove_file(transfer.progress_path()).await;
+
+        transfer.path = final_path.clone();
+        transfer.state = TransferState::Completed;
+
+        tracing::info!("✓ 已接收来自 {} 的文件: {}", from, final_path.display());
+        let _ = self.event_tx.send(FileTransferEvent::Completed {
+            peer_id: from,
+            transfer_id: transfer_id.to_string(),
+            direction: TransferDirection::Incoming,
+            path: final_path.clone(),
+        });
+        Ok(final_path)
+    }
+
+    // ------------------------------------------------------------------
+    // 通用
+    // ------------------------------------------------------------------
+
+    /// 本地取消传输
+    ///
+    /// 返回对方 Peer ID，调用者需要通知对方。
+    pub async fn cancel(&self, transfer_id: &str) -> Result<PeerId, FileTransferError> {
+        self.finish_cancelled(transfer_id, None, "本地取消".to_string()).await
+    }
+
+    /// 处理对方取消传输
+    pub async fn handle_cancel(&self, from: PeerId, transfer_id: &str, reason: String) -> Result<(), FileTransferError> {
+        self.finish_cancelled(transfer_id, Some(from), reason).await.map(|_| ())
+    }
+
+    /// 标记与指定节点之间的传输失败（例如对方返回错误）
+    pub async fn fail(&self, peer_id: PeerId, transfer_id: &str, error: String) {
+        let mut transfers = self.transfers.write().await;
+        if let Some(transfer) = transfers.get_mut(transfer_id).filter(|t| t.peer_id == peer_id) {
+            if transfer.state.is_finished() {
+                return;
+            }
+            transfer.state = TransferState::Failed;
+            tracing::error!("文件传输 {} 失败: {}", transfer_id, error);
+            let _ = self.event_tx.send(FileTransferEvent::Failed {
+                peer_id: transfer.peer_id,
+                transfer_id: transfer_id.to_string(),
+                direction: transfer.direction,
+                error,
+            });
+        }
+    }
+
+    /// 标记单个传输中断（例如请求超时）
+    pub async fn interrupt(&self, transfer_id: &str) {
+        let mut transfers = self.transfers.write().await;
+        if let Some(transfer) = transfers.get_mut(transfer_id) {
+            self.mark_interrupted(transfer);
+        }
+    }
+
+    /// 处理节点断开：将与该节点之间进行中的传输标记为中断
+    pub async fn handle_peer_disconnected(&self, peer_id: &PeerId) {
+        let mut transfers = self.transfers.write().await;
+        for transfer in transfers.values_mut().filter(|t| t.peer_id == *peer_id) {
+            self.mark_interrupted(transfer);
+        }
+    }
+
+    fn mark_interrupted(&self, transfer: &mut FileTransfer) {
+        if !matches!(transfer.state, TransferState::InProgress | TransferState::Pending) {
+            return;
+        }
+        // 等待用户接受的接收提议不受连接状态影响
+        if transfer.direction == TransferDirection::Incoming && transfer.state == TransferState::Pending {
+            return;
+        }
+
+        transfer.state = TransferState::Interrupted;
+        tracing::warn!("文件传输 {} 中断于分块 {}", transfer.transfer_id(), transfer.next_chunk);
+        let _ = self.event_tx.send(FileTransferEvent::Interrupted {
+            peer_id: transfer.peer_id,
+            transfer_id: transfer.transfer_id().to_string(),
+            direction: transfer.direction,
+        });
+    }
+
+    async fn finish_cancelled(&self, transfer_id: &str, from: Option<PeerId>, reason: String) -> Result<PeerId, FileTransferError> {
+        let mut transfers = self.transfers.write().await;
+        let transfer = transfers
+            .get_mut(transfer_id)
+            .filter(|t| from.is_none_or(|peer| t.peer_id == peer))
+            .ok_or_else(|| FileTransferError::TransferNotFound(transfer_id.to_string()))?;
+
+        if transfer.state.is_finished() {
+            return Err(FileTransferError::InvalidState(format!(
+                "{} 当前状态 {:?}",
+                transfer_id, transfer.state
+            )));
+        }
+
+        transfer.state = TransferState::Cancelled;
+        if transfer.direction == TransferDirection::Incoming {
+            let _ = tokio::fs::remove_file(transfer.part_path()).await;
+            let _ = tokio::fs::remove_file(transfer.progress_path()).await;
+        }
+
+        let peer_id = transfer.peer_id;
+        let _ = self.event_tx.send(FileTransferEvent::Cancelled {
+            peer_id,
+            transfer_id: transfer_id.to_string(),
+            direction: transfer.direction,
+            reason,
+        });
+        Ok(peer_id)
+    }
+}
+
+/// 两个提议是否对应同一个文件（续传还要求分块大小相同）
+fn is_same_file(a: &FileOffer, b: &FileOffer) -> bool {
+    a.file_name == b.file_name
+        && a.file_size == b.file_size
+        && a.chunk_size == b.chunk_size
+        && a.file_hash == b.file_hash
+}
+
+/// 获取指定节点的发送记录
+fn outgoing_mut<'a>(
+    transfers: &'a mut HashMap<String, FileTransfer>,
+    from: PeerId,
+    transfer_id: &str,
+) -> Result<&'a mut FileTransfer, FileTransferError> {
+    transfers
+        .get_mut(transfer_id)
+        .filter(|t| t.direction == TransferDirection::Outgoing && t.peer_id == from)
+        .ok_or_else(|| FileTransferError::TransferNotFound(transfer_id.to_string()))
+}
+
+/// 获取指定节点的接收记录
+fn incoming_mut<'a>(
+    transfers: &'a mut HashMap<String, FileTransfer>,
+    from: PeerId,
+    transfer_id: &str,
+) -> Result<&'a mut FileTransfer, FileTransferError> {
+    transfers
+        .get_mut(transfer_id)
+        .filter(|t| t.direction == TransferDirection::Incoming && t.peer_id == from)
+        .ok_or_else(|| FileTransferError::TransferNotFound(transfer_id.to_string()))
+}
+
+/// 保存续传状态
+async fn write_progress(transfer: &FileTransfer) -> Result<(), FileTransferError> {
+    let progress = PartialProgress {
+        transfer_id: transfer.offer.transfer_id.clone(),
+        sender_peer_id: transfer.offer.sender_peer_id.clone(),
+        file_name: transfer.offer.file_name.clone(),
+        file_size: transfer.offer.file_size,
+        chunk_size: transfer.offer.chunk_size,
+        file_hash: transfer.offer.file_hash.clone(),
+        verified_chunks: transfer.next_chunk,
+    };
+    let data = serde_json::to_vec(&progress).map_err(|e| FileTransferError::Io(e.to_string()))?;
+    tokio::fs::write(transfer.progress_path(), data).await?;
+    Ok(())
+}
+
+/// 计算整个文件的 SHA-256（十六进制小写），分段读取
+async fn file_hash(path: &Path) -> Result<String, FileTransferError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// 读取续传状态
+async fn read_progress(path: &Path) -> Option<PartialProgress> {
+    let data = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// 在下载目录中查找与提议对应同一文件、且不属于其他未结束传输的续传状态
+///
+/// 返回续传状态文件路径（去掉 `.json` 即为对应的 `.part` 文件）。
+async fn find_partial(
+    dir: &Path,
+    offer: &FileOffer,
+    transfers: &HashMap<String, FileTransfer>,
+) -> Option<(PathBuf, PartialProgress)> {
+    let suffix = format!(".{}.json", PART_SUFFIX);
+    let mut entries = tokio::fs::read_dir(dir).await.ok()?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if !path.to_string_lossy().ends_with(&suffix) {
+            continue;
+        }
+        let Some(progress) = read_progress(&path).await else {
+            continue;
+        };
+        let in_use = transfers
+            .get(&progress.transfer_id)
+            .is_some_and(|t| !t.state.is_finished());
+        if progress.matches(offer)
+            && !in_use
+            && tokio::fs::try_exists(path.with_extension("")).await.unwrap_or(false)
+        {
+            return Some((path, progress));
+        }
+    }
+    None
+}
+
+/// 在路径后追加后缀（`a.txt` -> `a.txt.part`）
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// 传输 ID 是否有效（只含字母、数字和 `-`，可以安全地用作文件名的一部分）
+fn is_valid_transfer_id(transfer_id: &str) -> bool {
+    !transfer_id.is_empty()
+        && transfer_id.len() <= MAX_TRANSFER_ID_LEN
+        && transfer_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// 只保留文件名部分，拒绝空名称和特殊目录
+fn sanitize_file_name(name: &str) -> Option<String> {
+    let name = Path::new(name).file_name()?.to_string_lossy().to_string();
+    if name.is_empty() || name == "." || name == ".." {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// 如果目标文件已存在，生成 `name (1).ext` 形式的新路径
+async fn unique_path(path: &Path) -> PathBuf {
+    if !tokio::fs::try_exists(path).await.unwrap_or(false) {
+        return path.to_path_buf();
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+
+    for i in 1.. {
+        let name = match extension {
+            Some(ref ext) => format!("{} ({}).{}", stem, i, ext),
+            None => format!("{} ({})", stem, i),
+        };
+        let candidate = path.with_file_name(name);
+        if !tokio::fs::try_exists(&candidate).await.unwrap_or(false) {
+            return candidate;
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{NodeManagerConfig, VerifiedNode};
+    use tempfile::TempDir;
+
+    struct Peer {
+        peer_id: PeerId,
+        manager: FileTransferManager,
+        events: mpsc::UnboundedReceiver<FileTransferEvent>,
+    }
+
+    async fn peer(dir: &Path, chunk_size: u32, known: &[PeerId]) -> Peer {
+        let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::default()));
+        for peer_id in known {
+            node_manager
+                .add_or_update_node(VerifiedNode::new(
+                    *peer_id,
+                    vec![],
+                    "/localp2p/1.0.0".to_string(),
+                    "localp2p-rust/1.0.0".to_string(),
+                ))
+                .await;
+        }
+
+        let peer_id = PeerId::random();
+        let config = FileTransferConfig::new(dir.to_path_buf()).with_chunk_size(chunk_size);
+        let (manager, events) = FileTransferManager::new(node_manager, peer_id, config);
+        Peer { peer_id, manager, events }
+    }
+
+    /// 在两个管理器之间驱动传输，直到完成或发送 `max_chunks` 个分块
+    async fn pump(sender: &Peer, receiver: &Peer, transfer_id: &str, max_chunks: usize) {
+        let mut sent = 0;
+        while let Some(request) = sender.manager.next_request(transfer_id).await.unwrap() {
+            match request {
+                FileTransferRequest::Chunk(chunk) => {
+                    if sent == max_chunks {
+                        return;
+                    }
+                    let index = chunk.index;
+                    let verified = receiver.manager.handle_chunk(sender.peer_id, chunk).await.unwrap();
+                    sender
+                        .manager
+                        .handle_chunk_ack(receiver.peer_id, transfer_id, index, verified)
+                        .await
+                        .unwrap();
+                    sent += 1;
+                }
+                FileTransferRequest::Complete { .. } => {
+                    receiver.manager.handle_complete(sender.peer_id, transfer_id).await.unwrap();
+                    sender.manager.handle_ack(receiver.peer_id, transfer_id).await;
+                }
+                other => panic!("unexpected request {:?}", other),
+            }
+        }
+    }
+
+    /// 整个文件的哈希与单个分块的哈希算法相同
+    fn chunk_hash_of(data: &[u8]) -> String {
+        crate::file_transfer::message::chunk_hash(data)
+    }
+
+    fn drain(events: &mut mpsc::UnboundedReceiver<FileTransferEvent>) -> Vec<FileTransferEvent> {
+        let mut all = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            all.push(event);
+        }
+        all
+    }
+
+    #[tokio::test]
+    async fn test_full_transfer() {
+        let src_dir = TempDir::new().unwrap();
+        let dst_dir = TempDir::new().unwrap();
+        let content: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let src = src_dir.path().join("data.bin");
+        std::fs::write(&src, &content).unwrap();
+
+        let mut receiver = peer(dst_dir.path(), 1024, &[]).await;
+        let sender = peer(src_dir.path(), 1024, &[receiver.peer_id]).await;
+
+        let offer = sender.manager.create_offer(receiver.peer_id, &src).await.unwrap();
+        assert_eq!(offer.total_chunks(), 10);
+        let transfer_id = offer.transfer_id.clone();
+
+        assert_eq!(receiver.manager.handle_offer(sender.peer_id, offer).await.unwrap(), None);
+        assert!(matches!(
+            drain(&mut receiver.events).first(),
+            Some(FileTransferEvent::OfferReceived { .. })
+        ));
+
+        let (from, resume_from) = receiver.manager.accept(&transfer_id).await.unwrap();
+        assert_eq!(from, sender.peer_id);
+        sender
+            .manager
+            .handle_accept(receiver.peer_id, &transfer_id, resume_from)
+            .await
+            .unwrap();
+
+        pump(&sender, &receiver, &transfer_id, usize::MAX).await;
+
+        let received = std::fs::read(dst_dir.path().join("data.bin")).unwrap();
+        assert_eq!(received, content);
+        assert_eq!(std::fs::read_dir(dst_dir.path()).unwrap().count(), 1);
+
+        let sent = sender.manager.get_transfer(&transfer_id).await.unwrap();
+        assert_eq!(sent.state, TransferState::Completed);
+        assert!(drain(&mut receiver.events)
+            .iter()
+            .any(|e| matches!(e, FileTransferEvent::Completed { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_interrupt() {
+        let src_dir = TempDir::new().unwrap();
+        let dst_dir = TempDir::new().unwrap();
+        let content: Vec<u8> = (0..5000u32).map(|i| (i % 13) as u8).collect();
+        let src = src_dir.path().join("resume.bin");
+        std::fs::write(&src, &content).unwrap();
+
+        let receiver = peer(dst_dir.path(), 1000, &[]).await;
+        let sender = peer(src_dir.path(), 1000, &[receiver.peer_id]).await;
+
+        let offer = sender.manager.create_offer(receiver.peer_id, &src).await.unwrap();
+        let transfer_id = offer.transfer_id.clone();
+        receiver.manager.handle_offer(sender.peer_id, offer).await.unwrap();
+        receiver.manager.accept(&transfer_id).await.unwrap();
+        sender.manager.handle_accept(receiver.peer_id, &transfer_id, 0).await.unwrap();
+
+        // 发送 2 个分块后连接断开
+        pump(&sender, &receiver, &transfer_id, 2).await;
+        sender.manager.handle_peer_disconnected(&receiver.peer_id).await;
+        receiver.manager.handle_peer_disconnected(&sender.peer_id).await;
+
+        // 重新上线后发送方重新提议，接收方自动从分块 2 继续
+        let offers = sender.manager.take_interrupted_offers(&receiver.peer_id).await;
+        assert_eq!(offers.len(), 1);
+        let resume_from = receiver
+            .manager
+            .handle_offer(sender.peer_id, offers[0].clone())
+            .await
+            .unwrap();
+        assert_eq!(resume_from, Some(2));
+        sender.manager.handle_accept(receiver.peer_id, &transfer_id, 2).await.unwrap();
+
+        pump(&sender, &receiver, &transfer_id, usize::MAX).await;
+        assert_eq!(std::fs::read(dst_dir.path().join("resume.bin")).unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_progress_file() {
+        let src_dir = TempDir::new().unwrap();
+        let dst_dir = TempDir::new().unwrap();
+        let content = vec![7u8; 3000];
+        let src = src_dir.path().join("restart.bin");
+        std::fs::write(&src, &content).unwrap();
+
+        let receiver = peer(dst_dir.path(), 1000, &[]).await;
+        let sender = peer(src_dir.path(), 1000, &[receiver.peer_id]).await;
+
+        let offer = sender.manager.create_offer(receiver.peer_id, &src).await.unwrap();
+        let transfer_id = offer.transfer_id.clone();
+        receiver.manager.handle_offer(sender.peer_id, offer).await.unwrap();
+        receiver.manager.accept(&transfer_id).await.unwrap();
+        sender.manager.handle_accept(receiver.peer_id, &transfer_id, 0).await.unwrap();
+        pump(&sender, &receiver, &transfer_id, 1).await;
+
+        // 接收方重启：新管理器只能依靠磁盘上的续传状态
+        let (restarted, _events) = FileTransferManager::new(
+            Arc::new(NodeManager::with_default_config()),
+            receiver.peer_id,
+            FileTransferConfig::new(dst_dir.path().to_path_buf()),
+        );
+        let file_hash = file_hash(&src).await.unwrap();
+        let mut new_offer = FileOffer::new("restart.bin".to_string(), 3000, 1000).with_file_hash(file_hash);
+        new_offer.sender_peer_id = sender.peer_id.to_string();
+        let resume_from = restarted.handle_offer(sender.peer_id, new_offer).await.unwrap();
+        assert_eq!(resume_from, Some(1));
+
+        // 同名同大小但内容不同的文件不会接在旧的 `.part` 后面
+        let mut other = FileOffer::new("restart.bin".to_string(), 3000, 1000).with_file_hash(chunk_hash_of(&[8u8; 3000]));
+        other.sender_peer_id = sender.peer_id.to_string();
+        assert_eq!(restarted.handle_offer(sender.peer_id, other).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_offer_cannot_replace_existing_transfer() {
+        let dir = TempDir::new().unwrap();
+        let src = dir.path().join("mine.txt");
+        std::fs::write(&src, b"hello").unwrap();
+
+        let other = PeerId::random();
+        let attacker = PeerId::random();
+        let node = peer(dir.path(), 1024, &[other]).await;
+
+        // 本地发送任务的 ID 被对方用于新的提议
+        let outgoing = node.manager.create_offer(other, &src).await.unwrap();
+        let mut forged = FileOffer::new("evil.txt".to_string(), 5, 1024).with_file_hash(chunk_hash_of(b"evil!"));
+        forged.transfer_id = outgoing.transfer_id.clone();
+        let result = node.manager.handle_offer(attacker, forged.clone()).await;
+        assert!(matches!(result, Err(FileTransferError::InvalidState(_))));
+        let transfer = node.manager.get_transfer(&outgoing.transfer_id).await.unwrap();
+        assert_eq!(transfer.direction, TransferDirection::Outgoing);
+        assert_eq!(transfer.peer_id, other);
+
+        // 其他节点的接收提议同样不能被覆盖，发送方也不能换成别的文件
+        let mut incoming = FileOffer::new("a.txt".to_string(), 5, 1024).with_file_hash(chunk_hash_of(b"hello"));
+        incoming.transfer_id = "shared-id".to_string();
+        node.manager.handle_offer(other, incoming.clone()).await.unwrap();
+        forged.transfer_id = incoming.transfer_id.clone();
+        assert!(node.manager.handle_offer(attacker, forged.clone()).await.is_err());
+        assert!(node.manager.handle_offer(other, forged).await.is_err());
+        let transfer = node.manager.get_transfer("shared-id").await.unwrap();
+        assert_eq!(transfer.peer_id, other);
+        assert_eq!(transfer.offer.file_name, "a.txt");
+
+        // 同一发送方重新提议同一文件不受影响
+        assert_eq!(node.manager.handle_offer(other, incoming).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_same_file_name_from_two_peers() {
+        let dst_dir = TempDir::new().unwrap();
+        let receiver = peer(dst_dir.path(), 1000, &[]).await;
+        let mut senders = Vec::new();
+        for fill in [1u8, 2] {
+            let src_dir = TempDir::new().unwrap();
+            let src = src_dir.path().join("report.bin");
+            std::fs::write(&src, vec![fill; 3000]).unwrap();
+            let sender = peer(src_dir.path(), 1000, &[receiver.peer_id]).await;
+            let offer = sender.manager.create_offer(receiver.peer_id, &src).await.unwrap();
+            receiver.manager.handle_offer(sender.peer_id, offer.clone()).await.unwrap();
+            receiver.manager.accept(&offer.transfer_id).await.unwrap();
+            sender.manager.handle_accept(receiver.peer_id, &offer.transfer_id, 0).await.unwrap();
+            senders.push((src_dir, sender, offer.transfer_id));
+        }
+
+        // 两个同名文件交替接收，各自写入自己的未完成文件
+        for _ in 0..3 {
+            for (_, sender, transfer_id) in &senders {
+                pump(sender, &receiver, transfer_id, 1).await;
+            }
+        }
+        for (_, sender, transfer_id) in &senders {
+            pump(sender, &receiver, transfer_id, usize::MAX).await;
+        }
+
+        let mut received = Vec::new();
+        for (_, _, transfer_id) in &senders {
+            let transfer = receiver.manager.get_transfer(transfer_id).await.unwrap();
+            assert_eq!(transfer.state, TransferState::Completed);
+            received.push(std::fs::read(&transfer.path).unwrap());
+        }
+        received.sort();
+        assert_eq!(received, vec![vec![1u8; 3000], vec![2u8; 3000]]);
+    }
+
+    #[tokio::test]
+    async fn test_fail_requires_transfer_peer() {
+        let dir = TempDir::new().unwrap();
+        let src = dir.path().join("a.txt");
+        std::fs::write(&src, b"hello").unwrap();
+
+        let receiver = PeerId::random();
+        let mut sender = peer(dir.path(), 1024, &[receiver]).await;
+        let offer = sender.manager.create_offer(receiver, &src).await.unwrap();
+
+        // 其他节点不能让本节点与 receiver 之间的传输失败
+        sender.manager.fail(PeerId::random(), &offer.transfer_id, "伪造".to_string()).await;
+        let transfer = sender.manager.get_transfer(&offer.transfer_id).await.unwrap();
+        assert_eq!(transfer.state, TransferState::Pending);
+        assert!(drain(&mut sender.events).is_empty());
+
+        sender.manager.fail(receiver, &offer.transfer_id, "磁盘已满".to_string()).await;
+        let transfer = sender.manager.get_transfer(&offer.transfer_id).await.unwrap();
+        assert_eq!(transfer.state, TransferState::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_complete_verifies_file_hash() {
+        let dst_dir = TempDir::new().unwrap();
+        let mut receiver = peer(dst_dir.path(), 4, &[]).await;
+        let sender_id = PeerId::random();
+
+        let mut offer = FileOffer::new("x.bin".to_string(), 8, 4).with_file_hash(chunk_hash_of(&[0u8; 8]));
+        offer.sender_peer_id = sender_id.to_string();
+        let transfer_id = offer.transfer_id.clone();
+        receiver.manager.handle_offer(sender_id, offer).await.unwrap();
+        receiver.manager.accept(&transfer_id).await.unwrap();
+
+        // 每个分块自身的哈希都正确，但拼起来不是提议中的文件
+        for (index, data) in [[1u8, 2, 3, 4], [5, 6, 7, 8]].into_iter().enumerate() {
+            let chunk = FileChunk::new(transfer_id.clone(), index as u64, data.to_vec());
+            assert!(receiver.manager.handle_chunk(sender_id, chunk).await.unwrap());
+        }
+        let result = receiver.manager.handle_complete(sender_id, &transfer_id).await;
+        assert!(matches!(result, Err(FileTransferError::FileHashMismatch(_))));
+
+        assert_eq!(std::fs::read_dir(dst_dir.path()).unwrap().count(), 0);
+        let transfer = receiver.manager.get_transfer(&transfer_id).await.unwrap();
+        assert_eq!(transfer.state, TransferState::Failed);
+        assert!(drain(&mut receiver.events)
+            .iter()
+            .any(|e| matches!(e, FileTransferEvent::Failed { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_chunk_rejected() {
+        let dst_dir = TempDir::new().unwrap();
+        let receiver = peer(dst_dir.path(), 4, &[]).await;
+        let sender_id = PeerId::random();
+
+        let mut offer = FileOffer::new("x.bin".to_string(), 8, 4).with_file_hash(chunk_hash_of(&[1, 2, 3, 4, 5, 6, 7, 8]));
+        offer.sender_peer_id = sender_id.to_string();
+        let transfer_id = offer.transfer_id.clone();
+        receiver.manager.handle_offer(sender_id, offer).await.unwrap();
+        receiver.manager.accept(&transfer_id).await.unwrap();
+
+        let mut chunk = FileChunk::new(transfer_id.clone(), 0, vec![1, 2, 3, 4]);
+        chunk.data[0] = 9;
+        assert!(!receiver.manager.handle_chunk(sender_id, chunk).await.unwrap());
+
+        let transfer = receiver.manager.get_transfer(&transfer_id).await.unwrap();
+        assert_eq!(transfer.next_chunk, 0);
+    }
+
+    #[tokio::test]
+    async fn test_offer_requires_verified_node() {
+        let dir = TempDir::new().unwrap();
+        let src = dir.path().join("a.txt");
+        std::fs::write(&src, b"hi").unwrap();
+
+        let sender = peer(dir.path(), 1024, &[]).await;
+        let result = sender.manager.create_offer(PeerId::random(), &src).await;
+        assert!(matches!(result, Err(FileTransferError::NodeNotVerified(_))));
+    }
+
+    #[test]
+    fn test_transfer_id_validation() {
+        assert!(is_valid_transfer_id(&uuid::Uuid::new_v4().to_string()));
+        assert!(!is_valid_transfer_id("../x"));
+        assert!(!is_valid_transfer_id(""));
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("../../etc/passwd"), Some("passwd".to_string()));
+        assert_eq!(sanitize_file_name("a.txt"), Some("a.txt".to_string()));
+        assert_eq!(sanitize_file_name(".."), None);
+        assert_eq!(sanitize_file_name(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_unique_path() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        assert_eq!(unique_path(&path).await, path);
+
+        std::fs::write(&path, b"x").unwrap();
+        assert_eq!(unique_path(&path).await, dir.path().join("a (1).txt"));
+    }
+}
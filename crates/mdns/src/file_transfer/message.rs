@@ -0,0 +1,305 @@
+//! 文件传输消息协议
+//!
+//! 定义文件传输使用的消息类型：offer、accept/reject、分块数据、完成和取消。
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 文件传输协议名称
+pub const FILE_TRANSFER_PROTOCOL: &str = "/localp2p/file/1.0.0";
+
+/// 默认分块大小（256KB）
+pub const DEFAULT_CHUNK_SIZE: u32 = 256 * 1024;
+
+/// 允许的最大分块大小（4MB）
+pub const MAX_CHUNK_SIZE: u32 = 4 * 1024 * 1024;
+
+/// 文件传输请求
+///
+/// `Chunk` 的数据部分不经过 JSON 序列化，由 codec 以原始字节追加在头部之后。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FileTransferRequest {
+    /// 发送方提议发送文件
+    Offer(FileOffer),
+    /// 接收方接受文件（从指定分块继续）
+    Accept {
+        /// 传输 ID
+        transfer_id: String,
+        /// 从该分块序号开始发送（断点续传）
+        resume_from: u64,
+    },
+    /// 接收方拒绝文件
+    Reject {
+        /// 传输 ID
+        transfer_id: String,
+        /// 拒绝原因
+        reason: String,
+    },
+    /// 分块数据
+    Chunk(FileChunk),
+    /// 发送完成
+    Complete {
+        /// 传输 ID
+        transfer_id: String,
+    },
+    /// 任一方取消传输
+    Cancel {
+        /// 传输 ID
+        transfer_id: String,
+        /// 取消原因
+        reason: String,
+    },
+}
+
+impl FileTransferRequest {
+    /// 获取请求对应的传输 ID
+    pub fn transfer_id(&self) -> &str {
+        match self {
+            Self::Offer(offer) => &offer.transfer_id,
+            Self::Chunk(chunk) => &chunk.transfer_id,
+            Self::Accept { transfer_id, .. }
+            | Self::Reject { transfer_id, .. }
+            | Self::Complete { transfer_id }
+            | Self::Cancel { transfer_id, .. } => transfer_id,
+        }
+    }
+}
+
+/// 文件传输响应
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FileTransferResponse {
+    /// 已收到（用于 offer/accept/reject/complete/cancel）
+    Ack {
+        /// 传输 ID
+        transfer_id: String,
+    },
+    /// 分块确认
+    ChunkAck {
+        /// 传输 ID
+        transfer_id: String,
+        /// 分块序号
+        index: u64,
+        /// 哈希校验是否通过并已写入磁盘
+        verified: bool,
+    },
+    /// 处理失败
+    Error {
+        /// 传输 ID
+        transfer_id: String,
+        /// 错误原因
+        reason: String,
+    },
+}
+
+/// 文件提议
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileOffer {
+    /// 传输唯一 ID（UUID）
+    pub transfer_id: String,
+
+    /// 发送者的 Peer ID
+    pub sender_peer_id: String,
+
+    /// 文件名（不含路径）
+    pub file_name: String,
+
+    /// 文件大小（字节）
+    pub file_size: u64,
+
+    /// 分块大小（字节）
+    pub chunk_size: u32,
+
+    /// 整个文件的 SHA-256（十六进制），接收完成后校验，续传时用于识别同一文件
+    pub file_hash: String,
+
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+}
+
+impl FileOffer {
+    /// 创建新的文件提议
+    pub fn new(file_name: String, file_size: u64, chunk_size: u32) -> Self {
+        Self {
+            transfer_id: uuid::Uuid::new_v4().to_string(),
+            sender_peer_id: String::new(), // 需要在发送时设置
+            file_name,
+            file_size,
+            chunk_size,
+            file_hash: String::new(), // 需要在发送时设置
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// 设置整个文件的哈希
+    pub fn with_file_hash(mut self, file_hash: String) -> Self {
+        self.file_hash = file_hash;
+        self
+    }
+
+    /// 分块总数
+    pub fn total_chunks(&self) -> u64 {
+        if self.chunk_size == 0 {
+            return 0;
+        }
+        self.file_size.div_ceil(self.chunk_size as u64)
+    }
+
+    /// 指定分块在文件中的偏移量
+    pub fn chunk_offset(&self, index: u64) -> u64 {
+        index * self.chunk_size as u64
+    }
+
+    /// 指定分块的实际长度（最后一块可能不足 chunk_size）
+    pub fn chunk_len(&self, index: u64) -> usize {
+        let offset = self.chunk_offset(index);
+        if offset >= self.file_size {
+            return 0;
+        }
+        (self.file_size - offset).min(self.chunk_size as u64) as usize
+    }
+}
+
+/// 文件分块
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileChunk {
+    /// 传输 ID
+    pub transfer_id: String,
+
+    /// 分块序号（从 0 开始）
+    pub index: u64,
+
+    /// 分块数据的 SHA-256（十六进制）
+    pub hash: String,
+
+    /// 分块数据（由 codec 单独传输）
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl FileChunk {
+    /// 创建分块并计算哈希
+    pub fn new(transfer_id: String, index: u64, data: Vec<u8>) -> Self {
+        let hash = chunk_hash(&data);
+        Self {
+            transfer_id,
+            index,
+            hash,
+            data,
+        }
+    }
+
+    /// 校验分块数据与哈希是否一致
+    pub fn verify(&self) -> bool {
+        chunk_hash(&self.data) == self.hash
+    }
+}
+
+/// 计算分块数据的 SHA-256（十六进制小写）
+pub fn chunk_hash(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// 十六进制小写编码
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 文件传输错误类型
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum FileTransferError {
+    /// 文件传输功能未启用
+    #[error("文件传输功能未启用")]
+    NotEnabled,
+
+    /// 节点未验证
+    #[error("节点未验证: {0}")]
+    NodeNotVerified(String),
+
+    /// 传输不存在
+    #[error("传输不存在: {0}")]
+    TransferNotFound(String),
+
+    /// 传输状态不允许该操作
+    #[error("传输状态无效: {0}")]
+    InvalidState(String),
+
+    /// 分块校验失败
+    #[error("分块 {0} 校验失败")]
+    HashMismatch(u64),
+
+    /// 接收完成后整个文件校验失败
+    #[error("文件校验失败: {0}")]
+    FileHashMismatch(String),
+
+    /// 文件读写错误
+    #[error("文件读写错误: {0}")]
+    Io(String),
+
+    /// 对方拒绝
+    #[error("对方拒绝: {0}")]
+    Rejected(String),
+
+    /// 节点未受信任（信任策略不允许与其传输文件）
+    #[error("节点未受信任: {0}")]
+    NotTrusted(String),
+}
+
+impl From<std::io::Error> for FileTransferError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offer_chunk_math() {
+        let offer = FileOffer::new("a.bin".to_string(), 1000, 300);
+        assert_eq!(offer.total_chunks(), 4);
+        assert_eq!(offer.chunk_offset(2), 600);
+        assert_eq!(offer.chunk_len(0), 300);
+        assert_eq!(offer.chunk_len(3), 100);
+        assert_eq!(offer.chunk_len(4), 0);
+    }
+
+    #[test]
+    fn test_empty_file_has_no_chunks() {
+        let offer = FileOffer::new("empty".to_string(), 0, DEFAULT_CHUNK_SIZE);
+        assert_eq!(offer.total_chunks(), 0);
+    }
+
+    #[test]
+    fn test_chunk_verify() {
+        let mut chunk = FileChunk::new("t1".to_string(), 0, b"hello".to_vec());
+        assert!(chunk.verify());
+        assert_eq!(
+            chunk.hash,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+
+        chunk.data[0] = b'H';
+        assert!(!chunk.verify());
+    }
+
+    #[test]
+    fn test_request_transfer_id() {
+        let offer = FileOffer::new("a.bin".to_string(), 10, 4);
+        let id = offer.transfer_id.clone();
+        assert_eq!(FileTransferRequest::Offer(offer).transfer_id(), id);
+
+        let cancel = FileTransferRequest::Cancel {
+            transfer_id: "t2".to_string(),
+            reason: "user".to_string(),
+        };
+        assert_eq!(cancel.transfer_id(), "t2");
+    }
+
+    #[test]
+    fn test_chunk_data_not_in_json() {
+        let chunk = FileChunk::new("t1".to_string(), 3, vec![1, 2, 3]);
+        let json = serde_json::to_string(&FileTransferRequest::Chunk(chunk)).unwrap();
+        assert!(!json.contains("data"));
+    }
+}
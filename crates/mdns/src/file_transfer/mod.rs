@@ -0,0 +1,44 @@
+//! 文件传输模块
+//!
+//! 提供局域网内已验证节点之间的分块文件传输，支持断点续传。
+//!
+//! # 模块化设计
+//!
+//! 本模块作为 mdns crate 的扩展，通过 `FileTransferExtension` trait 为 `ManagedDiscovery` 添加文件传输能力，
+//! 使用独立协议 `/localp2p/file/1.0.0`，与聊天协议并行运行。
+//!
+//! # 架构
+//!
+//! - [`message`] - 提议、分块、完成等消息类型
+//! - [`codec`] - request_response Codec（分块数据以原始字节传输）
+//! - [`traits`] - FileTransferExtension trait 和事件定义
+//! - [`manager`] - FileTransferManager 实现（分块读写、校验和续传状态）
+//!
+//! # 示例
+//!
+//! ```no_run
+//! use mdns::{ManagedDiscovery, FileTransferExtension, FileTransferConfig};
+//! use libp2p::PeerId;
+//!
+//! # async fn example(mut discovery: ManagedDiscovery) -> Result<(), Box<dyn std::error::Error>> {
+//! discovery.enable_file_transfer(FileTransferConfig::new("./downloads".into())).await?;
+//!
+//! let peer_id: PeerId = "12D3KooW...".parse()?;
+//! discovery.send_file(peer_id, "./photo.jpg".into()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod message;
+pub mod codec;
+pub mod traits;
+pub mod manager;
+
+// 公共 API 导出
+pub use message::{
+    FileOffer, FileChunk, FileTransferRequest, FileTransferResponse, FileTransferError,
+    FILE_TRANSFER_PROTOCOL, DEFAULT_CHUNK_SIZE, MAX_CHUNK_SIZE,
+};
+pub use codec::{FileTransferCodec, FileTransferProtocol};
+pub use traits::{FileTransferExtension, FileTransferEvent, TransferDirection};
+pub use manager::{FileTransferManager, FileTransferConfig, FileTransfer, TransferState};
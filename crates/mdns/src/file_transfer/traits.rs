@@ -0,0 +1,213 @@
+//! 文件传输扩展 trait
+//!
+//! 定义扩展 ManagedDiscovery 的接口，使其支持文件传输功能。
+
+use super::manager::{FileTransferConfig, FileTransferManager};
+use super::message::{FileOffer, FileTransferError};
+use libp2p::PeerId;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 文件传输扩展 trait
+///
+/// 为 ManagedDiscovery 提供可选的文件传输能力扩展。
+///
+/// # 示例
+///
+/// ```no_run
+/// use mdns::{ManagedDiscovery, FileTransferExtension, FileTransferConfig};
+/// use libp2p::PeerId;
+///
+/// # async fn example(mut discovery: ManagedDiscovery) -> Result<(), Box<dyn std::error::Error>> {
+/// // 启用文件传输功能
+/// discovery.enable_file_transfer(FileTransferConfig::new("./downloads".into())).await?;
+///
+/// // 发送文件
+/// let peer_id: PeerId = "12D3KooW...".parse()?;
+/// let transfer_id = discovery.send_file(peer_id, "./video.mp4".into()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait::async_trait]
+pub trait FileTransferExtension {
+    /// 启用文件传输功能
+    ///
+    /// # 错误
+    ///
+    /// 返回错误如果文件传输功能已经启用或下载目录无法创建。
+    async fn enable_file_transfer(&mut self, config: FileTransferConfig) -> Result<(), FileTransferError>;
+
+    /// 向指定节点提议发送文件
+    ///
+    /// 返回传输 ID。对方接受后自动开始分块发送。
+    ///
+    /// # 错误
+    ///
+    /// - `FileTransferError::NotEnabled` - 文件传输功能未启用
+    /// - `FileTransferError::NodeNotVerified` - 目标节点未验证
+    /// - `FileTransferError::Io` - 无法读取文件
+    async fn send_file(&mut self, target: PeerId, path: PathBuf) -> Result<String, FileTransferError>;
+
+    /// 接受收到的文件提议
+    ///
+    /// 如果下载目录中存在该文件的未完成部分，将从最后一个校验通过的分块继续。
+    async fn accept_file(&mut self, transfer_id: &str) -> Result<(), FileTransferError>;
+
+    /// 拒绝收到的文件提议
+    async fn reject_file(&mut self, transfer_id: &str, reason: String) -> Result<(), FileTransferError>;
+
+    /// 取消传输（发送或接收）
+    async fn cancel_file(&mut self, transfer_id: &str) -> Result<(), FileTransferError>;
+
+    /// 获取文件传输管理器
+    fn file_transfer_manager(&self) -> Option<Arc<FileTransferManager>>;
+
+    /// 检查文件传输功能是否已启用
+    fn is_file_transfer_enabled(&self) -> bool {
+        self.file_transfer_manager().is_some()
+    }
+}
+
+/// 传输方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    /// 发送
+    Outgoing,
+    /// 接收
+    Incoming,
+}
+
+/// 文件传输事件
+///
+/// 由 FileTransferManager 产生，通知上层应用文件传输相关事件。
+#[derive(Debug, Clone)]
+pub enum FileTransferEvent {
+    /// 收到文件提议，等待接受或拒绝
+    OfferReceived {
+        /// 发送者的 Peer ID
+        from: PeerId,
+        /// 文件提议
+        offer: FileOffer,
+    },
+
+    /// 传输已被接受（可能从中间分块继续）
+    Accepted {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 传输 ID
+        transfer_id: String,
+        /// 从该分块序号开始
+        resume_from: u64,
+    },
+
+    /// 传输被拒绝
+    Rejected {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 传输 ID
+        transfer_id: String,
+        /// 拒绝原因
+        reason: String,
+    },
+
+    /// 传输进度
+    Progress {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 传输 ID
+        transfer_id: String,
+        /// 传输方向
+        direction: TransferDirection,
+        /// 已传输（校验通过）的字节数
+        transferred_bytes: u64,
+        /// 文件总字节数
+        total_bytes: u64,
+    },
+
+    /// 传输完成
+    Completed {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 传输 ID
+        transfer_id: String,
+        /// 传输方向
+        direction: TransferDirection,
+        /// 本地文件路径（发送方为源文件，接收方为保存位置）
+        path: PathBuf,
+    },
+
+    /// 传输中断（连接断开），对方重新上线后可续传
+    Interrupted {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 传输 ID
+        transfer_id: String,
+        /// 传输方向
+        direction: TransferDirection,
+    },
+
+    /// 传输失败
+    Failed {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 传输 ID
+        transfer_id: String,
+        /// 传输方向
+        direction: TransferDirection,
+        /// 错误原因
+        error: String,
+    },
+
+    /// 传输已取消
+    Cancelled {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 传输 ID
+        transfer_id: String,
+        /// 传输方向
+        direction: TransferDirection,
+        /// 取消原因
+        reason: String,
+    },
+}
+
+impl FileTransferEvent {
+    /// 获取事件对应的传输 ID
+    pub fn transfer_id(&self) -> &str {
+        match self {
+            Self::OfferReceived { offer, .. } => &offer.transfer_id,
+            Self::Accepted { transfer_id, .. }
+            | Self::Rejected { transfer_id, .. }
+            | Self::Progress { transfer_id, .. }
+            | Self::Completed { transfer_id, .. }
+            | Self::Interrupted { transfer_id, .. }
+            | Self::Failed { transfer_id, .. }
+            | Self::Cancelled { transfer_id, .. } => transfer_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_transfer_id() {
+        let offer = FileOffer::new("a.bin".to_string(), 10, 4);
+        let id = offer.transfer_id.clone();
+        let event = FileTransferEvent::OfferReceived {
+            from: PeerId::random(),
+            offer,
+        };
+        assert_eq!(event.transfer_id(), id);
+
+        let event = FileTransferEvent::Progress {
+            peer_id: PeerId::random(),
+            transfer_id: "t1".to_string(),
+            direction: TransferDirection::Incoming,
+            transferred_bytes: 4,
+            total_bytes: 10,
+        };
+        assert_eq!(event.transfer_id(), "t1");
+    }
+}
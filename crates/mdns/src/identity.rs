@@ -1,12 +1,119 @@
 //! 密钥对持久化模块
 //!
 //! 提供密钥对的保存和加载功能，用于持久化 Peer ID
+//!
+//! 密钥文件有两种格式：
+//!
+//! - 明文：libp2p Protobuf 编码的密钥对，仅靠 0600 文件权限保护
+//! - 加密：带版本的文件头 + Argon2id 派生密钥的 ChaCha20-Poly1305 密文
+//!
+//! 加密文件格式（整数均为小端序，文件头作为 AEAD 附加数据参与认证）：
+//!
+//! ```text
+//! magic "LP2PKEY\0" (8) | version (1) | m_cost (4) | t_cost (4) | p_cost (4) | salt (16) | nonce (12) | ciphertext
+//! ```
+//!
+//! 身份迁移与轮换：
+//!
+//! - 导出包：密钥文件内容（可选口令加密）的 Base64 文本，可在新设备上导入以保留 Peer ID
+//! - 轮换：生成新密钥，由旧密钥签署 [`RotationStatement`] 引入新的 Peer ID；
+//!   信任旧 Peer ID 的节点收到声明后改为信任新 Peer ID，并迁移聊天记录
 
-use libp2p::identity::Keypair;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use libp2p::identity::{Keypair, PublicKey};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{Read, Write};
 use std::path::Path;
 
+/// 加密密钥文件的魔数
+const ENCRYPTED_MAGIC: &[u8; 8] = b"LP2PKEY\0";
+
+/// 加密密钥文件的格式版本
+const ENCRYPTED_VERSION: u8 = 1;
+
+/// 加密文件头长度
+const HEADER_LEN: usize = 8 + 1 + 4 * 3 + SALT_LEN + NONCE_LEN;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// 解密时接受的 Argon2 参数上限（防止篡改的文件头让密钥派生耗尽内存或时间）
+const MAX_M_COST: u32 = Params::DEFAULT_M_COST * 16;
+const MAX_T_COST: u32 = Params::DEFAULT_T_COST * 16;
+const MAX_P_COST: u32 = Params::DEFAULT_P_COST * 16;
+
+/// 轮换声明签名的域分隔前缀
+const ROTATION_DOMAIN: &[u8] = b"localp2p-identity-rotation";
+
+/// 轮换声明文件扩展名（与密钥文件放在同一目录）
+const ROTATION_EXTENSION: &str = "rotation";
+
+/// 身份轮换声明
+///
+/// 旧密钥对 `(旧 Peer ID, 新 Peer ID, 签发时间)` 的签名，证明新 Peer ID 由旧身份的持有者引入。
+/// 声明只能由新 Peer ID 的持有者通过已认证的连接出示，接收方需核对发送方就是新 Peer ID。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationStatement {
+    /// 旧公钥（Protobuf 编码）
+    pub old_public_key: Vec<u8>,
+    /// 新的 Peer ID
+    pub new_peer_id: String,
+    /// 签发时间（Unix 毫秒）
+    pub issued_at: i64,
+    /// 旧密钥的签名
+    pub signature: Vec<u8>,
+}
+
+impl RotationStatement {
+    /// 使用旧密钥签署轮换声明
+    pub fn sign(old: &Keypair, new_peer_id: PeerId) -> Result<Self, String> {
+        let old_public_key = old.public().encode_protobuf();
+        let issued_at = chrono::Utc::now().timestamp_millis();
+        let message = Self::signed_bytes(&old.public().to_peer_id(), &new_peer_id, issued_at);
+        let signature = old.sign(&message).map_err(|e| format!("签署轮换声明失败: {}", e))?;
+
+        Ok(Self {
+            old_public_key,
+            new_peer_id: new_peer_id.to_string(),
+            issued_at,
+            signature,
+        })
+    }
+
+    /// 验证签名，返回 `(旧 Peer ID, 新 Peer ID)`
+    pub fn verify(&self) -> Result<(PeerId, PeerId), String> {
+        let old_public_key = PublicKey::try_decode_protobuf(&self.old_public_key)
+            .map_err(|e| format!("轮换声明公钥无效: {}", e))?;
+        let new_peer_id: PeerId = self
+            .new_peer_id
+            .parse()
+            .map_err(|e| format!("轮换声明 Peer ID 无效: {}", e))?;
+        let old_peer_id = old_public_key.to_peer_id();
+        if old_peer_id == new_peer_id {
+            return Err("轮换声明的新旧 Peer ID 相同".to_string());
+        }
+
+        let message = Self::signed_bytes(&old_peer_id, &new_peer_id, self.issued_at);
+        if !old_public_key.verify(&message, &self.signature) {
+            return Err("轮换声明签名无效".to_string());
+        }
+        Ok((old_peer_id, new_peer_id))
+    }
+
+    fn signed_bytes(old_peer_id: &PeerId, new_peer_id: &PeerId, issued_at: i64) -> Vec<u8> {
+        let mut message = ROTATION_DOMAIN.to_vec();
+        message.extend_from_slice(&old_peer_id.to_bytes());
+        message.extend_from_slice(&new_peer_id.to_bytes());
+        message.extend_from_slice(&issued_at.to_le_bytes());
+        message
+    }
+}
+
 /// 密钥对持久化管理器
 pub struct IdentityManager;
 
@@ -23,6 +130,96 @@ impl IdentityManager {
     /// * `Ok(None)` - 文件不存在（首次运行）
     /// * `Err(String)` - 加载失败
     pub fn load_or_none(path: &Path) -> Result<Option<Keypair>, String> {
+        let Some(buffer) = Self::read_file(path)? else {
+            return Ok(None);
+        };
+
+        if Self::is_encrypted_bytes(&buffer) {
+            let msg = format!("密钥文件已加密，需要口令: {}", path.display());
+            tracing::error!("{}", msg);
+            return Err(msg);
+        }
+
+        // 解析密钥对
+        match Self::decode_keypair(&buffer) {
+            Ok(keypair) => {
+                tracing::info!("成功加载密钥对: {}", path.display());
+                Ok(Some(keypair))
+            }
+            Err(e) => {
+                let msg = format!("解析密钥对失败: {}", e);
+                tracing::error!("{}", msg);
+                Err(msg)
+            }
+        }
+    }
+
+    /// 检查密钥文件是否已加密（文件不存在时返回 false）
+    pub fn is_encrypted(path: &Path) -> bool {
+        matches!(Self::read_file(path), Ok(Some(buffer)) if Self::is_encrypted_bytes(&buffer))
+    }
+
+    /// 使用口令加载或生成密钥对
+    ///
+    /// - 文件不存在：生成新的密钥对并加密保存
+    /// - 明文文件：加载后自动迁移为加密格式
+    /// - 加密文件：使用口令解密
+    ///
+    /// # Arguments
+    /// * `path` - 密钥文件路径
+    /// * `passphrase` - 口令
+    ///
+    /// # Returns
+    /// * `Ok(Keypair)` - 密钥对
+    /// * `Err(String)` - 口令错误、文件损坏或读写失败
+    pub fn load_with_passphrase(path: &Path, passphrase: &str) -> Result<Keypair, String> {
+        let Some(buffer) = Self::read_file(path)? else {
+            let keypair = Keypair::generate_ed25519();
+            Self::save_encrypted(path, &keypair, passphrase)?;
+            tracing::info!("成功生成并加密保存密钥对: {}", path.display());
+            return Ok(keypair);
+        };
+
+        if Self::is_encrypted_bytes(&buffer) {
+            let keypair = Self::decrypt_keypair(&buffer, passphrase)?;
+            tracing::info!("成功解密密钥对: {}", path.display());
+            return Ok(keypair);
+        }
+
+        // 旧版明文密钥文件，迁移为加密格式
+        let keypair = Self::decode_keypair(&buffer)?;
+        Self::save_encrypted(path, &keypair, passphrase)?;
+        tracing::info!("已将明文密钥文件迁移为加密格式: {}", path.display());
+        Ok(keypair)
+    }
+
+    /// 修改加密密钥文件的口令
+    ///
+    /// # Arguments
+    /// * `path` - 密钥文件路径
+    /// * `old_passphrase` - 当前口令
+    /// * `new_passphrase` - 新口令
+    pub fn change_passphrase(path: &Path, old_passphrase: &str, new_passphrase: &str) -> Result<(), String> {
+        let buffer = Self::read_file(path)?
+            .ok_or_else(|| format!("密钥文件不存在: {}", path.display()))?;
+        if !Self::is_encrypted_bytes(&buffer) {
+            return Err(format!("密钥文件未加密: {}", path.display()));
+        }
+
+        let keypair = Self::decrypt_keypair(&buffer, old_passphrase)?;
+        Self::save_encrypted(path, &keypair, new_passphrase)?;
+        tracing::info!("已修改密钥文件口令: {}", path.display());
+        Ok(())
+    }
+
+    /// 使用口令加密保存密钥对
+    pub fn save_encrypted(path: &Path, keypair: &Keypair, passphrase: &str) -> Result<(), String> {
+        let encrypted = Self::encrypt_keypair(keypair, passphrase)?;
+        Self::write_file(path, &encrypted)
+    }
+
+    /// 读取文件内容，文件不存在时返回 None
+    fn read_file(path: &Path) -> Result<Option<Vec<u8>>, String> {
         // 检查文件是否存在
         if !path.exists() {
             tracing::info!("密钥文件不存在，将生成新密钥: {}", path.display());
@@ -46,18 +243,7 @@ impl IdentityManager {
             return Err(msg);
         }
 
-        // 解析密钥对
-        match Self::decode_keypair(&buffer) {
-            Ok(keypair) => {
-                tracing::info!("成功加载密钥对: {}", path.display());
-                Ok(Some(keypair))
-            }
-            Err(e) => {
-                let msg = format!("解析密钥对失败: {}", e);
-                tracing::error!("{}", msg);
-                Err(msg)
-            }
-        }
+        Ok(Some(buffer))
     }
 
     /// 生成并保存新的密钥对
@@ -74,7 +260,95 @@ impl IdentityManager {
 
         // 序列化密钥对
         let encoded = Self::encode_keypair(&keypair)?;
+        Self::write_file(path, &encoded)?;
+
+        tracing::info!("成功生成并保存密钥对: {}", path.display());
+        Ok(keypair)
+    }
+
+    /// 保存明文密钥对（覆盖已有文件）
+    pub fn save(path: &Path, keypair: &Keypair) -> Result<(), String> {
+        let encoded = Self::encode_keypair(keypair)?;
+        Self::write_file(path, &encoded)
+    }
 
+    /// 导出可移植的身份包（Base64 文本）
+    ///
+    /// 提供口令时导出包使用与加密密钥文件相同的格式加密，导入时需要相同的口令。
+    pub fn export_bundle(keypair: &Keypair, passphrase: Option<&str>) -> Result<String, String> {
+        let bytes = match passphrase {
+            Some(passphrase) => Self::encrypt_keypair(keypair, passphrase)?,
+            None => Self::encode_keypair(keypair)?,
+        };
+        Ok(BASE64.encode(bytes))
+    }
+
+    /// 导入身份包
+    ///
+    /// 导出包已加密时必须提供口令。导入后由调用者使用 [`save`](Self::save) 或
+    /// [`save_encrypted`](Self::save_encrypted) 写入本机的密钥文件。
+    pub fn import_bundle(bundle: &str, passphrase: Option<&str>) -> Result<Keypair, String> {
+        let bytes = BASE64
+            .decode(bundle.trim())
+            .map_err(|e| format!("身份包格式无效: {}", e))?;
+        if !Self::is_encrypted_bytes(&bytes) {
+            return Self::decode_keypair(&bytes);
+        }
+        let passphrase = passphrase.ok_or_else(|| "身份包已加密，需要口令".to_string())?;
+        Self::decrypt_keypair(&bytes, passphrase)
+    }
+
+    /// 轮换密钥：生成新密钥对，由旧密钥签署轮换声明，并替换密钥文件
+    ///
+    /// 轮换声明保存在密钥文件旁（见 [`load_rotation`](Self::load_rotation)），启动后需要通过
+    /// `TrustExtension::announce_rotation` 向其他节点出示。提供口令时新密钥文件会被加密。
+    pub fn rotate(
+        path: &Path,
+        old: &Keypair,
+        passphrase: Option<&str>,
+    ) -> Result<(Keypair, RotationStatement), String> {
+        let keypair = Keypair::generate_ed25519();
+        let statement = RotationStatement::sign(old, keypair.public().to_peer_id())?;
+
+        // 先写入声明，避免密钥已替换但声明丢失
+        let json = serde_json::to_vec_pretty(&statement)
+            .map_err(|e| format!("序列化轮换声明失败: {}", e))?;
+        Self::write_file(&Self::rotation_path(path), &json)?;
+
+        match passphrase {
+            Some(passphrase) => Self::save_encrypted(path, &keypair, passphrase)?,
+            None => Self::save(path, &keypair)?,
+        }
+
+        tracing::info!(
+            "已轮换密钥: {} -> {}",
+            old.public().to_peer_id(),
+            keypair.public().to_peer_id()
+        );
+        Ok((keypair, statement))
+    }
+
+    /// 加载密钥文件旁保存的轮换声明，不存在时返回 None
+    pub fn load_rotation(path: &Path) -> Result<Option<RotationStatement>, String> {
+        let rotation_path = Self::rotation_path(path);
+        match fs::read(&rotation_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| format!("解析轮换声明失败: {} - {}", rotation_path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("读取轮换声明失败: {} - {}", rotation_path.display(), e)),
+        }
+    }
+
+    fn rotation_path(path: &Path) -> std::path::PathBuf {
+        path.with_extension(ROTATION_EXTENSION)
+    }
+
+    /// 写入密钥文件，权限为仅所有者可读写
+    ///
+    /// 先写入同一目录下的临时文件并同步到磁盘，再重命名覆盖原文件，
+    /// 写入中途失败（例如迁移加密或修改口令时断电）不会损坏已有的密钥文件。
+    fn write_file(path: &Path, encoded: &[u8]) -> Result<(), String> {
         // 确保父目录存在
         if let Some(parent) = path.parent() {
             if let Err(e) = fs::create_dir_all(parent) {
@@ -84,33 +358,50 @@ impl IdentityManager {
             }
         }
 
-        // 写入文件
-        let mut file = match fs::File::create(path) {
-            Ok(f) => f,
-            Err(e) => {
-                let msg = format!("创建密钥文件失败: {} - {}", path.display(), e);
-                tracing::error!("{}", msg);
-                return Err(msg);
-            }
-        };
-
-        if let Err(e) = file.write_all(&encoded) {
-            let msg = format!("写入密钥文件失败: {} - {}", path.display(), e);
+        let tmp_path = Self::temp_path(path);
+        let result = Self::write_temp_file(&tmp_path, encoded).and_then(|()| {
+            fs::rename(&tmp_path, path).map_err(|e| format!("替换密钥文件失败: {} - {}", path.display(), e))
+        });
+        if let Err(msg) = &result {
             tracing::error!("{}", msg);
-            return Err(msg);
+            let _ = fs::remove_file(&tmp_path);
         }
+        result
+    }
+
+    /// 临时文件路径（与密钥文件在同一目录，保证重命名是原子的）
+    fn temp_path(path: &Path) -> std::path::PathBuf {
+        let mut name = std::ffi::OsString::from(".");
+        name.push(path.file_name().unwrap_or_default());
+        name.push(".tmp");
+        path.with_file_name(name)
+    }
 
-        // 设置文件权限为仅所有者可读写 (0600)
+    /// 创建权限为 0600 的临时文件，写入内容并同步到磁盘
+    fn write_temp_file(tmp_path: &Path, encoded: &[u8]) -> Result<(), String> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options
+            .open(tmp_path)
+            .map_err(|e| format!("创建密钥文件失败: {} - {}", tmp_path.display(), e))?;
+
+        // 临时文件可能是上次失败时留下的，重新设置权限
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o600)) {
-                tracing::warn!("设置密钥文件权限失败: {} - {}", path.display(), e);
-            }
+            file.set_permissions(fs::Permissions::from_mode(0o600))
+                .map_err(|e| format!("设置密钥文件权限失败: {} - {}", tmp_path.display(), e))?;
         }
 
-        tracing::info!("成功生成并保存密钥对: {}", path.display());
-        Ok(keypair)
+        file.write_all(encoded)
+            .and_then(|()| file.sync_all())
+            .map_err(|e| format!("写入密钥文件失败: {} - {}", tmp_path.display(), e))
     }
 
     /// 加载或生成密钥对
@@ -157,6 +448,76 @@ impl IdentityManager {
         }
     }
 
+    fn is_encrypted_bytes(bytes: &[u8]) -> bool {
+        bytes.starts_with(ENCRYPTED_MAGIC)
+    }
+
+    /// 从口令和盐派生加密密钥（Argon2id）
+    fn derive_key(passphrase: &str, salt: &[u8], params: Params) -> Result<Key, String> {
+        let mut key = Key::default();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| format!("派生密钥失败: {}", e))?;
+        Ok(key)
+    }
+
+    /// 加密密钥对，返回完整的加密文件内容
+    fn encrypt_keypair(keypair: &Keypair, passphrase: &str) -> Result<Vec<u8>, String> {
+        let params = Params::default();
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(ENCRYPTED_MAGIC);
+        header.push(ENCRYPTED_VERSION);
+        header.extend_from_slice(&params.m_cost().to_le_bytes());
+        header.extend_from_slice(&params.t_cost().to_le_bytes());
+        header.extend_from_slice(&params.p_cost().to_le_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce);
+
+        let key = Self::derive_key(passphrase, &salt, params)?;
+        let plaintext = Self::encode_keypair(keypair)?;
+        let ciphertext = ChaCha20Poly1305::new(&key)
+            .encrypt(&nonce, Payload { msg: &plaintext, aad: &header })
+            .map_err(|_| "密钥加密失败".to_string())?;
+
+        header.extend_from_slice(&ciphertext);
+        Ok(header)
+    }
+
+    /// 解密加密文件内容
+    fn decrypt_keypair(bytes: &[u8], passphrase: &str) -> Result<Keypair, String> {
+        if bytes.len() < HEADER_LEN {
+            return Err("加密密钥文件已损坏".to_string());
+        }
+        let (header, ciphertext) = bytes.split_at(HEADER_LEN);
+        let version = header[8];
+        if version != ENCRYPTED_VERSION {
+            return Err(format!("不支持的加密密钥文件版本: {}", version));
+        }
+
+        let read_u32 = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+        let (m_cost, t_cost, p_cost) = (read_u32(9), read_u32(13), read_u32(17));
+        if m_cost > MAX_M_COST || t_cost > MAX_T_COST || p_cost > MAX_P_COST {
+            return Err(format!(
+                "加密密钥文件参数超出上限: m_cost={} t_cost={} p_cost={}",
+                m_cost, t_cost, p_cost
+            ));
+        }
+        let params = Params::new(m_cost, t_cost, p_cost, None)
+            .map_err(|e| format!("加密密钥文件参数无效: {}", e))?;
+        let salt = &header[21..21 + SALT_LEN];
+        let nonce = Nonce::from_slice(&header[21 + SALT_LEN..]);
+
+        let key = Self::derive_key(passphrase, salt, params)?;
+        let plaintext = ChaCha20Poly1305::new(&key)
+            .decrypt(nonce, Payload { msg: ciphertext, aad: header })
+            .map_err(|_| "口令错误或密钥文件已损坏".to_string())?;
+        Self::decode_keypair(&plaintext)
+    }
+
     /// 删除密钥文件
     ///
     /// # Arguments
@@ -171,6 +532,13 @@ impl IdentityManager {
                 .map_err(|e| format!("删除密钥文件失败: {} - {}", path.display(), e))?;
             tracing::info!("已删除密钥文件: {}", path.display());
         }
+
+        // 轮换声明属于被删除的身份，一并删除
+        let rotation_path = Self::rotation_path(path);
+        if rotation_path.exists() {
+            fs::remove_file(&rotation_path)
+                .map_err(|e| format!("删除轮换声明失败: {} - {}", rotation_path.display(), e))?;
+        }
         Ok(())
     }
 }
@@ -233,4 +601,112 @@ mod tests {
         // 删除不存在的文件应该成功
         IdentityManager::delete(path).unwrap();
     }
+
+    #[test]
+    fn test_encrypted_identity() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("identity.key");
+
+        // 首次使用口令时生成加密文件
+        let keypair = IdentityManager::load_with_passphrase(&path, "正确口令").unwrap();
+        let peer_id = keypair.public().to_peer_id();
+        assert!(IdentityManager::is_encrypted(&path));
+
+        // 未提供口令或口令错误时无法加载
+        assert!(IdentityManager::load_or_none(&path).is_err());
+        assert!(IdentityManager::load_with_passphrase(&path, "错误口令").is_err());
+
+        // 修改口令后只能用新口令解密
+        IdentityManager::change_passphrase(&path, "正确口令", "新口令").unwrap();
+        assert!(IdentityManager::load_with_passphrase(&path, "正确口令").is_err());
+        let keypair = IdentityManager::load_with_passphrase(&path, "新口令").unwrap();
+        assert_eq!(keypair.public().to_peer_id(), peer_id);
+    }
+
+    #[test]
+    fn test_plaintext_identity_migrated() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("identity.key");
+
+        let keypair = IdentityManager::generate_and_save(&path).unwrap();
+        assert!(!IdentityManager::is_encrypted(&path));
+
+        let migrated = IdentityManager::load_with_passphrase(&path, "口令").unwrap();
+        assert_eq!(migrated.public().to_peer_id(), keypair.public().to_peer_id());
+        assert!(IdentityManager::is_encrypted(&path));
+
+        // 通过临时文件替换，替换后不留下临时文件，权限仍为 0600
+        assert!(!IdentityManager::temp_path(&path).exists());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 篡改文件头会导致认证失败
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[HEADER_LEN - 1] ^= 0xff;
+        std::fs::write(&path, bytes).unwrap();
+        assert!(IdentityManager::load_with_passphrase(&path, "口令").is_err());
+    }
+
+    #[test]
+    fn test_argon2_params_capped() {
+        let keypair = Keypair::generate_ed25519();
+        let encrypted = IdentityManager::encrypt_keypair(&keypair, "口令").unwrap();
+        assert!(IdentityManager::decrypt_keypair(&encrypted, "口令").is_ok());
+
+        // 超出上限的参数在派生密钥之前被拒绝
+        for (offset, value) in [(9, MAX_M_COST + 1), (13, MAX_T_COST + 1), (17, MAX_P_COST + 1), (9, u32::MAX)] {
+            let mut tampered = encrypted.clone();
+            tampered[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            let err = IdentityManager::decrypt_keypair(&tampered, "口令").unwrap_err();
+            assert!(err.contains("超出上限"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_export_import_bundle() {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+
+        let plain = IdentityManager::export_bundle(&keypair, None).unwrap();
+        let imported = IdentityManager::import_bundle(&plain, None).unwrap();
+        assert_eq!(imported.public().to_peer_id(), peer_id);
+
+        let encrypted = IdentityManager::export_bundle(&keypair, Some("口令")).unwrap();
+        assert!(IdentityManager::import_bundle(&encrypted, None).is_err());
+        assert!(IdentityManager::import_bundle(&encrypted, Some("错误口令")).is_err());
+        let imported = IdentityManager::import_bundle(&encrypted, Some("口令")).unwrap();
+        assert_eq!(imported.public().to_peer_id(), peer_id);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("identity.key");
+
+        let old = IdentityManager::load_or_generate(&path).unwrap();
+        assert!(IdentityManager::load_rotation(&path).unwrap().is_none());
+
+        let (new, statement) = IdentityManager::rotate(&path, &old, None).unwrap();
+        let (old_peer_id, new_peer_id) = statement.verify().unwrap();
+        assert_eq!(old_peer_id, old.public().to_peer_id());
+        assert_eq!(new_peer_id, new.public().to_peer_id());
+
+        // 密钥文件已替换，声明可以重新加载
+        let loaded = IdentityManager::load_or_generate(&path).unwrap();
+        assert_eq!(loaded.public().to_peer_id(), new_peer_id);
+        assert_eq!(IdentityManager::load_rotation(&path).unwrap(), Some(statement.clone()));
+
+        // 篡改新 Peer ID 后签名无效
+        let mut forged = statement;
+        forged.new_peer_id = PeerId::random().to_string();
+        assert!(forged.verify().is_err());
+
+        IdentityManager::delete(&path).unwrap();
+        assert!(IdentityManager::load_rotation(&path).unwrap().is_none());
+    }
 }
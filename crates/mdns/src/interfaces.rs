@@ -0,0 +1,303 @@
+//! 网卡选择
+//!
+//! 默认监听 `0.0.0.0` / `::`，mDNS 会把 Docker 网桥、VPN 等所有网卡上的地址都通告出去。
+//! 通过 [`InterfaceFilter`] 可以按网卡名称或网段选择使用的网卡：
+//!
+//! - 未指定地址（`0.0.0.0` / `::`）的监听地址展开为允许的网卡地址，其他网卡上的地址不再监听和通告；
+//! - mDNS 发现的地址不在允许网卡的网段内时被忽略，不会拨号；
+//! - 网卡列表在创建 swarm 时读取，之后新增的网卡不会自动加入。
+//!
+//! libp2p 的 mDNS 只能使用 IPv4 或 IPv6 之一，[`MdnsBehaviour`] 在启用 IPv6 时同时运行两个实例。
+
+use ipnet::IpNet;
+use libp2p::{mdns, multiaddr::Protocol, swarm::behaviour::toggle::Toggle, Multiaddr, PeerId};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// 网卡选择错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum InterfaceError {
+    /// 网段格式错误
+    #[error("无效的网段: {0}")]
+    InvalidCidr(String),
+
+    /// 读取网卡列表失败
+    #[error("读取网卡列表失败: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// 网卡匹配规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceRule {
+    /// 按网卡名称匹配，以 `*` 结尾时匹配前缀（如 `docker*`）
+    Name(String),
+    /// 按网段匹配网卡地址（如 `192.168.1.0/24`）
+    Cidr(IpNet),
+}
+
+impl InterfaceRule {
+    /// 是否匹配该网卡地址
+    pub fn matches(&self, name: &str, ip: IpAddr) -> bool {
+        match self {
+            Self::Name(pattern) => match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            },
+            Self::Cidr(net) => net.contains(&ip),
+        }
+    }
+}
+
+impl FromStr for InterfaceRule {
+    type Err = InterfaceError;
+
+    /// 含 `/` 的按网段解析，其余作为网卡名称
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.contains('/') {
+            return s.parse().map(Self::Cidr).map_err(|_| InterfaceError::InvalidCidr(s.to_string()));
+        }
+        Ok(Self::Name(s.to_string()))
+    }
+}
+
+impl fmt::Display for InterfaceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::Cidr(net) => write!(f, "{}", net),
+        }
+    }
+}
+
+/// 网卡地址（名称、地址和所在网段）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceAddress {
+    pub name: String,
+    pub ip: IpAddr,
+    pub network: IpNet,
+}
+
+impl InterfaceAddress {
+    /// 读取本机所有网卡地址
+    pub fn list() -> Result<Vec<Self>, InterfaceError> {
+        Ok(if_addrs::get_if_addrs()?
+            .into_iter()
+            .filter_map(|iface| {
+                let (ip, netmask) = match iface.addr {
+                    if_addrs::IfAddr::V4(ref addr) => (IpAddr::V4(addr.ip), IpAddr::V4(addr.netmask)),
+                    if_addrs::IfAddr::V6(ref addr) => (IpAddr::V6(addr.ip), IpAddr::V6(addr.netmask)),
+                };
+                let network = IpNet::with_netmask(ip, netmask).ok()?.trunc();
+                Some(Self { name: iface.name, ip, network })
+            })
+            .collect())
+    }
+}
+
+/// 网卡过滤器
+///
+/// 排除规则优先；包含规则为空时允许所有未被排除的网卡。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceFilter {
+    pub include: Vec<InterfaceRule>,
+    pub exclude: Vec<InterfaceRule>,
+}
+
+impl InterfaceFilter {
+    /// 创建空过滤器（使用所有网卡）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 只使用匹配的网卡（可多次调用）
+    pub fn include(mut self, rule: InterfaceRule) -> Self {
+        self.include.push(rule);
+        self
+    }
+
+    /// 不使用匹配的网卡（可多次调用）
+    pub fn exclude(mut self, rule: InterfaceRule) -> Self {
+        self.exclude.push(rule);
+        self
+    }
+
+    /// 是否没有任何规则
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// 是否允许使用该网卡地址
+    pub fn allows(&self, name: &str, ip: IpAddr) -> bool {
+        if self.exclude.iter().any(|rule| rule.matches(name, ip)) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|rule| rule.matches(name, ip))
+    }
+
+    /// 允许使用的网卡地址
+    pub fn select(&self, interfaces: &[InterfaceAddress]) -> Vec<InterfaceAddress> {
+        interfaces.iter().filter(|iface| self.allows(&iface.name, iface.ip)).cloned().collect()
+    }
+
+    /// 根据本机网卡解析监听地址和允许的远端网段
+    ///
+    /// 没有规则时原样返回，不读取网卡列表。
+    pub(crate) fn resolve(&self, listen_addresses: Vec<Multiaddr>) -> Result<ResolvedInterfaces, InterfaceError> {
+        if self.is_empty() {
+            return Ok(ResolvedInterfaces { listen_addresses, networks: None });
+        }
+        let allowed = self.select(&InterfaceAddress::list()?);
+        tracing::info!(
+            "使用网卡: {}",
+            allowed.iter().map(|iface| format!("{} ({})", iface.name, iface.ip)).collect::<Vec<_>>().join(", ")
+        );
+        let listen_addresses = expand_listen_addresses(listen_addresses, &allowed);
+        if listen_addresses.is_empty() {
+            tracing::warn!("没有符合网卡过滤规则的监听地址");
+        }
+        Ok(ResolvedInterfaces {
+            listen_addresses,
+            networks: Some(allowed.iter().map(|iface| iface.network).collect()),
+        })
+    }
+}
+
+/// 解析后的监听地址和远端地址过滤条件
+#[derive(Debug, Clone)]
+pub(crate) struct ResolvedInterfaces {
+    pub(crate) listen_addresses: Vec<Multiaddr>,
+    /// 允许的远端网段（None 表示不过滤）
+    pub(crate) networks: Option<Vec<IpNet>>,
+}
+
+impl ResolvedInterfaces {
+    /// 远端地址是否在允许的网段内（非 IP 地址不过滤）
+    pub(crate) fn permits(&self, addr: &Multiaddr) -> bool {
+        let Some(ref networks) = self.networks else {
+            return true;
+        };
+        match ip_of(addr) {
+            Some(ip) => networks.iter().any(|net| net.contains(&ip)),
+            None => true,
+        }
+    }
+}
+
+/// 地址的 IP 部分
+fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    match addr.iter().next()? {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    }
+}
+
+/// 把未指定地址的监听地址展开为同一协议族的网卡地址，指定了地址的保持不变
+pub fn expand_listen_addresses(addresses: Vec<Multiaddr>, interfaces: &[InterfaceAddress]) -> Vec<Multiaddr> {
+    let mut expanded = Vec::new();
+    for addr in addresses {
+        let Some(ip) = ip_of(&addr).filter(|ip| ip.is_unspecified()) else {
+            expanded.push(addr);
+            continue;
+        };
+        let rest: Vec<Protocol> = addr.iter().skip(1).collect();
+        for iface in interfaces.iter().filter(|iface| iface.ip.is_ipv4() == ip.is_ipv4()) {
+            let mut specific = Multiaddr::from(iface.ip);
+            for protocol in &rest {
+                specific.push(protocol.clone());
+            }
+            if !expanded.contains(&specific) {
+                expanded.push(specific);
+            }
+        }
+    }
+    expanded
+}
+
+/// mDNS 参数
+pub(crate) fn mdns_config(ttl: std::time::Duration, query_interval: std::time::Duration, ipv6: bool) -> mdns::Config {
+    mdns::Config { ttl, query_interval, enable_ipv6: ipv6 }
+}
+
+/// IPv4 mDNS，启用 IPv6 时同时运行 IPv6 mDNS
+#[derive(libp2p::swarm::NetworkBehaviour)]
+pub(crate) struct MdnsBehaviour {
+    ipv4: mdns::tokio::Behaviour,
+    ipv6: Toggle<mdns::tokio::Behaviour>,
+}
+
+impl MdnsBehaviour {
+    pub(crate) fn new(config: mdns::Config, peer_id: PeerId) -> std::io::Result<Self> {
+        let ipv6 = config.enable_ipv6;
+        let ipv4 = mdns::tokio::Behaviour::new(mdns::Config { enable_ipv6: false, ..config.clone() }, peer_id)?;
+        let ipv6 = if ipv6 { Some(mdns::tokio::Behaviour::new(config, peer_id)?) } else { None };
+        Ok(Self { ipv4, ipv6: Toggle::from(ipv6) })
+    }
+}
+
+impl MdnsBehaviourEvent {
+    /// 取出 mDNS 事件（不区分 IPv4 / IPv6）
+    pub(crate) fn into_inner(self) -> mdns::Event {
+        match self {
+            Self::Ipv4(event) | Self::Ipv6(event) => event,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iface(name: &str, cidr: &str) -> InterfaceAddress {
+        let net: IpNet = cidr.parse().unwrap();
+        InterfaceAddress { name: name.to_string(), ip: net.addr(), network: net.trunc() }
+    }
+
+    #[test]
+    fn test_filter_rules() {
+        let interfaces = vec![
+            iface("lo", "127.0.0.1/8"),
+            iface("eth0", "192.168.1.20/24"),
+            iface("docker0", "172.17.0.1/16"),
+            iface("wg0", "10.8.0.2/24"),
+            iface("eth0", "fe80::1/64"),
+        ];
+        assert!(matches!("10.0.0.0/33".parse::<InterfaceRule>(), Err(InterfaceError::InvalidCidr(_))));
+
+        let filter = InterfaceFilter::new().exclude("docker*".parse().unwrap()).exclude("10.8.0.0/16".parse().unwrap());
+        let names: Vec<String> = filter.select(&interfaces).into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["lo", "eth0", "eth0"]);
+
+        let filter = InterfaceFilter::new().include("eth0".parse().unwrap()).exclude("fe80::/10".parse().unwrap());
+        let allowed = filter.select(&interfaces);
+        assert_eq!(allowed, vec![interfaces[1].clone()]);
+
+        let expanded = expand_listen_addresses(
+            vec![
+                "/ip4/0.0.0.0/tcp/4001".parse().unwrap(),
+                "/ip4/0.0.0.0/udp/4001/quic-v1".parse().unwrap(),
+                "/ip6/::/tcp/0".parse().unwrap(),
+                "/ip4/127.0.0.1/tcp/5000".parse().unwrap(),
+            ],
+            &allowed,
+        );
+        assert_eq!(
+            expanded,
+            vec![
+                "/ip4/192.168.1.20/tcp/4001".parse::<Multiaddr>().unwrap(),
+                "/ip4/192.168.1.20/udp/4001/quic-v1".parse().unwrap(),
+                "/ip4/127.0.0.1/tcp/5000".parse().unwrap(),
+            ]
+        );
+
+        let resolved = ResolvedInterfaces {
+            listen_addresses: expanded,
+            networks: Some(allowed.iter().map(|i| i.network).collect()),
+        };
+        assert!(resolved.permits(&"/ip4/192.168.1.77/tcp/4001".parse().unwrap()));
+        assert!(!resolved.permits(&"/ip4/172.17.0.3/tcp/4001".parse().unwrap()));
+        assert!(resolved.permits(&"/dns4/nas.local/tcp/4001".parse().unwrap()));
+    }
+}
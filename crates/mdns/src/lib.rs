@@ -10,8 +10,18 @@ pub mod publisher;
 pub mod node;
 pub mod managed_discovery;
 pub mod user_info;
+pub mod avatar;
+pub mod codec;
 pub mod chat;
+pub mod file_transfer;
+pub mod channel;
+pub mod trust;
 pub mod identity;
+pub mod network_key;
+pub mod transport;
+pub mod static_peers;
+pub mod rendezvous;
+pub mod interfaces;
 
 pub use config::{MdnsConfig, ServiceInfo};
 pub use discovery::{MdnsDiscovery, DiscoveredPeer, DiscoveredEvent};
@@ -24,13 +34,37 @@ pub use managed_discovery::{
     HealthStatus,
     HealthCheckConfig,
 };
-pub use user_info::UserInfo;
-pub use identity::IdentityManager;
+pub use user_info::{Presence, SignedUserInfo, UserInfo, UserInfoError, VerifiedUserInfo};
+pub use avatar::{AvatarCache, AvatarError, AvatarFormat};
+pub use identity::{IdentityManager, RotationStatement};
+pub use network_key::NetworkKey;
+pub use transport::TransportKind;
+pub use static_peers::{StaticPeerError, StaticPeerStore};
+pub use rendezvous::{RendezvousConfig, RendezvousError, RendezvousServer, RendezvousServerEvent};
+pub use interfaces::{InterfaceError, InterfaceFilter, InterfaceRule};
 
 // 聊天模块公共 API
 pub use chat::{
-    ChatMessage, TextMessage, TypingIndicator, MessageAck,
-    ChatExtension, ChatEvent, ChatError, ChatManager, ChatSession,
+    ChatMessage, TextMessage, TypingIndicator, MessageAck, DeliveryStatus,
+    ImageMessage, FileAttachment, MessageEdit, MessageDelete, Reaction, MessageMeta, UnknownMessage,
+    ChatExtension, ChatEvent, ChatError, ChatConfig, ChatManager, ChatSession,
+    ChatStore, FileChatStore, MemoryChatStore, StoredMessage,
+    ChatRoom, RoomInvite, RoomLeave,
+};
+
+// 文件传输模块公共 API
+pub use file_transfer::{
+    FileOffer, FileTransferExtension, FileTransferEvent, FileTransferError,
+    FileTransferManager, FileTransferConfig, FileTransfer, TransferState, TransferDirection,
+};
+
+// 频道模块公共 API
+pub use channel::{ChannelMessage, ChannelExtension, ChannelEvent, ChannelError};
+
+// 信任模块公共 API
+pub use trust::{
+    TrustState, TrustEntry, TrustStore, MemoryTrustStore, FileTrustStore,
+    TrustExtension, TrustEvent, TrustError, TrustConfig,
 };
 
 /// mDNS 相关错误
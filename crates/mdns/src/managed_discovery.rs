@@ -2,17 +2,44 @@
 //!
 //! 集成 mDNS 发现、identify 验证、用户信息交换和 ping 心跳，自动管理验证通过的节点。
 
-use super::{node::{NodeManager, VerifiedNode}, user_info, MdnsError};
-use super::chat::{ChatExtension, ChatManager, ChatMessage, ChatError};
+use super::{identity::RotationStatement, network_key::NetworkKey, node::{NodeManager, VerifiedNode}, transport::{self, TransportKind}, user_info, MdnsError};
+use super::codec::{is_protocol_violation, ProtocolLimits};
+use super::static_peers::{self, StaticDial, StaticPeerError, StaticPeerStore};
+use super::rendezvous::{RendezvousClient, RendezvousConfig, RendezvousError};
+use super::avatar::{avatar_codec, AvatarCache, AvatarCodec, AvatarError, AvatarRequest, AvatarResponse};
+use super::chat::{
+    ChatConfig, ChatExtension, ChatManager, ChatMessage, ChatError, ChatRoom, ChatStore, DeliveryStatus,
+};
+use super::channel::{message::{channel_name, channel_topic}, ChannelError, ChannelEvent, ChannelExtension, ChannelMessage};
+use super::file_transfer::{
+    FileTransferCodec, FileTransferConfig, FileTransferError, FileTransferEvent,
+    FileTransferExtension, FileTransferManager, FileTransferProtocol, FileTransferRequest,
+    FileTransferResponse,
+};
+use super::interfaces::{MdnsBehaviour, ResolvedInterfaces};
+use super::trust::{
+    pairing::{pairing_codec, PairingCodec, PairingRequest, PairingResponse, PairingSession},
+    TrustConfig, TrustError, TrustEvent, TrustExtension, TrustState, TrustStore,
+};
 use futures::StreamExt;
 use libp2p::{
-    identify, mdns, ping, request_response, Swarm, SwarmBuilder, identity::Keypair, Multiaddr, PeerId,
+    dcutr, gossipsub, identify, mdns, noise, ping, relay, rendezvous, request_response, yamux, Swarm, SwarmBuilder,
+    identity::Keypair, multiaddr::Protocol, Multiaddr, PeerId,
+    swarm::{behaviour::toggle::Toggle, dial_opts::DialOpts, ConnectionId},
+    core::transport::ListenerId,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+/// 发件箱检查间隔（重试到期消息、清理过期消息）
+const OUTBOX_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 中继线路的最长持续时间（超时后断开，文件传输会在重连后续传）
+const RELAY_MAX_CIRCUIT_DURATION: Duration = Duration::from_secs(60 * 60);
+
 /// 健康状态
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HealthStatus {
@@ -82,6 +109,12 @@ impl NodeHealth {
     }
 }
 
+impl Default for NodeHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 健康检查配置
 #[derive(Debug, Clone)]
 pub struct HealthCheckConfig {
@@ -112,22 +145,108 @@ impl Default for HealthCheckConfig {
 /// - `identify`: 用于节点身份验证和信息交换
 /// - `request_response`: 用于用户信息交换（自定义协议）
 /// - `ping`: 用于心跳检测（自动发送）
+/// - `chat` / `file_transfer`: 可选的聊天和文件传输协议
+/// - `gossipsub`: 可选的一对多频道（只接受已验证节点发布的消息）
+/// - `pairing`: 可选的节点配对协议（启用信任管理后使用）
+/// - `avatar`: 头像交换协议（按内容哈希获取头像图片）
+/// - `rendezvous`: 可选的会合点客户端（跨子网发现节点）
+/// - `relay_client` / `relay` / `dcutr`: 中继客户端、可选的中继服务和打洞升级直连
 pub struct ManagedDiscovery {
     swarm: Swarm<ManagedBehaviour>,
     node_manager: Arc<NodeManager>,
     local_user_info: user_info::UserInfo,
+    /// 本地密钥对（用于签名用户信息）
+    local_key: Keypair,
+    /// 签名后的本地用户信息
+    local_profile: user_info::SignedUserInfo,
+    /// 本地用户信息的序列号（每次修改递增）
+    local_profile_seq: u64,
     protocol_version: String,
     agent_version: String,
+    /// 可选的私有网络密钥
+    network_key: Option<NetworkKey>,
+    /// 是否启用 QUIC 传输
+    quic: bool,
+    /// 网卡过滤（忽略不在允许网段内的 mDNS 地址）
+    interfaces: ResolvedInterfaces,
     health_status: HashMap<PeerId, NodeHealth>,
     health_config: HealthCheckConfig,
     /// 跟踪每个节点的活跃连接数
     active_connections: HashMap<PeerId, u32>,
+    /// 每个连接使用的传输协议
+    connection_transports: HashMap<ConnectionId, (PeerId, TransportKind)>,
+    /// 手动添加的静态节点列表
+    static_peers: StaticPeerStore,
+    /// 静态节点的拨号状态
+    static_dials: HashMap<Multiaddr, StaticDial>,
+    /// 进行中的静态节点拨号（连接 ID -> 地址）
+    static_connections: HashMap<ConnectionId, Multiaddr>,
+    /// 可选的会合点客户端（启用后存在）
+    rendezvous: Option<RendezvousClient>,
+    /// 是否作为中继节点
+    relay_server: bool,
+    /// 可以在本中继节点上预留地址和建立线路的节点（已验证且当前在线）
+    relay_peers: Arc<std::sync::RwLock<HashSet<PeerId>>>,
+    /// 主动拨号建立的直连地址（节点 -> 地址，用于预留中继地址）
+    dialed_addresses: HashMap<PeerId, Multiaddr>,
+    /// 已在中继节点上预留的监听器（中继节点 -> 监听器）
+    relay_reservations: HashMap<PeerId, ListenerId>,
     /// 已收到的用户信息
     peer_user_info: HashMap<PeerId, user_info::UserInfo>,
+    /// 已验证的签名用户信息（包括导入的缓存，可导出持久化）
+    peer_profiles: HashMap<PeerId, (user_info::VerifiedUserInfo, user_info::SignedUserInfo)>,
     /// 可选的聊天管理器
     chat_manager: Option<Arc<ChatManager>>,
     /// 聊天事件接收器（用于处理聊天消息）
     chat_event_rx: Option<mpsc::UnboundedReceiver<super::chat::ChatEvent>>,
+    /// 进行中的用户信息请求（请求 ID -> 目标节点）
+    user_info_requests: HashMap<request_response::OutboundRequestId, PeerId>,
+    /// 进行中的用户信息推送
+    user_info_pushes: HashSet<request_response::OutboundRequestId>,
+    /// 头像缓存（按内容哈希索引）
+    avatar_cache: AvatarCache,
+    /// 进行中的头像请求（请求 ID -> 目标节点和头像哈希）
+    avatar_requests: HashMap<request_response::OutboundRequestId, (PeerId, String)>,
+    /// 进行中的聊天请求（请求 ID -> 目标节点和消息 ID，用于更新投递状态）
+    chat_requests: HashMap<request_response::OutboundRequestId, (PeerId, String)>,
+    /// 发件箱检查定时器（重试到期消息、清理过期消息）
+    outbox_timer: tokio::time::Interval,
+    /// 可选的文件传输管理器
+    file_transfer_manager: Option<Arc<FileTransferManager>>,
+    /// 文件传输事件接收器
+    file_transfer_event_rx: Option<mpsc::UnboundedReceiver<FileTransferEvent>>,
+    /// 进行中的文件传输请求（用于失败时定位传输）
+    file_requests: HashMap<request_response::OutboundRequestId, String>,
+    /// 待返回的发现事件（一次 swarm 事件可能产生多个发现事件）
+    pending_events: VecDeque<DiscoveryEvent>,
+    /// 频道事件发送器（启用频道功能后存在）
+    channel_event_tx: Option<mpsc::UnboundedSender<ChannelEvent>>,
+    /// 频道事件接收器
+    channel_event_rx: Option<mpsc::UnboundedReceiver<ChannelEvent>>,
+    /// 已订阅的频道
+    channels: BTreeSet<String>,
+    /// 协议限制（帧大小、读取超时和违规封禁策略）
+    protocol_limits: ProtocolLimits,
+    /// 每个节点的协议违规次数
+    violations: HashMap<PeerId, u32>,
+    /// 被封禁的节点及解封时间
+    banned_peers: HashMap<PeerId, Instant>,
+    /// 可选的信任存储（启用信任管理后存在）
+    trust_store: Option<Arc<dyn TrustStore>>,
+    /// 信任策略
+    trust_config: TrustConfig,
+    /// 进行中的配对
+    pairing_sessions: HashMap<PeerId, PairingSession>,
+    /// 进行中的配对请求（用于失败时定位配对）
+    pairing_requests: HashMap<request_response::OutboundRequestId, PeerId>,
+    /// 本节点的身份轮换声明（出示给其他节点）
+    rotation: Option<RotationStatement>,
+    /// 已出示过轮换声明的节点
+    rotation_announced: HashSet<PeerId>,
+    /// 信任事件发送器
+    trust_event_tx: Option<mpsc::UnboundedSender<TrustEvent>>,
+    /// 信任事件接收器
+    trust_event_rx: Option<mpsc::UnboundedReceiver<TrustEvent>>,
 }
 
 /// 组合的 Behaviour，包含 mDNS、identify、ping 和 request_response
@@ -135,12 +254,29 @@ pub struct ManagedDiscovery {
 /// 使用 libp2p 的 `#[derive(NetworkBehaviour)]` 宏组合多个 behaviour
 #[derive(libp2p::swarm::NetworkBehaviour)]
 struct ManagedBehaviour {
-    mdns: mdns::tokio::Behaviour,
+    /// mDNS（启用 IPv6 时同时运行 IPv4 和 IPv6）
+    mdns: MdnsBehaviour,
     identify: identify::Behaviour,
     ping: ping::Behaviour,
     request_response: request_response::Behaviour<user_info::UserInfoCodec>,
     /// 聊天协议（使用 request_response 模式）
     chat: request_response::Behaviour<crate::chat::ChatCodec>,
+    /// 文件传输协议（分块 request_response）
+    file_transfer: request_response::Behaviour<FileTransferCodec>,
+    /// 频道协议（GossipSub，消息需经过验证才会转发；启用 `channels` 时存在）
+    gossipsub: Toggle<gossipsub::Behaviour>,
+    /// 配对协议（承诺-揭示握手，生成短验证码）
+    pairing: request_response::Behaviour<PairingCodec>,
+    /// 头像协议（按内容哈希获取头像图片）
+    avatar: request_response::Behaviour<AvatarCodec>,
+    /// 会合点客户端（libp2p rendezvous，向会合服务器注册和查询）
+    rendezvous: rendezvous::client::Behaviour,
+    /// 中继客户端（在中继节点上预留地址、通过中继拨号）
+    relay_client: relay::client::Behaviour,
+    /// 中继服务（启用 `relay_server` 时存在）
+    relay: Toggle<relay::Behaviour>,
+    /// 通过中继连接后尝试打洞升级为直连
+    dcutr: dcutr::Behaviour,
 }
 
 impl ManagedDiscovery {
@@ -152,12 +288,14 @@ impl ManagedDiscovery {
     /// * `health_config` - 健康检查配置
     /// * `local_user_info` - 本地用户信息
     /// * `identity` - 可选的密钥对，如果为 None 则生成新的
+    /// * `network_key` - 可选的私有网络密钥，只有使用相同密钥的节点才能互相连接和验证
     pub async fn new(
         node_manager: Arc<NodeManager>,
         listen_addresses: Vec<Multiaddr>,
         health_config: HealthCheckConfig,
         local_user_info: user_info::UserInfo,
         identity: Option<Keypair>,
+        network_key: Option<NetworkKey>,
     ) -> std::result::Result<Self, MdnsError> {
         // 使用提供的密钥对，或生成新的
         let local_key = identity.unwrap_or_else(|| {
@@ -168,27 +306,49 @@ impl ManagedDiscovery {
         let peer_id = local_key.public().to_peer_id();
         tracing::info!("使用密钥对生成 Peer ID: {}", peer_id);
 
+        let local_profile_seq = user_info::next_seq(0);
+        let local_profile = user_info::SignedUserInfo::sign(&local_key, &local_user_info, local_profile_seq)
+            .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
+
         let config = node_manager.config();
-        let protocol_version = config.expected_protocol_version.clone();
+        // 私有网络在 identify 协议版本中附加密钥指纹
+        let protocol_version = match network_key {
+            Some(ref key) => {
+                tracing::info!("启用私有网络，密钥指纹: {}", key.fingerprint());
+                key.namespace(&config.expected_protocol_version)
+            }
+            None => config.expected_protocol_version.clone(),
+        };
         let agent_version = config.build_agent_version();
+        let cbor_frames = config.cbor_frames;
+        let protocol_limits = config.protocol_limits.clone();
+        // pnet 握手只能保护 TCP，私有网络中不启用 QUIC
+        let quic = config.quic && network_key.is_none();
+        if config.quic && !quic {
+            tracing::warn!("私有网络不支持 QUIC，仅使用 TCP");
+        }
+        let relay_server = config.relay_server;
+        let relay_peers = Arc::new(std::sync::RwLock::new(HashSet::new()));
+        let relay_limiter = relay_peers.clone();
+        let channels = config.channels;
+        let mdns_config = config.mdns_config();
 
         // 创建组合 behaviour
-        let mut swarm = SwarmBuilder::with_existing_identity(local_key)
+        let mut swarm = SwarmBuilder::with_existing_identity(local_key.clone())
             .with_tokio()
-            .with_tcp(
-                libp2p::tcp::Config::default(),
-                libp2p::noise::Config::new,
-                libp2p::yamux::Config::default,
-            )
+            .with_other_transport(|key| transport::build(key, network_key, quic))
             .map_err(|e| {
-                tracing::error!("TCP transport build failed: {:?}", e);
-                MdnsError::SwarmBuild(format!("TCP: {}", e))
+                tracing::error!("Transport build failed: {:?}", e);
+                MdnsError::SwarmBuild(format!("Transport: {}", e))
             })?
-            .with_behaviour(|_key| {
-                let mdns = mdns::tokio::Behaviour::new(
-                    mdns::Config::default(),
-                    _key.public().into()
-                ).expect("mdns behaviour creation failed");
+            .with_relay_client(noise::Config::new, yamux::Config::default)
+            .map_err(|e| {
+                tracing::error!("Relay transport build failed: {:?}", e);
+                MdnsError::SwarmBuild(format!("Relay transport: {}", e))
+            })?
+            .with_behaviour(|_key, relay_client| {
+                let mdns = MdnsBehaviour::new(mdns_config, _key.public().into())
+                    .expect("mdns behaviour creation failed");
 
                 let identify = identify::Behaviour::new(
                     identify::Config::new(protocol_version.clone(), _key.public())
@@ -199,18 +359,90 @@ impl ManagedDiscovery {
                 let ping = ping::Behaviour::new(ping::Config::default());
 
                 // 创建 request_response Behaviour 用于用户信息交换
-                let request_response = request_response::Behaviour::new(
-                    [(user_info::UserInfoProtocol, request_response::ProtocolSupport::Full)],
+                let request_response = request_response::Behaviour::with_codec(
+                    user_info::user_info_codec(&protocol_limits),
+                    user_info::UserInfoProtocol::supported(cbor_frames)
+                        .into_iter()
+                        .map(|protocol| (protocol, request_response::ProtocolSupport::Full)),
                     request_response::Config::default(),
                 );
 
                 // 创建 request_response Behaviour 用于聊天
-                let chat = request_response::Behaviour::new(
-                    [(crate::chat::ChatProtocol, request_response::ProtocolSupport::Full)],
+                let chat = request_response::Behaviour::with_codec(
+                    crate::chat::ChatCodec::new(&protocol_limits),
+                    crate::chat::ChatProtocol::supported(cbor_frames)
+                        .into_iter()
+                        .map(|protocol| (protocol, request_response::ProtocolSupport::Full)),
+                    request_response::Config::default(),
+                );
+
+                // 创建 request_response Behaviour 用于文件传输
+                // 单个分块最大 4MB，适当放宽请求超时
+                let file_transfer = request_response::Behaviour::new(
+                    [(FileTransferProtocol, request_response::ProtocolSupport::Full)],
+                    request_response::Config::default()
+                        .with_request_timeout(Duration::from_secs(60)),
+                );
+
+                // 创建 GossipSub Behaviour 用于频道（仅在启用频道时）
+                // 消息签名并由应用层校验发布者是否已验证，校验通过后才转发
+                let gossipsub = if channels {
+                    let gossipsub_config = gossipsub::ConfigBuilder::default()
+                        .validation_mode(gossipsub::ValidationMode::Strict)
+                        .validate_messages()
+                        .build()
+                        .map_err(|e| MdnsError::SwarmBuild(format!("GossipSub: {}", e)))?;
+                    let gossipsub = gossipsub::Behaviour::new(
+                        gossipsub::MessageAuthenticity::Signed(_key.clone()),
+                        gossipsub_config,
+                    )
+                    .map_err(|e| MdnsError::SwarmBuild(format!("GossipSub: {}", e)))?;
+                    Toggle::from(Some(gossipsub))
+                } else {
+                    Toggle::from(None)
+                };
+
+                // 创建 request_response Behaviour 用于节点配对
+                let pairing = request_response::Behaviour::with_codec(
+                    pairing_codec(&protocol_limits),
+                    [(crate::trust::PairingProtocol, request_response::ProtocolSupport::Full)],
+                    request_response::Config::default(),
+                );
+
+                // 创建 request_response Behaviour 用于头像交换
+                let avatar = request_response::Behaviour::with_codec(
+                    avatar_codec(&protocol_limits),
+                    [(crate::avatar::AvatarProtocol, request_response::ProtocolSupport::Full)],
                     request_response::Config::default(),
                 );
 
-                Ok(ManagedBehaviour { mdns, identify, ping, request_response, chat })
+                // 会合点客户端（启用会合点发现后才会发送请求）
+                let rendezvous = rendezvous::client::Behaviour::new(_key.clone());
+
+                // 中继服务放宽线路限制，聊天和文件传输都可以经过中继；
+                // 因此只为已验证的节点预留地址和建立线路，其他节点不能使用本节点的带宽
+                let relay = Toggle::from(relay_server.then(|| {
+                    tracing::info!("启用中继服务");
+                    let mut config = relay::Config {
+                        max_circuit_duration: RELAY_MAX_CIRCUIT_DURATION,
+                        max_circuit_bytes: 0,
+                        ..Default::default()
+                    };
+                    let limiters = [&mut config.reservation_rate_limiters, &mut config.circuit_src_rate_limiters];
+                    for limiters in limiters {
+                        let relay_peers = relay_limiter.clone();
+                        limiters.push(Box::new(move |peer: PeerId, _: &Multiaddr, _| {
+                            relay_peers.read().is_ok_and(|peers| peers.contains(&peer))
+                        }));
+                    }
+                    relay::Behaviour::new(peer_id, config)
+                }));
+                let dcutr = dcutr::Behaviour::new(peer_id);
+
+                Ok(ManagedBehaviour {
+                    mdns, identify, ping, request_response, chat, file_transfer, gossipsub, pairing, avatar, rendezvous,
+                    relay_client, relay, dcutr,
+                })
             })
             .map_err(|e| {
                 tracing::error!("Behaviour build failed: {:?}", e);
@@ -219,7 +451,16 @@ impl ManagedDiscovery {
             .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
             .build();
 
-        for addr in listen_addresses {
+        let listen_addresses = if quic {
+            transport::with_quic_listen_addresses(listen_addresses)
+        } else {
+            listen_addresses.into_iter().filter(|addr| !transport::is_quic(addr)).collect()
+        };
+        let interfaces = config
+            .interfaces
+            .resolve(listen_addresses)
+            .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
+        for addr in interfaces.listen_addresses.clone() {
             swarm.listen_on(addr)
                 .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
         }
@@ -228,14 +469,58 @@ impl ManagedDiscovery {
             swarm,
             node_manager,
             local_user_info,
+            local_key,
+            local_profile,
+            local_profile_seq,
             protocol_version,
             agent_version,
+            network_key,
+            quic,
+            interfaces,
             health_status: HashMap::new(),
             health_config,
             active_connections: HashMap::new(),
+            connection_transports: HashMap::new(),
+            static_peers: StaticPeerStore::in_memory(),
+            static_dials: HashMap::new(),
+            static_connections: HashMap::new(),
+            rendezvous: None,
+            relay_server,
+            relay_peers,
+            dialed_addresses: HashMap::new(),
+            relay_reservations: HashMap::new(),
             peer_user_info: HashMap::new(),
+            peer_profiles: HashMap::new(),
             chat_manager: None,
             chat_event_rx: None,
+            user_info_requests: HashMap::new(),
+            user_info_pushes: HashSet::new(),
+            avatar_cache: AvatarCache::in_memory(),
+            avatar_requests: HashMap::new(),
+            chat_requests: HashMap::new(),
+            outbox_timer: {
+                let mut timer = tokio::time::interval(OUTBOX_CHECK_INTERVAL);
+                timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                timer
+            },
+            file_transfer_manager: None,
+            file_transfer_event_rx: None,
+            file_requests: HashMap::new(),
+            pending_events: VecDeque::new(),
+            channel_event_tx: None,
+            channel_event_rx: None,
+            channels: BTreeSet::new(),
+            protocol_limits,
+            violations: HashMap::new(),
+            banned_peers: HashMap::new(),
+            trust_store: None,
+            trust_config: TrustConfig::default(),
+            pairing_sessions: HashMap::new(),
+            pairing_requests: HashMap::new(),
+            rotation: None,
+            rotation_announced: HashSet::new(),
+            trust_event_tx: None,
+            trust_event_rx: None,
         })
     }
 
@@ -247,25 +532,47 @@ impl ManagedDiscovery {
     /// 注意：libp2p 的 ping behaviour 会自动对所有已连接的节点发送周期性心跳。
     pub async fn run(&mut self) -> std::result::Result<DiscoveryEvent, MdnsError> {
         loop {
-            match self.swarm.select_next_some().await {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Ok(event);
+            }
+
+            let swarm_event = tokio::select! {
+                event = self.swarm.select_next_some() => event,
+                _ = self.outbox_timer.tick() => {
+                    self.process_outbox().await;
+                    self.process_static_peers().await;
+                    self.process_rendezvous();
+                    continue;
+                }
+            };
+
+            match swarm_event {
                 libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Mdns(event)) => {
-                    match event {
+                    match event.into_inner() {
                         mdns::Event::Discovered(list) => {
+                            let mut discovered: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
                             for (peer_id, addr) in list {
+                                if !self.interfaces.permits(&addr) {
+                                    tracing::debug!("忽略过滤网卡外的地址: {} at {}", peer_id, addr);
+                                    continue;
+                                }
                                 tracing::info!("通过 mDNS 发现节点: {} at {}", peer_id, addr);
+                                discovered.entry(peer_id).or_default().push(addr.clone());
+                                self.pending_events.push_back(DiscoveryEvent::Discovered(peer_id, addr));
+                            }
 
-                                // 尝试主动连接该节点以触发 identify 验证
-                                if let Err(e) = self.swarm.dial(addr.clone()) {
-                                    tracing::debug!("无法主动连接节点 {}: {}", peer_id, e);
+                            for (peer_id, addresses) in discovered {
+                                if self.is_banned(&peer_id) || self.is_blocked(&peer_id).await {
+                                    tracing::debug!("节点 {} 已被封禁，不主动连接", peer_id);
+                                    continue;
                                 }
-
-                                return Ok(DiscoveryEvent::Discovered(peer_id, addr));
+                                self.dial_discovered(peer_id, addresses);
                             }
                         }
                         mdns::Event::Expired(list) => {
                             for (peer_id, _addr) in list {
                                 tracing::info!("节点 mDNS 记录过期: {}", peer_id);
-                                return Ok(DiscoveryEvent::Expired(peer_id));
+                                self.pending_events.push_back(DiscoveryEvent::Expired(peer_id));
                             }
                         }
                     }
@@ -273,11 +580,10 @@ impl ManagedDiscovery {
                 libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Identify(event)) => {
                     match event {
                         identify::Event::Received { peer_id, info, .. } => {
-                            // 验证节点信息
-                            match self.node_manager.verify_node_info(
-                                &info.protocol_version,
-                                &info.agent_version,
-                            ) {
+                            // 验证节点信息（私有网络先核对密钥指纹）
+                            match self.verify_network(&info.protocol_version).and_then(|protocol_version| {
+                                self.node_manager.verify_node_info(protocol_version, &info.agent_version)
+                            }) {
                                 Ok(()) => {
                                     // 检查是否是自己的 Peer ID
                                     if peer_id == self.local_peer_id() {
@@ -285,24 +591,27 @@ impl ManagedDiscovery {
                                         continue;
                                     }
 
+                                    // 对方提供中继服务时预留中继地址
+                                    if info.protocols.contains(&relay::HOP_PROTOCOL_NAME) {
+                                        self.reserve_relay(peer_id);
+                                    }
+
                                     // 检查是否已经验证过（避免重复返回事件）
                                     let is_already_verified = self.node_manager.is_node_verified(&peer_id).await;
 
                                     // 验证通过，添加到节点管理器
-                                    let addresses = info
-                                        .listen_addrs
-                                        .iter()
-                                        .cloned()
-                                        .collect();
+                                    let addresses = info.listen_addrs.to_vec();
 
                                     let node = VerifiedNode::new(
                                         peer_id,
                                         addresses,
                                         info.protocol_version.clone(),
                                         info.agent_version.clone(),
-                                    );
+                                    )
+                                    .with_transport(self.peer_transport(&peer_id));
 
                                     self.node_manager.add_or_update_node(node).await;
+                                    self.allow_relay(peer_id);
 
                                     if is_already_verified {
                                         // 已验证过，只更新不返回事件（静默更新）
@@ -313,11 +622,18 @@ impl ManagedDiscovery {
                                         tracing::debug!("  协议版本: {}", info.protocol_version);
                                         tracing::debug!("  代理版本: {}", info.agent_version);
                                         tracing::info!("✓ 节点 {} 验证通过，已添加到管理器", peer_id);
+                                        self.announce_rotation_to(peer_id);
+                                        self.resume_file_transfers(peer_id).await;
+                                        self.resume_outbox(peer_id).await;
                                         return Ok(DiscoveryEvent::Verified(peer_id));
                                     }
                                 }
                                 Err(e) => {
                                     tracing::warn!("✗ 节点 {} 验证失败: {}", peer_id, e);
+                                    // 未通过验证的节点不参与频道消息转发
+                                    if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+                                        gossipsub.blacklist_peer(&peer_id);
+                                    }
                                     return Ok(DiscoveryEvent::VerificationFailed(
                                         peer_id,
                                         e.to_string(),
@@ -342,7 +658,7 @@ impl ManagedDiscovery {
 
                             let health = self.health_status
                                 .entry(peer)
-                                .or_insert_with(|| NodeHealth::new());
+                                .or_default();
 
                             let was_offline = health.is_offline();
                             health.record_success(rtt);
@@ -357,7 +673,7 @@ impl ManagedDiscovery {
 
                             let health = self.health_status
                                 .entry(peer)
-                                .or_insert_with(|| NodeHealth::new());
+                                .or_default();
 
                             let was_healthy = health.status == HealthStatus::Healthy;
                             health.record_failure(self.health_config.max_failures);
@@ -370,6 +686,10 @@ impl ManagedDiscovery {
                                     tracing::info!("已从管理器中移除离线节点 {}", peer);
                                 }
 
+                                if let Some(ref manager) = self.file_transfer_manager {
+                                    manager.handle_peer_disconnected(&peer).await;
+                                }
+
                                 return Ok(DiscoveryEvent::NodeOffline(peer));
                             }
                         }
@@ -377,9 +697,52 @@ impl ManagedDiscovery {
                 }
                 libp2p::swarm::SwarmEvent::NewListenAddr { address, .. } => {
                     tracing::info!("开始监听: {}", address);
+                    // 中继节点只能在预留应答中给出已确认的外部地址，局域网和 VPN 中直接使用监听地址；
+                    // 会合点注册的是外部地址，启用后监听地址（包括中继地址）都作为外部地址，变化时自动重新注册
+                    if (self.relay_server && !transport::is_relayed(&address)) || self.rendezvous.is_some() {
+                        self.swarm.add_external_address(address.clone());
+                    }
+                }
+                libp2p::swarm::SwarmEvent::ExpiredListenAddr { address, .. }
+                    if self.relay_server || self.rendezvous.is_some() =>
+                {
+                    self.swarm.remove_external_address(&address);
                 }
-                libp2p::swarm::SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                    tracing::info!("✓ 与 {} 建立新连接", peer_id);
+                libp2p::swarm::SwarmEvent::ListenerClosed { listener_id, .. } => {
+                    self.relay_reservations.retain(|_, id| *id != listener_id);
+                }
+                libp2p::swarm::SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, .. } => {
+                    if let Some(addr) = self.static_connections.remove(&connection_id) {
+                        tracing::info!("已连接静态节点 {} ({})", addr, peer_id);
+                        if let Some(dial) = self.static_dials.get_mut(&addr) {
+                            dial.connected(peer_id);
+                        }
+                    }
+                    if self.is_banned(&peer_id) || self.is_blocked(&peer_id).await {
+                        tracing::info!("拒绝被封禁节点 {} 的连接", peer_id);
+                        self.swarm.close_connection(connection_id);
+                        continue;
+                    }
+
+                    if self.is_rendezvous_server(&peer_id) {
+                        tracing::info!("已连接会合服务器 {}", peer_id);
+                        self.process_rendezvous();
+                        continue;
+                    }
+
+                    if endpoint.is_dialer() && !endpoint.is_relayed() {
+                        self.dialed_addresses.insert(peer_id, endpoint.get_remote_address().clone());
+                    }
+                    let transport = TransportKind::from_multiaddr(endpoint.get_remote_address());
+                    tracing::info!(
+                        "✓ 与 {} 建立新连接 ({})",
+                        peer_id,
+                        transport.map(|t| t.as_str()).unwrap_or("未知传输")
+                    );
+                    if let Some(transport) = transport {
+                        self.connection_transports.insert(connection_id, (peer_id, transport));
+                        self.node_manager.set_transport(&peer_id, self.peer_transport(&peer_id)).await;
+                    }
                     let conn_count = self.active_connections.entry(peer_id).or_insert(0);
                     let is_first_connection = *conn_count == 0;
                     *conn_count += 1;
@@ -387,16 +750,40 @@ impl ManagedDiscovery {
                     if is_first_connection {
                         tracing::info!("与 {} 建立首个连接，请求用户信息", peer_id);
                         // 仅在首个连接建立时请求用户信息
-                        let _ = self.swarm.behaviour_mut().request_response.send_request(
+                        let request_id = self.swarm.behaviour_mut().request_response.send_request(
                             &peer_id,
-                            user_info::UserInfoRequest,
+                            user_info::UserInfoRequest::Fetch,
                         );
+                        self.user_info_requests.insert(request_id, peer_id);
                     } else {
                         tracing::info!("与 {} 建立额外连接 (当前连接数: {})", peer_id, *conn_count);
                     }
                 }
-                libp2p::swarm::SwarmEvent::ConnectionClosed { peer_id, .. } => {
+                libp2p::swarm::SwarmEvent::ConnectionClosed { peer_id, connection_id, num_established, .. } => {
                     tracing::debug!("与 {} 的连接关闭", peer_id);
+                    if num_established == 0 {
+                        for dial in self.static_dials.values_mut().filter(|dial| dial.peer_id == Some(peer_id)) {
+                            dial.disconnected();
+                        }
+                        self.dialed_addresses.remove(&peer_id);
+                        if let Some(listener_id) = self.relay_reservations.remove(&peer_id) {
+                            self.swarm.remove_listener(listener_id);
+                        }
+                        self.disallow_relay(&peer_id);
+                    }
+                    if self.connection_transports.remove(&connection_id).is_some() {
+                        self.node_manager.set_transport(&peer_id, self.peer_transport(&peer_id)).await;
+                    }
+                    // 会合服务器不是节点，不参与在线状态跟踪
+                    if self.is_rendezvous_server(&peer_id) {
+                        continue;
+                    }
+                    // 被拒绝的封禁节点连接没有计数，不再重复报告离线
+                    if (self.is_banned(&peer_id) || self.is_blocked(&peer_id).await)
+                        && self.active_connections.get(&peer_id).copied().unwrap_or(0) == 0
+                    {
+                        continue;
+                    }
                     let conn_count = self.active_connections.entry(peer_id).or_insert(0);
                     if *conn_count > 0 {
                         *conn_count -= 1;
@@ -411,6 +798,10 @@ impl ManagedDiscovery {
                             tracing::info!("已从管理器中移除离线节点 {}", peer_id);
                         }
 
+                        if let Some(ref manager) = self.file_transfer_manager {
+                            manager.handle_peer_disconnected(&peer_id).await;
+                        }
+
                         return Ok(DiscoveryEvent::NodeOffline(peer_id));
                     }
                 }
@@ -420,44 +811,79 @@ impl ManagedDiscovery {
                             request_response::Message::Request {
                                 request_id: _,
                                 channel,
-                                request: _,
+                                request,
                             } => {
                                 tracing::debug!("收到来自 {} 的用户信息请求", peer);
 
-                                // 响应用户信息请求
-                                let response = user_info::UserInfoResponse {
-                                    device_name: self.local_user_info.device_name.clone(),
-                                    nickname: self.local_user_info.nickname.clone(),
-                                    avatar_url: self.local_user_info.avatar_url.clone(),
-                                    status: self.local_user_info.status.clone(),
-                                    custom_data: self.local_user_info.custom_data.clone(),
-                                };
+                                // 响应签名的用户信息（旧版节点由 Codec 降级为未签名）
+                                let response = user_info::UserInfoResponse::Signed(self.local_profile.clone());
 
                                 let _ = self.swarm.behaviour_mut().request_response.send_response(
                                     channel,
                                     response,
                                 );
+
+                                // 对方推送了更新后的用户信息
+                                if let user_info::UserInfoRequest::Update(signed) = request {
+                                    match self.accept_profile(peer, signed) {
+                                        Ok(info) => {
+                                            if let Some(event) = self.store_peer_user_info(peer, info) {
+                                                return Ok(event);
+                                            }
+                                        }
+                                        Err(e) => tracing::warn!("拒绝 {} 推送的用户信息: {}", peer, e),
+                                    }
+                                }
                             }
                             request_response::Message::Response {
-                                request_id: _,
+                                request_id,
                                 response,
                             } => {
-                                // 检查是否已经收到过该节点的用户信息
-                                let is_new_info = !self.peer_user_info.contains_key(&peer);
-
-                                // 存储或更新用户信息
-                                self.peer_user_info.insert(peer, response.clone());
+                                self.user_info_requests.remove(&request_id);
+                                self.user_info_pushes.remove(&request_id);
+
+                                let response = match response {
+                                    user_info::UserInfoResponse::Signed(signed) => {
+                                        match self.accept_profile(peer, signed) {
+                                            Ok(info) => info,
+                                            Err(e) => {
+                                                tracing::warn!("拒绝 {} 的用户信息: {}", peer, e);
+                                                return Ok(DiscoveryEvent::UserInfoRequestFailed(peer, e.to_string()));
+                                            }
+                                        }
+                                    }
+                                    // 已有签名记录的节点不接受未签名的用户信息（防止降级）
+                                    user_info::UserInfoResponse::Unsigned(_) if self.peer_profiles.contains_key(&peer) => {
+                                        tracing::warn!("忽略 {} 的未签名用户信息", peer);
+                                        continue;
+                                    }
+                                    user_info::UserInfoResponse::Unsigned(info) => info,
+                                };
 
-                                if is_new_info {
-                                    // 首次收到用户信息，记录日志并返回事件
-                                    tracing::info!("📝 收到来自 {} 的用户信息: {}", peer, response.display_name());
-                                    return Ok(DiscoveryEvent::UserInfoReceived(peer, response));
-                                } else {
-                                    // 已收到过，只更新不返回事件（静默更新）
-                                    tracing::debug!("更新来自 {} 的用户信息: {}", peer, response.display_name());
+                                if let Some(event) = self.store_peer_user_info(peer, response) {
+                                    return Ok(event);
                                 }
                             }
                         },
+                        request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                            if let request_response::OutboundFailure::Io(ref e) = error {
+                                self.record_violation(peer, e).await;
+                            }
+                            if self.user_info_pushes.remove(&request_id) {
+                                // 旧版节点不支持推送，等待其重新连接时获取
+                                tracing::debug!("向 {} 推送用户信息失败: {}", peer, error);
+                            }
+                            if self.user_info_requests.remove(&request_id).is_some() {
+                                tracing::warn!("向 {} 请求用户信息失败: {}", peer, error);
+                                return Ok(DiscoveryEvent::UserInfoRequestFailed(peer, error.to_string()));
+                            }
+                        }
+                        request_response::Event::InboundFailure { peer, error, .. } => {
+                            tracing::warn!("响应 {} 的用户信息请求失败: {}", peer, error);
+                            if let request_response::InboundFailure::Io(ref e) = error {
+                                self.record_violation(peer, e).await;
+                            }
+                        }
                         _ => {
                             // 忽略其他事件类型
                             tracing::debug!("其他 request_response 事件");
@@ -475,6 +901,16 @@ impl ManagedDiscovery {
                             } => {
                                 tracing::info!("📨 收到来自 {} 的聊天消息: {:?}", peer, request);
 
+                                // 信任策略不允许的节点，拒绝接收
+                                if !self.is_peer_allowed(&peer, self.trust_config.require_trusted_chat).await {
+                                    tracing::warn!("拒绝来自未受信任节点 {} 的聊天消息", peer);
+                                    let _ = self.swarm.behaviour_mut().chat.send_response(
+                                        channel,
+                                        crate::chat::ChatResponse::rejected(),
+                                    );
+                                    continue;
+                                }
+
                                 // 处理收到的聊天消息
                                 if let Some(ref chat_manager) = self.chat_manager {
                                     chat_manager.handle_received_message(peer, request.clone()).await;
@@ -491,19 +927,95 @@ impl ManagedDiscovery {
                                 );
                             }
                             request_response::Message::Response {
-                                request_id: _,
-                                response: _,
+                                request_id,
+                                response,
                             } => {
                                 tracing::info!("✓ 收到来自 {} 的聊天消息确认", peer);
-                                // 可以在这里更新消息发送状态
+                                // 对方已收到消息，从发件箱移除并更新为已送达
+                                if let Some((target, message_id)) = self.chat_requests.remove(&request_id) {
+                                    if let Some(chat_manager) = self.chat_manager.clone() {
+                                        if response.received {
+                                            chat_manager.handle_delivered(&target, &message_id).await;
+                                            // 聊天室控制消息送达后，发送暂缓的同一聊天室消息
+                                            self.flush_outbox(&chat_manager, target).await;
+                                        } else {
+                                            // 拒绝是最终结果，重试也不会成功
+                                            chat_manager
+                                                .handle_rejected(&target, &message_id, "对方拒绝接收".to_string())
+                                                .await;
+                                        }
+                                    }
+                                }
                             }
                         },
+                        request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                            tracing::warn!("向 {} 发送聊天消息失败: {}", peer, error);
+                            if let request_response::OutboundFailure::Io(ref e) = error {
+                                self.record_violation(peer, e).await;
+                            }
+                            // 对方不支持的消息直接失败，其他错误留在发件箱中退避后重试
+                            if let Some((target, message_id)) = self.chat_requests.remove(&request_id) {
+                                if let Some(ref chat_manager) = self.chat_manager {
+                                    chat_manager.handle_outbound_failure(&target, &message_id, &error).await;
+                                }
+                            }
+                        }
+                        request_response::Event::InboundFailure { peer, error, .. } => {
+                            tracing::warn!("接收来自 {} 的聊天消息失败: {}", peer, error);
+                            if let request_response::InboundFailure::Io(ref e) = error {
+                                self.record_violation(peer, e).await;
+                            }
+                        }
                         _ => {
                             // 忽略其他事件类型
                             tracing::debug!("其他聊天事件");
                         }
                     }
                 }
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::FileTransfer(event)) => {
+                    self.handle_file_transfer_event(event).await;
+                }
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Gossipsub(event)) => {
+                    self.handle_gossipsub_event(event).await;
+                }
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Pairing(event)) => {
+                    self.handle_pairing_event(event).await;
+                }
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Avatar(event)) => {
+                    self.handle_avatar_event(event).await;
+                }
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Rendezvous(event)) => {
+                    self.handle_rendezvous_event(event).await;
+                }
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::RelayClient(event)) => match event {
+                    relay::client::Event::ReservationReqAccepted { relay_peer_id, renewal: false, .. } => {
+                        tracing::info!("已在中继节点 {} 上预留地址", relay_peer_id);
+                    }
+                    relay::client::Event::OutboundCircuitEstablished { relay_peer_id, .. } => {
+                        tracing::info!("已通过中继节点 {} 建立线路", relay_peer_id);
+                    }
+                    relay::client::Event::InboundCircuitEstablished { src_peer_id, .. } => {
+                        tracing::info!("节点 {} 通过中继连接本节点", src_peer_id);
+                    }
+                    _ => {}
+                },
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Relay(event)) => {
+                    tracing::debug!("中继服务事件: {:?}", event);
+                }
+                libp2p::swarm::SwarmEvent::Behaviour(ManagedBehaviourEvent::Dcutr(dcutr::Event { remote_peer_id, result })) => {
+                    match result {
+                        Ok(_) => tracing::info!("✓ 与 {} 打洞成功，已升级为直连", remote_peer_id),
+                        Err(e) => tracing::info!("与 {} 打洞失败，继续使用中继: {}", remote_peer_id, e),
+                    }
+                }
+                libp2p::swarm::SwarmEvent::OutgoingConnectionError { connection_id, error, .. } => {
+                    if let Some(addr) = self.static_connections.remove(&connection_id) {
+                        if let Some(dial) = self.static_dials.get_mut(&addr) {
+                            let delay = dial.failed();
+                            tracing::warn!("连接静态节点 {} 失败: {}，{:?} 后重试", addr, error, delay);
+                        }
+                    }
+                }
                 _ => {}
             }
         }
@@ -529,6 +1041,129 @@ impl ManagedDiscovery {
         &self.agent_version
     }
 
+    /// 获取私有网络密钥
+    pub fn network_key(&self) -> Option<&NetworkKey> {
+        self.network_key.as_ref()
+    }
+
+    /// 是否启用了 QUIC 传输（设置网络密钥时始终为 false）
+    pub fn is_quic_enabled(&self) -> bool {
+        self.quic
+    }
+
+    /// 连接 mDNS 发现的节点：依次尝试地址，QUIC 在前，失败后回退到 TCP
+    ///
+    /// 已连接或正在拨号的节点不会重复拨号。
+    fn dial_discovered(&mut self, peer_id: PeerId, addresses: Vec<Multiaddr>) {
+        let addresses = transport::dial_order(addresses, self.quic);
+        if addresses.is_empty() {
+            return;
+        }
+        let opts = DialOpts::peer_id(peer_id)
+            .addresses(addresses)
+            .override_dial_concurrency_factor(std::num::NonZeroU8::MIN)
+            .build();
+        // 尝试主动连接该节点以触发 identify 验证
+        if let Err(e) = self.swarm.dial(opts) {
+            tracing::debug!("无法主动连接节点 {}: {}", peer_id, e);
+        }
+    }
+
+    /// 节点当前使用的传输协议（存在 QUIC 连接时为 QUIC）
+    fn peer_transport(&self, peer_id: &PeerId) -> Option<TransportKind> {
+        self.connection_transports
+            .values()
+            .filter(|(peer, _)| peer == peer_id)
+            .map(|(_, transport)| *transport)
+            .min_by_key(|transport| transport.rank())
+    }
+
+    /// 核对对方 identify 协议版本中的网络密钥指纹，返回去掉指纹后的协议版本
+    fn verify_network<'a>(&self, protocol_version: &'a str) -> std::result::Result<&'a str, MdnsError> {
+        let Some(ref key) = self.network_key else {
+            return Ok(protocol_version);
+        };
+        key.strip_namespace(protocol_version).ok_or_else(|| {
+            MdnsError::SwarmBuild(format!("网络密钥不匹配: 收到 {}", protocol_version))
+        })
+    }
+
+    /// 获取协议限制
+    pub fn protocol_limits(&self) -> &ProtocolLimits {
+        &self.protocol_limits
+    }
+
+    /// 节点是否处于封禁期
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.banned_peers
+            .get(peer_id)
+            .is_some_and(|until| Instant::now() < *until)
+    }
+
+    /// 处于封禁期的节点
+    pub fn banned_peers(&self) -> Vec<PeerId> {
+        let now = Instant::now();
+        self.banned_peers
+            .iter()
+            .filter(|(_, until)| now < **until)
+            .map(|(peer_id, _)| *peer_id)
+            .collect()
+    }
+
+    /// 封禁节点：断开所有连接，封禁期内拒绝其连接
+    ///
+    /// 产生 [`DiscoveryEvent::PeerBanned`] 事件。
+    pub async fn ban_peer(&mut self, peer_id: PeerId, reason: String) {
+        tracing::warn!("⛔ 封禁节点 {}: {}", peer_id, reason);
+        self.violations.remove(&peer_id);
+        self.banned_peers
+            .insert(peer_id, Instant::now() + self.protocol_limits.ban_duration);
+        if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+            gossipsub.blacklist_peer(&peer_id);
+        }
+        self.disallow_relay(&peer_id);
+        let _ = self.swarm.disconnect_peer_id(peer_id);
+
+        if self.node_manager.remove_node(&peer_id).await.is_some() {
+            tracing::info!("已从管理器中移除被封禁节点 {}", peer_id);
+        }
+
+        self.pending_events.push_back(DiscoveryEvent::PeerBanned(peer_id, reason));
+    }
+
+    /// 解除封禁，返回节点之前是否被封禁
+    pub fn unban_peer(&mut self, peer_id: &PeerId) -> bool {
+        self.violations.remove(peer_id);
+        if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+            gossipsub.remove_blacklisted_peer(peer_id);
+        }
+        self.banned_peers.remove(peer_id).is_some()
+    }
+
+    /// 记录一次协议违规，达到上限后封禁节点
+    ///
+    /// 只统计对方造成的错误（帧过大、格式错误、读取超时），连接中断等不计入。
+    async fn record_violation(&mut self, peer_id: PeerId, error: &std::io::Error) {
+        if !is_protocol_violation(error) || self.is_banned(&peer_id) {
+            return;
+        }
+
+        let count = self.violations.entry(peer_id).or_insert(0);
+        *count += 1;
+        let count = *count;
+        tracing::warn!(
+            "节点 {} 违反协议 ({}/{}): {}",
+            peer_id,
+            count,
+            self.protocol_limits.max_violations,
+            error
+        );
+
+        if count >= self.protocol_limits.max_violations {
+            self.ban_peer(peer_id, error.to_string()).await;
+        }
+    }
+
     /// 获取节点的健康信息
     pub fn get_health(&self, peer_id: &PeerId) -> Option<&NodeHealth> {
         self.health_status.get(peer_id)
@@ -544,14 +1179,22 @@ impl ManagedDiscovery {
         &self.health_config
     }
 
-    /// 获取节点的用户信息
+    /// 获取节点的用户信息（本次运行未收到时使用缓存的签名记录）
     pub fn get_user_info(&self, peer_id: &PeerId) -> Option<&user_info::UserInfo> {
-        self.peer_user_info.get(peer_id)
+        self.peer_user_info
+            .get(peer_id)
+            .or_else(|| self.peer_profiles.get(peer_id).map(|(verified, _)| &verified.info))
     }
 
-    /// 获取所有用户信息
+    /// 获取所有用户信息（包括缓存的离线节点）
     pub fn list_user_info(&self) -> HashMap<PeerId, user_info::UserInfo> {
-        self.peer_user_info.clone()
+        let mut list: HashMap<PeerId, user_info::UserInfo> = self
+            .peer_profiles
+            .iter()
+            .map(|(peer_id, (verified, _))| (*peer_id, verified.info.clone()))
+            .collect();
+        list.extend(self.peer_user_info.clone());
+        list
     }
 
     /// 获取本地用户信息
@@ -559,133 +1202,1538 @@ impl ManagedDiscovery {
         &self.local_user_info
     }
 
-    /// 获取聊天事件接收器
+    /// 获取签名后的本地用户信息
+    pub fn local_profile(&self) -> &user_info::SignedUserInfo {
+        &self.local_profile
+    }
+
+    /// 修改本地用户信息（如昵称、状态）
     ///
-    /// 这是一个 consuming 操作，调用后 `chat_event_rx` 将被移除。
-    pub fn take_chat_events(&mut self) -> Option<mpsc::UnboundedReceiver<super::chat::ChatEvent>> {
-        self.chat_event_rx.take()
+    /// 递增序列号并重新签名，然后推送给所有已连接的已验证节点；
+    /// 只支持用户信息协议 1.0.0 的旧版节点会在重新连接时获取新的用户信息。
+    pub async fn set_local_user_info(
+        &mut self,
+        info: user_info::UserInfo,
+    ) -> std::result::Result<(), user_info::UserInfoError> {
+        let seq = user_info::next_seq(self.local_profile_seq);
+        let signed = user_info::SignedUserInfo::sign(&self.local_key, &info, seq)?;
+        if let Some(ref chat_manager) = self.chat_manager {
+            chat_manager.set_do_not_disturb(info.presence().is_do_not_disturb());
+        }
+        self.local_user_info = info;
+        self.local_profile = signed.clone();
+        self.local_profile_seq = seq;
+
+        let mut pushed = 0;
+        for node in self.node_manager.list_nodes().await {
+            if !self.swarm.is_connected(&node.peer_id) {
+                continue;
+            }
+            let request_id = self
+                .swarm
+                .behaviour_mut()
+                .request_response
+                .send_request(&node.peer_id, user_info::UserInfoRequest::Update(signed.clone()));
+            self.user_info_pushes.insert(request_id);
+            pushed += 1;
+        }
+
+        tracing::info!("本地用户信息已更新（序列号 {}），已推送给 {} 个节点", seq, pushed);
+        Ok(())
     }
-}
 
-/// 发现事件
-#[derive(Debug, Clone)]
-pub enum DiscoveryEvent {
-    /// 通过 mDNS 发现节点
-    Discovered(PeerId, Multiaddr),
+    /// 设置静态节点列表，列表中的节点会立即拨号
+    pub fn set_static_peer_store(&mut self, store: StaticPeerStore) {
+        self.static_dials = store
+            .addresses()
+            .iter()
+            .map(|addr| (addr.clone(), StaticDial::new(addr)))
+            .collect();
+        self.static_connections.clear();
+        self.static_peers = store;
+    }
 
-    /// 节点 mDNS 记录过期
-    Expired(PeerId),
+    /// 所有静态节点地址
+    pub fn static_peers(&self) -> &[Multiaddr] {
+        self.static_peers.addresses()
+    }
 
-    /// 节点验证通过
-    Verified(PeerId),
+    /// 手动添加节点地址（用于组播被过滤、mDNS 无法发现节点的网络）
+    ///
+    /// 地址会保存到静态节点列表并立即拨号，断开后自动重拨。已存在时返回 false。
+    pub async fn add_static_peer(&mut self, addr: Multiaddr) -> std::result::Result<bool, StaticPeerError> {
+        static_peers::validate(&addr, &self.local_peer_id())?;
+        if !self.static_peers.insert(addr.clone())? {
+            return Ok(false);
+        }
+        tracing::info!("添加静态节点: {}", addr);
+        self.static_dials.insert(addr.clone(), StaticDial::new(&addr));
+        self.dial_static_peer(addr).await;
+        Ok(true)
+    }
 
-    /// 节点验证失败
-    VerificationFailed(PeerId, String),
+    /// 移除静态节点（已建立的连接不会断开），不存在时返回 false
+    pub fn remove_static_peer(&mut self, addr: &Multiaddr) -> std::result::Result<bool, StaticPeerError> {
+        if !self.static_peers.remove(addr)? {
+            return Ok(false);
+        }
+        tracing::info!("移除静态节点: {}", addr);
+        self.static_dials.remove(addr);
+        self.static_connections.retain(|_, a| a != addr);
+        Ok(true)
+    }
 
-    /// 节点恢复健康
-    NodeRecovered(PeerId, Duration),
+    /// 拨号到期的静态节点
+    async fn process_static_peers(&mut self) {
+        let now = Instant::now();
+        let due: Vec<Multiaddr> = self
+            .static_dials
+            .iter()
+            .filter(|(_, dial)| dial.is_due(now))
+            .map(|(addr, _)| addr.clone())
+            .collect();
+        for addr in due {
+            self.dial_static_peer(addr).await;
+        }
+    }
 
-    /// 节点离线
-    NodeOffline(PeerId),
+    /// 拨号静态节点，已连接的节点直接标记为已连接
+    async fn dial_static_peer(&mut self, addr: Multiaddr) {
+        let Some(dial) = self.static_dials.get(&addr) else {
+            return;
+        };
+        let opts = match dial.peer_id {
+            Some(peer_id) => {
+                if self.is_banned(&peer_id) || self.is_blocked(&peer_id).await {
+                    return;
+                }
+                if self.swarm.is_connected(&peer_id) {
+                    if let Some(dial) = self.static_dials.get_mut(&addr) {
+                        dial.connected(peer_id);
+                    }
+                    return;
+                }
+                DialOpts::peer_id(peer_id).addresses(vec![addr.clone()]).build()
+            }
+            None => DialOpts::unknown_peer_id().address(addr.clone()).build(),
+        };
+
+        let connection_id = opts.connection_id();
+        let Some(dial) = self.static_dials.get_mut(&addr) else {
+            return;
+        };
+        match self.swarm.dial(opts) {
+            Ok(()) => {
+                tracing::debug!("拨号静态节点: {}", addr);
+                dial.dialing();
+                self.static_connections.insert(connection_id, addr);
+            }
+            Err(e) => {
+                let delay = dial.failed();
+                tracing::warn!("无法拨号静态节点 {}: {}，{:?} 后重试", addr, e, delay);
+            }
+        }
+    }
 
-    /// 收到用户信息
-    UserInfoReceived(PeerId, user_info::UserInfo),
-}
+    /// 当前的监听地址（包括在中继节点上预留的 `/p2p-circuit` 地址）
+    pub fn listen_addresses(&self) -> Vec<Multiaddr> {
+        self.swarm.listeners().cloned().collect()
+    }
 
-/// 为 ManagedDiscovery 实现 ChatExtension trait
-///
-/// 提供可选的聊天功能扩展。
-#[async_trait::async_trait]
-impl ChatExtension for ManagedDiscovery {
-    /// 启用聊天功能
-    async fn enable_chat(&mut self) -> Result<(), ChatError> {
-        // 检查是否已经启用
-        if self.chat_manager.is_some() {
-            return Err(ChatError::SendFailed("聊天功能已经启用".to_string()));
+    /// 是否作为中继节点
+    pub fn is_relay_server(&self) -> bool {
+        self.relay_server
+    }
+
+    /// 允许已验证的节点使用本节点的中继服务
+    ///
+    /// 对方可能在本节点验证它之前就请求预留（两边同时交换 identify），请求会被拒绝；
+    /// 首次允许时推送 identify 信息，对方收到后重新预留。
+    fn allow_relay(&mut self, peer_id: PeerId) {
+        if !self.relay_server {
+            return;
+        }
+        let added = self
+            .relay_peers
+            .write()
+            .is_ok_and(|mut peers| peers.insert(peer_id));
+        if added {
+            self.swarm.behaviour_mut().identify.push([peer_id]);
         }
+    }
 
-        // 创建 ChatManager
-        let (chat_manager, event_rx) = ChatManager::new(
-            self.node_manager.clone(),
-            self.local_peer_id(),
-        );
+    /// 不再为该节点提供中继服务（断开、封禁或屏蔽）
+    fn disallow_relay(&mut self, peer_id: &PeerId) {
+        if let Ok(mut peers) = self.relay_peers.write() {
+            peers.remove(peer_id);
+        }
+    }
 
-        // 保存管理器和事件接收器
-        self.chat_manager = Some(Arc::new(chat_manager));
-        self.chat_event_rx = Some(event_rx);
+    /// 在提供中继服务的节点上预留中继地址
+    ///
+    /// 只使用主动拨号建立的直连地址，这样通告出去的中继地址对其他节点同样可达。
+    fn reserve_relay(&mut self, relay_peer_id: PeerId) {
+        if self.relay_reservations.contains_key(&relay_peer_id) {
+            return;
+        }
+        let Some(addr) = self.dialed_addresses.get(&relay_peer_id).cloned() else {
+            return;
+        };
+        let Ok(relay_addr) = addr.with_p2p(relay_peer_id) else {
+            return;
+        };
+        let circuit = relay_addr.with(Protocol::P2pCircuit);
+        match self.swarm.listen_on(circuit.clone()) {
+            Ok(listener_id) => {
+                tracing::info!("请求在中继节点上预留地址: {}", circuit);
+                self.relay_reservations.insert(relay_peer_id, listener_id);
+            }
+            Err(e) => tracing::warn!("预留中继地址 {} 失败: {}", circuit, e),
+        }
+    }
 
-        tracing::info!("✓ 聊天功能已启用");
+    /// 启用会合点发现（用于 mDNS 无法跨越的子网）
+    ///
+    /// 节点会连接会合服务器，在团队命名空间下注册自己的地址并定期查询其他节点，
+    /// 查询到的节点与 mDNS 发现的节点一样拨号并经过 identify 验证。
+    pub fn enable_rendezvous(&mut self, config: RendezvousConfig) -> std::result::Result<(), RendezvousError> {
+        let client = RendezvousClient::new(config, &self.local_peer_id())?;
+        tracing::info!("启用会合点发现: {}（命名空间 {}）", client.config.server, client.config.namespace);
+        self.rendezvous = Some(client);
+        let listeners: Vec<Multiaddr> = self.swarm.listeners().cloned().collect();
+        for address in listeners {
+            self.swarm.add_external_address(address);
+        }
+        self.process_rendezvous();
         Ok(())
     }
 
-    /// 发送消息给指定节点
-    async fn send_message(&mut self, target: PeerId, message: ChatMessage) -> Result<(), ChatError> {
-        if let Some(ref chat_manager) = self.chat_manager {
-            // 1. 先通过 ChatManager 验证和设置消息元数据
-            chat_manager.send(target, message.clone()).await?;
+    /// 会合点配置（未启用时为 None）
+    pub fn rendezvous_config(&self) -> Option<&RendezvousConfig> {
+        self.rendezvous.as_ref().map(|client| &client.config)
+    }
 
-            // 2. 实际通过 Swarm 的 chat behaviour 发送消息
-            let _request_id = self.swarm.behaviour_mut().chat.send_request(&target, message);
+    fn is_rendezvous_server(&self, peer_id: &PeerId) -> bool {
+        self.rendezvous.as_ref().is_some_and(|client| client.server == *peer_id)
+    }
 
-            Ok(())
-        } else {
-            Err(ChatError::NotEnabled)
+    /// 注册到期时续期，查询到期时查询；未连接服务器时先拨号
+    fn process_rendezvous(&mut self) {
+        let now = Instant::now();
+        let Some(ref mut client) = self.rendezvous else {
+            return;
+        };
+        if !client.is_due(now) {
+            return;
         }
-    }
 
-    /// 广播消息给多个节点（一对多）
-    async fn broadcast_message(&mut self, targets: Vec<PeerId>, message: ChatMessage) -> Result<(), ChatError> {
-        if let Some(ref chat_manager) = self.chat_manager {
-            // 1. 先通过 ChatManager 验证和设置消息元数据
-            chat_manager.broadcast(targets.clone(), message.clone()).await?;
-
-            // 2. 为每个目标实际发送消息
-            for target in targets {
-                tracing::info!("尝试向 {} 发送聊天消息", target);
-
-                // 检查连接状态
-                let has_connection = self.swarm.is_connected(&target);
-                tracing::info!("与 {} 的连接状态: {}", target, if has_connection { "已连接" } else { "未连接" });
-
-                if !has_connection {
-                    tracing::warn!("没有与 {} 的活跃连接，尝试拨号...", target);
-                    // 尝试从已知的地址拨号
-                    if let Some(node) = self.node_manager.get_node(&target).await {
-                        for addr in &node.addresses {
-                            tracing::info!("正在向 {} 拨号: {}", target, addr);
-                            match self.swarm.dial(addr.clone()) {
-                                Ok(_) => tracing::info!("拨号请求已发送"),
-                                Err(e) => tracing::error!("拨号失败: {:?}", e),
-                            }
-                        }
-                        // 等待连接建立
-                        tokio::time::sleep(Duration::from_millis(200)).await;
-
-                        // 再次检查连接状态
-                        let still_not_connected = !self.swarm.is_connected(&target);
-                        if still_not_connected {
-                            tracing::error!("拨号后仍未与 {} 建立连接", target);
-                            continue;
-                        }
-                    } else {
-                        tracing::error!("找不到节点 {} 的地址信息", target);
-                        continue;
-                    }
+        let server = client.server;
+        if !self.swarm.is_connected(&server) {
+            if client.take_dial(now) {
+                let opts = DialOpts::peer_id(server).addresses(vec![client.config.server.clone()]).build();
+                if let Err(e) = self.swarm.dial(opts) {
+                    tracing::debug!("无法连接会合服务器 {}: {}", server, e);
                 }
-
-                // 发送请求
-                let request_id = self.swarm.behaviour_mut().chat.send_request(&target, message.clone());
-                tracing::info!("消息发送请求已接受，ID: {:?}", request_id);
             }
+            return;
+        }
 
-            Ok(())
-        } else {
-            Err(ChatError::NotEnabled)
+        let behaviour = &mut self.swarm.behaviour_mut().rendezvous;
+        if client.take_register(now) {
+            // 还没有监听地址时稍后重试
+            if let Err(e) = behaviour.register(client.namespace.clone(), server, Some(client.config.ttl.as_secs())) {
+                tracing::debug!("暂时无法注册到会合服务器: {}", e);
+                client.failed();
+            }
+        }
+        if client.take_discover(now) {
+            behaviour.discover(Some(client.namespace.clone()), None, None, server);
         }
     }
 
-    /// 获取聊天管理器
-    fn chat_manager(&self) -> Option<Arc<ChatManager>> {
-        self.chat_manager.clone()
+    /// 处理会合服务器的响应
+    async fn handle_rendezvous_event(&mut self, event: rendezvous::client::Event) {
+        match event {
+            rendezvous::client::Event::Registered { rendezvous_node, ttl, .. } => {
+                if !self.is_rendezvous_server(&rendezvous_node) {
+                    return;
+                }
+                tracing::info!("已在会合服务器注册（TTL {} 秒）", ttl);
+                if let Some(ref mut client) = self.rendezvous {
+                    client.registered(Duration::from_secs(ttl));
+                }
+            }
+            rendezvous::client::Event::Discovered { rendezvous_node, registrations, .. } => {
+                if !self.is_rendezvous_server(&rendezvous_node) {
+                    return;
+                }
+                if let Some(ref mut client) = self.rendezvous {
+                    client.discovered();
+                }
+                tracing::debug!("会合服务器返回 {} 个节点", registrations.len());
+                for registration in registrations {
+                    let peer_id = registration.record.peer_id();
+                    if peer_id == self.local_peer_id()
+                        || self.swarm.is_connected(&peer_id)
+                        || self.is_banned(&peer_id)
+                        || self.is_blocked(&peer_id).await
+                    {
+                        continue;
+                    }
+                    tracing::info!("通过会合服务器发现节点: {}", peer_id);
+                    let addresses = registration.record.addresses().to_vec();
+                    for addr in &addresses {
+                        self.pending_events.push_back(DiscoveryEvent::Discovered(peer_id, addr.clone()));
+                    }
+                    self.dial_discovered(peer_id, addresses);
+                }
+            }
+            rendezvous::client::Event::RegisterFailed { error, .. } => {
+                tracing::warn!("会合服务器拒绝注册: {:?}", error);
+                if let Some(ref mut client) = self.rendezvous {
+                    client.failed();
+                }
+            }
+            rendezvous::client::Event::DiscoverFailed { error, .. } => {
+                tracing::debug!("会合查询失败: {:?}", error);
+                if let Some(ref mut client) = self.rendezvous {
+                    client.failed();
+                }
+            }
+            rendezvous::client::Event::Expired { peer } => {
+                tracing::debug!("会合服务器上 {} 的注册已过期", peer);
+            }
+        }
+    }
+
+    /// 设置头像缓存（如指定缓存目录，重启后无需重新获取）
+    pub fn set_avatar_cache(&mut self, cache: AvatarCache) {
+        self.avatar_cache = cache;
+    }
+
+    /// 获取头像缓存
+    pub fn avatar_cache(&self) -> &AvatarCache {
+        &self.avatar_cache
+    }
+
+    /// 设置本地头像（None 表示清除），返回头像哈希
+    ///
+    /// 头像写入缓存后更新本地用户信息中的 `avatar_hash`，并推送给已连接的节点。
+    pub async fn set_local_avatar(&mut self, data: Option<Vec<u8>>) -> std::result::Result<Option<String>, AvatarError> {
+        let hash = match data {
+            Some(data) => Some(self.avatar_cache.insert(data)?),
+            None => None,
+        };
+
+        let mut info = self.local_user_info.clone();
+        info.avatar_hash = hash.clone();
+        self.set_local_user_info(info).await?;
+        Ok(hash)
+    }
+
+    /// 获取节点的头像数据（尚未获取或没有头像时返回 None）
+    pub fn avatar(&self, peer_id: &PeerId) -> Option<Vec<u8>> {
+        let hash = self.get_user_info(peer_id)?.avatar_hash.as_ref()?;
+        self.avatar_cache.get(hash)
+    }
+
+    /// 获取节点头像的缓存文件路径（只有目录缓存才有）
+    pub fn avatar_path(&self, peer_id: &PeerId) -> Option<PathBuf> {
+        let hash = self.get_user_info(peer_id)?.avatar_hash.as_ref()?;
+        self.avatar_cache.path(hash)
+    }
+
+    /// 缓存未命中时向节点请求头像
+    fn fetch_avatar(&mut self, peer_id: PeerId, info: &user_info::UserInfo) {
+        let Some(hash) = info.avatar_hash.as_ref() else {
+            return;
+        };
+        if !crate::avatar::is_valid_hash(hash)
+            || self.avatar_cache.contains(hash)
+            || self.avatar_requests.values().any(|(_, pending)| pending == hash)
+        {
+            return;
+        }
+
+        tracing::debug!("向 {} 请求头像 {}", peer_id, hash);
+        let request_id = self
+            .swarm
+            .behaviour_mut()
+            .avatar
+            .send_request(&peer_id, AvatarRequest { hash: hash.clone() });
+        self.avatar_requests.insert(request_id, (peer_id, hash.clone()));
+    }
+
+    async fn handle_avatar_event(&mut self, event: request_response::Event<AvatarRequest, AvatarResponse>) {
+        match event {
+            request_response::Event::Message { peer, connection_id: _, message } => match message {
+                request_response::Message::Request { request_id: _, request, channel } => {
+                    // 只提供本地头像，不转发缓存的其他节点头像
+                    let response = match self.local_user_info.avatar_hash {
+                        Some(ref hash) if *hash == request.hash => self
+                            .avatar_cache
+                            .get(hash)
+                            .map(AvatarResponse::Found)
+                            .unwrap_or(AvatarResponse::NotFound),
+                        _ => AvatarResponse::NotFound,
+                    };
+                    let _ = self.swarm.behaviour_mut().avatar.send_response(channel, response);
+                }
+                request_response::Message::Response { request_id, response } => {
+                    let Some((_, hash)) = self.avatar_requests.remove(&request_id) else {
+                        return;
+                    };
+                    match response {
+                        AvatarResponse::Found(data) => match self.avatar_cache.insert_verified(&hash, data) {
+                            Ok(()) => {
+                                tracing::info!("🖼 已缓存 {} 的头像 {}", peer, hash);
+                                self.pending_events.push_back(DiscoveryEvent::AvatarReceived(peer, hash));
+                            }
+                            Err(AvatarError::Io(e)) => tracing::warn!("保存 {} 的头像失败: {}", peer, e),
+                            Err(e) => {
+                                // 格式错误或与哈希不一致，计为协议违规
+                                let error = std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string());
+                                self.record_violation(peer, &error).await;
+                            }
+                        },
+                        AvatarResponse::NotFound => tracing::debug!("{} 没有头像 {}", peer, hash),
+                    }
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                tracing::debug!("向 {} 请求头像失败: {}", peer, error);
+                self.avatar_requests.remove(&request_id);
+                if let request_response::OutboundFailure::Io(ref e) = error {
+                    self.record_violation(peer, e).await;
+                }
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                tracing::debug!("响应 {} 的头像请求失败: {}", peer, error);
+                if let request_response::InboundFailure::Io(ref e) = error {
+                    self.record_violation(peer, e).await;
+                }
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    /// 设置本地在线状态和状态说明，并推送给已连接的节点
+    ///
+    /// 同时把最后活跃时间更新为当前时间。请勿打扰状态下不再产生 `PeerTyping` 事件。
+    pub async fn set_presence(
+        &mut self,
+        presence: user_info::Presence,
+        message: Option<String>,
+    ) -> std::result::Result<(), user_info::UserInfoError> {
+        let mut info = self.local_user_info.clone();
+        info.presence = Some(presence);
+        info.status = message;
+        info.last_active = Some(chrono::Utc::now().timestamp_millis());
+        self.set_local_user_info(info).await
+    }
+
+    /// 本地是否处于请勿打扰状态（界面据此决定是否发出消息通知）
+    pub fn is_do_not_disturb(&self) -> bool {
+        self.local_user_info.presence().is_do_not_disturb()
+    }
+
+    /// 保存节点的用户信息，返回需要上报的事件（首次收到或内容变化）
+    fn store_peer_user_info(&mut self, peer_id: PeerId, info: user_info::UserInfo) -> Option<DiscoveryEvent> {
+        self.fetch_avatar(peer_id, &info);
+        match self.peer_user_info.insert(peer_id, info.clone()) {
+            None => {
+                tracing::info!("📝 收到来自 {} 的用户信息: {}", peer_id, info.display_name());
+                Some(DiscoveryEvent::UserInfoReceived(peer_id, info))
+            }
+            Some(previous) if previous != info => {
+                tracing::info!("📝 {} 的用户信息已更新: {}", peer_id, info.display_name());
+                Some(DiscoveryEvent::UserInfoUpdated(peer_id, info))
+            }
+            Some(_) => {
+                tracing::debug!("来自 {} 的用户信息没有变化", peer_id);
+                None
+            }
+        }
+    }
+
+    /// 节点的用户信息是否经过签名验证（旧版节点的用户信息未签名）
+    pub fn is_user_info_verified(&self, peer_id: &PeerId) -> bool {
+        self.peer_profiles.contains_key(peer_id)
+    }
+
+    /// 导出已验证的签名用户信息（用于持久化缓存）
+    pub fn signed_profiles(&self) -> Vec<user_info::SignedUserInfo> {
+        self.peer_profiles.values().map(|(_, signed)| signed.clone()).collect()
+    }
+
+    /// 导入缓存的签名用户信息，使离线节点的用户信息在重启后仍然可用
+    ///
+    /// 每条记录都会重新验证签名，比已知记录更旧的会被忽略。返回导入的条数。
+    pub fn import_profiles(&mut self, profiles: impl IntoIterator<Item = user_info::SignedUserInfo>) -> usize {
+        let mut imported = 0;
+        for signed in profiles {
+            let peer_id = match signed.verify() {
+                Ok(verified) if verified.peer_id != self.local_peer_id() => verified.peer_id,
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::warn!("跳过无效的用户信息缓存: {}", e);
+                    continue;
+                }
+            };
+            if self.accept_profile(peer_id, signed).is_ok() {
+                imported += 1;
+            }
+        }
+        imported
+    }
+
+    /// 验证节点的签名用户信息并保存，返回其中的用户信息
+    fn accept_profile(
+        &mut self,
+        peer_id: PeerId,
+        signed: user_info::SignedUserInfo,
+    ) -> std::result::Result<user_info::UserInfo, user_info::UserInfoError> {
+        let known_seq = self.peer_profiles.get(&peer_id).map(|(verified, _)| verified.seq);
+        let verified = signed.verify_from(&peer_id, known_seq)?;
+        let info = verified.info.clone();
+        self.peer_profiles.insert(peer_id, (verified, signed));
+        Ok(info)
+    }
+
+    /// 获取聊天事件接收器
+    ///
+    /// 这是一个 consuming 操作，调用后 `chat_event_rx` 将被移除。
+    pub fn take_chat_events(&mut self) -> Option<mpsc::UnboundedReceiver<super::chat::ChatEvent>> {
+        self.chat_event_rx.take()
+    }
+
+    /// 获取文件传输事件接收器
+    ///
+    /// 这是一个 consuming 操作，调用后 `file_transfer_event_rx` 将被移除。
+    pub fn take_file_transfer_events(&mut self) -> Option<mpsc::UnboundedReceiver<FileTransferEvent>> {
+        self.file_transfer_event_rx.take()
+    }
+
+    /// 获取频道事件接收器
+    ///
+    /// 这是一个 consuming 操作，调用后 `channel_event_rx` 将被移除。
+    pub fn take_channel_events(&mut self) -> Option<mpsc::UnboundedReceiver<ChannelEvent>> {
+        self.channel_event_rx.take()
+    }
+
+    /// 获取信任事件接收器
+    ///
+    /// 这是一个 consuming 操作，调用后 `trust_event_rx` 将被移除。
+    pub fn take_trust_events(&mut self) -> Option<mpsc::UnboundedReceiver<TrustEvent>> {
+        self.trust_event_rx.take()
+    }
+
+    /// 获取节点的信任状态（未启用信任管理时为 `Unknown`）
+    ///
+    /// 以 `&mut self` 接收，保证持有引用跨越 await 的 future 仍然是 `Send`。
+    async fn peer_trust_state(&mut self, peer_id: &PeerId) -> TrustState {
+        let Some(store) = self.trust_store.clone() else {
+            return TrustState::Unknown;
+        };
+        store.state(peer_id).await.unwrap_or_else(|e| {
+            tracing::error!("无法读取 {} 的信任状态: {}", peer_id, e);
+            TrustState::Unknown
+        })
+    }
+
+    /// 节点是否已被用户屏蔽（未启用信任管理时总是 false）
+    async fn is_blocked(&mut self, peer_id: &PeerId) -> bool {
+        self.peer_trust_state(peer_id).await == TrustState::Blocked
+    }
+
+    /// 信任策略是否允许与节点交换聊天或文件
+    ///
+    /// 未启用信任管理时总是允许；已屏蔽的节点总是拒绝；`require_trusted` 时只允许已信任的节点。
+    async fn is_peer_allowed(&mut self, peer_id: &PeerId, require_trusted: bool) -> bool {
+        if self.trust_store.is_none() {
+            return true;
+        }
+        match self.peer_trust_state(peer_id).await {
+            TrustState::Blocked => false,
+            TrustState::Trusted => true,
+            TrustState::Unknown | TrustState::Pending => !require_trusted,
+        }
+    }
+
+    /// 写入信任状态并产生 [`TrustEvent::TrustChanged`] 事件
+    async fn store_trust_state(&mut self, peer_id: PeerId, state: TrustState) -> Result<(), TrustError> {
+        let store = self.trust_store.clone().ok_or(TrustError::NotEnabled)?;
+        if store.state(&peer_id).await? == state {
+            return Ok(());
+        }
+        store.set(&peer_id, state).await?;
+        tracing::info!("节点 {} 的信任状态变为 {}", peer_id, state.as_str());
+        self.emit_trust_event(TrustEvent::TrustChanged { peer_id, state });
+        Ok(())
+    }
+
+    fn emit_trust_event(&self, event: TrustEvent) {
+        if let Some(ref tx) = self.trust_event_tx {
+            let _ = tx.send(event);
+        }
+    }
+
+    /// 配对失败：清理会话，未完成的配对恢复为未知状态
+    async fn fail_pairing(&mut self, peer_id: PeerId, reason: String) {
+        if self.pairing_sessions.remove(&peer_id).is_none() {
+            return;
+        }
+        tracing::warn!("与 {} 的配对失败: {}", peer_id, reason);
+        if self.peer_trust_state(&peer_id).await == TrustState::Pending {
+            if let Err(e) = self.store_trust_state(peer_id, TrustState::Unknown).await {
+                tracing::error!("无法更新信任状态: {}", e);
+            }
+        }
+        self.emit_trust_event(TrustEvent::PairingFailed { peer_id, reason });
+    }
+
+    /// 双方都确认后完成配对，开始投递等待中的消息
+    async fn complete_pairing_if_confirmed(&mut self, peer_id: PeerId) {
+        if !self.pairing_sessions.get(&peer_id).is_some_and(PairingSession::is_confirmed) {
+            return;
+        }
+        self.pairing_sessions.remove(&peer_id);
+        tracing::info!("🤝 与 {} 配对完成", peer_id);
+        if let Err(e) = self.store_trust_state(peer_id, TrustState::Trusted).await {
+            tracing::error!("无法保存信任状态: {}", e);
+            return;
+        }
+        self.resume_file_transfers(peer_id).await;
+        self.resume_outbox(peer_id).await;
+    }
+
+    fn send_pairing_request(&mut self, peer_id: PeerId, request: PairingRequest) {
+        let request_id = self.swarm.behaviour_mut().pairing.send_request(&peer_id, request);
+        self.pairing_requests.insert(request_id, peer_id);
+    }
+
+    /// 处理配对协议事件
+    async fn handle_pairing_event(&mut self, event: request_response::Event<PairingRequest, PairingResponse>) {
+        match event {
+            request_response::Event::Message { peer, connection_id: _, message } => match message {
+                request_response::Message::Request { request_id: _, request, channel } => {
+                    let response = self.handle_pairing_request(peer, request).await;
+                    let _ = self.swarm.behaviour_mut().pairing.send_response(channel, response);
+                }
+                request_response::Message::Response { request_id, response } => {
+                    if self.pairing_requests.remove(&request_id).is_none() {
+                        // 未登记的请求是轮换声明，不影响配对会话
+                        tracing::debug!("{} 对身份轮换声明的响应: {:?}", peer, response);
+                        return;
+                    }
+                    match response {
+                        PairingResponse::Nonce { nonce } => {
+                            let local = self.local_peer_id();
+                            let Some(session) = self.pairing_sessions.get_mut(&peer) else {
+                                return;
+                            };
+                            match session.on_nonce(&local, &peer, &nonce) {
+                                Ok(reveal) => {
+                                    let sas = session.sas.clone().unwrap_or_default();
+                                    self.send_pairing_request(peer, reveal);
+                                    self.emit_trust_event(TrustEvent::PairingSas { peer_id: peer, sas });
+                                }
+                                Err(e) => self.fail_pairing(peer, e.to_string()).await,
+                            }
+                        }
+                        PairingResponse::Ack => {}
+                        PairingResponse::Rejected { reason } => {
+                            self.fail_pairing(peer, format!("对方拒绝: {}", reason)).await;
+                        }
+                    }
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                if let request_response::OutboundFailure::Io(ref e) = error {
+                    self.record_violation(peer, e).await;
+                }
+                if self.pairing_requests.remove(&request_id).is_some() {
+                    self.fail_pairing(peer, error.to_string()).await;
+                }
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                tracing::debug!("处理 {} 的配对请求失败: {}", peer, error);
+                if let request_response::InboundFailure::Io(ref e) = error {
+                    self.record_violation(peer, e).await;
+                }
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    /// 处理收到的配对请求，返回响应
+    async fn handle_pairing_request(&mut self, peer: PeerId, request: PairingRequest) -> PairingResponse {
+        let rejected = |reason: &str| PairingResponse::Rejected { reason: reason.to_string() };
+        if self.trust_store.is_none() {
+            return rejected("信任管理未启用");
+        }
+
+        match request {
+            PairingRequest::Commit { commitment } => {
+                if self.is_blocked(&peer).await {
+                    return rejected("节点已被屏蔽");
+                }
+                if self.pairing_sessions.contains_key(&peer) {
+                    return rejected("配对进行中");
+                }
+
+                tracing::info!("收到来自 {} 的配对请求", peer);
+                let (session, response) = PairingSession::respond(commitment);
+                self.pairing_sessions.insert(peer, session);
+                if let Err(e) = self.store_trust_state(peer, TrustState::Pending).await {
+                    tracing::error!("无法更新信任状态: {}", e);
+                }
+                self.emit_trust_event(TrustEvent::PairingRequested { peer_id: peer });
+                response
+            }
+            PairingRequest::Reveal { nonce } => {
+                let local = self.local_peer_id();
+                let Some(session) = self.pairing_sessions.get_mut(&peer) else {
+                    return rejected("没有进行中的配对");
+                };
+                match session.on_reveal(&local, &peer, &nonce) {
+                    Ok(sas) => {
+                        let sas = sas.to_string();
+                        self.emit_trust_event(TrustEvent::PairingSas { peer_id: peer, sas });
+                        PairingResponse::Ack
+                    }
+                    Err(e) => {
+                        let reason = e.to_string();
+                        self.fail_pairing(peer, reason.clone()).await;
+                        PairingResponse::Rejected { reason }
+                    }
+                }
+            }
+            PairingRequest::Confirm { accepted } => {
+                let Some(session) = self.pairing_sessions.get_mut(&peer) else {
+                    return rejected("没有进行中的配对");
+                };
+                if accepted {
+                    session.confirmed_remotely = true;
+                    self.complete_pairing_if_confirmed(peer).await;
+                } else {
+                    self.fail_pairing(peer, "对方拒绝了验证码".to_string()).await;
+                }
+                PairingResponse::Ack
+            }
+            PairingRequest::Rotate { statement } => match self.accept_rotation(peer, statement).await {
+                Ok(()) => PairingResponse::Ack,
+                Err(e) => {
+                    tracing::warn!("拒绝 {} 的身份轮换声明: {}", peer, e);
+                    PairingResponse::Rejected { reason: e.to_string() }
+                }
+            },
+        }
+    }
+
+    /// 向节点出示本节点的轮换声明（每个节点只出示一次）
+    fn announce_rotation_to(&mut self, peer_id: PeerId) {
+        let Some(statement) = self.rotation.clone() else {
+            return;
+        };
+        if self.rotation_announced.insert(peer_id) {
+            // 不登记到 pairing_requests，响应和失败不会影响配对会话
+            self.swarm
+                .behaviour_mut()
+                .pairing
+                .send_request(&peer_id, PairingRequest::Rotate { statement });
+        }
+    }
+
+    /// 接受节点出示的轮换声明：旧 Peer ID 已信任时，信任转移到新 Peer ID 并迁移聊天记录
+    async fn accept_rotation(&mut self, peer: PeerId, statement: RotationStatement) -> Result<(), TrustError> {
+        let (old_peer_id, new_peer_id) = statement.verify().map_err(TrustError::InvalidRotation)?;
+        if new_peer_id != peer {
+            return Err(TrustError::InvalidRotation("声明中的新 Peer ID 与发送方不一致".to_string()));
+        }
+        if self.is_blocked(&peer).await {
+            return Err(TrustError::Blocked(peer.to_string()));
+        }
+
+        match self.peer_trust_state(&old_peer_id).await {
+            TrustState::Trusted => {}
+            // 重复出示：已经迁移过
+            TrustState::Blocked if self.peer_trust_state(&peer).await == TrustState::Trusted => return Ok(()),
+            _ => return Err(TrustError::InvalidRotation(format!("未信任原身份 {}", old_peer_id))),
+        }
+
+        self.store_trust_state(new_peer_id, TrustState::Trusted).await?;
+        self.set_trust_state(old_peer_id, TrustState::Blocked).await?;
+        if let Some(chat_manager) = self.chat_manager.clone() {
+            if let Err(e) = chat_manager.migrate_peer(&old_peer_id, &new_peer_id).await {
+                tracing::error!("迁移聊天记录失败: {}", e);
+            }
+        }
+
+        tracing::info!("节点 {} 已轮换身份为 {}", old_peer_id, new_peer_id);
+        self.emit_trust_event(TrustEvent::IdentityRotated { old_peer_id, new_peer_id });
+        Ok(())
+    }
+
+    /// GossipSub behaviour（未在配置中启用频道时返回错误）
+    fn gossipsub(&mut self) -> Result<&mut gossipsub::Behaviour, ChannelError> {
+        self.swarm.behaviour_mut().gossipsub.as_mut().ok_or(ChannelError::NotConfigured)
+    }
+
+    /// 处理 GossipSub 事件
+    ///
+    /// 收到的消息只有在发布者和转发者都已验证时才接受（并继续转发），
+    /// 未验证发布者的消息被拒绝，转发者未验证时忽略。
+    async fn handle_gossipsub_event(&mut self, event: gossipsub::Event) {
+        match event {
+            gossipsub::Event::Message { propagation_source, message_id, message } => {
+                let source_verified = match message.source {
+                    Some(source) => self.node_manager.is_node_verified(&source).await,
+                    None => false,
+                };
+                let acceptance = if !source_verified {
+                    gossipsub::MessageAcceptance::Reject
+                } else if !self.node_manager.is_node_verified(&propagation_source).await {
+                    gossipsub::MessageAcceptance::Ignore
+                } else {
+                    gossipsub::MessageAcceptance::Accept
+                };
+
+                let accepted = matches!(acceptance, gossipsub::MessageAcceptance::Accept);
+                if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+                    gossipsub.report_message_validation_result(&message_id, &propagation_source, acceptance);
+                }
+                if !accepted {
+                    tracing::warn!("丢弃来自未验证节点的频道消息 (转发者 {})", propagation_source);
+                    return;
+                }
+
+                let (Some(from), Some(tx)) = (message.source, self.channel_event_tx.as_ref()) else {
+                    return;
+                };
+                let Some(channel) = channel_name(message.topic.as_str()) else {
+                    return;
+                };
+                match ChannelMessage::decode(&message.data) {
+                    // 发送者以签名的消息来源为准
+                    Ok(mut channel_message) => {
+                        channel_message.sender_peer_id = from.to_string();
+                        let _ = tx.send(ChannelEvent::MessageReceived {
+                            channel: channel.to_string(),
+                            from,
+                            message: channel_message,
+                        });
+                    }
+                    Err(e) => tracing::warn!("无法解析频道 {} 的消息: {}", channel, e),
+                }
+            }
+            gossipsub::Event::Subscribed { peer_id, topic } => {
+                if let (Some(channel), Some(tx)) = (channel_name(topic.as_str()), self.channel_event_tx.as_ref()) {
+                    tracing::debug!("{} 订阅了频道 {}", peer_id, channel);
+                    let _ = tx.send(ChannelEvent::PeerSubscribed { channel: channel.to_string(), peer_id });
+                }
+            }
+            gossipsub::Event::Unsubscribed { peer_id, topic } => {
+                if let (Some(channel), Some(tx)) = (channel_name(topic.as_str()), self.channel_event_tx.as_ref()) {
+                    tracing::debug!("{} 退订了频道 {}", peer_id, channel);
+                    let _ = tx.send(ChannelEvent::PeerUnsubscribed { channel: channel.to_string(), peer_id });
+                }
+            }
+            gossipsub::Event::GossipsubNotSupported { peer_id } => {
+                tracing::debug!("节点 {} 不支持 GossipSub", peer_id);
+            }
+            gossipsub::Event::SlowPeer { peer_id, .. } => {
+                tracing::warn!("节点 {} 接收频道消息过慢", peer_id);
+            }
+        }
+    }
+
+    /// 通过 chat behaviour 发送消息，需要可靠投递的消息会被标记为已发送并跟踪其请求
+    async fn send_chat_request(&mut self, target: PeerId, message: ChatMessage) -> request_response::OutboundRequestId {
+        let message_id = message
+            .id()
+            .filter(|_| message.requires_delivery())
+            .map(str::to_string);
+
+        let request_id = self.swarm.behaviour_mut().chat.send_request(&target, message);
+        if let Some(message_id) = message_id {
+            if let Some(ref chat_manager) = self.chat_manager {
+                chat_manager.update_status(&target, &message_id, DeliveryStatus::Sent).await;
+            }
+            self.chat_requests.insert(request_id, (target, message_id));
+        }
+        request_id
+    }
+
+    /// 发送聊天消息：需要可靠投递的消息从发件箱中取出到期的消息发送，其他消息直接发送
+    async fn dispatch_chat_message(&mut self, chat_manager: &ChatManager, target: PeerId, message: ChatMessage) {
+        if message.requires_delivery() {
+            self.flush_outbox(chat_manager, target).await;
+        } else if self.node_manager.is_node_verified(&target).await
+            && self.is_peer_allowed(&target, self.trust_config.require_trusted_chat).await
+        {
+            self.send_chat_request(target, message).await;
+        }
+    }
+
+    /// 发送发件箱中到期的消息（仅对在线且信任策略允许的节点）
+    ///
+    /// 未连接时把已知地址交给 Swarm，由 request_response 自动拨号；
+    /// 拨号失败会以 `OutboundFailure` 返回，消息按退避时间稍后重试。
+    async fn flush_outbox(&mut self, chat_manager: &ChatManager, target: PeerId) {
+        if !self.node_manager.is_node_verified(&target).await
+            || !self.is_peer_allowed(&target, self.trust_config.require_trusted_chat).await
+        {
+            return;
+        }
+
+        let messages = chat_manager.take_due_messages(&target).await;
+        if messages.is_empty() {
+            return;
+        }
+
+        if !self.swarm.is_connected(&target) {
+            if let Some(node) = self.node_manager.get_node(&target).await {
+                for addr in node.addresses {
+                    self.swarm.add_peer_address(target, addr);
+                }
+            }
+        }
+
+        tracing::info!("向 {} 发送 {} 条聊天消息", target, messages.len());
+        for message in messages {
+            self.send_chat_request(target, message).await;
+        }
+    }
+
+    /// 节点重新验证后，立即重试发件箱中的消息
+    async fn resume_outbox(&mut self, peer_id: PeerId) {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return;
+        };
+
+        chat_manager.reset_backoff(&peer_id).await;
+        self.flush_outbox(&chat_manager, peer_id).await;
+    }
+
+    /// 定时处理发件箱：放弃过期消息，重试到期消息
+    async fn process_outbox(&mut self) {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return;
+        };
+
+        chat_manager.expire_outbox().await;
+        for peer_id in chat_manager.peers_with_due_messages().await {
+            self.flush_outbox(&chat_manager, peer_id).await;
+        }
+    }
+
+    /// 发送文件传输请求并记录请求 ID
+    fn send_file_request(&mut self, peer: PeerId, request: FileTransferRequest) {
+        let transfer_id = request.transfer_id().to_string();
+        let request_id = self.swarm.behaviour_mut().file_transfer.send_request(&peer, request);
+        self.file_requests.insert(request_id, transfer_id);
+    }
+
+    /// 发送传输的下一个分块（或完成通知）
+    async fn send_next_file_request(&mut self, manager: &FileTransferManager, peer: PeerId, transfer_id: &str) {
+        match manager.next_request(transfer_id).await {
+            Ok(Some(request)) => self.send_file_request(peer, request),
+            Ok(None) => {}
+            Err(e) => manager.fail(peer, transfer_id, e.to_string()).await,
+        }
+    }
+
+    /// 节点重新验证后，重新提议之前中断的发送任务
+    async fn resume_file_transfers(&mut self, peer_id: PeerId) {
+        let Some(manager) = self.file_transfer_manager.clone() else {
+            return;
+        };
+        if !self.is_peer_allowed(&peer_id, self.trust_config.require_trusted_files).await {
+            return;
+        }
+
+        for offer in manager.take_interrupted_offers(&peer_id).await {
+            tracing::info!("重新向 {} 提议文件 {}", peer_id, offer.file_name);
+            self.send_file_request(peer_id, FileTransferRequest::Offer(offer));
+        }
+    }
+
+    /// 处理文件传输协议事件
+    async fn handle_file_transfer_event(
+        &mut self,
+        event: request_response::Event<FileTransferRequest, FileTransferResponse>,
+    ) {
+        let Some(manager) = self.file_transfer_manager.clone() else {
+            tracing::debug!("文件传输未启用，忽略事件");
+            return;
+        };
+
+        match event {
+            request_response::Event::Message { peer, connection_id: _, message } => match message {
+                request_response::Message::Request { request_id: _, request, channel } => {
+                    let transfer_id = request.transfer_id().to_string();
+                    let ack = FileTransferResponse::Ack { transfer_id: transfer_id.clone() };
+
+                    let (response, follow_up) = match request {
+                        FileTransferRequest::Offer(_)
+                            if !self.is_peer_allowed(&peer, self.trust_config.require_trusted_files).await =>
+                        {
+                            tracing::warn!("拒绝来自未受信任节点 {} 的文件提议", peer);
+                            let reason = FileTransferError::NotTrusted(peer.to_string()).to_string();
+                            (FileTransferResponse::Error { transfer_id, reason }, None)
+                        }
+                        FileTransferRequest::Offer(offer) => match manager.handle_offer(peer, offer).await {
+                            // 存在未完成部分，直接续传
+                            Ok(Some(resume_from)) => (ack, Some(FileTransferRequest::Accept {
+                                transfer_id: transfer_id.clone(),
+                                resume_from,
+                            })),
+                            Ok(None) => (ack, None),
+                            Err(e) => (FileTransferResponse::Error { transfer_id, reason: e.to_string() }, None),
+                        },
+                        FileTransferRequest::Accept { resume_from, .. } => {
+                            match manager.handle_accept(peer, &transfer_id, resume_from).await {
+                                Ok(()) => {
+                                    let _ = self.swarm.behaviour_mut().file_transfer.send_response(channel, ack);
+                                    self.send_next_file_request(&manager, peer, &transfer_id).await;
+                                    return;
+                                }
+                                Err(e) => (FileTransferResponse::Error { transfer_id, reason: e.to_string() }, None),
+                            }
+                        }
+                        FileTransferRequest::Reject { reason, .. } => {
+                            let _ = manager.handle_reject(peer, &transfer_id, reason).await;
+                            (ack, None)
+                        }
+                        FileTransferRequest::Chunk(chunk) => {
+                            let index = chunk.index;
+                            match manager.handle_chunk(peer, chunk).await {
+                                Ok(verified) => (FileTransferResponse::ChunkAck { transfer_id, index, verified }, None),
+                                Err(e) => (FileTransferResponse::Error { transfer_id, reason: e.to_string() }, None),
+                            }
+                        }
+                        FileTransferRequest::Complete { .. } => match manager.handle_complete(peer, &transfer_id).await {
+                            Ok(_) => (ack, None),
+                            Err(e) => (FileTransferResponse::Error { transfer_id, reason: e.to_string() }, None),
+                        },
+                        FileTransferRequest::Cancel { reason, .. } => {
+                            let _ = manager.handle_cancel(peer, &transfer_id, reason).await;
+                            (ack, None)
+                        }
+                    };
+
+                    let _ = self.swarm.behaviour_mut().file_transfer.send_response(channel, response);
+                    if let Some(request) = follow_up {
+                        self.send_file_request(peer, request);
+                    }
+                }
+                request_response::Message::Response { request_id, response } => {
+                    let request_transfer_id = self.file_requests.remove(&request_id);
+                    match response {
+                        FileTransferResponse::Ack { transfer_id } => {
+                            manager.handle_ack(peer, &transfer_id).await;
+                        }
+                        FileTransferResponse::ChunkAck { transfer_id, index, verified } => {
+                            if manager.handle_chunk_ack(peer, &transfer_id, index, verified).await.is_ok() {
+                                self.send_next_file_request(&manager, peer, &transfer_id).await;
+                            }
+                        }
+                        // 只让发出请求的传输失败，不信任响应中的传输 ID
+                        FileTransferResponse::Error { reason, .. } => {
+                            tracing::warn!("{} 返回文件传输错误: {}", peer, reason);
+                            if let Some(transfer_id) = request_transfer_id {
+                                manager.fail(peer, &transfer_id, reason).await;
+                            }
+                        }
+                    }
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                if let request_response::OutboundFailure::Io(ref e) = error {
+                    self.record_violation(peer, e).await;
+                }
+                if let Some(transfer_id) = self.file_requests.remove(&request_id) {
+                    tracing::warn!("向 {} 发送文件请求失败: {}", peer, error);
+                    manager.interrupt(&transfer_id).await;
+                }
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                tracing::debug!("处理 {} 的文件请求失败: {}", peer, error);
+                if let request_response::InboundFailure::Io(ref e) = error {
+                    self.record_violation(peer, e).await;
+                }
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+}
+
+/// 发现事件
+#[derive(Debug, Clone)]
+pub enum DiscoveryEvent {
+    /// 通过 mDNS 发现节点
+    Discovered(PeerId, Multiaddr),
+
+    /// 节点 mDNS 记录过期
+    Expired(PeerId),
+
+    /// 节点验证通过
+    Verified(PeerId),
+
+    /// 节点验证失败
+    VerificationFailed(PeerId, String),
+
+    /// 节点恢复健康
+    NodeRecovered(PeerId, Duration),
+
+    /// 节点离线
+    NodeOffline(PeerId),
+
+    /// 首次收到节点的用户信息
+    UserInfoReceived(PeerId, user_info::UserInfo),
+
+    /// 节点的用户信息发生变化（对方推送或重新获取到不同的内容）
+    UserInfoUpdated(PeerId, user_info::UserInfo),
+
+    /// 节点的头像已获取并写入缓存（节点 ID，头像哈希）
+    AvatarReceived(PeerId, String),
+
+    /// 请求用户信息失败（节点 ID，失败原因）
+    UserInfoRequestFailed(PeerId, String),
+
+    /// 节点因多次违反协议被封禁（节点 ID，最后一次违规原因）
+    PeerBanned(PeerId, String),
+}
+
+/// 为 ManagedDiscovery 实现 ChatExtension trait
+///
+/// 提供可选的聊天功能扩展。
+#[async_trait::async_trait]
+impl ChatExtension for ManagedDiscovery {
+    /// 启用聊天功能（聊天记录仅保存在内存中）
+    async fn enable_chat(&mut self) -> Result<(), ChatError> {
+        self.enable_chat_with_config(ChatConfig::default()).await
+    }
+
+    /// 使用指定的聊天记录存储启用聊天功能
+    async fn enable_chat_with_store(&mut self, store: Arc<dyn ChatStore>) -> Result<(), ChatError> {
+        self.enable_chat_with_config(ChatConfig::new().with_store(store)).await
+    }
+
+    /// 使用完整配置启用聊天功能
+    async fn enable_chat_with_config(&mut self, config: ChatConfig) -> Result<(), ChatError> {
+        // 检查是否已经启用
+        if self.chat_manager.is_some() {
+            return Err(ChatError::SendFailed("聊天功能已经启用".to_string()));
+        }
+
+        // 创建 ChatManager
+        let (chat_manager, event_rx) = ChatManager::with_config(
+            self.node_manager.clone(),
+            self.local_peer_id(),
+            config,
+        );
+
+        // 重新加载聊天记录
+        chat_manager.load_history().await?;
+        chat_manager.set_do_not_disturb(self.local_user_info.presence().is_do_not_disturb());
+
+        // 保存管理器和事件接收器
+        self.chat_manager = Some(Arc::new(chat_manager));
+        self.chat_event_rx = Some(event_rx);
+
+        tracing::info!("✓ 聊天功能已启用");
+        Ok(())
+    }
+
+    /// 发送消息给指定节点
+    async fn send_message(&mut self, target: PeerId, message: ChatMessage) -> Result<(), ChatError> {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return Err(ChatError::NotEnabled);
+        };
+
+        // 1. 先通过 ChatManager 验证、设置消息元数据并放入发件箱
+        chat_manager.send(target, message.clone()).await?;
+
+        // 2. 实际通过 Swarm 的 chat behaviour 发送消息（节点离线时留在发件箱中）
+        self.dispatch_chat_message(&chat_manager, target, message).await;
+        Ok(())
+    }
+
+    /// 广播消息给多个节点（一对多）
+    async fn broadcast_message(&mut self, targets: Vec<PeerId>, message: ChatMessage) -> Result<(), ChatError> {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return Err(ChatError::NotEnabled);
+        };
+
+        // 1. 先通过 ChatManager 验证和设置消息元数据（部分目标失败时其余目标照常发送）
+        let result = chat_manager.broadcast(targets.clone(), message.clone()).await;
+
+        // 2. 为每个目标实际发送消息
+        for target in targets {
+            self.dispatch_chat_message(&chat_manager, target, message.clone()).await;
+        }
+
+        result
+    }
+
+    /// 将会话标记为已读并发送已读回执
+    async fn mark_conversation_read(&mut self, peer: PeerId) -> Result<(), ChatError> {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return Err(ChatError::NotEnabled);
+        };
+
+        if let Some(receipt) = chat_manager.mark_read(&peer).await {
+            self.send_chat_request(peer, receipt).await;
+        }
+        Ok(())
+    }
+
+    /// 创建聊天室并通知所有成员
+    async fn create_room(&mut self, name: String, members: Vec<PeerId>) -> Result<ChatRoom, ChatError> {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return Err(ChatError::NotEnabled);
+        };
+
+        let room = chat_manager.create_room(name, &members).await;
+        for member in room.other_members(&self.local_peer_id()) {
+            self.flush_outbox(&chat_manager, member).await;
+        }
+        Ok(room)
+    }
+
+    /// 邀请新成员并把更新后的聊天室发送给所有成员
+    async fn invite_to_room(&mut self, room_id: &str, members: Vec<PeerId>) -> Result<ChatRoom, ChatError> {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return Err(ChatError::NotEnabled);
+        };
+
+        let room = chat_manager.invite_to_room(room_id, &members).await?;
+        for member in room.other_members(&self.local_peer_id()) {
+            self.flush_outbox(&chat_manager, member).await;
+        }
+        Ok(room)
+    }
+
+    /// 退出聊天室并通知其他成员
+    async fn leave_room(&mut self, room_id: &str) -> Result<(), ChatError> {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return Err(ChatError::NotEnabled);
+        };
+
+        let room = chat_manager.leave_room(room_id).await?;
+        for member in room.other_members(&self.local_peer_id()) {
+            self.flush_outbox(&chat_manager, member).await;
+        }
+        Ok(())
+    }
+
+    /// 在聊天室中发送消息
+    async fn send_room_message(&mut self, room_id: &str, message: ChatMessage) -> Result<(), ChatError> {
+        let Some(chat_manager) = self.chat_manager.clone() else {
+            return Err(ChatError::NotEnabled);
+        };
+
+        for member in chat_manager.send_room_message(room_id, message).await? {
+            self.flush_outbox(&chat_manager, member).await;
+        }
+        Ok(())
+    }
+
+    /// 获取聊天管理器
+    fn chat_manager(&self) -> Option<Arc<ChatManager>> {
+        self.chat_manager.clone()
+    }
+}
+
+/// 为 ManagedDiscovery 实现 ChannelExtension trait
+///
+/// 提供可选的 GossipSub 频道功能扩展。
+#[async_trait::async_trait]
+impl ChannelExtension for ManagedDiscovery {
+    /// 启用频道功能
+    async fn enable_channels(&mut self) -> Result<(), ChannelError> {
+        if !self.swarm.behaviour().gossipsub.is_enabled() {
+            return Err(ChannelError::NotConfigured);
+        }
+        if self.channel_event_tx.is_some() {
+            return Err(ChannelError::SubscribeFailed("频道功能已经启用".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.channel_event_tx = Some(tx);
+        self.channel_event_rx = Some(rx);
+
+        tracing::info!("✓ 频道功能已启用");
+        Ok(())
+    }
+
+    /// 订阅频道
+    async fn subscribe_channel(&mut self, channel: &str) -> Result<(), ChannelError> {
+        if !self.is_channels_enabled() {
+            return Err(ChannelError::NotEnabled);
+        }
+
+        let topic = channel_topic(channel)?;
+        self.gossipsub()?
+            .subscribe(&topic)
+            .map_err(|e| ChannelError::SubscribeFailed(e.to_string()))?;
+        self.channels.insert(channel.to_string());
+
+        tracing::info!("已订阅频道 {}", channel);
+        Ok(())
+    }
+
+    /// 退订频道
+    async fn unsubscribe_channel(&mut self, channel: &str) -> Result<(), ChannelError> {
+        if !self.is_channels_enabled() {
+            return Err(ChannelError::NotEnabled);
+        }
+        if !self.channels.remove(channel) {
+            return Err(ChannelError::NotSubscribed(channel.to_string()));
+        }
+
+        let topic = channel_topic(channel)?;
+        self.gossipsub()?.unsubscribe(&topic);
+
+        tracing::info!("已退订频道 {}", channel);
+        Ok(())
+    }
+
+    /// 向频道发布消息
+    async fn publish_to_channel(&mut self, channel: &str, content: String) -> Result<String, ChannelError> {
+        if !self.is_channels_enabled() {
+            return Err(ChannelError::NotEnabled);
+        }
+        if !self.channels.contains(channel) {
+            return Err(ChannelError::NotSubscribed(channel.to_string()));
+        }
+
+        let message = ChannelMessage::new(channel.to_string(), self.local_peer_id().to_string(), content);
+        let topic = channel_topic(channel)?;
+        self.gossipsub()?
+            .publish(topic, message.encode()?)
+            .map_err(|e| ChannelError::PublishFailed(e.to_string()))?;
+        Ok(message.id)
+    }
+
+    /// 已订阅的频道
+    fn subscribed_channels(&self) -> Vec<String> {
+        self.channels.iter().cloned().collect()
+    }
+
+    /// 检查频道功能是否已启用
+    fn is_channels_enabled(&self) -> bool {
+        self.channel_event_tx.is_some()
+    }
+}
+
+/// 为 ManagedDiscovery 实现 TrustExtension trait
+///
+/// 提供可选的节点配对和信任管理功能扩展。
+#[async_trait::async_trait]
+impl TrustExtension for ManagedDiscovery {
+    /// 启用信任管理
+    async fn enable_trust(&mut self, store: Arc<dyn TrustStore>, config: TrustConfig) -> Result<(), TrustError> {
+        if self.trust_store.is_some() {
+            return Err(TrustError::AlreadyEnabled);
+        }
+
+        // 上次退出时未完成的配对不再有效
+        for (peer_id, entry) in store.entries().await? {
+            if entry.state == TrustState::Pending {
+                store.set(&peer_id, TrustState::Unknown).await?;
+            }
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.trust_store = Some(store);
+        self.trust_config = config;
+        self.trust_event_tx = Some(tx);
+        self.trust_event_rx = Some(rx);
+
+        tracing::info!("✓ 信任管理已启用");
+        Ok(())
+    }
+
+    /// 向节点发起配对
+    async fn request_pairing(&mut self, peer_id: PeerId) -> Result<(), TrustError> {
+        if !self.is_trust_enabled() {
+            return Err(TrustError::NotEnabled);
+        }
+        if self.is_blocked(&peer_id).await {
+            return Err(TrustError::Blocked(peer_id.to_string()));
+        }
+        if self.pairing_sessions.contains_key(&peer_id) {
+            return Err(TrustError::PairingInProgress(peer_id.to_string()));
+        }
+
+        if !self.swarm.is_connected(&peer_id) {
+            if let Some(node) = self.node_manager.get_node(&peer_id).await {
+                for addr in node.addresses {
+                    self.swarm.add_peer_address(peer_id, addr);
+                }
+            }
+        }
+
+        let (session, request) = PairingSession::initiate();
+        self.pairing_sessions.insert(peer_id, session);
+        self.store_trust_state(peer_id, TrustState::Pending).await?;
+        self.send_pairing_request(peer_id, request);
+
+        tracing::info!("向 {} 发起配对", peer_id);
+        Ok(())
+    }
+
+    /// 用户核对验证码后确认或拒绝配对
+    async fn confirm_pairing(&mut self, peer_id: PeerId, accepted: bool) -> Result<(), TrustError> {
+        if !self.is_trust_enabled() {
+            return Err(TrustError::NotEnabled);
+        }
+        let session = self
+            .pairing_sessions
+            .get_mut(&peer_id)
+            .filter(|session| session.sas.is_some())
+            .ok_or_else(|| TrustError::NoPairing(peer_id.to_string()))?;
+        session.confirmed_locally = accepted;
+
+        self.send_pairing_request(peer_id, PairingRequest::Confirm { accepted });
+        if accepted {
+            self.complete_pairing_if_confirmed(peer_id).await;
+        } else {
+            self.fail_pairing(peer_id, "已拒绝验证码".to_string()).await;
+        }
+        Ok(())
+    }
+
+    /// 直接设置节点的信任状态
+    async fn set_trust_state(&mut self, peer_id: PeerId, state: TrustState) -> Result<(), TrustError> {
+        self.pairing_sessions.remove(&peer_id);
+        self.store_trust_state(peer_id, state).await?;
+
+        if state == TrustState::Blocked {
+            if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+                gossipsub.blacklist_peer(&peer_id);
+            }
+            self.disallow_relay(&peer_id);
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+            if self.node_manager.remove_node(&peer_id).await.is_some() {
+                tracing::info!("已从管理器中移除被屏蔽节点 {}", peer_id);
+            }
+        } else if !self.is_banned(&peer_id) {
+            if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+                gossipsub.remove_blacklisted_peer(&peer_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// 出示本节点的身份轮换声明
+    fn announce_rotation(&mut self, statement: RotationStatement) -> Result<(), TrustError> {
+        let (_, new_peer_id) = statement.verify().map_err(TrustError::InvalidRotation)?;
+        if new_peer_id != self.local_peer_id() {
+            return Err(TrustError::InvalidRotation("声明中的新 Peer ID 不是本节点".to_string()));
+        }
+
+        self.rotation = Some(statement);
+        self.rotation_announced.clear();
+        let peers: Vec<PeerId> = self.swarm.connected_peers().copied().collect();
+        for peer_id in peers {
+            self.announce_rotation_to(peer_id);
+        }
+        Ok(())
+    }
+
+    /// 获取信任存储
+    fn trust_store(&self) -> Option<Arc<dyn TrustStore>> {
+        self.trust_store.clone()
+    }
+
+    /// 检查信任管理是否已启用
+    fn is_trust_enabled(&self) -> bool {
+        self.trust_store.is_some()
+    }
+}
+
+/// 为 ManagedDiscovery 实现 FileTransferExtension trait
+///
+/// 提供可选的文件传输功能扩展。
+#[async_trait::async_trait]
+impl FileTransferExtension for ManagedDiscovery {
+    /// 启用文件传输功能
+    async fn enable_file_transfer(&mut self, config: FileTransferConfig) -> Result<(), FileTransferError> {
+        if self.file_transfer_manager.is_some() {
+            return Err(FileTransferError::InvalidState("文件传输功能已经启用".to_string()));
+        }
+
+        tokio::fs::create_dir_all(&config.download_dir).await?;
+
+        let (manager, event_rx) = FileTransferManager::new(
+            self.node_manager.clone(),
+            self.local_peer_id(),
+            config,
+        );
+
+        self.file_transfer_manager = Some(Arc::new(manager));
+        self.file_transfer_event_rx = Some(event_rx);
+
+        tracing::info!("✓ 文件传输功能已启用");
+        Ok(())
+    }
+
+    /// 向指定节点提议发送文件
+    async fn send_file(&mut self, target: PeerId, path: PathBuf) -> Result<String, FileTransferError> {
+        let manager = self.file_transfer_manager.clone().ok_or(FileTransferError::NotEnabled)?;
+        if !self.is_peer_allowed(&target, self.trust_config.require_trusted_files).await {
+            return Err(FileTransferError::NotTrusted(target.to_string()));
+        }
+
+        let offer = manager.create_offer(target, &path).await?;
+        let transfer_id = offer.transfer_id.clone();
+        self.send_file_request(target, FileTransferRequest::Offer(offer));
+
+        Ok(transfer_id)
+    }
+
+    /// 接受收到的文件提议
+    async fn accept_file(&mut self, transfer_id: &str) -> Result<(), FileTransferError> {
+        let manager = self.file_transfer_manager.clone().ok_or(FileTransferError::NotEnabled)?;
+
+        let (peer_id, resume_from) = manager.accept(transfer_id).await?;
+        self.send_file_request(peer_id, FileTransferRequest::Accept {
+            transfer_id: transfer_id.to_string(),
+            resume_from,
+        });
+        Ok(())
+    }
+
+    /// 拒绝收到的文件提议
+    async fn reject_file(&mut self, transfer_id: &str, reason: String) -> Result<(), FileTransferError> {
+        let manager = self.file_transfer_manager.clone().ok_or(FileTransferError::NotEnabled)?;
+
+        let peer_id = manager.reject(transfer_id, reason.clone()).await?;
+        self.send_file_request(peer_id, FileTransferRequest::Reject {
+            transfer_id: transfer_id.to_string(),
+            reason,
+        });
+        Ok(())
+    }
+
+    /// 取消传输
+    async fn cancel_file(&mut self, transfer_id: &str) -> Result<(), FileTransferError> {
+        let manager = self.file_transfer_manager.clone().ok_or(FileTransferError::NotEnabled)?;
+
+        let peer_id = manager.cancel(transfer_id).await?;
+        self.send_file_request(peer_id, FileTransferRequest::Cancel {
+            transfer_id: transfer_id.to_string(),
+            reason: "对方取消".to_string(),
+        });
+        Ok(())
+    }
+
+    /// 获取文件传输管理器
+    fn file_transfer_manager(&self) -> Option<Arc<FileTransferManager>> {
+        self.file_transfer_manager.clone()
     }
 }
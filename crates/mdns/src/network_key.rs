@@ -0,0 +1,200 @@
+//! 私有网络密钥
+//!
+//! 同一局域网中的团队可以共享一个网络密钥，把自己的节点与其他 localp2p 用户隔离：
+//!
+//! - 传输层：TCP 连接建立后先进行 libp2p pnet 握手（XSalsa20 加密），
+//!   密钥不同的节点无法完成后续的 Noise 握手，连接直接失败；
+//! - 应用层：identify 协议版本附加密钥指纹（`/localp2p/1.0.0/pnet/<指纹>`），
+//!   即使传输层被绕过，也不会把不同网络的节点验证为可信节点。
+//!
+//! 密钥文件格式与 go-libp2p 的 `swarm.key` 兼容。口令通过 Argon2id 派生密钥，
+//! 所有节点必须得到相同的密钥，因此使用固定的域分隔盐。
+
+use libp2p::{
+    core::{muxing::StreamMuxerBox, transport::Boxed, upgrade},
+    identity::Keypair,
+    noise,
+    pnet::{KeyParseError, PnetConfig, PreSharedKey},
+    yamux, PeerId, Transport,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use futures::future::Either;
+use std::fmt;
+use std::str::FromStr;
+
+/// 口令派生的固定盐（域分隔，避免与其他用途的 Argon2 派生结果相同）
+const PASSPHRASE_SALT: &[u8] = b"localp2p-network-key";
+
+/// 网络密钥
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct NetworkKey(PreSharedKey);
+
+impl NetworkKey {
+    /// 从 32 字节原始数据创建
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(PreSharedKey::new(bytes))
+    }
+
+    /// 从口令派生（团队成员输入相同口令即可加入同一网络）
+    ///
+    /// 使用 Argon2id（默认参数），提高离线猜测口令的代价。
+    pub fn from_passphrase(passphrase: &str) -> Self {
+        let mut bytes = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+            .hash_password_into(passphrase.as_bytes(), PASSPHRASE_SALT, &mut bytes)
+            .expect("默认参数和固定盐的 Argon2 派生不会失败");
+        Self::new(bytes)
+    }
+
+    /// 随机生成新密钥（系统随机数）
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        Self::new(bytes)
+    }
+
+    /// 密钥指纹（十六进制，可以安全地显示和记录）
+    pub fn fingerprint(&self) -> String {
+        self.0.fingerprint().to_string()
+    }
+
+    /// 附加密钥指纹后的 identify 协议版本
+    pub fn namespace(&self, protocol_version: &str) -> String {
+        format!("{}/pnet/{}", protocol_version, self.fingerprint())
+    }
+
+    /// 去掉协议版本中的密钥指纹，指纹不匹配时返回 None
+    pub fn strip_namespace<'a>(&self, protocol_version: &'a str) -> Option<&'a str> {
+        protocol_version.strip_suffix(&format!("/pnet/{}", self.fingerprint()))
+    }
+}
+
+/// 解析 `swarm.key` 文件内容，或 64 个十六进制字符
+impl FromStr for NetworkKey {
+    type Err = KeyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.lines().count() == 1 {
+            return format!("/key/swarm/psk/1.0.0/\n/base16/\n{}", s).parse().map(Self);
+        }
+        s.parse().map(Self)
+    }
+}
+
+/// 输出 `swarm.key` 文件格式（用于分享给团队成员）
+impl fmt::Display for NetworkKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// 调试输出只显示指纹，避免密钥出现在日志中
+impl fmt::Debug for NetworkKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NetworkKey").field(&self.fingerprint()).finish()
+    }
+}
+
+/// 构建 TCP 传输：可选的 pnet 握手，然后 Noise 认证和 Yamux 多路复用
+pub(crate) fn tcp_transport(
+    keypair: &Keypair,
+    network_key: Option<NetworkKey>,
+) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Box<dyn std::error::Error + Send + Sync>> {
+    let tcp = libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default())
+        .and_then(move |socket, _| async move {
+            match network_key {
+                Some(NetworkKey(psk)) => PnetConfig::new(psk).handshake(socket).await.map(Either::Left),
+                None => Ok(Either::Right(socket)),
+            }
+        });
+
+    Ok(tcp
+        .upgrade(upgrade::Version::V1Lazy)
+        .authenticate(noise::Config::new(keypair)?)
+        .multiplex(yamux::Config::default())
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+        .boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use libp2p::{identify, swarm::SwarmEvent, Multiaddr, Swarm, SwarmBuilder};
+    use std::time::Duration;
+
+    const PROTOCOL_VERSION: &str = "/localp2p/1.0.0";
+
+    fn swarm(network_key: Option<NetworkKey>) -> Swarm<identify::Behaviour> {
+        let protocol_version = match network_key {
+            Some(key) => key.namespace(PROTOCOL_VERSION),
+            None => PROTOCOL_VERSION.to_string(),
+        };
+        SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_other_transport(|key| tcp_transport(key, network_key))
+            .unwrap()
+            .with_behaviour(|key| identify::Behaviour::new(identify::Config::new(protocol_version, key.public())))
+            .unwrap()
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(5)))
+            .build()
+    }
+
+    /// 在同一主机上启动两个 Swarm，返回拨号方是否收到对方的 identify 信息
+    async fn identifies(a: Option<NetworkKey>, b: Option<NetworkKey>) -> bool {
+        let mut listener = swarm(a);
+        let mut dialer = swarm(b);
+
+        listener.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap()).unwrap();
+        let addr: Multiaddr = loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = listener.select_next_some().await {
+                break address;
+            }
+        };
+        dialer.dial(addr).unwrap();
+
+        let run = async {
+            loop {
+                tokio::select! {
+                    _ = listener.select_next_some() => {}
+                    event = dialer.select_next_some() => match event {
+                        SwarmEvent::Behaviour(identify::Event::Received { .. }) => return true,
+                        SwarmEvent::OutgoingConnectionError { .. } => return false,
+                        _ => {}
+                    },
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(3), run).await.unwrap_or(false)
+    }
+
+    #[tokio::test]
+    async fn test_swarms_with_different_keys_are_isolated() {
+        let team = NetworkKey::from_passphrase("team");
+        let other = NetworkKey::from_passphrase("other");
+        assert_eq!(NetworkKey::from_passphrase("team"), team);
+
+        assert!(identifies(Some(team), Some(team)).await);
+        assert!(!identifies(Some(team), Some(other)).await);
+        assert!(!identifies(Some(team), None).await);
+        assert!(!identifies(None, Some(team)).await);
+        assert!(identifies(None, None).await);
+    }
+
+    #[test]
+    fn test_parse_and_namespace() {
+        let key = NetworkKey::generate();
+        assert_eq!(key.to_string().parse::<NetworkKey>().unwrap(), key);
+        let hex = key.to_string().lines().nth(2).unwrap().to_string();
+        assert_eq!(hex.parse::<NetworkKey>().unwrap(), key);
+        assert!("not a key".parse::<NetworkKey>().is_err());
+
+        let version = key.namespace(PROTOCOL_VERSION);
+        assert_eq!(key.strip_namespace(&version), Some(PROTOCOL_VERSION));
+        assert_eq!(NetworkKey::generate().strip_namespace(&version), None);
+        assert_eq!(key.strip_namespace(PROTOCOL_VERSION), None);
+        assert!(!format!("{:?}", key).contains(&hex));
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! 提供节点管理器来维护验证通过的节点列表。
 
+use crate::interfaces::InterfaceFilter;
 use libp2p::{PeerId, Multiaddr};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -33,6 +34,9 @@ pub struct VerifiedNode {
 
     /// 自定义属性
     pub attributes: HashMap<String, String>,
+
+    /// 当前连接使用的传输协议（TCP 或 QUIC）
+    pub transport: Option<TransportKind>,
 }
 
 impl VerifiedNode {
@@ -54,6 +58,7 @@ impl VerifiedNode {
             first_seen: now,
             last_seen: now,
             attributes: HashMap::new(),
+            transport: None,
         }
     }
 
@@ -82,6 +87,12 @@ impl VerifiedNode {
         self
     }
 
+    /// 设置连接使用的传输协议
+    pub fn with_transport(mut self, transport: Option<TransportKind>) -> Self {
+        self.transport = transport;
+        self
+    }
+
     /// 获取节点存活时长
     pub fn age(&self) -> Duration {
         self.first_seen.elapsed()
@@ -110,6 +121,47 @@ pub struct NodeManagerConfig {
 
     /// 本设备名称（会包含在 agent_version 中）
     pub device_name: Option<String>,
+
+    /// 聊天和用户信息协议是否提供 CBOR 变体
+    ///
+    /// 启用后与同样启用的节点优先使用 CBOR，其余节点仍使用 JSON。
+    pub cbor_frames: bool,
+
+    /// 协议限制（帧大小、读取超时和违规封禁策略）
+    pub protocol_limits: ProtocolLimits,
+
+    /// 是否在 TCP 之外启用 QUIC 传输
+    ///
+    /// 启用后同时监听 QUIC 地址，对方通告 QUIC 地址时优先使用 QUIC 拨号。
+    /// 私有网络密钥只能保护 TCP，设置网络密钥时该选项被忽略。
+    pub quic: bool,
+
+    /// 是否作为中继节点为其他节点转发连接
+    ///
+    /// 所有节点都会在支持中继的已连接节点上预留中继地址，并尝试通过 DCUtR 打洞升级为直连；
+    /// 只有启用该选项的节点才会接受预留和转发。
+    ///
+    /// 为了让文件传输可以经过中继，线路不限制字节数，单条线路最长 1 小时。预留和线路只对
+    /// 已通过 identify 验证的在线节点开放；未设置网络密钥时，任何运行本应用的节点都能通过验证，
+    /// 都可以不受流量限制地使用本节点的带宽，只应在可信的网络中启用。
+    pub relay_server: bool,
+
+    /// 是否启用 GossipSub 频道协议
+    ///
+    /// 未启用时不创建 GossipSub behaviour，`enable_channels` 会返回错误。
+    pub channels: bool,
+
+    /// mDNS 记录 TTL
+    pub mdns_ttl: Duration,
+
+    /// mDNS 查询间隔
+    pub mdns_query_interval: Duration,
+
+    /// 是否同时在 IPv6 上运行 mDNS
+    pub mdns_ipv6: bool,
+
+    /// 网卡过滤（为空时使用所有网卡）
+    pub interfaces: InterfaceFilter,
 }
 
 impl Default for NodeManagerConfig {
@@ -120,6 +172,15 @@ impl Default for NodeManagerConfig {
             expected_protocol_version: "/localp2p/1.0.0".to_string(),
             expected_agent_prefix: Some("localp2p-rust/".to_string()),
             device_name: None,
+            cbor_frames: false,
+            protocol_limits: ProtocolLimits::default(),
+            quic: false,
+            relay_server: false,
+            channels: false,
+            mdns_ttl: Duration::from_secs(6 * 60),
+            mdns_query_interval: Duration::from_secs(5 * 60),
+            mdns_ipv6: false,
+            interfaces: InterfaceFilter::new(),
         }
     }
 }
@@ -160,6 +221,65 @@ impl NodeManagerConfig {
         self
     }
 
+    /// 启用或禁用 CBOR 协议变体
+    pub fn with_cbor_frames(mut self, enabled: bool) -> Self {
+        self.cbor_frames = enabled;
+        self
+    }
+
+    /// 设置协议限制
+    pub fn with_protocol_limits(mut self, limits: ProtocolLimits) -> Self {
+        self.protocol_limits = limits;
+        self
+    }
+
+    /// 启用或禁用 QUIC 传输
+    pub fn with_quic(mut self, enabled: bool) -> Self {
+        self.quic = enabled;
+        self
+    }
+
+    /// 启用或禁用中继服务
+    pub fn with_relay_server(mut self, enabled: bool) -> Self {
+        self.relay_server = enabled;
+        self
+    }
+
+    /// 启用或禁用 GossipSub 频道协议
+    pub fn with_channels(mut self, enabled: bool) -> Self {
+        self.channels = enabled;
+        self
+    }
+
+    /// 设置 mDNS 记录 TTL
+    pub fn with_mdns_ttl(mut self, ttl: Duration) -> Self {
+        self.mdns_ttl = ttl;
+        self
+    }
+
+    /// 设置 mDNS 查询间隔
+    pub fn with_mdns_query_interval(mut self, interval: Duration) -> Self {
+        self.mdns_query_interval = interval;
+        self
+    }
+
+    /// 启用或禁用 IPv6 mDNS
+    pub fn with_mdns_ipv6(mut self, enabled: bool) -> Self {
+        self.mdns_ipv6 = enabled;
+        self
+    }
+
+    /// 设置网卡过滤
+    pub fn with_interface_filter(mut self, filter: InterfaceFilter) -> Self {
+        self.interfaces = filter;
+        self
+    }
+
+    /// libp2p mDNS 参数
+    pub(crate) fn mdns_config(&self) -> libp2p::mdns::Config {
+        crate::interfaces::mdns_config(self.mdns_ttl, self.mdns_query_interval, self.mdns_ipv6)
+    }
+
     /// 构建完整的 agent_version（包含设备名称）
     pub fn build_agent_version(&self) -> String {
         if let Some(ref name) = self.device_name {
@@ -170,6 +290,8 @@ impl NodeManagerConfig {
     }
 }
 
+use crate::codec::ProtocolLimits;
+use crate::transport::TransportKind;
 use crate::MdnsError;
 
 /// 节点管理器
@@ -206,6 +328,9 @@ impl NodeManager {
         if let Some(existing) = nodes.get_mut(&peer_id) {
             existing.update_last_seen();
             existing.addresses = node.addresses;
+            if node.transport.is_some() {
+                existing.transport = node.transport;
+            }
             tracing::debug!("更新节点: {}", peer_id);
         } else {
             // 新节点，直接添加
@@ -214,6 +339,13 @@ impl NodeManager {
         }
     }
 
+    /// 更新节点当前连接使用的传输协议
+    pub async fn set_transport(&self, peer_id: &PeerId, transport: Option<TransportKind>) {
+        if let Some(node) = self.nodes.write().await.get_mut(peer_id) {
+            node.transport = transport;
+        }
+    }
+
     /// 移除节点
     pub async fn remove_node(&self, peer_id: &PeerId) -> Option<VerifiedNode> {
         let mut nodes = self.nodes.write().await;
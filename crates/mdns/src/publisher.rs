@@ -1,6 +1,7 @@
 //! mDNS 服务发布模块
 
 use super::{MdnsConfig, ServiceInfo, Result, MdnsError};
+use super::interfaces::MdnsBehaviour;
 use libp2p::{
     mdns,
     Swarm,
@@ -13,7 +14,7 @@ use std::time::Duration;
 
 /// mDNS 服务发布器
 pub struct MdnsPublisher {
-    swarm: Swarm<mdns::tokio::Behaviour>,
+    swarm: Swarm<MdnsBehaviour>,
     service_info: Option<ServiceInfo>,
 }
 
@@ -21,6 +22,10 @@ impl MdnsPublisher {
     /// 创建新的服务发布器
     pub async fn new(config: MdnsConfig) -> Result<Self> {
         let local_key = Keypair::generate_ed25519();
+        let interfaces = config
+            .interfaces
+            .resolve(config.resolved_listen_addresses())
+            .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
 
         // 使用 libp2p 0.56 的 SwarmBuilder API 构建 Swarm
         let mut swarm = SwarmBuilder::with_existing_identity(local_key)
@@ -31,15 +36,16 @@ impl MdnsPublisher {
                 libp2p::yamux::Config::default,
             )
             .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?
+            .with_quic()
             .with_behaviour(|_key| {
-                mdns::tokio::Behaviour::new(mdns::Config::default(), _key.public().into())
+                MdnsBehaviour::new(config.mdns_config(), _key.public().into())
                     .expect("mdns behaviour creation failed")
             })
             .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?
             .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
             .build();
 
-        for addr in config.listen_addresses {
+        for addr in interfaces.listen_addresses {
             swarm.listen_on(addr)
                 .map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
         }
@@ -62,18 +68,20 @@ impl MdnsPublisher {
         }
 
         loop {
-            match self.swarm.select_next_some().await {
-                libp2p::swarm::SwarmEvent::Behaviour(mdns::Event::Discovered(list)) => {
+            let libp2p::swarm::SwarmEvent::Behaviour(event) = self.swarm.select_next_some().await else {
+                continue;
+            };
+            match event.into_inner() {
+                mdns::Event::Discovered(list) => {
                     for (peer_id, addr) in list {
                         tracing::debug!("发现对等节点: {} at {}", peer_id, addr);
                     }
                 }
-                libp2p::swarm::SwarmEvent::Behaviour(mdns::Event::Expired(list)) => {
+                mdns::Event::Expired(list) => {
                     for (peer_id, addr) in list {
                         tracing::debug!("对等节点过期: {} at {}", peer_id, addr);
                     }
                 }
-                _ => {}
             }
         }
     }
@@ -0,0 +1,318 @@
+//! 会合点发现
+//!
+//! mDNS 只在同一子网内有效，不同 VLAN 的团队成员互相看不到。可以在双方都能访问的主机上运行
+//! 会合服务器（`localp2p rendezvous-server`），节点连接后在团队命名空间下注册自己的地址，
+//! 并定期查询同一命名空间中的其他节点：
+//!
+//! - 命名空间由团队名称哈希派生，服务器不知道团队名称；
+//! - 注册内容是节点签名的 PeerRecord，节点不能替其他节点注册；
+//! - 注册带有 TTL，节点在过期前自动续期；
+//! - 查询到的节点与 mDNS 发现的节点一样经过 identify 验证。
+//!
+//! 协议使用 libp2p rendezvous（`/rendezvous/1.0.0`），客户端和服务器分别基于
+//! `rendezvous::client::Behaviour` 和 `rendezvous::server::Behaviour`。
+
+use crate::network_key::NetworkKey;
+use crate::MdnsError;
+use futures::StreamExt;
+use libp2p::{
+    identity::Keypair, multiaddr::Protocol, rendezvous, swarm::SwarmEvent, Multiaddr, PeerId, Swarm,
+    SwarmBuilder,
+};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+/// 默认注册 TTL（服务器接受的最短 TTL）
+pub const DEFAULT_TTL: Duration = Duration::from_secs(rendezvous::DEFAULT_TTL);
+
+/// 默认查询间隔
+pub const DEFAULT_DISCOVER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 连接服务器或请求失败后的重试间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 会合错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum RendezvousError {
+    /// 服务器地址没有 Peer ID，无法认证服务器
+    #[error("会合服务器地址缺少 /p2p/<peer_id>: {0}")]
+    MissingPeerId(Multiaddr),
+
+    /// 服务器地址指向本节点
+    #[error("会合服务器不能是本节点")]
+    LocalPeer,
+
+    /// 命名空间过长
+    #[error("会合命名空间无效: {0}")]
+    InvalidNamespace(String),
+}
+
+/// 由团队名称派生命名空间
+pub fn namespace(team: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"localp2p-rendezvous");
+    hasher.update(team.trim().as_bytes());
+    let digest = hasher.finalize();
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("localp2p/{}", hex)
+}
+
+/// 会合客户端配置
+#[derive(Debug, Clone)]
+pub struct RendezvousConfig {
+    /// 会合服务器地址（必须带 `/p2p/<peer_id>`）
+    pub server: Multiaddr,
+    /// 注册和查询使用的命名空间
+    pub namespace: String,
+    /// 注册 TTL（在过半时续期，服务器只接受 2 到 72 小时）
+    pub ttl: Duration,
+    /// 查询间隔
+    pub discover_interval: Duration,
+}
+
+impl RendezvousConfig {
+    /// 创建配置，命名空间由团队名称派生
+    pub fn new(server: Multiaddr, team: &str) -> Self {
+        Self {
+            server,
+            namespace: namespace(team),
+            ttl: DEFAULT_TTL,
+            discover_interval: DEFAULT_DISCOVER_INTERVAL,
+        }
+    }
+
+    /// 设置注册 TTL
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// 设置查询间隔
+    pub fn with_discover_interval(mut self, interval: Duration) -> Self {
+        self.discover_interval = interval;
+        self
+    }
+
+    /// 服务器的 Peer ID
+    pub fn server_peer_id(&self) -> Option<PeerId> {
+        self.server.iter().find_map(|protocol| match protocol {
+            Protocol::P2p(peer_id) => Some(peer_id),
+            _ => None,
+        })
+    }
+}
+
+/// 会合客户端状态（注册续期、定期查询、重连服务器）
+///
+/// 请求本身由 `rendezvous::client::Behaviour` 发送，这里只决定何时发送。
+#[derive(Debug)]
+pub(crate) struct RendezvousClient {
+    pub(crate) config: RendezvousConfig,
+    pub(crate) server: PeerId,
+    pub(crate) namespace: rendezvous::Namespace,
+    /// 下次注册时间（请求进行中时为 None）
+    next_register: Option<Instant>,
+    /// 下次查询时间（请求进行中时为 None）
+    next_discover: Option<Instant>,
+    /// 下次允许拨号服务器的时间
+    next_dial: Instant,
+}
+
+impl RendezvousClient {
+    pub(crate) fn new(config: RendezvousConfig, local_peer_id: &PeerId) -> Result<Self, RendezvousError> {
+        let server = config
+            .server_peer_id()
+            .ok_or_else(|| RendezvousError::MissingPeerId(config.server.clone()))?;
+        if server == *local_peer_id {
+            return Err(RendezvousError::LocalPeer);
+        }
+        let namespace = rendezvous::Namespace::new(config.namespace.clone())
+            .map_err(|_| RendezvousError::InvalidNamespace(config.namespace.clone()))?;
+        let now = Instant::now();
+        Ok(Self { config, server, namespace, next_register: Some(now), next_discover: Some(now), next_dial: now })
+    }
+
+    /// 是否需要注册或查询
+    pub(crate) fn is_due(&self, now: Instant) -> bool {
+        [self.next_register, self.next_discover].iter().flatten().any(|at| *at <= now)
+    }
+
+    /// 到了拨号时间时返回 true，并推迟下次拨号
+    pub(crate) fn take_dial(&mut self, now: Instant) -> bool {
+        if self.next_dial > now {
+            return false;
+        }
+        self.next_dial = now + RETRY_INTERVAL;
+        true
+    }
+
+    /// 到了注册时间时返回 true，并标记为进行中
+    pub(crate) fn take_register(&mut self, now: Instant) -> bool {
+        take_due(&mut self.next_register, now)
+    }
+
+    /// 到了查询时间时返回 true，并标记为进行中
+    pub(crate) fn take_discover(&mut self, now: Instant) -> bool {
+        take_due(&mut self.next_discover, now)
+    }
+
+    /// 注册成功，TTL 过半时续期
+    pub(crate) fn registered(&mut self, ttl: Duration) {
+        self.next_register = Some(Instant::now() + ttl / 2);
+    }
+
+    /// 查询完成
+    pub(crate) fn discovered(&mut self) {
+        self.next_discover = Some(Instant::now() + self.config.discover_interval);
+    }
+
+    /// 请求失败，稍后重试进行中的请求
+    pub(crate) fn failed(&mut self) {
+        let retry = Some(Instant::now() + RETRY_INTERVAL);
+        self.next_register = self.next_register.or(retry);
+        self.next_discover = self.next_discover.or(retry);
+    }
+}
+
+fn take_due(next: &mut Option<Instant>, now: Instant) -> bool {
+    if next.is_some_and(|at| at <= now) {
+        *next = None;
+        return true;
+    }
+    false
+}
+
+/// 会合服务器事件
+#[derive(Debug, Clone)]
+pub enum RendezvousServerEvent {
+    /// 开始监听（地址已附带 `/p2p/<peer_id>`，可直接分发给客户端）
+    Listening(Multiaddr),
+    /// 节点注册或续期（节点，命名空间）
+    Registered(PeerId, String),
+    /// 节点取消注册（节点，命名空间）
+    Unregistered(PeerId, String),
+    /// 节点查询（节点，返回的节点数）
+    Discovered(PeerId, usize),
+}
+
+/// 会合服务器（基于 `rendezvous::server::Behaviour`）
+pub struct RendezvousServer {
+    swarm: Swarm<rendezvous::server::Behaviour>,
+}
+
+impl RendezvousServer {
+    /// 创建服务器并开始监听
+    ///
+    /// 私有网络的节点只能连接使用相同 `network_key` 的服务器（此时不监听 QUIC）。
+    pub fn new(
+        keypair: Keypair,
+        listen_addresses: Vec<Multiaddr>,
+        network_key: Option<NetworkKey>,
+    ) -> Result<Self, MdnsError> {
+        let quic = network_key.is_none();
+        let listen_addresses = if quic {
+            crate::transport::with_quic_listen_addresses(listen_addresses)
+        } else {
+            listen_addresses
+        };
+        let mut swarm = SwarmBuilder::with_existing_identity(keypair)
+            .with_tokio()
+            .with_other_transport(|key| crate::transport::build(key, network_key, quic))
+            .map_err(|e| MdnsError::SwarmBuild(format!("Transport: {}", e)))?
+            .with_behaviour(|_key| rendezvous::server::Behaviour::new(rendezvous::server::Config::default()))
+            .map_err(|e| MdnsError::SwarmBuild(format!("Behaviour: {}", e)))?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+            .build();
+
+        for addr in listen_addresses {
+            swarm.listen_on(addr).map_err(|e| MdnsError::SwarmBuild(e.to_string()))?;
+        }
+        Ok(Self { swarm })
+    }
+
+    /// 服务器的 Peer ID
+    pub fn local_peer_id(&self) -> PeerId {
+        *self.swarm.local_peer_id()
+    }
+
+    /// 处理网络事件，直到产生下一个服务器事件
+    pub async fn run(&mut self) -> Result<RendezvousServerEvent, MdnsError> {
+        loop {
+            match self.swarm.select_next_some().await {
+                SwarmEvent::NewListenAddr { address, .. } => {
+                    let address = address.with_p2p(self.local_peer_id()).unwrap_or_else(|addr| addr);
+                    return Ok(RendezvousServerEvent::Listening(address));
+                }
+                SwarmEvent::Behaviour(event) => match event {
+                    rendezvous::server::Event::PeerRegistered { peer, registration } => {
+                        return Ok(RendezvousServerEvent::Registered(peer, registration.namespace.to_string()));
+                    }
+                    rendezvous::server::Event::PeerUnregistered { peer, namespace } => {
+                        return Ok(RendezvousServerEvent::Unregistered(peer, namespace.to_string()));
+                    }
+                    rendezvous::server::Event::DiscoverServed { enquirer, registrations } => {
+                        return Ok(RendezvousServerEvent::Discovered(enquirer, registrations.len()));
+                    }
+                    rendezvous::server::Event::PeerNotRegistered { peer, namespace, error } => {
+                        tracing::warn!("拒绝 {} 在 {} 的注册: {:?}", peer, namespace, error);
+                    }
+                    rendezvous::server::Event::DiscoverNotServed { enquirer, error } => {
+                        tracing::debug!("拒绝 {} 的查询: {:?}", enquirer, error);
+                    }
+                    rendezvous::server::Event::RegistrationExpired(registration) => {
+                        tracing::debug!("{} 的注册已过期", registration.record.peer_id());
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_namespace_and_config() {
+        assert_eq!(namespace("design"), namespace(" design "));
+        assert_ne!(namespace("design"), namespace("sales"));
+        assert!(!namespace("design").contains("design"));
+
+        let server = PeerId::random();
+        let config = RendezvousConfig::new(addr("/ip4/10.0.0.1/tcp/4001").with(Protocol::P2p(server)), "design");
+        assert_eq!(config.server_peer_id(), Some(server));
+        let client = RendezvousClient::new(config.clone(), &PeerId::random()).unwrap();
+        assert_eq!(client.namespace.to_string(), config.namespace);
+        assert!(matches!(RendezvousClient::new(config, &server), Err(RendezvousError::LocalPeer)));
+        assert!(matches!(
+            RendezvousClient::new(RendezvousConfig::new(addr("/ip4/10.0.0.1/tcp/4001"), "design"), &server),
+            Err(RendezvousError::MissingPeerId(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_schedule() {
+        let server = PeerId::random();
+        let config = RendezvousConfig::new(addr("/ip4/10.0.0.1/tcp/4001").with(Protocol::P2p(server)), "design");
+        let mut client = RendezvousClient::new(config, &PeerId::random()).unwrap();
+        let now = Instant::now();
+
+        // 启动后立即注册和查询，请求进行中不重复发送
+        assert!(client.is_due(now));
+        assert!(client.take_register(now) && client.take_discover(now));
+        assert!(!client.is_due(now));
+
+        // 注册成功后在 TTL 过半时续期，失败的查询稍后重试
+        client.registered(DEFAULT_TTL);
+        client.failed();
+        assert!(!client.is_due(now + RETRY_INTERVAL / 2));
+        assert!(client.take_discover(now + RETRY_INTERVAL * 2));
+        assert!(!client.take_register(now + RETRY_INTERVAL * 2));
+        assert!(client.take_register(now + DEFAULT_TTL));
+    }
+}
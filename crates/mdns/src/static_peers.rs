@@ -0,0 +1,256 @@
+//! 静态节点
+//!
+//! 组播被过滤的网络（如企业 Wi‑Fi）中 mDNS 发现不了节点，可以手动添加节点的 multiaddr：
+//!
+//! - 静态节点列表可保存到文件（JSON 字符串数组），启动时自动拨号；
+//! - 拨号失败或连接断开后按指数退避重新拨号（2 秒起，最长 5 分钟）；
+//! - 连接建立后与 mDNS 发现的节点一样经过 identify 验证；
+//! - 也可以添加中继地址（`<中继地址>/p2p/<中继>/p2p-circuit/p2p/<节点>`）。
+
+use crate::transport::TransportKind;
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 首次重拨的等待时间
+const MIN_REDIAL_DELAY: Duration = Duration::from_secs(2);
+
+/// 重拨等待时间上限
+const MAX_REDIAL_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// 静态节点错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum StaticPeerError {
+    /// 地址不包含支持的传输协议
+    #[error("不支持的节点地址: {0}（需要 /tcp/ 或 /udp/../quic-v1，中继地址需以 /p2p-circuit/p2p/<节点> 结尾）")]
+    UnsupportedAddress(Multiaddr),
+
+    /// 地址指向本节点
+    #[error("不能添加本节点的地址")]
+    LocalPeer,
+
+    /// 读写静态节点列表失败
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// 地址中的 Peer ID（`/p2p/<peer_id>` 后缀；中继地址取 `/p2p-circuit` 之后的目标节点）
+pub fn peer_id_of(addr: &Multiaddr) -> Option<PeerId> {
+    addr.iter().fold(None, |peer_id, protocol| match protocol {
+        Protocol::P2p(id) => Some(id),
+        Protocol::P2pCircuit => None,
+        _ => peer_id,
+    })
+}
+
+/// 校验静态节点地址
+pub fn validate(addr: &Multiaddr, local_peer_id: &PeerId) -> Result<(), StaticPeerError> {
+    match TransportKind::from_multiaddr(addr) {
+        None => return Err(StaticPeerError::UnsupportedAddress(addr.clone())),
+        // 中继地址必须指明目标节点
+        Some(TransportKind::Relay) if peer_id_of(addr).is_none() => {
+            return Err(StaticPeerError::UnsupportedAddress(addr.clone()));
+        }
+        Some(_) => {}
+    }
+    if peer_id_of(addr).as_ref() == Some(local_peer_id) {
+        return Err(StaticPeerError::LocalPeer);
+    }
+    Ok(())
+}
+
+/// 静态节点列表
+#[derive(Debug, Default)]
+pub struct StaticPeerStore {
+    path: Option<PathBuf>,
+    addresses: Vec<Multiaddr>,
+}
+
+impl StaticPeerStore {
+    /// 创建内存列表（不保存）
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// 打开列表文件（不存在时为空列表，无法解析的地址会被忽略）
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StaticPeerError> {
+        let path = path.as_ref().to_path_buf();
+        let addresses = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str::<Vec<String>>(&json)
+                .map_err(std::io::Error::from)?
+                .iter()
+                .filter_map(|addr| match addr.parse() {
+                    Ok(addr) => Some(addr),
+                    Err(e) => {
+                        tracing::warn!("忽略无效的静态节点地址 {}: {}", addr, e);
+                        None
+                    }
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path: Some(path), addresses })
+    }
+
+    /// 列表文件路径
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// 所有静态节点地址
+    pub fn addresses(&self) -> &[Multiaddr] {
+        &self.addresses
+    }
+
+    /// 是否包含该地址
+    pub fn contains(&self, addr: &Multiaddr) -> bool {
+        self.addresses.contains(addr)
+    }
+
+    /// 添加地址并保存，已存在时返回 false
+    pub fn insert(&mut self, addr: Multiaddr) -> Result<bool, StaticPeerError> {
+        if self.contains(&addr) {
+            return Ok(false);
+        }
+        self.addresses.push(addr);
+        self.save()?;
+        Ok(true)
+    }
+
+    /// 移除地址并保存，不存在时返回 false
+    pub fn remove(&mut self, addr: &Multiaddr) -> Result<bool, StaticPeerError> {
+        let len = self.addresses.len();
+        self.addresses.retain(|a| a != addr);
+        if self.addresses.len() == len {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn save(&self) -> Result<(), StaticPeerError> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let addresses: Vec<String> = self.addresses.iter().map(|addr| addr.to_string()).collect();
+        let json = serde_json::to_string_pretty(&addresses).map_err(std::io::Error::from)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// 单个静态节点的拨号状态
+#[derive(Debug, Clone)]
+pub(crate) struct StaticDial {
+    /// 连接后得知的 Peer ID（地址带 `/p2p/` 时一开始就知道）
+    pub(crate) peer_id: Option<PeerId>,
+    /// 下次拨号时间（拨号中或已连接时为 None）
+    next_dial: Option<Instant>,
+    /// 连续失败次数
+    failures: u32,
+}
+
+impl StaticDial {
+    /// 新添加的节点立即拨号
+    pub(crate) fn new(addr: &Multiaddr) -> Self {
+        Self { peer_id: peer_id_of(addr), next_dial: Some(Instant::now()), failures: 0 }
+    }
+
+    /// 是否到了拨号时间
+    pub(crate) fn is_due(&self, now: Instant) -> bool {
+        self.next_dial.is_some_and(|at| at <= now)
+    }
+
+    /// 开始拨号
+    pub(crate) fn dialing(&mut self) {
+        self.next_dial = None;
+    }
+
+    /// 连接成功
+    pub(crate) fn connected(&mut self, peer_id: PeerId) {
+        self.peer_id = Some(peer_id);
+        self.next_dial = None;
+        self.failures = 0;
+    }
+
+    /// 拨号失败，返回下次重拨前的等待时间
+    pub(crate) fn failed(&mut self) -> Duration {
+        let delay = redial_delay(self.failures);
+        self.failures = self.failures.saturating_add(1);
+        self.next_dial = Some(Instant::now() + delay);
+        delay
+    }
+
+    /// 连接断开，稍后重拨
+    pub(crate) fn disconnected(&mut self) {
+        if self.next_dial.is_none() {
+            self.next_dial = Some(Instant::now() + redial_delay(self.failures));
+        }
+    }
+}
+
+/// 第 `failures` 次失败后的重拨等待时间
+fn redial_delay(failures: u32) -> Duration {
+    MIN_REDIAL_DELAY.saturating_mul(1 << failures.min(16)).min(MAX_REDIAL_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_persists_addresses() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("static_peers.json");
+        let addr: Multiaddr = "/ip4/10.0.0.5/tcp/4001".parse().unwrap();
+
+        let mut store = StaticPeerStore::open(&path).unwrap();
+        assert!(store.addresses().is_empty());
+        assert!(store.insert(addr.clone()).unwrap());
+        assert!(!store.insert(addr.clone()).unwrap());
+
+        let mut store = StaticPeerStore::open(&path).unwrap();
+        assert_eq!(store.addresses(), std::slice::from_ref(&addr));
+        assert!(store.remove(&addr).unwrap());
+        assert!(StaticPeerStore::open(&path).unwrap().addresses().is_empty());
+    }
+
+    #[test]
+    fn test_validate_and_backoff() {
+        let local = PeerId::random();
+        let tcp: Multiaddr = "/ip4/10.0.0.5/tcp/4001".parse().unwrap();
+        assert!(validate(&tcp, &local).is_ok());
+        assert!(validate(&"/ip4/10.0.0.5/udp/4001/quic-v1".parse().unwrap(), &local).is_ok());
+        assert!(matches!(
+            validate(&"/ip4/10.0.0.5".parse().unwrap(), &local),
+            Err(StaticPeerError::UnsupportedAddress(_))
+        ));
+        let own = tcp.clone().with(Protocol::P2p(local));
+        assert!(matches!(validate(&own, &local), Err(StaticPeerError::LocalPeer)));
+
+        let relay = PeerId::random();
+        let target = PeerId::random();
+        let circuit = tcp.clone().with(Protocol::P2p(relay)).with(Protocol::P2pCircuit);
+        assert!(matches!(validate(&circuit, &local), Err(StaticPeerError::UnsupportedAddress(_))));
+        let circuit = circuit.with(Protocol::P2p(target));
+        assert!(validate(&circuit, &local).is_ok());
+        assert_eq!(peer_id_of(&circuit), Some(target));
+
+        let mut dial = StaticDial::new(&tcp);
+        assert!(dial.is_due(Instant::now()));
+        dial.dialing();
+        assert!(!dial.is_due(Instant::now()));
+        assert_eq!(dial.failed(), MIN_REDIAL_DELAY);
+        assert_eq!(dial.failed(), MIN_REDIAL_DELAY * 2);
+        for _ in 0..20 {
+            dial.failed();
+        }
+        assert_eq!(dial.failed(), MAX_REDIAL_DELAY);
+        dial.connected(local);
+        assert_eq!(dial.failed(), MIN_REDIAL_DELAY);
+    }
+}
@@ -0,0 +1,206 @@
+//! 传输层选择
+//!
+//! 默认只使用 TCP；启用 QUIC 后同时监听 `/udp/<端口>/quic-v1` 地址：
+//!
+//! - QUIC 连接不绑定源地址，移动设备切换 Wi‑Fi 后重连更快；
+//! - 每个流独立传输，文件分块不会因为 TCP 队头阻塞互相拖慢。
+//!
+//! 拨号时优先尝试对方通告的 QUIC 地址，失败后再回退到 TCP，最后才使用中继地址（`/p2p-circuit`）。
+//! 私有网络的 pnet 握手只能保护 TCP，启用网络密钥时 QUIC 会被禁用。
+
+use crate::network_key::{self, NetworkKey};
+use futures::future::Either;
+use libp2p::{
+    core::{muxing::StreamMuxerBox, transport::Boxed},
+    identity::Keypair,
+    multiaddr::Protocol,
+    Multiaddr, PeerId, Transport,
+};
+use std::fmt;
+
+/// 连接使用的传输协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransportKind {
+    Tcp,
+    Quic,
+    /// 经中继节点转发（`/p2p-circuit`）
+    Relay,
+}
+
+impl TransportKind {
+    /// 根据地址判断传输协议，无法识别时返回 None
+    pub fn from_multiaddr(addr: &Multiaddr) -> Option<Self> {
+        if is_relayed(addr) {
+            return Some(Self::Relay);
+        }
+        addr.iter().find_map(|protocol| match protocol {
+            Protocol::Tcp(_) => Some(Self::Tcp),
+            Protocol::QuicV1 => Some(Self::Quic),
+            _ => None,
+        })
+    }
+
+    /// 显示名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tcp => "TCP",
+            Self::Quic => "QUIC",
+            Self::Relay => "Relay",
+        }
+    }
+
+    /// 优先级（越小越优先）：QUIC、TCP、中继
+    pub(crate) fn rank(&self) -> u8 {
+        match self {
+            Self::Quic => 0,
+            Self::Tcp => 1,
+            Self::Relay => 2,
+        }
+    }
+}
+
+impl fmt::Display for TransportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 地址是否为 QUIC 地址
+pub fn is_quic(addr: &Multiaddr) -> bool {
+    TransportKind::from_multiaddr(addr) == Some(TransportKind::Quic)
+}
+
+/// 地址是否经过中继
+pub fn is_relayed(addr: &Multiaddr) -> bool {
+    addr.iter().any(|protocol| protocol == Protocol::P2pCircuit)
+}
+
+/// 由 TCP 监听地址得到对应的 QUIC 监听地址（`/ip4/x/tcp/p` → `/ip4/x/udp/p/quic-v1`）
+fn quic_listen_address(tcp: &Multiaddr) -> Option<Multiaddr> {
+    let mut iter = tcp.iter();
+    let ip = match iter.next()? {
+        ip @ (Protocol::Ip4(_) | Protocol::Ip6(_)) => ip,
+        _ => return None,
+    };
+    let Protocol::Tcp(port) = iter.next()? else {
+        return None;
+    };
+    if iter.next().is_some() {
+        return None;
+    }
+    Some(Multiaddr::empty().with(ip).with(Protocol::Udp(port)).with(Protocol::QuicV1))
+}
+
+/// 为每个 TCP 监听地址补充对应的 QUIC 地址（已显式给出的 QUIC 地址不重复添加）
+pub fn with_quic_listen_addresses(addresses: Vec<Multiaddr>) -> Vec<Multiaddr> {
+    let extra: Vec<Multiaddr> = addresses
+        .iter()
+        .filter_map(quic_listen_address)
+        .filter(|quic| !addresses.contains(quic))
+        .collect();
+    addresses.into_iter().chain(extra).collect()
+}
+
+/// 按拨号优先级排列地址：QUIC、TCP、中继；未启用 QUIC 时去掉 QUIC 地址
+pub(crate) fn dial_order(mut addresses: Vec<Multiaddr>, quic: bool) -> Vec<Multiaddr> {
+    if !quic {
+        addresses.retain(|addr| !is_quic(addr));
+    }
+    addresses.sort_by_key(|addr| TransportKind::from_multiaddr(addr).map_or(u8::MAX, |kind| kind.rank()));
+    addresses.dedup();
+    addresses
+}
+
+/// 构建 ManagedDiscovery 使用的传输：TCP（可选 pnet 握手），启用时再加上 QUIC
+pub(crate) fn build(
+    keypair: &Keypair,
+    network_key: Option<NetworkKey>,
+    quic: bool,
+) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Box<dyn std::error::Error + Send + Sync>> {
+    let tcp = network_key::tcp_transport(keypair, network_key)?;
+    if !quic {
+        return Ok(tcp);
+    }
+
+    let quic = libp2p::quic::tokio::Transport::new(libp2p::quic::Config::new(keypair))
+        .map(|(peer_id, connection), _| (peer_id, StreamMuxerBox::new(connection)));
+    Ok(quic
+        .or_transport(tcp)
+        .map(|either, _| match either {
+            Either::Left(output) | Either::Right(output) => output,
+        })
+        .boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use libp2p::{identify, swarm::SwarmEvent, Swarm, SwarmBuilder};
+    use std::time::Duration;
+
+    fn swarm(quic: bool) -> Swarm<identify::Behaviour> {
+        SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_other_transport(|key| build(key, None, quic))
+            .unwrap()
+            .with_behaviour(|key| identify::Behaviour::new(identify::Config::new("/localp2p/1.0.0".into(), key.public())))
+            .unwrap()
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(5)))
+            .build()
+    }
+
+    #[test]
+    fn test_addresses() {
+        let tcp: Multiaddr = "/ip4/0.0.0.0/tcp/0".parse().unwrap();
+        let quic: Multiaddr = "/ip4/0.0.0.0/udp/0/quic-v1".parse().unwrap();
+        assert_eq!(TransportKind::from_multiaddr(&tcp), Some(TransportKind::Tcp));
+        assert_eq!(TransportKind::from_multiaddr(&quic), Some(TransportKind::Quic));
+        assert!(is_quic(&quic) && !is_quic(&tcp));
+
+        let ip6: Multiaddr = "/ip6/::/tcp/4001".parse().unwrap();
+        let addrs = with_quic_listen_addresses(vec![tcp.clone(), ip6, quic.clone()]);
+        assert_eq!(addrs.len(), 4);
+        assert!(addrs.contains(&"/ip6/::/udp/4001/quic-v1".parse().unwrap()));
+
+        assert_eq!(dial_order(vec![tcp.clone(), quic.clone()], true), vec![quic.clone(), tcp.clone()]);
+        let relayed: Multiaddr = format!("/ip4/10.0.0.1/tcp/4001/p2p/{}/p2p-circuit", PeerId::random()).parse().unwrap();
+        assert_eq!(TransportKind::from_multiaddr(&relayed), Some(TransportKind::Relay));
+        assert_eq!(
+            dial_order(vec![relayed.clone(), tcp.clone(), quic.clone()], true),
+            vec![quic.clone(), tcp.clone(), relayed]
+        );
+        assert_eq!(dial_order(vec![tcp.clone(), quic], false), vec![tcp]);
+    }
+
+    #[tokio::test]
+    async fn test_quic_connection() {
+        let mut listener = swarm(true);
+        let mut dialer = swarm(true);
+
+        listener.listen_on("/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()).unwrap();
+        let addr: Multiaddr = loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = listener.select_next_some().await {
+                break address;
+            }
+        };
+        dialer.dial(addr).unwrap();
+
+        let run = async {
+            loop {
+                tokio::select! {
+                    _ = listener.select_next_some() => {}
+                    event = dialer.select_next_some() => match event {
+                        SwarmEvent::ConnectionEstablished { endpoint, .. } => {
+                            return TransportKind::from_multiaddr(endpoint.get_remote_address());
+                        }
+                        SwarmEvent::OutgoingConnectionError { .. } => return None,
+                        _ => {}
+                    },
+                }
+            }
+        };
+        let transport = tokio::time::timeout(Duration::from_secs(3), run).await.unwrap_or(None);
+        assert_eq!(transport, Some(TransportKind::Quic));
+    }
+}
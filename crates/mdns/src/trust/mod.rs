@@ -0,0 +1,24 @@
+//! 节点信任模块
+//!
+//! `NodeManager::verify_node_info` 只检查协议版本和代理版本字符串，任何局域网节点都可以通过。
+//! 本模块以 Peer ID 为键持久化信任状态，并通过配对握手让双方用户核对短验证码后建立信任。
+//!
+//! # 模块化设计
+//!
+//! 本模块作为 mdns crate 的扩展，通过 `TrustExtension` trait 为 `ManagedDiscovery` 添加信任管理能力。
+//! 已屏蔽的节点会被拒绝连接；按 [`TrustConfig`] 配置，聊天和文件传输可以只与已信任的节点进行。
+//!
+//! # 架构
+//!
+//! - [`store`] - 信任状态、存储 trait 及内存和文件实现
+//! - [`pairing`] - 配对协议和短验证码
+//! - [`traits`] - TrustExtension trait、策略和事件定义
+
+pub mod pairing;
+pub mod store;
+pub mod traits;
+
+// 公共 API 导出
+pub use pairing::{pairing_codec, PairingCodec, PairingProtocol, PairingRequest, PairingResponse, PAIRING_PROTOCOL};
+pub use store::{FileTrustStore, MemoryTrustStore, TrustEntry, TrustError, TrustState, TrustStore};
+pub use traits::{TrustConfig, TrustEvent, TrustExtension};
@@ -0,0 +1,286 @@
+//! 配对协议
+//!
+//! 两个节点通过承诺-揭示握手交换随机数，然后各自计算相同的短验证码（SAS），
+//! 由双方用户核对一致后确认信任：
+//!
+//! 1. 发起方发送随机数的承诺 `Commit { commitment = SHA-256(nonce_a) }`
+//! 2. 响应方回复自己的随机数 `Nonce { nonce_b }`
+//! 3. 发起方揭示 `Reveal { nonce_a }`，响应方校验承诺
+//! 4. 双方根据两个 Peer ID 和两个随机数计算短验证码并展示给用户
+//! 5. 用户核对后各自确认，`Confirm { accepted }` 通知对方结果
+//!
+//! 承诺保证发起方在看到 `nonce_b` 之前就固定了 `nonce_a`，中间人无法挑选随机数来伪造相同的验证码。
+//!
+//! 同一协议还用于出示身份轮换声明（`Rotate`），让已信任旧 Peer ID 的节点无需重新配对即可信任新 Peer ID。
+
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::store::TrustError;
+use crate::identity::RotationStatement;
+use crate::codec::{Format, FramedProtocol, LengthPrefixedCodec, ProtocolLimits};
+
+/// 配对协议名称
+pub const PAIRING_PROTOCOL: &str = "/localp2p/pairing/1.0.0";
+
+/// 短验证码的位数
+pub const SAS_DIGITS: u32 = 6;
+
+/// 配对请求和响应的最大字节数
+const MAX_PAIRING_FRAME_SIZE: usize = 4 * 1024;
+
+/// 配对协议（标记类型）
+#[derive(Debug, Clone, Default)]
+pub struct PairingProtocol;
+
+impl AsRef<str> for PairingProtocol {
+    fn as_ref(&self) -> &str {
+        PAIRING_PROTOCOL
+    }
+}
+
+impl FramedProtocol for PairingProtocol {
+    fn format(&self) -> Format {
+        Format::Json
+    }
+}
+
+/// 配对请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PairingRequest {
+    /// 发起配对，携带随机数的承诺
+    Commit {
+        /// SHA-256(nonce)
+        commitment: Vec<u8>,
+    },
+    /// 揭示随机数
+    Reveal {
+        /// 发起方随机数
+        nonce: Vec<u8>,
+    },
+    /// 用户核对验证码后的结果
+    Confirm {
+        /// 是否接受
+        accepted: bool,
+    },
+    /// 出示身份轮换声明（发送方为声明中的新 Peer ID）
+    Rotate {
+        /// 旧密钥签署的轮换声明
+        statement: RotationStatement,
+    },
+}
+
+/// 配对响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PairingResponse {
+    /// 响应方随机数
+    Nonce {
+        /// 响应方随机数
+        nonce: Vec<u8>,
+    },
+    /// 已收到
+    Ack,
+    /// 拒绝配对
+    Rejected {
+        /// 拒绝原因
+        reason: String,
+    },
+}
+
+/// 配对 Codec
+pub type PairingCodec = LengthPrefixedCodec<PairingProtocol, PairingRequest, PairingResponse>;
+
+/// 按协议限制创建配对 Codec
+pub fn pairing_codec(limits: &ProtocolLimits) -> PairingCodec {
+    PairingCodec::new(MAX_PAIRING_FRAME_SIZE, MAX_PAIRING_FRAME_SIZE).with_read_timeout(limits.read_timeout)
+}
+
+/// 配对中的角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairingRole {
+    /// 发起方
+    Initiator,
+    /// 响应方
+    Responder,
+}
+
+/// 进行中的配对
+#[derive(Debug, Clone)]
+pub struct PairingSession {
+    /// 本地角色
+    pub role: PairingRole,
+    /// 本地随机数
+    nonce: Vec<u8>,
+    /// 对方的承诺（仅响应方）
+    commitment: Option<Vec<u8>>,
+    /// 计算出的短验证码（握手完成后存在）
+    pub sas: Option<String>,
+    /// 本地用户已确认
+    pub confirmed_locally: bool,
+    /// 对方用户已确认
+    pub confirmed_remotely: bool,
+}
+
+impl PairingSession {
+    /// 作为发起方开始配对，返回会话和要发送的承诺
+    pub fn initiate() -> (Self, PairingRequest) {
+        let nonce = random_nonce();
+        let request = PairingRequest::Commit {
+            commitment: commitment(&nonce),
+        };
+        let session = Self {
+            role: PairingRole::Initiator,
+            nonce,
+            commitment: None,
+            sas: None,
+            confirmed_locally: false,
+            confirmed_remotely: false,
+        };
+        (session, request)
+    }
+
+    /// 作为响应方收到承诺，返回会话和要回复的随机数
+    pub fn respond(commitment: Vec<u8>) -> (Self, PairingResponse) {
+        let nonce = random_nonce();
+        let response = PairingResponse::Nonce { nonce: nonce.clone() };
+        let session = Self {
+            role: PairingRole::Responder,
+            nonce,
+            commitment: Some(commitment),
+            sas: None,
+            confirmed_locally: false,
+            confirmed_remotely: false,
+        };
+        (session, response)
+    }
+
+    /// 发起方收到对方随机数：计算验证码，返回揭示请求
+    pub fn on_nonce(
+        &mut self,
+        local: &PeerId,
+        remote: &PeerId,
+        remote_nonce: &[u8],
+    ) -> Result<PairingRequest, TrustError> {
+        if self.role != PairingRole::Initiator || self.sas.is_some() {
+            return Err(TrustError::PairingFailed("意外的随机数".to_string()));
+        }
+        self.sas = Some(short_auth_string(local, remote, &self.nonce, remote_nonce));
+        Ok(PairingRequest::Reveal { nonce: self.nonce.clone() })
+    }
+
+    /// 响应方收到揭示的随机数：校验承诺并计算验证码
+    pub fn on_reveal(
+        &mut self,
+        local: &PeerId,
+        remote: &PeerId,
+        remote_nonce: &[u8],
+    ) -> Result<&str, TrustError> {
+        let expected = match (&self.role, &self.commitment, &self.sas) {
+            (PairingRole::Responder, Some(expected), None) => expected,
+            _ => return Err(TrustError::PairingFailed("意外的揭示".to_string())),
+        };
+        if commitment(remote_nonce) != *expected {
+            return Err(TrustError::PairingFailed("随机数与承诺不符".to_string()));
+        }
+        Ok(self
+            .sas
+            .insert(short_auth_string(remote, local, remote_nonce, &self.nonce)))
+    }
+
+    /// 双方用户是否都已确认
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_locally && self.confirmed_remotely
+    }
+}
+
+/// 生成 32 字节随机数（系统随机数）
+fn random_nonce() -> Vec<u8> {
+    let mut nonce = vec![0u8; 32];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
+/// 随机数的承诺
+fn commitment(nonce: &[u8]) -> Vec<u8> {
+    Sha256::digest(nonce).to_vec()
+}
+
+/// 计算短验证码（如 "042 917"）
+///
+/// 双方以相同的顺序（发起方在前）输入两个 Peer ID 和两个随机数，得到相同的结果。
+pub fn short_auth_string(
+    initiator: &PeerId,
+    responder: &PeerId,
+    initiator_nonce: &[u8],
+    responder_nonce: &[u8],
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"localp2p-pairing-sas");
+    hasher.update(initiator.to_bytes());
+    hasher.update(responder.to_bytes());
+    hasher.update(initiator_nonce);
+    hasher.update(responder_nonce);
+    let digest = hasher.finalize();
+
+    let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 10u32.pow(SAS_DIGITS);
+    let digits = format!("{:06}", value);
+    format!("{} {}", &digits[..3], &digits[3..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_produces_same_sas() {
+        let alice = PeerId::random();
+        let bob = PeerId::random();
+
+        let (mut initiator, commit) = PairingSession::initiate();
+        let PairingRequest::Commit { commitment } = commit else {
+            panic!("Expected Commit");
+        };
+        let (mut responder, nonce) = PairingSession::respond(commitment);
+        let PairingResponse::Nonce { nonce } = nonce else {
+            panic!("Expected Nonce");
+        };
+
+        let PairingRequest::Reveal { nonce: revealed } = initiator.on_nonce(&alice, &bob, &nonce).unwrap() else {
+            panic!("Expected Reveal");
+        };
+        let sas = responder.on_reveal(&bob, &alice, &revealed).unwrap().to_string();
+
+        assert_eq!(initiator.sas.as_deref(), Some(sas.as_str()));
+        assert_eq!(sas.len(), 7);
+        assert!(sas.replace(' ', "").chars().all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn test_reveal_must_match_commitment() {
+        let alice = PeerId::random();
+        let bob = PeerId::random();
+
+        let (_, commit) = PairingSession::initiate();
+        let PairingRequest::Commit { commitment } = commit else {
+            panic!("Expected Commit");
+        };
+        let (mut responder, _) = PairingSession::respond(commitment);
+
+        let result = responder.on_reveal(&bob, &alice, &random_nonce());
+        assert!(matches!(result, Err(TrustError::PairingFailed(_))));
+        assert!(responder.sas.is_none());
+    }
+
+    #[test]
+    fn test_sas_depends_on_peers() {
+        let alice = PeerId::random();
+        let bob = PeerId::random();
+        let mallory = PeerId::random();
+        let (a, b) = (random_nonce(), random_nonce());
+
+        assert_eq!(short_auth_string(&alice, &bob, &a, &b), short_auth_string(&alice, &bob, &a, &b));
+        assert_ne!(short_auth_string(&alice, &bob, &a, &b), short_auth_string(&alice, &mallory, &a, &b));
+    }
+}
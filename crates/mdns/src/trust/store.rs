@@ -0,0 +1,284 @@
+//! 信任存储
+//!
+//! 以 Peer ID 为键保存节点的信任状态，定义可插拔的 `TrustStore` trait 以及内存和文件两种实现。
+//!
+//! 文件实现把全部记录保存在一个 JSON 文件中，每次修改后整体重写（先写临时文件再重命名）。
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+/// 节点的信任状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustState {
+    /// 未知（从未配对）
+    #[default]
+    Unknown,
+    /// 配对中（等待双方核对短验证码）
+    Pending,
+    /// 已信任（配对完成）
+    Trusted,
+    /// 已屏蔽（拒绝连接）
+    Blocked,
+}
+
+impl TrustState {
+    /// 状态名称（用于日志和 FFI）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Pending => "pending",
+            Self::Trusted => "trusted",
+            Self::Blocked => "blocked",
+        }
+    }
+}
+
+/// 信任记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrustEntry {
+    /// 信任状态
+    pub state: TrustState,
+
+    /// 最后修改时间（Unix 时间戳，毫秒）
+    pub updated_at: i64,
+}
+
+impl TrustEntry {
+    /// 创建当前时间的记录
+    pub fn new(state: TrustState) -> Self {
+        Self {
+            state,
+            updated_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// 信任管理错误类型
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TrustError {
+    /// 信任管理未启用
+    #[error("信任管理未启用")]
+    NotEnabled,
+
+    /// 信任管理已经启用
+    #[error("信任管理已经启用")]
+    AlreadyEnabled,
+
+    /// 节点已被屏蔽
+    #[error("节点已被屏蔽: {0}")]
+    Blocked(String),
+
+    /// 没有进行中的配对
+    #[error("没有进行中的配对: {0}")]
+    NoPairing(String),
+
+    /// 已有进行中的配对
+    #[error("配对进行中: {0}")]
+    PairingInProgress(String),
+
+    /// 配对失败
+    #[error("配对失败: {0}")]
+    PairingFailed(String),
+
+    /// 身份轮换声明无效
+    #[error("身份轮换声明无效: {0}")]
+    InvalidRotation(String),
+
+    /// 存储错误
+    #[error("存储错误: {0}")]
+    Storage(String),
+}
+
+/// 信任存储 trait
+///
+/// `ManagedDiscovery` 在配对完成、屏蔽或解除屏蔽时写入，在建立连接和收发聊天、文件时查询。
+#[async_trait::async_trait]
+pub trait TrustStore: Send + Sync {
+    /// 获取节点的信任记录
+    async fn get(&self, peer_id: &PeerId) -> Result<Option<TrustEntry>, TrustError>;
+
+    /// 设置节点的信任状态（`Unknown` 会删除记录）
+    async fn set(&self, peer_id: &PeerId, state: TrustState) -> Result<(), TrustError>;
+
+    /// 列出所有记录
+    async fn entries(&self) -> Result<Vec<(PeerId, TrustEntry)>, TrustError>;
+
+    /// 获取节点的信任状态（没有记录时为 `Unknown`）
+    async fn state(&self, peer_id: &PeerId) -> Result<TrustState, TrustError> {
+        Ok(self.get(peer_id).await?.map(|entry| entry.state).unwrap_or_default())
+    }
+}
+
+/// 内存存储（不持久化，重启后丢失）
+#[derive(Default)]
+pub struct MemoryTrustStore {
+    entries: RwLock<HashMap<PeerId, TrustEntry>>,
+}
+
+impl MemoryTrustStore {
+    /// 创建新的内存存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl TrustStore for MemoryTrustStore {
+    async fn get(&self, peer_id: &PeerId) -> Result<Option<TrustEntry>, TrustError> {
+        Ok(self.entries.read().await.get(peer_id).cloned())
+    }
+
+    async fn set(&self, peer_id: &PeerId, state: TrustState) -> Result<(), TrustError> {
+        let mut entries = self.entries.write().await;
+        if state == TrustState::Unknown {
+            entries.remove(peer_id);
+        } else {
+            entries.insert(*peer_id, TrustEntry::new(state));
+        }
+        Ok(())
+    }
+
+    async fn entries(&self) -> Result<Vec<(PeerId, TrustEntry)>, TrustError> {
+        Ok(self
+            .entries
+            .read()
+            .await
+            .iter()
+            .map(|(peer_id, entry)| (*peer_id, entry.clone()))
+            .collect())
+    }
+}
+
+/// 文件存储（单个 JSON 文件，键为 Peer ID 字符串）
+pub struct FileTrustStore {
+    /// 存储文件路径
+    path: PathBuf,
+    /// 内存中的记录（启动时从文件加载）
+    entries: RwLock<HashMap<PeerId, TrustEntry>>,
+}
+
+impl FileTrustStore {
+    /// 打开文件存储，文件不存在时从空记录开始
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, TrustError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| TrustError::Storage(format!("无法创建目录 {}: {}", parent.display(), e)))?;
+        }
+
+        let entries = match tokio::fs::read(&path).await {
+            Ok(data) => {
+                let raw: HashMap<String, TrustEntry> = serde_json::from_slice(&data)
+                    .map_err(|e| TrustError::Storage(format!("无法解析 {}: {}", path.display(), e)))?;
+                raw.into_iter()
+                    .filter_map(|(peer_id, entry)| match peer_id.parse() {
+                        Ok(peer_id) => Some((peer_id, entry)),
+                        Err(_) => {
+                            tracing::warn!("跳过无效的信任记录: {}", peer_id);
+                            None
+                        }
+                    })
+                    .collect()
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(TrustError::Storage(e.to_string())),
+        };
+
+        Ok(Self {
+            path,
+            entries: RwLock::new(entries),
+        })
+    }
+
+    /// 存储文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 把全部记录写回文件
+    async fn save(&self, entries: &HashMap<PeerId, TrustEntry>) -> Result<(), TrustError> {
+        let raw: HashMap<String, &TrustEntry> = entries
+            .iter()
+            .map(|(peer_id, entry)| (peer_id.to_string(), entry))
+            .collect();
+        let data = serde_json::to_vec_pretty(&raw).map_err(|e| TrustError::Storage(e.to_string()))?;
+
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, data)
+            .await
+            .map_err(|e| TrustError::Storage(e.to_string()))?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .map_err(|e| TrustError::Storage(e.to_string()))
+    }
+}
+
+#[async_trait::async_trait]
+impl TrustStore for FileTrustStore {
+    async fn get(&self, peer_id: &PeerId) -> Result<Option<TrustEntry>, TrustError> {
+        Ok(self.entries.read().await.get(peer_id).cloned())
+    }
+
+    async fn set(&self, peer_id: &PeerId, state: TrustState) -> Result<(), TrustError> {
+        let mut entries = self.entries.write().await;
+        if state == TrustState::Unknown {
+            entries.remove(peer_id);
+        } else {
+            entries.insert(*peer_id, TrustEntry::new(state));
+        }
+        self.save(&entries).await
+    }
+
+    async fn entries(&self) -> Result<Vec<(PeerId, TrustEntry)>, TrustError> {
+        Ok(self
+            .entries
+            .read()
+            .await
+            .iter()
+            .map(|(peer_id, entry)| (*peer_id, entry.clone()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_store_states() {
+        let store = MemoryTrustStore::new();
+        let peer = PeerId::random();
+
+        assert_eq!(store.state(&peer).await.unwrap(), TrustState::Unknown);
+        store.set(&peer, TrustState::Trusted).await.unwrap();
+        assert_eq!(store.state(&peer).await.unwrap(), TrustState::Trusted);
+
+        // 设置为 Unknown 等于删除记录
+        store.set(&peer, TrustState::Unknown).await.unwrap();
+        assert!(store.get(&peer).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_store_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trust.json");
+        let trusted = PeerId::random();
+        let blocked = PeerId::random();
+
+        {
+            let store = FileTrustStore::open(&path).await.unwrap();
+            store.set(&trusted, TrustState::Trusted).await.unwrap();
+            store.set(&blocked, TrustState::Blocked).await.unwrap();
+        }
+
+        let store = FileTrustStore::open(&path).await.unwrap();
+        assert_eq!(store.state(&trusted).await.unwrap(), TrustState::Trusted);
+        assert_eq!(store.state(&blocked).await.unwrap(), TrustState::Blocked);
+        assert_eq!(store.entries().await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,151 @@
+//! 信任扩展 trait
+//!
+//! 定义扩展 ManagedDiscovery 的接口，使其支持节点配对和信任管理。
+
+use super::store::{TrustError, TrustState, TrustStore};
+use crate::identity::RotationStatement;
+use libp2p::PeerId;
+use std::sync::Arc;
+
+/// 信任策略
+///
+/// 默认只拒绝已屏蔽的节点；开启对应选项后，聊天和文件传输只与已信任的节点进行。
+#[derive(Debug, Clone, Default)]
+pub struct TrustConfig {
+    /// 聊天只与已信任的节点进行
+    pub require_trusted_chat: bool,
+
+    /// 文件传输只与已信任的节点进行
+    pub require_trusted_files: bool,
+}
+
+impl TrustConfig {
+    /// 创建默认策略
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 聊天是否要求已信任
+    pub fn with_require_trusted_chat(mut self, required: bool) -> Self {
+        self.require_trusted_chat = required;
+        self
+    }
+
+    /// 文件传输是否要求已信任
+    pub fn with_require_trusted_files(mut self, required: bool) -> Self {
+        self.require_trusted_files = required;
+        self
+    }
+}
+
+/// 信任扩展 trait
+///
+/// 为 ManagedDiscovery 提供可选的配对和信任管理能力。
+///
+/// # 示例
+///
+/// ```no_run
+/// use mdns::{ManagedDiscovery, TrustExtension, TrustConfig, FileTrustStore};
+/// use std::sync::Arc;
+///
+/// # async fn example(mut discovery: ManagedDiscovery, peer_id: libp2p::PeerId) -> Result<(), Box<dyn std::error::Error>> {
+/// let store = Arc::new(FileTrustStore::open("trust.json").await?);
+/// discovery
+///     .enable_trust(store, TrustConfig::new().with_require_trusted_chat(true))
+///     .await?;
+///
+/// // 发起配对，双方收到 TrustEvent::PairingSas 后核对验证码
+/// discovery.request_pairing(peer_id).await?;
+/// discovery.confirm_pairing(peer_id, true).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait::async_trait]
+pub trait TrustExtension {
+    /// 启用信任管理
+    ///
+    /// # 错误
+    ///
+    /// 返回错误如果信任管理已经启用。
+    async fn enable_trust(&mut self, store: Arc<dyn TrustStore>, config: TrustConfig) -> Result<(), TrustError>;
+
+    /// 向节点发起配对
+    ///
+    /// # 错误
+    ///
+    /// - `TrustError::NotEnabled` - 信任管理未启用
+    /// - `TrustError::Blocked` - 节点已被屏蔽
+    /// - `TrustError::PairingInProgress` - 已有进行中的配对
+    async fn request_pairing(&mut self, peer_id: PeerId) -> Result<(), TrustError>;
+
+    /// 用户核对验证码后确认或拒绝配对
+    ///
+    /// # 错误
+    ///
+    /// - `TrustError::NotEnabled` - 信任管理未启用
+    /// - `TrustError::NoPairing` - 没有已生成验证码的配对
+    async fn confirm_pairing(&mut self, peer_id: PeerId, accepted: bool) -> Result<(), TrustError>;
+
+    /// 直接设置节点的信任状态（例如屏蔽或取消信任）
+    ///
+    /// 屏蔽节点会立即断开与其的连接。
+    async fn set_trust_state(&mut self, peer_id: PeerId, state: TrustState) -> Result<(), TrustError>;
+
+    /// 出示本节点的身份轮换声明
+    ///
+    /// 声明会发送给当前已连接的节点，以及之后验证通过的节点。信任旧 Peer ID 的节点收到后
+    /// 改为信任本节点，屏蔽旧 Peer ID，并把聊天记录迁移到本节点名下。
+    ///
+    /// # 错误
+    ///
+    /// 返回 `TrustError::InvalidRotation` 如果签名无效或声明中的新 Peer ID 不是本节点。
+    fn announce_rotation(&mut self, statement: RotationStatement) -> Result<(), TrustError>;
+
+    /// 获取信任存储（用于查询节点的信任状态）
+    fn trust_store(&self) -> Option<Arc<dyn TrustStore>>;
+
+    /// 检查信任管理是否已启用
+    fn is_trust_enabled(&self) -> bool;
+}
+
+/// 信任事件
+#[derive(Debug, Clone)]
+pub enum TrustEvent {
+    /// 收到配对请求
+    PairingRequested {
+        /// 发起方的 Peer ID
+        peer_id: PeerId,
+    },
+
+    /// 握手完成，需要用户核对短验证码
+    PairingSas {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 短验证码（双方应看到相同的值）
+        sas: String,
+    },
+
+    /// 配对失败或被对方拒绝
+    PairingFailed {
+        /// 对方的 Peer ID
+        peer_id: PeerId,
+        /// 失败原因
+        reason: String,
+    },
+
+    /// 已信任的节点轮换了身份（信任和聊天记录已迁移到新 Peer ID）
+    IdentityRotated {
+        /// 旧 Peer ID（已屏蔽）
+        old_peer_id: PeerId,
+        /// 新 Peer ID（已信任）
+        new_peer_id: PeerId,
+    },
+
+    /// 信任状态变化
+    TrustChanged {
+        /// 节点的 Peer ID
+        peer_id: PeerId,
+        /// 新状态
+        state: TrustState,
+    },
+}
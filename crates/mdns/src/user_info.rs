@@ -1,18 +1,134 @@
 //! 用户信息交换协议
 //!
 //! 自定义协议，用于在节点之间交换用户信息（设备名称、用户名、头像等）。
+//!
+//! 2.0.0 版本的响应是 libp2p 签名信封（[`SignedUserInfo`]），载荷为带序列号的用户信息记录：
+//! 接收方核对签名密钥就是对方的 Peer ID，并拒绝序列号比已知记录更旧的重放。
+//! 签名记录可以脱离连接缓存和转存，重新加载时仍可验证。
+//! 与只支持 1.0.0 的旧版节点通信时，响应降级为未签名的用户信息。
+//!
+//! 本地用户信息变化时，2.0.0 节点之间通过 [`UserInfoRequest::Update`] 主动推送新的签名记录，
+//! 对方在响应中返回自己的记录。旧版节点只在首次连接时获取一次用户信息。
 
 use async_trait::async_trait;
-use futures::{AsyncRead, AsyncWrite, AsyncReadExt, AsyncWriteExt};
-use libp2p::request_response;
+use futures::{AsyncRead, AsyncWrite};
+use libp2p::{core::SignedEnvelope, identity::Keypair, request_response, PeerId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::codec::{read_message, write_message, Format, FramedProtocol, ProtocolLimits};
 
 /// 用户信息协议名称
 pub const USER_INFO_PROTOCOL: &str = "/localp2p/user-info/1.0.0";
 
+/// 用户信息协议名称（CBOR 变体）
+pub const USER_INFO_PROTOCOL_CBOR: &str = "/localp2p/user-info/1.0.0/cbor";
+
+/// 签名用户信息协议名称
+pub const SIGNED_USER_INFO_PROTOCOL: &str = "/localp2p/user-info/2.0.0";
+
+/// 签名用户信息协议名称（CBOR 变体）
+pub const SIGNED_USER_INFO_PROTOCOL_CBOR: &str = "/localp2p/user-info/2.0.0/cbor";
+
+/// 签名信封的域分隔字符串
+const ENVELOPE_DOMAIN: &str = "localp2p-user-info";
+
+/// 签名信封的载荷类型
+const ENVELOPE_PAYLOAD_TYPE: &[u8] = b"/localp2p/user-info/record";
+
+/// 用户信息错误
+#[derive(Debug, thiserror::Error)]
+pub enum UserInfoError {
+    /// 签名失败
+    #[error("签名用户信息失败: {0}")]
+    Signing(String),
+
+    /// 信封或记录格式错误
+    #[error("用户信息记录无效: {0}")]
+    InvalidRecord(String),
+
+    /// 签名无效
+    #[error("用户信息签名无效")]
+    InvalidSignature,
+
+    /// 签名密钥与发送方不一致
+    #[error("用户信息不是由 {0} 签名的")]
+    PeerMismatch(String),
+
+    /// 序列号比已知记录更旧（重放）
+    #[error("用户信息已过期: 序列号 {received} 早于已知的 {known}")]
+    Stale {
+        /// 收到的序列号
+        received: u64,
+        /// 已知的序列号
+        known: u64,
+    },
+}
+
+/// 在线状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Presence {
+    /// 在线
+    #[default]
+    Online,
+    /// 离开（一段时间没有操作）
+    Away,
+    /// 忙碌
+    Busy,
+    /// 请勿打扰（不显示对方的正在输入提示，收到消息不发出通知）
+    DoNotDisturb,
+    /// 隐身（其他节点应显示为离线）
+    Invisible,
+}
+
+impl Presence {
+    /// 所有状态
+    pub const ALL: [Presence; 5] = [Self::Online, Self::Away, Self::Busy, Self::DoNotDisturb, Self::Invisible];
+
+    /// 协议中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Online => "online",
+            Self::Away => "away",
+            Self::Busy => "busy",
+            Self::DoNotDisturb => "do_not_disturb",
+            Self::Invisible => "invisible",
+        }
+    }
+
+    /// 显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Online => "在线",
+            Self::Away => "离开",
+            Self::Busy => "忙碌",
+            Self::DoNotDisturb => "请勿打扰",
+            Self::Invisible => "隐身",
+        }
+    }
+
+    /// 解析协议名称、显示名称或常见缩写（旧版节点的 status 字符串）
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        Self::ALL
+            .into_iter()
+            .find(|presence| presence.as_str().eq_ignore_ascii_case(s) || presence.label() == s)
+            .or(match s {
+                "dnd" | "DND" | "勿扰" => Some(Self::DoNotDisturb),
+                _ => None,
+            })
+    }
+
+    /// 是否处于请勿打扰状态
+    pub fn is_do_not_disturb(self) -> bool {
+        self == Self::DoNotDisturb
+    }
+}
+
 /// 用户信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserInfo {
     /// 设备名称（如："我的电脑"、"客厅电视"）
     pub device_name: String,
@@ -20,12 +136,26 @@ pub struct UserInfo {
     /// 用户昵称（可选）
     pub nickname: Option<String>,
 
-    /// 头像 URL（可选）
+    /// 头像 URL（可选，局域网离线时通常无法访问，优先使用 `avatar_hash`）
     pub avatar_url: Option<String>,
 
-    /// 用户状态（如："在线"、"忙碌"）
+    /// 头像内容的 SHA-256（可选，图片数据通过头像协议获取，见 [`crate::avatar`]）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_hash: Option<String>,
+
+    /// 状态说明（自定义文字，如："开会中"）
+    ///
+    /// 旧版节点在这里发送 "在线"、"忙碌" 等状态名称，见 [`UserInfo::presence`]。
     pub status: Option<String>,
 
+    /// 在线状态（旧版节点不发送）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence: Option<Presence>,
+
+    /// 最后活跃时间（Unix 时间戳，毫秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_active: Option<i64>,
+
     /// 自定义扩展数据
     #[serde(flatten)]
     pub custom_data: HashMap<String, String>,
@@ -38,7 +168,10 @@ impl UserInfo {
             device_name,
             nickname: None,
             avatar_url: None,
+            avatar_hash: None,
             status: None,
+            presence: None,
+            last_active: None,
             custom_data: HashMap::new(),
         }
     }
@@ -55,12 +188,56 @@ impl UserInfo {
         self
     }
 
-    /// 设置状态
+    /// 设置头像哈希
+    pub fn with_avatar_hash(mut self, hash: String) -> Self {
+        self.avatar_hash = Some(hash);
+        self
+    }
+
+    /// 设置状态说明
     pub fn with_status(mut self, status: String) -> Self {
         self.status = Some(status);
         self
     }
 
+    /// 设置在线状态
+    pub fn with_presence(mut self, presence: Presence) -> Self {
+        self.presence = Some(presence);
+        self
+    }
+
+    /// 设置最后活跃时间（Unix 时间戳，毫秒）
+    pub fn with_last_active(mut self, timestamp: i64) -> Self {
+        self.last_active = Some(timestamp);
+        self
+    }
+
+    /// 获取在线状态
+    ///
+    /// 旧版节点没有 `presence` 字段，从 status 字符串中识别，无法识别时视为在线。
+    pub fn presence(&self) -> Presence {
+        self.presence
+            .or_else(|| self.status.as_deref().and_then(Presence::parse))
+            .unwrap_or_default()
+    }
+
+    /// 转换为旧版节点能理解的形式：没有状态说明时把在线状态名称写入 status
+    pub fn into_legacy(mut self) -> Self {
+        if self.status.is_none() {
+            self.status = self.presence.map(|presence| presence.label().to_string());
+        }
+        self
+    }
+
+    /// 获取状态说明（旧版节点的状态名称不算作说明）
+    pub fn status_message(&self) -> Option<&str> {
+        let status = self.status.as_deref()?;
+        if self.presence.is_none() && Presence::parse(status).is_some() {
+            return None;
+        }
+        Some(status)
+    }
+
     /// 添加自定义数据
     pub fn with_custom_data(mut self, key: String, value: String) -> Self {
         self.custom_data.insert(key, value);
@@ -76,28 +253,205 @@ impl UserInfo {
     }
 }
 
-/// 用户信息协议（标记类型）
-#[derive(Debug, Clone, Default)]
-pub struct UserInfoProtocol;
+/// 签名信封中的用户信息记录
+///
+/// 与 [`UserInfo`] 的 JSON 不同，自定义数据不展开到顶层，不会与固定字段冲突。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UserInfoRecord {
+    seq: u64,
+    device_name: String,
+    nickname: Option<String>,
+    avatar_url: Option<String>,
+    #[serde(default)]
+    avatar_hash: Option<String>,
+    status: Option<String>,
+    #[serde(default)]
+    presence: Option<Presence>,
+    #[serde(default)]
+    last_active: Option<i64>,
+    #[serde(default)]
+    custom_data: HashMap<String, String>,
+}
+
+/// 验证通过的用户信息记录
+#[derive(Debug, Clone)]
+pub struct VerifiedUserInfo {
+    /// 签名者的 Peer ID
+    pub peer_id: PeerId,
+    /// 序列号（越大越新）
+    pub seq: u64,
+    /// 用户信息
+    pub info: UserInfo,
+}
+
+/// 签名的用户信息（libp2p 签名信封的 Protobuf 编码）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedUserInfo {
+    /// 签名信封
+    pub envelope: Vec<u8>,
+}
+
+impl SignedUserInfo {
+    /// 使用本地密钥签名用户信息
+    pub fn sign(keypair: &Keypair, info: &UserInfo, seq: u64) -> Result<Self, UserInfoError> {
+        let record = UserInfoRecord {
+            seq,
+            device_name: info.device_name.clone(),
+            nickname: info.nickname.clone(),
+            avatar_url: info.avatar_url.clone(),
+            avatar_hash: info.avatar_hash.clone(),
+            status: info.status.clone(),
+            presence: info.presence,
+            last_active: info.last_active,
+            custom_data: info.custom_data.clone(),
+        };
+        let payload = serde_json::to_vec(&record).map_err(|e| UserInfoError::Signing(e.to_string()))?;
+        let envelope = SignedEnvelope::new(
+            keypair,
+            ENVELOPE_DOMAIN.to_string(),
+            ENVELOPE_PAYLOAD_TYPE.to_vec(),
+            payload,
+        )
+        .map_err(|e| UserInfoError::Signing(e.to_string()))?;
+
+        Ok(Self {
+            envelope: envelope.into_protobuf_encoding(),
+        })
+    }
+
+    /// 验证签名并解析记录
+    ///
+    /// 只证明记录由返回的 `peer_id` 签名，调用者需要核对它是否为期望的节点。
+    pub fn verify(&self) -> Result<VerifiedUserInfo, UserInfoError> {
+        let envelope = SignedEnvelope::from_protobuf_encoding(&self.envelope)
+            .map_err(|e| UserInfoError::InvalidRecord(e.to_string()))?;
+        let (payload, key) = envelope
+            .payload_and_signing_key(ENVELOPE_DOMAIN.to_string(), ENVELOPE_PAYLOAD_TYPE)
+            .map_err(|_| UserInfoError::InvalidSignature)?;
+        let record: UserInfoRecord =
+            serde_json::from_slice(payload).map_err(|e| UserInfoError::InvalidRecord(e.to_string()))?;
+
+        Ok(VerifiedUserInfo {
+            peer_id: key.to_peer_id(),
+            seq: record.seq,
+            info: UserInfo {
+                device_name: record.device_name,
+                nickname: record.nickname,
+                avatar_url: record.avatar_url,
+                avatar_hash: record.avatar_hash,
+                status: record.status,
+                presence: record.presence,
+                last_active: record.last_active,
+                custom_data: record.custom_data,
+            },
+        })
+    }
+
+    /// 验证记录由 `peer_id` 签名，且不比已知的序列号 `known_seq` 更旧
+    pub fn verify_from(&self, peer_id: &PeerId, known_seq: Option<u64>) -> Result<VerifiedUserInfo, UserInfoError> {
+        let verified = self.verify()?;
+        if verified.peer_id != *peer_id {
+            return Err(UserInfoError::PeerMismatch(peer_id.to_string()));
+        }
+        if let Some(known) = known_seq.filter(|known| verified.seq < *known) {
+            return Err(UserInfoError::Stale { received: verified.seq, known });
+        }
+        Ok(verified)
+    }
+}
+
+/// 生成下一个序列号（毫秒时间戳，且严格大于上一个）
+pub(crate) fn next_seq(last: u64) -> u64 {
+    let now = u64::try_from(chrono::Utc::now().timestamp_millis()).unwrap_or_default();
+    now.max(last.saturating_add(1))
+}
+
+/// 用户信息协议
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UserInfoProtocol {
+    /// 1.0.0 JSON 格式（所有节点都支持，未签名）
+    #[default]
+    Json,
+    /// 1.0.0 CBOR 格式（需双方启用，未签名）
+    Cbor,
+    /// 2.0.0 签名记录，JSON 格式
+    Signed,
+    /// 2.0.0 签名记录，CBOR 格式（需双方启用）
+    SignedCbor,
+}
+
+impl UserInfoProtocol {
+    /// 支持的协议（按优先级排序）
+    ///
+    /// 优先协商签名版本；`cbor` 为 `true` 时同一版本优先协商 CBOR 变体。
+    pub fn supported(cbor: bool) -> Vec<Self> {
+        if cbor {
+            vec![Self::SignedCbor, Self::Signed, Self::Cbor, Self::Json]
+        } else {
+            vec![Self::Signed, Self::Json]
+        }
+    }
+
+    /// 响应是否为签名记录
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Self::Signed | Self::SignedCbor)
+    }
+}
 
 impl AsRef<str> for UserInfoProtocol {
     fn as_ref(&self) -> &str {
-        USER_INFO_PROTOCOL
+        match self {
+            Self::Json => USER_INFO_PROTOCOL,
+            Self::Cbor => USER_INFO_PROTOCOL_CBOR,
+            Self::Signed => SIGNED_USER_INFO_PROTOCOL,
+            Self::SignedCbor => SIGNED_USER_INFO_PROTOCOL_CBOR,
+        }
     }
 }
 
-/// 用户信息请求（空）
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserInfoRequest;
+impl FramedProtocol for UserInfoProtocol {
+    fn format(&self) -> Format {
+        match self {
+            Self::Json | Self::Signed => Format::Json,
+            Self::Cbor | Self::SignedCbor => Format::Cbor,
+        }
+    }
+}
+
+/// 用户信息请求
+#[derive(Debug, Clone)]
+pub enum UserInfoRequest {
+    /// 获取对方的用户信息（线上格式为空值，与 1.0.0 兼容）
+    Fetch,
+    /// 推送本地更新后的签名记录（仅 2.0.0）
+    Update(SignedUserInfo),
+}
 
 /// 用户信息响应
-pub type UserInfoResponse = UserInfo;
+#[derive(Debug, Clone)]
+pub enum UserInfoResponse {
+    /// 签名记录（2.0.0）
+    Signed(SignedUserInfo),
+    /// 未签名的用户信息（旧版节点，1.0.0）
+    Unsigned(UserInfo),
+}
 
 /// 用户信息 Codec
 ///
-/// 使用 JSON 序列化，带长度前缀的分帧协议。
-#[derive(Debug, Clone, Default)]
-pub struct UserInfoCodec;
+/// 带长度前缀的分帧协议，序列化格式由协商到的协议决定。
+/// 对方只支持 1.0.0 时，签名记录降级为其中的未签名用户信息。
+#[derive(Debug, Clone)]
+pub struct UserInfoCodec {
+    max_request_size: usize,
+    max_response_size: usize,
+    read_timeout: Duration,
+}
+
+impl Default for UserInfoCodec {
+    fn default() -> Self {
+        user_info_codec(&ProtocolLimits::default())
+    }
+}
 
 #[async_trait]
 impl request_response::Codec for UserInfoCodec {
@@ -105,92 +459,78 @@ impl request_response::Codec for UserInfoCodec {
     type Request = UserInfoRequest;
     type Response = UserInfoResponse;
 
-    async fn read_request<T>(
-        &mut self,
-        _protocol: &Self::Protocol,
-        io: &mut T,
-    ) -> std::io::Result<Self::Request>
+    async fn read_request<T>(&mut self, protocol: &Self::Protocol, io: &mut T) -> std::io::Result<Self::Request>
     where
         T: AsyncRead + Unpin + Send,
     {
-        // 读取长度前缀（u32 big endian）
-        let mut len_bytes = [0u8; 4];
-        io.read_exact(&mut len_bytes).await?;
-        let len = u32::from_be_bytes(len_bytes) as usize;
-
-        // 读取 JSON 数据
-        let mut buffer = vec![0u8; len];
-        io.read_exact(&mut buffer).await?;
+        let format = protocol.format();
+        if !protocol.is_signed() {
+            read_message::<_, ()>(io, format, self.max_request_size, self.read_timeout).await?;
+            return Ok(UserInfoRequest::Fetch);
+        }
 
-        // 解析 JSON（UserInfoRequest 是空结构体，任何 JSON 都可以解析）
-        serde_json::from_slice::<UserInfoRequest>(&buffer)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        let update: Option<SignedUserInfo> =
+            read_message(io, format, self.max_request_size, self.read_timeout).await?;
+        Ok(update.map_or(UserInfoRequest::Fetch, UserInfoRequest::Update))
     }
 
-    async fn read_response<T>(
-        &mut self,
-        _protocol: &Self::Protocol,
-        io: &mut T,
-    ) -> std::io::Result<Self::Response>
+    async fn read_response<T>(&mut self, protocol: &Self::Protocol, io: &mut T) -> std::io::Result<Self::Response>
     where
         T: AsyncRead + Unpin + Send,
     {
-        // 读取长度前缀（u32 big endian）
-        let mut len_bytes = [0u8; 4];
-        io.read_exact(&mut len_bytes).await?;
-        let len = u32::from_be_bytes(len_bytes) as usize;
-
-        // 读取 JSON 数据
-        let mut buffer = vec![0u8; len];
-        io.read_exact(&mut buffer).await?;
-
-        // 解析 JSON
-        serde_json::from_slice::<UserInfo>(&buffer)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
-    }
-
-    async fn write_request<T>(
-        &mut self,
-        _protocol: &Self::Protocol,
-        io: &mut T,
-        req: Self::Request,
-    ) -> std::io::Result<()>
+        let format = protocol.format();
+        if protocol.is_signed() {
+            read_message(io, format, self.max_response_size, self.read_timeout).await.map(UserInfoResponse::Signed)
+        } else {
+            read_message(io, format, self.max_response_size, self.read_timeout).await.map(UserInfoResponse::Unsigned)
+        }
+    }
+
+    async fn write_request<T>(&mut self, protocol: &Self::Protocol, io: &mut T, req: Self::Request) -> std::io::Result<()>
     where
         T: AsyncWrite + Unpin + Send,
     {
-        // 序列化为 JSON
-        let data = serde_json::to_vec(&req)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-
-        // 写入长度前缀（u32 big endian）
-        let len = data.len() as u32;
-        io.write_all(&len.to_be_bytes()).await?;
-
-        // 写入数据
-        io.write_all(&data).await?;
-        io.flush().await
+        match req {
+            UserInfoRequest::Fetch => write_message(io, protocol.format(), ()).await,
+            UserInfoRequest::Update(signed) if protocol.is_signed() => {
+                write_message(io, protocol.format(), Some(signed)).await
+            }
+            UserInfoRequest::Update(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "对方使用用户信息协议 1.0.0，不支持推送更新",
+            )),
+        }
     }
 
-    async fn write_response<T>(
-        &mut self,
-        _protocol: &Self::Protocol,
-        io: &mut T,
-        res: Self::Response,
-    ) -> std::io::Result<()>
+    async fn write_response<T>(&mut self, protocol: &Self::Protocol, io: &mut T, res: Self::Response) -> std::io::Result<()>
     where
         T: AsyncWrite + Unpin + Send,
     {
-        // 序列化为 JSON
-        let data = serde_json::to_vec(&res)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-
-        // 写入长度前缀（u32 big endian）
-        let len = data.len() as u32;
-        io.write_all(&len.to_be_bytes()).await?;
+        let format = protocol.format();
+        match (protocol.is_signed(), res) {
+            (true, UserInfoResponse::Signed(signed)) => write_message(io, format, signed).await,
+            (false, UserInfoResponse::Unsigned(info)) => write_message(io, format, info).await,
+            // 对方只支持 1.0.0 时降级为未签名的用户信息
+            (false, UserInfoResponse::Signed(signed)) => {
+                let verified = signed
+                    .verify()
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+                write_message(io, format, verified.info.into_legacy()).await
+            }
+            (true, UserInfoResponse::Unsigned(_)) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "签名用户信息协议不能发送未签名的用户信息",
+            )),
+        }
+    }
+}
 
-        // 写入数据
-        io.write_all(&data).await?;
-        io.flush().await
+/// 按协议限制创建用户信息 Codec
+pub fn user_info_codec(limits: &ProtocolLimits) -> UserInfoCodec {
+    UserInfoCodec {
+        max_request_size: limits.user_info_request,
+        max_response_size: limits.user_info_response,
+        read_timeout: limits.read_timeout,
     }
 }
 
@@ -219,6 +559,30 @@ mod tests {
         assert_eq!(info2.display_name(), "设备");
     }
 
+    #[test]
+    fn test_presence() {
+        // 旧版节点只发送 status 字符串
+        let legacy: UserInfo = serde_json::from_str(r#"{"device_name":"旧设备","nickname":null,"avatar_url":null,"status":"忙碌"}"#).unwrap();
+        assert_eq!(legacy.presence(), Presence::Busy);
+        assert_eq!(legacy.status_message(), None);
+
+        let info = UserInfo::new("设备".to_string())
+            .with_presence(Presence::DoNotDisturb)
+            .with_status("开会中".to_string())
+            .with_last_active(1_700_000_000_000);
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains(r#""presence":"do_not_disturb""#));
+        let decoded: UserInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, info);
+        assert!(decoded.presence().is_do_not_disturb());
+        assert_eq!(decoded.status_message(), Some("开会中"));
+
+        assert_eq!(UserInfo::new("设备".to_string()).presence(), Presence::Online);
+        assert_eq!(Presence::parse("dnd"), Some(Presence::DoNotDisturb));
+        assert_eq!(Presence::parse("Away"), Some(Presence::Away));
+        assert_eq!(Presence::parse("开会中"), None);
+    }
+
     #[test]
     fn test_user_info_serialization() {
         let info = UserInfo::new("我的电脑".to_string())
@@ -233,4 +597,120 @@ mod tests {
         assert_eq!(info.nickname, deserialized.nickname);
         assert_eq!(info.status, deserialized.status);
     }
+
+    #[test]
+    fn test_user_info_cbor_serialization() {
+        let info = UserInfo::new("我的电脑".to_string())
+            .with_nickname("开发者".to_string())
+            .with_custom_data("os".to_string(), "linux".to_string());
+
+        let encoded = Format::Cbor.encode(&info).unwrap();
+        assert!(encoded.len() < Format::Json.encode(&info).unwrap().len());
+
+        let decoded: UserInfo = Format::Cbor.decode(&encoded).unwrap();
+        assert_eq!(decoded.nickname, info.nickname);
+        assert_eq!(decoded.custom_data.get("os").map(String::as_str), Some("linux"));
+        assert_eq!(UserInfoProtocol::supported(true)[0].as_ref(), SIGNED_USER_INFO_PROTOCOL_CBOR);
+    }
+
+    #[test]
+    fn test_signed_user_info() {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+        let info = UserInfo::new("我的电脑".to_string())
+            .with_avatar_hash(crate::avatar::avatar_hash(b"avatar"))
+            .with_custom_data("nickname".to_string(), "不会覆盖昵称".to_string());
+
+        let signed = SignedUserInfo::sign(&keypair, &info, 2).unwrap();
+        let verified = signed.verify_from(&peer_id, Some(1)).unwrap();
+        assert_eq!(verified.seq, 2);
+        assert_eq!(verified.info, info);
+        assert_eq!(verified.info.nickname, None);
+        assert_eq!(verified.info.custom_data.get("nickname").map(String::as_str), Some("不会覆盖昵称"));
+
+        // 其他节点的记录、旧序列号和篡改的信封都会被拒绝
+        assert!(matches!(
+            signed.verify_from(&PeerId::random(), None),
+            Err(UserInfoError::PeerMismatch(_))
+        ));
+        assert!(matches!(
+            signed.verify_from(&peer_id, Some(3)),
+            Err(UserInfoError::Stale { received: 2, known: 3 })
+        ));
+        let mut tampered = signed.clone();
+        let last = tampered.envelope.len() - 1;
+        tampered.envelope[last] ^= 0xff;
+        assert!(tampered.verify().is_err());
+
+        assert!(next_seq(2) > 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_request() {
+        use futures::io::Cursor;
+        use libp2p::request_response::Codec;
+
+        let keypair = Keypair::generate_ed25519();
+        let signed = SignedUserInfo::sign(&keypair, &UserInfo::new("设备".to_string()), 1).unwrap();
+        let mut codec = UserInfoCodec::default();
+
+        // 获取请求在所有协议上的编码相同（空值）
+        for protocol in [UserInfoProtocol::Json, UserInfoProtocol::Signed] {
+            let mut io = Cursor::new(Vec::new());
+            codec.write_request(&protocol, &mut io, UserInfoRequest::Fetch).await.unwrap();
+            assert_eq!(&io.get_ref()[4..], b"null");
+            io.set_position(0);
+            assert!(matches!(codec.read_request(&protocol, &mut io).await.unwrap(), UserInfoRequest::Fetch));
+        }
+
+        let mut io = Cursor::new(Vec::new());
+        codec
+            .write_request(&UserInfoProtocol::SignedCbor, &mut io, UserInfoRequest::Update(signed.clone()))
+            .await
+            .unwrap();
+        io.set_position(0);
+        match codec.read_request(&UserInfoProtocol::SignedCbor, &mut io).await.unwrap() {
+            UserInfoRequest::Update(received) => assert_eq!(received, signed),
+            UserInfoRequest::Fetch => panic!("应解析为更新请求"),
+        }
+
+        // 旧版协议不支持推送
+        let result = codec
+            .write_request(&UserInfoProtocol::Json, &mut Cursor::new(Vec::new()), UserInfoRequest::Update(signed))
+            .await;
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    }
+
+    #[tokio::test]
+    async fn test_signed_response_downgraded_for_v1() {
+        use futures::io::Cursor;
+        use libp2p::request_response::Codec;
+
+        let keypair = Keypair::generate_ed25519();
+        let info = UserInfo::new("设备".to_string()).with_presence(Presence::Busy);
+        let signed = SignedUserInfo::sign(&keypair, &info, 1).unwrap();
+
+        for protocol in [UserInfoProtocol::Json, UserInfoProtocol::Signed, UserInfoProtocol::SignedCbor] {
+            let mut io = Cursor::new(Vec::new());
+            let mut codec = UserInfoCodec::default();
+            codec
+                .write_response(&protocol, &mut io, UserInfoResponse::Signed(signed.clone()))
+                .await
+                .unwrap();
+
+            io.set_position(0);
+            match codec.read_response(&protocol, &mut io).await.unwrap() {
+                UserInfoResponse::Signed(received) => {
+                    assert!(protocol.is_signed());
+                    assert_eq!(received, signed);
+                }
+                UserInfoResponse::Unsigned(received) => {
+                    assert!(!protocol.is_signed());
+                    // 旧版节点按 status 字符串显示状态
+                    assert_eq!(received.status.as_deref(), Some("忙碌"));
+                    assert_eq!(received.presence(), Presence::Busy);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,76 @@
+//! 聊天集成测试：发送请求失败时产生带有目标节点和消息 ID 的失败事件
+
+use libp2p::Multiaddr;
+use mdns::chat::ChatEvent;
+use mdns::{ChatExtension, ChatMessage, HealthCheckConfig, ManagedDiscovery, NodeManager, NodeManagerConfig, UserInfo};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn node(name: &str) -> ManagedDiscovery {
+    let mut node = ManagedDiscovery::new(
+        Arc::new(NodeManager::new(NodeManagerConfig::new())),
+        vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        HealthCheckConfig::default(),
+        UserInfo::new(name.to_string()),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    node.enable_chat().await.unwrap();
+    node
+}
+
+async fn listen_address(node: &mut ManagedDiscovery) -> Multiaddr {
+    loop {
+        if let Some(addr) = node.listen_addresses().into_iter().next() {
+            return addr;
+        }
+        let _ = tokio::time::timeout(Duration::from_millis(50), node.run()).await;
+    }
+}
+
+#[tokio::test]
+async fn test_outbound_failure_emits_message_send_failed() {
+    let mut alice = node("alice").await;
+    let mut bob = node("bob").await;
+    let bob_id = bob.local_peer_id();
+    let mut alice_events = alice.take_chat_events().unwrap();
+
+    let connect = async {
+        let bob_addr = listen_address(&mut bob).await.with_p2p(bob_id).unwrap();
+        alice.add_static_peer(bob_addr).await.unwrap();
+        while !alice.node_manager().is_node_verified(&bob_id).await {
+            tokio::select! {
+                _ = alice.run() => {}
+                _ = bob.run() => {}
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(30), connect).await.expect("未能建立连接");
+
+    // bob 退出后 alice 还没有处理连接关闭，消息仍通过原连接发送，请求失败
+    drop(bob);
+    let message = ChatMessage::text("Hello".to_string());
+    let message_id = message.id().unwrap().to_string();
+    alice.send_message(bob_id, message).await.unwrap();
+
+    let failed = async {
+        loop {
+            tokio::select! {
+                _ = alice.run() => {}
+                Some(event) = alice_events.recv() => {
+                    if let ChatEvent::MessageSendFailed { peer, message_id, .. } = event {
+                        return (peer, message_id);
+                    }
+                }
+            }
+        }
+    };
+    let (peer, failed_id) = tokio::time::timeout(Duration::from_secs(30), failed)
+        .await
+        .expect("未收到发送失败事件");
+    assert_eq!(peer, bob_id);
+    assert_eq!(failed_id, message_id);
+}
@@ -0,0 +1,72 @@
+//! 中继集成测试：节点在中继节点上预留地址，其他节点通过中继地址连接并完成验证
+
+use libp2p::Multiaddr;
+use mdns::{transport, HealthCheckConfig, ManagedDiscovery, NodeManager, NodeManagerConfig, UserInfo};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn node(name: &str, relay_server: bool) -> ManagedDiscovery {
+    let config = NodeManagerConfig::new().with_relay_server(relay_server);
+    ManagedDiscovery::new(
+        Arc::new(NodeManager::new(config)),
+        vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        HealthCheckConfig::default(),
+        UserInfo::new(name.to_string()),
+        None,
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+async fn listen_address(node: &mut ManagedDiscovery) -> Multiaddr {
+    loop {
+        if let Some(addr) = node.listen_addresses().into_iter().next() {
+            return addr;
+        }
+        let _ = tokio::time::timeout(Duration::from_millis(50), node.run()).await;
+    }
+}
+
+fn relayed_address(node: &ManagedDiscovery) -> Option<Multiaddr> {
+    node.listen_addresses().into_iter().find(transport::is_relayed)
+}
+
+#[tokio::test]
+async fn test_connect_via_relay() {
+    let mut relay = node("relay", true).await;
+    let mut alice = node("alice", false).await;
+    let mut bob = node("bob", false).await;
+    let alice_id = alice.local_peer_id();
+    assert!(relay.is_relay_server() && !alice.is_relay_server());
+
+    let run = async {
+        // 等待中继节点开始监听，然后 alice 把它作为静态节点连接
+        let relay_addr = listen_address(&mut relay).await.with_p2p(relay.local_peer_id()).unwrap();
+        alice.add_static_peer(relay_addr).await.unwrap();
+
+        let mut circuit: Option<Multiaddr> = None;
+        loop {
+            tokio::select! {
+                _ = relay.run() => {}
+                _ = alice.run() => {}
+                _ = bob.run() => {}
+                // run() 只在产生事件时返回，定期检查地址和验证状态
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+            }
+            // alice 获得中继地址后，bob 通过该地址连接 alice
+            if let Some(ref addr) = circuit {
+                if bob.node_manager().is_node_verified(&alice_id).await {
+                    return addr.clone();
+                }
+            } else if let Some(addr) = relayed_address(&alice) {
+                bob.add_static_peer(addr.clone()).await.unwrap();
+                circuit = Some(addr);
+            }
+        }
+    };
+
+    let circuit = tokio::time::timeout(Duration::from_secs(30), run).await.expect("未能通过中继连接节点");
+    assert!(transport::is_relayed(&circuit));
+    assert!(circuit.to_string().ends_with(&format!("/p2p-circuit/p2p/{}", alice_id)));
+}
@@ -0,0 +1,117 @@
+//! 会合点发现集成测试：在本机运行会合服务器，两个节点通过它互相发现并完成验证
+
+use libp2p::{identity::Keypair, Multiaddr, PeerId};
+use mdns::{
+    HealthCheckConfig, ManagedDiscovery, ManagedDiscoveryEvent, NodeManager, NodeManagerConfig, RendezvousConfig,
+    RendezvousServer, RendezvousServerEvent, TransportKind, UserInfo,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn start_server() -> (RendezvousServer, Multiaddr) {
+    let mut server = RendezvousServer::new(
+        Keypair::generate_ed25519(),
+        vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        None,
+    )
+    .unwrap();
+    // 节点默认不启用 QUIC，使用服务器的 TCP 地址
+    loop {
+        match server.run().await.unwrap() {
+            RendezvousServerEvent::Listening(addr) if TransportKind::from_multiaddr(&addr) == Some(TransportKind::Tcp) => {
+                return (server, addr);
+            }
+            RendezvousServerEvent::Listening(_) => {}
+            event => panic!("unexpected event: {:?}", event),
+        }
+    }
+}
+
+async fn node(name: &str, team: &str, server: &Multiaddr) -> ManagedDiscovery {
+    let node_manager = Arc::new(NodeManager::new(NodeManagerConfig::new()));
+    let mut discovery = ManagedDiscovery::new(
+        node_manager,
+        vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        HealthCheckConfig::default(),
+        UserInfo::new(name.to_string()),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    let config = RendezvousConfig::new(server.clone(), team).with_discover_interval(Duration::from_secs(1));
+    discovery.enable_rendezvous(config).unwrap();
+    discovery
+}
+
+#[tokio::test]
+async fn test_rendezvous_discovery() {
+    let (mut server, server_addr) = start_server().await;
+    let mut alice = node("alice", "测试团队", &server_addr).await;
+    let mut bob = node("bob", "测试团队", &server_addr).await;
+    let alice_id = alice.local_peer_id();
+    let bob_id = bob.local_peer_id();
+
+    let run = async {
+        let mut registered: Vec<PeerId> = Vec::new();
+        let (mut alice_verified, mut bob_verified) = (false, false);
+        loop {
+            tokio::select! {
+                event = server.run() => {
+                    if let RendezvousServerEvent::Registered(peer_id, _) = event.unwrap() {
+                        if !registered.contains(&peer_id) {
+                            registered.push(peer_id);
+                        }
+                    }
+                }
+                event = alice.run() => {
+                    if let ManagedDiscoveryEvent::Verified(peer_id) = event.unwrap() {
+                        alice_verified |= peer_id == bob_id;
+                    }
+                }
+                event = bob.run() => {
+                    if let ManagedDiscoveryEvent::Verified(peer_id) = event.unwrap() {
+                        bob_verified |= peer_id == alice_id;
+                    }
+                }
+            }
+            if alice_verified && bob_verified && registered.len() == 2 {
+                return registered;
+            }
+        }
+    };
+
+    let registered = tokio::time::timeout(Duration::from_secs(20), run).await.expect("节点未通过会合服务器互相发现");
+    assert!(registered.contains(&alice_id) && registered.contains(&bob_id));
+}
+
+#[tokio::test]
+async fn test_rendezvous_separates_teams() {
+    let (mut server, server_addr) = start_server().await;
+    let mut alice = node("alice", "测试团队", &server_addr).await;
+    let mut carol = node("carol", "其他团队", &server_addr).await;
+
+    // 两个节点都注册后，各自的查询结果中只有自己的注册
+    let run = async {
+        let mut registered = 0;
+        let mut own_discoveries = 0;
+        loop {
+            tokio::select! {
+                event = server.run() => match event.unwrap() {
+                    RendezvousServerEvent::Registered(..) => registered += 1,
+                    RendezvousServerEvent::Discovered(_, count) if registered >= 2 => {
+                        assert_eq!(count, 1);
+                        own_discoveries += 1;
+                    }
+                    _ => {}
+                },
+                _ = alice.run() => {}
+                _ = carol.run() => {}
+            }
+            if own_discoveries >= 2 {
+                return;
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(20), run).await.expect("会合服务器未处理查询");
+}
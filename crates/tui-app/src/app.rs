@@ -2,15 +2,20 @@
 //!
 //! 管理应用状态和主事件循环。
 
-use crate::components::{NodeItem, NodeListState, NodeStatus, AppTab, ChatPanelState};
-use crate::event::{AppResult, Event};
+use crate::components::{
+    NodeItem, NodeListState, NodeStatus, AppTab, ChatPanelState, FilePickerState, TransferListState,
+};
+use crate::event::{AppError, AppResult, Event};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use futures::StreamExt;
-use libp2p::PeerId;
+use libp2p::{Multiaddr, PeerId};
 use mdns::{
     ManagedDiscovery, ManagedDiscoveryEvent, NodeManager, NodeManagerConfig,
     HealthCheckConfig, UserInfo, ChatExtension, ChatMessage, ChatEvent,
-    IdentityManager,
+    IdentityManager, FileTransferExtension, FileTransferConfig, FileTransferEvent,
+    ChatStore, FileChatStore, DeliveryStatus,
+    TrustExtension, TrustConfig, TrustEvent, TrustState, TrustStore, FileTrustStore,
+    SignedUserInfo, AvatarCache, Presence, StaticPeerStore, RendezvousConfig,
 };
 use ratatui::{
     backend::CrosstermBackend,
@@ -21,6 +26,48 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
+/// 发送给发现任务的命令
+#[derive(Debug)]
+enum Command {
+    /// 发送聊天消息
+    SendMessage(Vec<PeerId>, ChatMessage),
+    /// 发送文件（队列 ID，目标节点，文件路径）
+    SendFile(u64, PeerId, PathBuf),
+    /// 接受文件提议
+    AcceptFile(String),
+    /// 拒绝文件提议
+    RejectFile(String),
+    /// 将会话标记为已读（发送已读回执）
+    MarkRead(PeerId),
+    /// 创建聊天室（名称，其他成员）
+    CreateRoom(String, Vec<PeerId>),
+    /// 在聊天室中发送消息
+    SendRoomMessage(String, ChatMessage),
+    /// 向节点发起配对
+    RequestPairing(PeerId),
+    /// 确认或拒绝配对验证码
+    ConfirmPairing(PeerId, bool),
+    /// 设置在线状态（状态，状态说明，最后活跃时间）
+    SetPresence(Presence, Option<String>, i64),
+    /// 手动添加节点地址
+    AddStaticPeer(Multiaddr),
+}
+
+/// 添加节点对话框
+#[derive(Debug, Default)]
+pub struct PeerDialog {
+    /// 输入的节点地址
+    pub input: String,
+    /// 上次提交的错误提示
+    pub error: Option<String>,
+}
+
+/// 每次从聊天记录中加载的消息条数
+const HISTORY_PAGE_SIZE: usize = 50;
+
+/// 无操作多久后自动切换为离开
+const AUTO_AWAY_AFTER: Duration = Duration::from_secs(5 * 60);
+
 /// TUI 应用
 pub struct TuiApp {
     /// 节点管理器
@@ -35,12 +82,44 @@ pub struct TuiApp {
     local_peer_id: Option<PeerId>,
     /// 密钥文件路径
     identity_path: PathBuf,
+    /// 是否使用口令加密密钥文件
+    encrypt_identity: bool,
+    /// 启动时是否轮换密钥
+    rotate_identity: bool,
     /// 当前选中的 Tab
     current_tab: AppTab,
     /// 聊天面板状态
     chat_panel_state: ChatPanelState,
-    /// 发送消息的命令发送器
-    cmd_tx: Option<mpsc::Sender<(Vec<PeerId>, ChatMessage)>>,
+    /// 文件选择状态
+    file_picker_state: FilePickerState,
+    /// 文件传输列表状态
+    transfer_list_state: TransferListState,
+    /// 接收文件的保存目录
+    download_dir: PathBuf,
+    /// 聊天记录存储（在 run() 中打开）
+    chat_store: Option<Arc<dyn ChatStore>>,
+    /// 信任存储（在 run() 中打开）
+    trust_store: Option<Arc<dyn TrustStore>>,
+    /// 节点的信任状态
+    trust_states: std::collections::HashMap<PeerId, TrustState>,
+    /// 等待核对的配对验证码（peer_id -> 验证码）
+    pairing_sas: std::collections::HashMap<PeerId, String>,
+    /// 本地在线状态
+    presence: Presence,
+    /// 本地状态说明
+    status_message: Option<String>,
+    /// 最后一次按键的时间（用于自动离开）
+    last_input: std::time::Instant,
+    /// 当前的离开状态是否由空闲自动设置
+    auto_away: bool,
+    /// 启动时添加的静态节点地址（命令行 `--peer`）
+    initial_static_peers: Vec<Multiaddr>,
+    /// 会合点配置（命令行 `--rendezvous`）
+    rendezvous: Option<RendezvousConfig>,
+    /// 添加节点对话框（打开时为 Some）
+    peer_dialog: Option<PeerDialog>,
+    /// 发送命令的命令发送器
+    cmd_tx: Option<mpsc::Sender<Command>>,
     /// 运行状态
     running: bool,
 }
@@ -70,20 +149,31 @@ impl TuiApp {
         PathBuf::from(".localp2p_tui_identity.key")
     }
 
+    /// 获取接收文件的保存目录
+    fn get_download_dir() -> PathBuf {
+        if let Ok(home) = std::env::var("HOME") {
+            let mut path = PathBuf::from(home);
+            path.push("Downloads");
+            path.push("localp2p");
+            return path;
+        }
+
+        // 回退到当前目录
+        PathBuf::from("downloads")
+    }
+
     /// 创建新的 TUI 应用
     pub async fn new(device_name: String) -> AppResult<Self> {
         // 创建节点管理器配置
         let config = NodeManagerConfig::new()
             .with_protocol_version("/localp2p/1.0.0".to_string())
             .with_agent_prefix(Some("localp2p-rust/".to_string()))
-            .with_device_name(device_name.clone());
+            .with_device_name(device_name.clone())
+            .with_quic(true);
 
-        // 创建节点管理器
+        // 创建节点管理器（后台清理任务在 run() 中启动）
         let node_manager = Arc::new(NodeManager::new(config));
 
-        // 启动后台清理任务
-        let _cleanup_handle = node_manager.clone().spawn_cleanup_task();
-
         // 获取密钥文件路径
         let identity_path = Self::get_identity_path();
 
@@ -100,20 +190,107 @@ impl TuiApp {
             device_name,
             local_peer_id: Some(temp_peer_id),
             identity_path: identity_path_clone,
+            encrypt_identity: false,
+            rotate_identity: false,
             current_tab: AppTab::Panel1,
             chat_panel_state: ChatPanelState::new(temp_peer_id),
+            file_picker_state: FilePickerState::default(),
+            transfer_list_state: TransferListState::new(),
+            download_dir: Self::get_download_dir(),
+            chat_store: None,
+            trust_store: None,
+            trust_states: std::collections::HashMap::new(),
+            pairing_sas: std::collections::HashMap::new(),
+            presence: Presence::Online,
+            status_message: None,
+            last_input: std::time::Instant::now(),
+            auto_away: false,
+            initial_static_peers: Vec::new(),
+            rendezvous: None,
+            peer_dialog: None,
             cmd_tx: None,
             running: true,
         })
     }
 
+    /// 使用口令加密密钥文件（已有的明文密钥文件会被迁移为加密格式）
+    pub fn with_encrypted_identity(mut self, encrypt: bool) -> Self {
+        self.encrypt_identity = encrypt;
+        self
+    }
+
+    /// 启动时轮换密钥（新 Peer ID 由旧密钥签署的声明引入，已信任旧身份的节点会自动迁移）
+    pub fn with_rotated_identity(mut self, rotate: bool) -> Self {
+        self.rotate_identity = rotate;
+        self
+    }
+
+    /// 启动时添加静态节点（用于组播被过滤、mDNS 无法发现节点的网络）
+    pub fn with_static_peers(mut self, peers: Vec<Multiaddr>) -> Self {
+        self.initial_static_peers = peers;
+        self
+    }
+
+    /// 作为中继节点为其他节点转发连接（需在 run() 之前调用）
+    pub fn with_relay_server(mut self, enabled: bool) -> Self {
+        let config = self.node_manager.config().clone().with_relay_server(enabled);
+        self.node_manager = Arc::new(NodeManager::new(config));
+        self
+    }
+
+    /// 启用会合点发现（用于团队成员分布在多个子网的情况）
+    pub fn with_rendezvous(mut self, config: Option<RendezvousConfig>) -> Self {
+        self.rendezvous = config;
+        self
+    }
+
+    /// 加载或生成持久化密钥对
+    ///
+    /// 密钥文件已加密或启用了加密时，在进入界面前提示输入口令，成功后一并返回口令。
+    fn load_identity(&self) -> AppResult<Result<(libp2p::identity::Keypair, Option<String>), String>> {
+        let path = &self.identity_path;
+        if !IdentityManager::is_encrypted(path) && !self.encrypt_identity {
+            return Ok(IdentityManager::load_or_generate(path).map(|keypair| (keypair, None)));
+        }
+
+        // 首次设置口令时需要确认
+        if !path.exists() || !IdentityManager::is_encrypted(path) {
+            let passphrase = prompt_passphrase("设置密钥口令: ")?;
+            if prompt_passphrase("确认密钥口令: ")? != passphrase {
+                return Err(AppError::Mdns("两次输入的口令不一致".to_string()));
+            }
+            return Ok(IdentityManager::load_with_passphrase(path, &passphrase)
+                .map(|keypair| (keypair, Some(passphrase))));
+        }
+
+        let mut result = Err(String::new());
+        for _ in 0..PASSPHRASE_ATTEMPTS {
+            let passphrase = prompt_passphrase(&format!("密钥文件已加密（{}），请输入口令: ", path.display()))?;
+            match IdentityManager::load_with_passphrase(path, &passphrase) {
+                Ok(keypair) => return Ok(Ok((keypair, Some(passphrase)))),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    result = Err(e);
+                }
+            }
+        }
+        Ok(result)
+    }
+
     /// 运行应用
     pub async fn run(&mut self) -> AppResult<()> {
         use crossterm::event::EventStream;
 
         // 加载或生成持久化密钥对
-        let _identity = match IdentityManager::load_or_generate(&self.identity_path) {
-            Ok(keypair) => {
+        let identity = match self.load_identity()? {
+            Ok((keypair, passphrase)) => {
+                let keypair = if self.rotate_identity {
+                    IdentityManager::rotate(&self.identity_path, &keypair, passphrase.as_deref())
+                        .map_err(AppError::Mdns)?
+                        .0
+                } else {
+                    keypair
+                };
                 let peer_id = keypair.public().to_peer_id();
                 tracing::info!("使用持久化密钥对，Peer ID: {}", peer_id);
                 self.local_peer_id = Some(peer_id);
@@ -123,12 +300,64 @@ impl TuiApp {
 
                 Some(keypair)
             }
+            Err(e) if IdentityManager::is_encrypted(&self.identity_path) => {
+                // 口令错误时不回退到临时密钥，避免 Peer ID 意外变化
+                return Err(AppError::Mdns(e));
+            }
             Err(e) => {
                 tracing::warn!("加载密钥对失败，将生成临时密钥: {}", e);
                 None
             }
         };
 
+        // 上次轮换密钥时签署的声明，启动后出示给其他节点
+        let rotation = match IdentityManager::load_rotation(&self.identity_path) {
+            Ok(rotation) => rotation,
+            Err(e) => {
+                tracing::warn!("加载身份轮换声明失败: {}", e);
+                None
+            }
+        };
+
+        // 打开聊天记录存储（与密钥文件放在同一目录）
+        let chat_dir = self
+            .identity_path
+            .parent()
+            .map(|dir| dir.join("chat"))
+            .unwrap_or_else(|| PathBuf::from("chat"));
+        match FileChatStore::open(&chat_dir).await {
+            Ok(store) => {
+                tracing::info!("聊天记录目录: {}", chat_dir.display());
+                self.chat_store = Some(Arc::new(store));
+            }
+            Err(e) => {
+                tracing::warn!("打开聊天记录存储失败，聊天记录将不会保存: {}", e);
+            }
+        }
+
+        // 打开信任存储（与密钥文件放在同一目录）
+        let trust_path = self
+            .identity_path
+            .parent()
+            .map(|dir| dir.join("trust.json"))
+            .unwrap_or_else(|| PathBuf::from("trust.json"));
+        match FileTrustStore::open(&trust_path).await {
+            Ok(store) => {
+                if let Ok(entries) = store.entries().await {
+                    // 未完成的配对在启用信任管理时会被重置
+                    self.trust_states = entries
+                        .into_iter()
+                        .filter(|(_, entry)| entry.state != TrustState::Pending)
+                        .map(|(peer_id, entry)| (peer_id, entry.state))
+                        .collect();
+                }
+                self.trust_store = Some(Arc::new(store));
+            }
+            Err(e) => {
+                tracing::warn!("打开信任存储失败，配对功能不可用: {}", e);
+            }
+        }
+
         // 启用原始模式
         crossterm::terminal::enable_raw_mode()?;
 
@@ -145,35 +374,47 @@ impl TuiApp {
         // 创建事件通道
         let (event_tx, mut event_rx) = mpsc::channel(100);
 
-        // 创建发送消息的命令通道 (PeerId, ChatMessage)
-        let (cmd_tx, mut cmd_rx) = mpsc::channel::<(Vec<PeerId>, ChatMessage)>(100);
+        // 创建发送命令的通道（聊天消息、文件传输）
+        let (cmd_tx, mut cmd_rx) = mpsc::channel::<Command>(100);
 
         // 保存 cmd_tx 到 TuiApp
         self.cmd_tx = Some(cmd_tx.clone());
 
+        // 启动后台清理任务
+        let _cleanup_handle = self.node_manager.clone().spawn_cleanup_task();
+
         // 创建发现器并启动发现任务
         let discovery_tx = event_tx.clone();
         let node_manager = self.node_manager.clone();
         let device_name = self.device_name.clone();
-        let identity_path = self.identity_path.clone();
+        let download_dir = self.download_dir.clone();
+        let chat_store = self.chat_store.clone();
+        let trust_store = self.trust_store.clone();
+        // 签名用户信息缓存（与密钥文件放在同一目录）
+        let profiles_path = self
+            .identity_path
+            .parent()
+            .map(|dir| dir.join("profiles.json"))
+            .unwrap_or_else(|| PathBuf::from("profiles.json"));
+        // 头像缓存目录
+        let avatar_dir = self
+            .identity_path
+            .parent()
+            .map(|dir| dir.join("avatars"))
+            .unwrap_or_else(|| PathBuf::from("avatars"));
+        // 静态节点列表
+        let static_peers_path = self
+            .identity_path
+            .parent()
+            .map(|dir| dir.join("static_peers.json"))
+            .unwrap_or_else(|| PathBuf::from("static_peers.json"));
+        let initial_static_peers = std::mem::take(&mut self.initial_static_peers);
+        let rendezvous = self.rendezvous.take();
 
         tokio::spawn(async move {
-            // 加载或生成持久化密钥对（在后台任务中也使用相同的密钥）
-            let identity = match IdentityManager::load_or_generate(&identity_path) {
-                Ok(keypair) => {
-                    let peer_id = keypair.public().to_peer_id();
-                    tracing::info!("后台任务使用持久化密钥对，Peer ID: {}", peer_id);
-                    Some(keypair)
-                }
-                Err(e) => {
-                    tracing::warn!("后台任务加载密钥对失败: {}", e);
-                    None
-                }
-            };
-
             // 创建用户信息
             let user_info = UserInfo::new(device_name.clone())
-                .with_status("在线".to_string());
+                .with_presence(Presence::Online);
 
             let health_config = HealthCheckConfig {
                 heartbeat_interval: Duration::from_secs(10),
@@ -189,6 +430,7 @@ impl TuiApp {
                 health_config,
                 user_info,
                 identity,  // 传入密钥对
+                None,      // 不使用私有网络密钥
             ).await;
 
             if let Err(err) = &discovery {
@@ -198,8 +440,12 @@ impl TuiApp {
 
             let mut discovery = discovery.unwrap();
 
-            // 启用聊天功能
-            if let Err(err) = discovery.enable_chat().await {
+            // 启用聊天功能（有存储时持久化聊天记录）
+            let enable_result = match chat_store {
+                Some(store) => discovery.enable_chat_with_store(store).await,
+                None => discovery.enable_chat().await,
+            };
+            if let Err(err) = enable_result {
                 tracing::error!("启用聊天功能失败: {:?}", err);
                 return;
             }
@@ -213,13 +459,73 @@ impl TuiApp {
                 }
             };
 
-            // 使用 select! 同时监听发现事件、发送命令和聊天事件
+            // 启用文件传输功能
+            if let Err(err) = discovery.enable_file_transfer(FileTransferConfig::new(download_dir)).await {
+                tracing::error!("启用文件传输功能失败: {:?}", err);
+                return;
+            }
+
+            let mut file_event_rx = match discovery.take_file_transfer_events() {
+                Some(rx) => rx,
+                None => {
+                    tracing::error!("无法获取文件传输事件接收器");
+                    return;
+                }
+            };
+
+            // 启用信任管理，失败时不影响其他功能
+            if let Some(store) = trust_store {
+                if let Err(err) = discovery.enable_trust(store, TrustConfig::new()).await {
+                    tracing::error!("启用信任管理失败: {:?}", err);
+                }
+            }
+            if let Some(statement) = rotation {
+                if let Err(err) = discovery.announce_rotation(statement) {
+                    tracing::warn!("出示身份轮换声明失败: {}", err);
+                }
+            }
+            let mut trust_event_rx = discovery
+                .take_trust_events()
+                .unwrap_or_else(|| mpsc::unbounded_channel().1);
+
+            // 导入缓存的签名用户信息（离线节点的资料在重启后仍然可用）
+            let imported = discovery.import_profiles(load_profile_cache(&profiles_path));
+            tracing::info!("已导入 {} 条缓存的用户信息", imported);
+
+            match AvatarCache::open(&avatar_dir) {
+                Ok(cache) => discovery.set_avatar_cache(cache),
+                Err(e) => tracing::warn!("打开头像缓存失败，将仅保存在内存中: {}", e),
+            }
+
+            // 静态节点列表中的节点立即拨号，命令行给出的地址一并保存
+            match StaticPeerStore::open(&static_peers_path) {
+                Ok(store) => discovery.set_static_peer_store(store),
+                Err(e) => tracing::warn!("打开静态节点列表失败，将仅保存在内存中: {}", e),
+            }
+            for addr in initial_static_peers {
+                if let Err(err) = discovery.add_static_peer(addr.clone()).await {
+                    tracing::error!("添加静态节点 {} 失败: {}", addr, err);
+                }
+            }
+            if let Some(config) = rendezvous {
+                if let Err(err) = discovery.enable_rendezvous(config) {
+                    tracing::error!("启用会合点发现失败: {}", err);
+                }
+            }
+
+            // 使用 select! 同时监听发现事件、发送命令、聊天事件和文件传输事件
             loop {
                 tokio::select! {
                     // 处理发现事件
                     event_result = discovery.run() => {
                         match event_result {
                             Ok(event) => {
+                                if matches!(
+                                    event,
+                                    ManagedDiscoveryEvent::UserInfoReceived(..) | ManagedDiscoveryEvent::UserInfoUpdated(..)
+                                ) {
+                                    save_profile_cache(&profiles_path, &discovery.signed_profiles());
+                                }
                                 if discovery_tx.send(Event::Discovery(event)).await.is_err() {
                                     break;
                                 }
@@ -230,11 +536,75 @@ impl TuiApp {
                             }
                         }
                     }
-                    // 处理发送消息命令
-                    Some((targets, message)) = cmd_rx.recv() => {
-                        tracing::info!("发送消息给 {} 个目标", targets.len());
-                        if let Err(err) = discovery.broadcast_message(targets, message).await {
-                            tracing::error!("发送消息失败: {:?}", err);
+                    // 处理命令
+                    Some(command) = cmd_rx.recv() => {
+                        match command {
+                            Command::SendMessage(targets, message) => {
+                                tracing::info!("发送消息给 {} 个目标", targets.len());
+                                if let Err(err) = discovery.broadcast_message(targets, message).await {
+                                    tracing::error!("发送消息失败: {:?}", err);
+                                }
+                            }
+                            Command::SendFile(queue_id, target, path) => {
+                                let result = discovery
+                                    .send_file(target, path)
+                                    .await
+                                    .map_err(|e| e.to_string());
+                                if discovery_tx.send(Event::FileSendStarted(queue_id, result)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Command::AcceptFile(transfer_id) => {
+                                if let Err(err) = discovery.accept_file(&transfer_id).await {
+                                    tracing::error!("接受文件失败: {:?}", err);
+                                }
+                            }
+                            Command::RejectFile(transfer_id) => {
+                                if let Err(err) = discovery.reject_file(&transfer_id, "用户拒绝".to_string()).await {
+                                    tracing::error!("拒绝文件失败: {:?}", err);
+                                }
+                            }
+                            Command::MarkRead(peer_id) => {
+                                if let Err(err) = discovery.mark_conversation_read(peer_id).await {
+                                    tracing::error!("发送已读回执失败: {:?}", err);
+                                }
+                            }
+                            Command::CreateRoom(name, members) => {
+                                if let Err(err) = discovery.create_room(name, members).await {
+                                    tracing::error!("创建聊天室失败: {:?}", err);
+                                }
+                            }
+                            Command::SendRoomMessage(room_id, message) => {
+                                if let Err(err) = discovery.send_room_message(&room_id, message).await {
+                                    tracing::error!("发送聊天室消息失败: {:?}", err);
+                                }
+                            }
+                            Command::RequestPairing(peer_id) => {
+                                if let Err(err) = discovery.request_pairing(peer_id).await {
+                                    tracing::error!("发起配对失败: {:?}", err);
+                                }
+                            }
+                            Command::ConfirmPairing(peer_id, accepted) => {
+                                if let Err(err) = discovery.confirm_pairing(peer_id, accepted).await {
+                                    tracing::error!("确认配对失败: {:?}", err);
+                                }
+                            }
+                            Command::SetPresence(presence, message, last_active) => {
+                                let mut info = discovery.local_user_info().clone();
+                                info.presence = Some(presence);
+                                info.status = message;
+                                info.last_active = Some(last_active);
+                                if let Err(err) = discovery.set_local_user_info(info).await {
+                                    tracing::error!("设置在线状态失败: {}", err);
+                                }
+                            }
+                            Command::AddStaticPeer(addr) => {
+                                match discovery.add_static_peer(addr.clone()).await {
+                                    Ok(true) => {}
+                                    Ok(false) => tracing::info!("静态节点已存在: {}", addr),
+                                    Err(err) => tracing::error!("添加静态节点 {} 失败: {}", addr, err),
+                                }
+                            }
                         }
                     }
                     // 处理聊天事件
@@ -244,6 +614,18 @@ impl TuiApp {
                             break;
                         }
                     }
+                    // 处理文件传输事件
+                    Some(file_event) = file_event_rx.recv() => {
+                        if discovery_tx.send(Event::FileTransfer(file_event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    // 处理信任事件
+                    Some(trust_event) = trust_event_rx.recv() => {
+                        if discovery_tx.send(Event::Trust(trust_event)).await.is_err() {
+                            break;
+                        }
+                    }
                 }
             }
         });
@@ -290,7 +672,7 @@ impl TuiApp {
             // 处理事件
             match event_rx.recv().await {
                 Some(Event::Input(key_event)) => {
-                    self.handle_key_event(key_event)?;
+                    self.handle_key_event(key_event).await?;
                 }
                 Some(Event::Paste(content)) => {
                     self.handle_paste_event(content)?;
@@ -301,6 +683,19 @@ impl TuiApp {
                 Some(Event::Chat(chat_event)) => {
                     self.handle_chat_event(chat_event).await;
                 }
+                Some(Event::FileTransfer(file_event)) => {
+                    self.handle_file_transfer_event(file_event);
+                }
+                Some(Event::Trust(trust_event)) => {
+                    self.handle_trust_event(trust_event);
+                }
+                Some(Event::FileSendStarted(queue_id, result)) => {
+                    if let Err(ref err) = result {
+                        tracing::error!("发送文件失败: {}", err);
+                    }
+                    self.transfer_list_state.set_started(queue_id, result);
+                    self.dispatch_queued_files();
+                }
                 Some(Event::Tick) => {
                     self.update();
                 }
@@ -321,7 +716,12 @@ impl TuiApp {
     }
 
     /// 处理键盘事件
-    fn handle_key_event(&mut self, key_event: KeyEvent) -> AppResult<()> {
+    async fn handle_key_event(&mut self, key_event: KeyEvent) -> AppResult<()> {
+        self.record_activity();
+        if self.peer_dialog.is_some() {
+            self.handle_peer_dialog_key(key_event);
+            return Ok(());
+        }
         match key_event.code {
             KeyCode::Char('q') | KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.running = false;
@@ -332,9 +732,13 @@ impl TuiApp {
                 // 如果从面板1切换到面板2，设置选中的节点为聊天对象
                 if self.current_tab == AppTab::Panel2 {
                     let selected_peers = self.node_list_state.get_selected_peer_ids();
-                    if !selected_peers.is_empty() {
+                    if selected_peers.len() > 1 {
+                        self.open_room(selected_peers);
+                    } else if !selected_peers.is_empty() {
                         self.chat_panel_state.set_active_chats(selected_peers);
+                        self.load_older_history().await;
                     }
+                    self.mark_current_chat_read();
                 }
             }
             // 方向键操作（仅在焦点在面板1时有效）
@@ -350,29 +754,89 @@ impl TuiApp {
             KeyCode::Char(' ') if self.current_tab == AppTab::Panel1 => {
                 self.node_list_state.toggle_selection();
             }
+            KeyCode::Char('p') if self.current_tab == AppTab::Panel1 => {
+                if let Some(peer_id) = self.node_list_state.get_current().map(|node| node.peer_id) {
+                    self.send_command(Command::RequestPairing(peer_id));
+                }
+            }
+            KeyCode::Char('a') if self.current_tab == AppTab::Panel1 => {
+                self.peer_dialog = Some(PeerDialog::default());
+            }
+            KeyCode::Char(c @ ('y' | 'n')) if self.current_tab == AppTab::Panel1 => {
+                let peer_id = self.node_list_state.get_current().map(|node| node.peer_id);
+                if let Some(peer_id) = peer_id.filter(|peer_id| self.pairing_sas.contains_key(peer_id)) {
+                    self.send_command(Command::ConfirmPairing(peer_id, c == 'y'));
+                }
+            }
             // 聊天面板输入处理（当焦点在面板2时）
             KeyCode::Enter if self.current_tab == AppTab::Panel2 => {
                 // 发送消息
                 let input = self.chat_panel_state.take_input();
-                if !input.is_empty() {
-                    let message = ChatMessage::text(input.clone());
+                if let Some(arg) = input.trim().strip_prefix("/status") {
+                    self.apply_status_command(arg);
+                    return Ok(());
+                }
+                // /edit、/delete、/react 命令生成更新消息，其余作为文本发送
+                let Some(mut message) = self.compose_message(input) else {
+                    return Ok(());
+                };
+                message.set_sender(self.local_peer_id().to_string());
+                if let Some(room_id) = self.chat_panel_state.active_room().map(|room| room.id.clone()) {
+                    let message = message.with_room(room_id.clone());
+                    if let (true, Some(id)) = (message.is_content(), message.id()) {
+                        self.chat_panel_state.set_delivery_status(id, DeliveryStatus::Pending);
+                    }
+                    self.chat_panel_state.add_message(self.local_peer_id(), message.clone());
+                    self.send_command(Command::SendRoomMessage(room_id, message));
+                } else {
                     let targets = self.chat_panel_state.active_chats().to_vec();
 
                     if !targets.is_empty() {
                         // 先添加到聊天历史（用于立即显示），使用本地 Peer ID
+                        if let (true, Some(id)) = (message.is_content(), message.id()) {
+                            self.chat_panel_state.set_delivery_status(id, DeliveryStatus::Pending);
+                        }
                         self.chat_panel_state.add_message(self.local_peer_id(), message.clone());
 
                         // 通过 cmd_tx 发送消息到 discovery 任务
-                        if let Some(ref cmd_tx) = self.cmd_tx {
-                            if let Err(err) = cmd_tx.try_send((targets, message)) {
-                                tracing::error!("发送消息失败: {:?}", err);
-                            }
-                        }
+                        self.send_command(Command::SendMessage(targets, message));
                     } else {
                         tracing::warn!("没有选择聊天对象");
                     }
                 }
             }
+            // 文件面板操作（当焦点在面板3时）
+            KeyCode::Up if self.current_tab == AppTab::Panel3 => {
+                self.file_picker_state.move_up();
+            }
+            KeyCode::Down if self.current_tab == AppTab::Panel3 => {
+                self.file_picker_state.move_down();
+            }
+            KeyCode::Enter | KeyCode::Right if self.current_tab == AppTab::Panel3 => {
+                self.file_picker_state.enter();
+            }
+            KeyCode::Backspace | KeyCode::Left if self.current_tab == AppTab::Panel3 => {
+                self.file_picker_state.go_parent();
+            }
+            KeyCode::Char(' ') if self.current_tab == AppTab::Panel3 => {
+                self.file_picker_state.toggle_mark();
+            }
+            KeyCode::Char('s') if self.current_tab == AppTab::Panel3 => {
+                self.enqueue_marked_files();
+            }
+            KeyCode::Char('y') if self.current_tab == AppTab::Panel3 => {
+                if let Some(transfer_id) = self.pending_incoming_transfer() {
+                    self.send_command(Command::AcceptFile(transfer_id));
+                }
+            }
+            KeyCode::Char('n') if self.current_tab == AppTab::Panel3 => {
+                if let Some(transfer_id) = self.pending_incoming_transfer() {
+                    self.send_command(Command::RejectFile(transfer_id));
+                }
+            }
+            KeyCode::Char('c') if self.current_tab == AppTab::Panel3 => {
+                self.transfer_list_state.clear_finished();
+            }
             KeyCode::Backspace if self.current_tab == AppTab::Panel2 => {
                 self.chat_panel_state.handle_backspace();
             }
@@ -383,6 +847,10 @@ impl TuiApp {
                 self.chat_panel_state.move_cursor_right();
             }
             KeyCode::Up if self.current_tab == AppTab::Panel2 => {
+                // 已在最上方时从聊天记录中加载更早的消息
+                if self.chat_panel_state.is_at_top() {
+                    self.load_older_history().await;
+                }
                 self.chat_panel_state.scroll_up();
             }
             KeyCode::Down if self.current_tab == AppTab::Panel2 => {
@@ -403,6 +871,10 @@ impl TuiApp {
 
     /// 处理粘贴/输入法输入事件
     fn handle_paste_event(&mut self, content: String) -> AppResult<()> {
+        if let Some(ref mut dialog) = self.peer_dialog {
+            dialog.input.push_str(content.trim());
+            return Ok(());
+        }
         // 只在聊天面板且当前是面板2时处理
         if self.current_tab == AppTab::Panel2 {
             // 将每个字符添加到输入缓冲区
@@ -413,6 +885,28 @@ impl TuiApp {
         Ok(())
     }
 
+    /// 处理添加节点对话框的按键
+    fn handle_peer_dialog_key(&mut self, key_event: KeyEvent) {
+        let Some(ref mut dialog) = self.peer_dialog else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Esc => self.peer_dialog = None,
+            KeyCode::Enter => match dialog.input.trim().parse::<Multiaddr>() {
+                Ok(addr) => {
+                    self.peer_dialog = None;
+                    self.send_command(Command::AddStaticPeer(addr));
+                }
+                Err(e) => dialog.error = Some(format!("地址无效: {}", e)),
+            },
+            KeyCode::Backspace => {
+                dialog.input.pop();
+            }
+            KeyCode::Char(c) => dialog.input.push(c),
+            _ => {}
+        }
+    }
+
     /// 处理发现事件
     async fn handle_discovery_event(&mut self, event: ManagedDiscoveryEvent) {
         match event {
@@ -431,7 +925,9 @@ impl TuiApp {
                         display_name: node.display_name(),
                         device_name: node.name.clone().unwrap_or_default(),
                         status: NodeStatus::Online,
+                        presence: self.user_info_map.get(&peer_id).map(|info| info.presence()).unwrap_or_default(),
                         addresses: node.addresses.iter().map(|a| a.to_string()).collect(),
+                        transport: node.transport,
                     };
                     self.node_list_state.add_node(node_item);
                 }
@@ -439,7 +935,8 @@ impl TuiApp {
             ManagedDiscoveryEvent::VerificationFailed(peer_id, reason) => {
                 tracing::warn!("节点验证失败: {} - {}", peer_id, reason);
             }
-            ManagedDiscoveryEvent::UserInfoReceived(peer_id, user_info) => {
+            ManagedDiscoveryEvent::UserInfoReceived(peer_id, user_info)
+            | ManagedDiscoveryEvent::UserInfoUpdated(peer_id, user_info) => {
                 tracing::info!("收到用户信息: {} - {}", peer_id, user_info.display_name());
                 // 保存用户信息
                 self.user_info_map.insert(peer_id, user_info.clone());
@@ -447,11 +944,24 @@ impl TuiApp {
                 // 更新节点的显示名称
                 let display_name = user_info.display_name();
                 let device_name = user_info.device_name.clone();
+                let presence = user_info.presence();
                 self.node_list_state.update_node(&peer_id, |node| {
                     node.display_name = display_name.clone();
                     node.device_name = device_name.clone();
+                    node.presence = presence;
                 });
             }
+            ManagedDiscoveryEvent::AvatarReceived(peer_id, hash) => {
+                tracing::info!("已获取 {} 的头像: {}", peer_id, hash);
+            }
+            ManagedDiscoveryEvent::UserInfoRequestFailed(peer_id, reason) => {
+                tracing::warn!("获取 {} 的用户信息失败: {}", peer_id, reason);
+            }
+            ManagedDiscoveryEvent::PeerBanned(peer_id, reason) => {
+                tracing::warn!("节点 {} 已被封禁: {}", peer_id, reason);
+                self.node_list_state.remove_node(&peer_id);
+                self.user_info_map.remove(&peer_id);
+            }
             ManagedDiscoveryEvent::NodeRecovered(peer_id, _rtt) => {
                 tracing::info!("节点恢复健康: {}", peer_id);
                 self.node_list_state.update_node(&peer_id, |node| {
@@ -472,6 +982,20 @@ impl TuiApp {
             ChatEvent::MessageReceived { from, message } => {
                 tracing::info!("收到来自 {} 的消息", from);
                 self.chat_panel_state.add_message(from, message);
+                // 正在查看该会话时立即回执已读
+                if self.current_tab == AppTab::Panel2 && self.chat_panel_state.current_peer() == Some(from) {
+                    self.mark_current_chat_read();
+                }
+            }
+            ChatEvent::MessageStatusChanged { message_id, status, .. } => {
+                self.chat_panel_state.set_delivery_status(&message_id, status);
+            }
+            ChatEvent::MessageSendFailed { peer, message_id, reason } => {
+                tracing::warn!("消息 {} 发送给 {} 失败，稍后重试: {}", message_id, peer, reason);
+            }
+            ChatEvent::DeliveryFailed { peer_id, message_id, reason } => {
+                tracing::warn!("消息 {} 未能送达 {}: {}", message_id, peer_id, reason);
+                self.chat_panel_state.set_delivery_status(&message_id, DeliveryStatus::Failed);
             }
             ChatEvent::MessageSent { to, message_id } => {
                 tracing::info!("消息 {} 已发送给 {}", message_id, to);
@@ -479,16 +1003,253 @@ impl TuiApp {
             ChatEvent::PeerTyping { from, is_typing } => {
                 self.chat_panel_state.set_peer_typing(from, is_typing);
             }
+            ChatEvent::RoomMessage { room_id, from, message } => {
+                tracing::info!("收到来自 {} 的聊天室 {} 消息", from, room_id);
+                self.chat_panel_state.add_message(from, message);
+            }
+            ChatEvent::RoomUpdated { room } => {
+                tracing::info!("聊天室 {} 已更新（{} 名成员）", room.name, room.members.len());
+                let room_id = room.id.clone();
+                self.chat_panel_state.upsert_room(room);
+                // 正在等待新建的聊天室时自动切换过去
+                let waiting = self.chat_panel_state.active_room().is_none()
+                    && self.chat_panel_state.active_chats().len() > 1;
+                if waiting {
+                    let peers = self.chat_panel_state.active_chats().to_vec();
+                    if self.chat_panel_state.find_room_with_members(&peers).map(|r| &r.id) == Some(&room_id) {
+                        self.chat_panel_state.set_active_room(&room_id);
+                    }
+                }
+            }
             _ => {
                 tracing::debug!("未处理的聊天事件: {:?}", event);
             }
         }
     }
 
+    /// 把输入框内容转换为待发送的消息
+    ///
+    /// - `/edit <内容>` 编辑自己最后一条消息
+    /// - `/delete` 删除自己最后一条消息
+    /// - `/react <表情>` 回应对方最后一条消息
+    fn compose_message(&self, input: String) -> Option<ChatMessage> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        let (command, arg) = input.split_once(' ').unwrap_or((input, ""));
+        let arg = arg.trim();
+        let message = match command {
+            "/edit" if !arg.is_empty() => {
+                ChatMessage::edit(self.chat_panel_state.last_own_message_id()?, arg.to_string())
+            }
+            "/delete" => ChatMessage::delete(self.chat_panel_state.last_own_message_id()?),
+            "/react" if !arg.is_empty() => ChatMessage::reaction(
+                self.chat_panel_state.last_peer_message_id()?,
+                arg.to_string(),
+                true,
+            ),
+            _ => ChatMessage::text(input.to_string()),
+        };
+        Some(message)
+    }
+
+    /// 打开与多个节点的群聊：复用成员相同的聊天室，没有则新建
+    fn open_room(&mut self, peers: Vec<PeerId>) {
+        let existing = self
+            .chat_panel_state
+            .find_room_with_members(&peers)
+            .map(|room| room.id.clone());
+        self.chat_panel_state.set_active_chats(peers.clone());
+
+        match existing {
+            Some(room_id) => self.chat_panel_state.set_active_room(&room_id),
+            None => {
+                let name = peers
+                    .iter()
+                    .map(|peer_id| {
+                        self.user_info_map
+                            .get(peer_id)
+                            .map(|info| info.nickname.clone().unwrap_or_else(|| info.device_name.clone()))
+                            .unwrap_or_else(|| peer_id.to_string()[..8].to_string())
+                    })
+                    .collect::<Vec<_>>()
+                    .join("、");
+                self.send_command(Command::CreateRoom(name, peers));
+            }
+        }
+    }
+
+    /// 将当前查看的会话标记为已读
+    fn mark_current_chat_read(&mut self) {
+        if let Some(peer_id) = self.chat_panel_state.current_peer() {
+            self.send_command(Command::MarkRead(peer_id));
+        }
+    }
+
+    /// 从聊天记录存储加载当前会话中更早的一页消息
+    async fn load_older_history(&mut self) {
+        let (Some(store), Some(peer_id)) = (self.chat_store.clone(), self.chat_panel_state.current_peer()) else {
+            return;
+        };
+
+        let before = self.chat_panel_state.oldest_message_id();
+        match store.history_before(&peer_id, before.as_deref(), HISTORY_PAGE_SIZE).await {
+            Ok(records) => {
+                let local_peer_id = self.local_peer_id();
+                let messages = records
+                    .into_iter()
+                    .map(|record| {
+                        let from = if record.outgoing { local_peer_id } else { peer_id };
+                        (from, record.message)
+                    })
+                    .collect();
+                let count = self.chat_panel_state.prepend_history(messages);
+                tracing::debug!("从聊天记录加载了 {} 条消息", count);
+            }
+            Err(e) => {
+                tracing::warn!("加载聊天记录失败: {}", e);
+            }
+        }
+    }
+
+    /// 处理文件传输事件
+    fn handle_file_transfer_event(&mut self, event: FileTransferEvent) {
+        match &event {
+            FileTransferEvent::OfferReceived { from, offer } => {
+                tracing::info!("收到来自 {} 的文件提议: {}", from, offer.file_name);
+            }
+            FileTransferEvent::Completed { path, .. } => {
+                tracing::info!("文件传输完成: {}", path.display());
+            }
+            FileTransferEvent::Failed { transfer_id, error, .. } => {
+                tracing::warn!("文件传输 {} 失败: {}", transfer_id, error);
+            }
+            _ => {
+                tracing::debug!("文件传输事件: {:?}", event);
+            }
+        }
+
+        self.transfer_list_state.handle_event(&event);
+        self.dispatch_queued_files();
+    }
+
+    /// 处理信任事件
+    fn handle_trust_event(&mut self, event: TrustEvent) {
+        match event {
+            TrustEvent::PairingRequested { peer_id } => {
+                tracing::info!("收到来自 {} 的配对请求", peer_id);
+            }
+            TrustEvent::PairingSas { peer_id, sas } => {
+                tracing::info!("与 {} 配对的验证码: {}", peer_id, sas);
+                self.pairing_sas.insert(peer_id, sas);
+            }
+            TrustEvent::PairingFailed { peer_id, reason } => {
+                tracing::warn!("与 {} 配对失败: {}", peer_id, reason);
+                self.pairing_sas.remove(&peer_id);
+            }
+            TrustEvent::IdentityRotated { old_peer_id, new_peer_id } => {
+                tracing::info!("节点 {} 已轮换身份为 {}", old_peer_id, new_peer_id);
+                self.chat_panel_state.rename_peer(old_peer_id, new_peer_id);
+                if let Some(user_info) = self.user_info_map.remove(&old_peer_id) {
+                    self.user_info_map.entry(new_peer_id).or_insert(user_info);
+                }
+            }
+            TrustEvent::TrustChanged { peer_id, state } => {
+                if state != TrustState::Pending {
+                    self.pairing_sas.remove(&peer_id);
+                }
+                self.trust_states.insert(peer_id, state);
+            }
+        }
+    }
+
+    /// 将标记的文件加入发送队列（发送给设备列表中选中的节点）
+    fn enqueue_marked_files(&mut self) {
+        let targets = self.node_list_state.get_selected_peer_ids();
+        if targets.is_empty() {
+            tracing::warn!("没有选择发送文件的设备");
+            return;
+        }
+
+        let files = self.file_picker_state.take_marked();
+        let count = self.transfer_list_state.enqueue(&files, &targets);
+        tracing::info!("已将 {} 个文件加入发送队列", count);
+        self.dispatch_queued_files();
+    }
+
+    /// 开始发送队列中等待的文件
+    fn dispatch_queued_files(&mut self) {
+        for (queue_id, target, path) in self.transfer_list_state.start_queued() {
+            self.send_command(Command::SendFile(queue_id, target, path));
+        }
+    }
+
+    /// 最早一个等待接受的文件提议
+    fn pending_incoming_transfer(&self) -> Option<String> {
+        self.transfer_list_state
+            .pending_incoming()
+            .and_then(|item| item.transfer_id.clone())
+    }
+
+    /// 发送命令到 discovery 任务
+    fn send_command(&self, command: Command) {
+        if let Some(ref cmd_tx) = self.cmd_tx {
+            if let Err(err) = cmd_tx.try_send(command) {
+                tracing::error!("发送命令失败: {:?}", err);
+            }
+        }
+    }
+
     /// 更新应用状态
     fn update(&mut self) {
-        // 定期更新逻辑
-        // 可以在这里处理一些定时的状态更新
+        // 长时间没有操作时自动切换为离开（忙碌、请勿打扰和隐身不受影响）
+        if self.presence == Presence::Online && self.last_input.elapsed() >= AUTO_AWAY_AFTER {
+            self.auto_away = true;
+            self.set_presence(Presence::Away, self.status_message.clone());
+        }
+    }
+
+    /// 记录用户操作，从自动离开中恢复
+    fn record_activity(&mut self) {
+        self.last_input = std::time::Instant::now();
+        if self.auto_away {
+            self.auto_away = false;
+            self.set_presence(Presence::Online, self.status_message.clone());
+        }
+    }
+
+    /// 处理 `/status <状态> [说明]` 命令
+    fn apply_status_command(&mut self, arg: &str) {
+        let arg = arg.trim();
+        let (name, message) = arg.split_once(' ').unwrap_or((arg, ""));
+        let Some(presence) = Presence::parse(name) else {
+            tracing::warn!("未知的在线状态: {}（可用: online / away / busy / dnd / invisible）", name);
+            return;
+        };
+        let message = message.trim();
+        self.auto_away = false;
+        self.set_presence(presence, (!message.is_empty()).then(|| message.to_string()));
+    }
+
+    /// 更新本地在线状态并推送给其他节点
+    fn set_presence(&mut self, presence: Presence, message: Option<String>) {
+        self.presence = presence;
+        self.status_message = message.clone();
+        // 自动离开时最后活跃时间为最后一次操作的时间
+        let idle = chrono::Duration::from_std(self.last_input.elapsed()).unwrap_or_default();
+        let last_active = (chrono::Utc::now() - idle).timestamp_millis();
+        self.send_command(Command::SetPresence(presence, message, last_active));
+    }
+
+    /// 获取本地在线状态
+    pub fn presence(&self) -> Presence {
+        self.presence
+    }
+
+    /// 获取添加节点对话框（未打开时为 None）
+    pub fn peer_dialog(&self) -> Option<&PeerDialog> {
+        self.peer_dialog.as_ref()
     }
 
     /// 获取设备名称
@@ -538,6 +1299,12 @@ impl TuiApp {
                 details.push_str(&format!("\n地址: {}", addr));
             }
 
+            let trust = self.trust_states.get(&node.peer_id).copied().unwrap_or_default();
+            details.push_str(&format!("\n信任: {}", trust_label(trust)));
+            if let Some(sas) = self.pairing_sas.get(&node.peer_id) {
+                details.push_str(&format!("\n验证码: {}（与对方核对一致后按 y 确认，n 拒绝）", sas));
+            }
+
             Some(details)
         } else {
             None
@@ -553,10 +1320,106 @@ impl TuiApp {
     pub fn chat_panel_state(&self) -> &ChatPanelState {
         &self.chat_panel_state
     }
+
+    /// 获取文件选择状态
+    pub fn file_picker_state(&self) -> &FilePickerState {
+        &self.file_picker_state
+    }
+
+    /// 获取文件传输列表状态
+    pub fn transfer_list_state(&self) -> &TransferListState {
+        &self.transfer_list_state
+    }
+}
+
+/// 信任状态的显示名称
+fn trust_label(state: TrustState) -> &'static str {
+    match state {
+        TrustState::Unknown => "未配对（按 p 配对）",
+        TrustState::Pending => "配对中",
+        TrustState::Trusted => "已信任",
+        TrustState::Blocked => "已屏蔽",
+    }
+}
+
+/// 读取签名用户信息缓存（文件不存在或损坏时返回空列表）
+fn load_profile_cache(path: &std::path::Path) -> Vec<SignedUserInfo> {
+    match std::fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+            tracing::warn!("用户信息缓存已损坏，将重新建立: {}", e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 保存签名用户信息缓存（失败时仅记录日志）
+fn save_profile_cache(path: &std::path::Path, profiles: &[SignedUserInfo]) {
+    let result = serde_json::to_vec(profiles)
+        .map_err(|e| e.to_string())
+        .and_then(|data| std::fs::write(path, data).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("保存用户信息缓存失败: {}", e);
+    }
+}
+
+/// 口令最多尝试次数
+const PASSPHRASE_ATTEMPTS: usize = 3;
+
+/// 在终端中读取口令（不回显）
+fn prompt_passphrase(prompt: &str) -> AppResult<String> {
+    use crossterm::event::{read, Event as TermEvent, KeyEventKind};
+    use std::io::Write;
+
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+
+    crossterm::terminal::enable_raw_mode()?;
+    let mut passphrase = String::new();
+    let result = loop {
+        match read() {
+            Ok(TermEvent::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "已取消输入口令"));
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                _ => {}
+            },
+            Ok(TermEvent::Paste(text)) => passphrase.push_str(&text),
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    crossterm::terminal::disable_raw_mode()?;
+    eprintln!();
+
+    result?;
+    Ok(passphrase)
 }
 
 /// 运行 TUI 应用的便捷函数
-pub async fn run_tui(device_name: String) -> AppResult<()> {
-    let mut app = TuiApp::new(device_name).await?;
+///
+/// `encrypt_identity` 为 true 时使用口令加密密钥文件；`rotate_identity` 为 true 时启动前轮换密钥；
+/// `static_peers` 为启动时添加的静态节点地址，`rendezvous` 为会合点配置，
+/// `relay_server` 为 true 时作为中继节点。
+pub async fn run_tui(
+    device_name: String,
+    encrypt_identity: bool,
+    rotate_identity: bool,
+    static_peers: Vec<Multiaddr>,
+    rendezvous: Option<RendezvousConfig>,
+    relay_server: bool,
+) -> AppResult<()> {
+    let mut app = TuiApp::new(device_name)
+        .await?
+        .with_encrypted_identity(encrypt_identity)
+        .with_rotated_identity(rotate_identity)
+        .with_static_peers(static_peers)
+        .with_rendezvous(rendezvous)
+        .with_relay_server(relay_server);
     app.run().await
 }
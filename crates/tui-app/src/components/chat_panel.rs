@@ -3,7 +3,8 @@
 //! 提供功能完整的聊天界面，包括消息列表和输入框。
 
 use libp2p::PeerId;
-use mdns::ChatMessage;
+use mdns::{ChatMessage, ChatRoom, DeliveryStatus};
+use mdns::chat::apply_update_to_history;
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -11,6 +12,7 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Widget, Wrap},
 };
+use std::collections::HashMap;
 
 /// 最大显示的消息条数
 const MAX_DISPLAY_MESSAGES: usize = 100;
@@ -36,6 +38,12 @@ pub struct ChatPanelState {
     is_typing: bool,
     /// 对方正在输入提示
     peer_typing: Vec<(PeerId, bool)>,
+    /// 自己发出消息的投递状态（消息 ID -> 状态）
+    delivery_status: HashMap<String, DeliveryStatus>,
+    /// 已加入的聊天室（聊天室 ID -> 聊天室）
+    rooms: HashMap<String, ChatRoom>,
+    /// 当前显示的聊天室（多选节点时的群聊）
+    active_room: Option<String>,
 }
 
 impl Default for ChatPanelState {
@@ -57,12 +65,16 @@ impl ChatPanelState {
             scroll_offset: 0,
             is_typing: false,
             peer_typing: Vec::new(),
+            delivery_status: HashMap::new(),
+            rooms: HashMap::new(),
+            active_room: None,
         }
     }
 
-    /// 设置当前聊天的节点
+    /// 设置当前聊天的节点（同时退出聊天室视图）
     pub fn set_active_chats(&mut self, peers: Vec<PeerId>) {
         self.active_chats = peers;
+        self.active_room = None;
         if !self.active_chats.is_empty() {
             self.current_chat_index = Some(0);
         } else {
@@ -75,8 +87,40 @@ impl ChatPanelState {
         &self.active_chats
     }
 
+    /// 添加或更新聊天室
+    pub fn upsert_room(&mut self, room: ChatRoom) {
+        self.rooms.insert(room.id.clone(), room);
+    }
+
+    /// 查找成员恰好为本地节点加上指定节点的聊天室
+    pub fn find_room_with_members(&self, peers: &[PeerId]) -> Option<&ChatRoom> {
+        self.rooms.values().find(|room| {
+            room.members.len() == peers.len() + 1
+                && room.is_member(&self.local_peer_id)
+                && peers.iter().all(|peer_id| room.is_member(peer_id))
+        })
+    }
+
+    /// 切换到聊天室视图（聊天室必须已知）
+    pub fn set_active_room(&mut self, room_id: &str) {
+        if self.rooms.contains_key(room_id) {
+            self.active_room = Some(room_id.to_string());
+        }
+    }
+
+    /// 当前显示的聊天室
+    pub fn active_room(&self) -> Option<&ChatRoom> {
+        self.active_room.as_ref().and_then(|id| self.rooms.get(id))
+    }
+
     /// 添加消息到历史
+    ///
+    /// 编辑、删除和表情回应就地应用到目标消息上。
     pub fn add_message(&mut self, from: PeerId, message: ChatMessage) {
+        if message.is_update() {
+            apply_update_to_history(self.message_history.iter_mut().map(|(_, msg)| msg), &message);
+            return;
+        }
         self.message_history.push((from, message));
 
         // 限制历史大小
@@ -88,15 +132,112 @@ impl ChatPanelState {
         self.scroll_to_bottom();
     }
 
+    /// 在历史开头插入更早的消息（从聊天记录存储加载），跳过已存在的消息
+    ///
+    /// 返回实际插入的条数。
+    pub fn prepend_history(&mut self, messages: Vec<(PeerId, ChatMessage)>) -> usize {
+        let older: Vec<(PeerId, ChatMessage)> = messages
+            .into_iter()
+            .filter(|(_, msg)| {
+                !self
+                    .message_history
+                    .iter()
+                    .any(|(_, existing)| existing.id().is_some() && existing.id() == msg.id())
+            })
+            .collect();
+
+        let count = older.len();
+        self.message_history.splice(0..0, older);
+        count
+    }
+
+    /// 对方轮换身份后，把旧 Peer ID 的会话和消息归到新 Peer ID 名下
+    pub fn rename_peer(&mut self, old: PeerId, new: PeerId) {
+        for (from, _) in self.message_history.iter_mut().filter(|(from, _)| *from == old) {
+            *from = new;
+        }
+        for peer_id in self.active_chats.iter_mut().filter(|peer_id| **peer_id == old) {
+            *peer_id = new;
+        }
+    }
+
+        /// 更新发出消息的投递状态（状态只会向前推进）
+    pub fn set_delivery_status(&mut self, message_id: &str, status: DeliveryStatus) {
+        let current = self
+            .delivery_status
+            .entry(message_id.to_string())
+            .or_insert(status);
+        if *current < status {
+            *current = status;
+        }
+    }
+
+    /// 获取发出消息的投递状态
+    pub fn delivery_status(&self, message_id: &str) -> Option<DeliveryStatus> {
+        self.delivery_status.get(message_id).copied()
+    }
+
+    /// 当前私聊会话的节点（聊天室视图中为 None）
+    pub fn current_peer(&self) -> Option<PeerId> {
+        if self.active_room.is_some() {
+            return None;
+        }
+        self.current_chat_index
+            .and_then(|index| self.active_chats.get(index))
+            .copied()
+    }
+
+    /// 当前会话中最早一条消息的 ID（用于向前翻页）
+    pub fn oldest_message_id(&self) -> Option<String> {
+        self.get_current_history()
+            .iter()
+            .find_map(|(_, msg)| msg.id().map(str::to_string))
+    }
+
+    /// 当前会话中自己发出的最后一条未删除消息的 ID（用于编辑和删除）
+    pub fn last_own_message_id(&self) -> Option<String> {
+        self.last_message_id(|from| from == self.local_peer_id)
+    }
+
+    /// 当前会话中对方发来的最后一条未删除消息的 ID（用于表情回应）
+    pub fn last_peer_message_id(&self) -> Option<String> {
+        self.last_message_id(|from| from != self.local_peer_id)
+    }
+
+    fn last_message_id(&self, filter: impl Fn(PeerId) -> bool) -> Option<String> {
+        self.get_current_history()
+            .iter()
+            .rev()
+            .filter(|(from, msg)| filter(*from) && !msg.meta().is_some_and(|meta| meta.deleted))
+            .find_map(|(_, msg)| msg.id().map(str::to_string))
+    }
+
+    /// 消息列表是否已滚动到最上方
+    pub fn is_at_top(&self) -> bool {
+        self.scroll_offset == 0
+    }
+
     /// 获取当前会话的消息历史
     pub fn get_current_history(&self) -> Vec<(PeerId, ChatMessage)> {
+        if let Some(room_id) = &self.active_room {
+            return self
+                .message_history
+                .iter()
+                .filter(|(_, msg)| msg.room_id() == Some(room_id.as_str()))
+                .cloned()
+                .collect();
+        }
+
         if let Some(index) = self.current_chat_index {
             if let Some(&peer_id) = self.active_chats.get(index) {
                 return self
                     .message_history
                     .iter()
                     // 显示：对方发送的消息 + 自己发送的消息（本地 peer_id 在消息中存储）
-                    .filter(|(from, _)| *from == peer_id || *from == self.local_peer_id)
+                    // 聊天室消息只在聊天室视图中显示
+                    .filter(|(from, msg)| {
+                        msg.room_id().is_none() && (*from == peer_id || *from == self.local_peer_id)
+                    })
                     .cloned()
                     .collect();
             }
@@ -189,7 +330,6 @@ impl ChatPanelState {
     /// 滚动到底部
     pub fn scroll_to_bottom(&mut self) {
         self.scroll_offset = self.scroll_offset.saturating_sub(1);
-        self.scroll_offset = self.scroll_offset.max(0);
     }
 
     /// 获取输入框内容并清空
@@ -324,18 +464,35 @@ impl<'a> ChatPanel<'a> {
                 break; // 超出显示区域
             }
 
-            if let ChatMessage::Text(text) = msg {
+            if let (true, Some(meta)) = (msg.is_content(), msg.meta()) {
                 let is_self = from.to_string() == self.local_peer_id.to_string();
-                let timestamp = self.format_timestamp(text.timestamp);
-                let prefix = if is_self { "你" } else { "对方" };
-                let style = if is_self {
+                let timestamp = self.format_timestamp(msg.timestamp().unwrap_or_default());
+                // 聊天室中用 Peer ID 末尾几位区分不同成员
+                let sender = from.to_string();
+                let prefix = if is_self {
+                    "你"
+                } else if msg.room_id().is_some() {
+                    &sender[sender.len().saturating_sub(6)..]
+                } else {
+                    "对方"
+                };
+                let style = if meta.deleted {
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)
+                } else if is_self {
                     Style::default().fg(Color::Cyan)
                 } else {
                     Style::default().fg(Color::Green)
                 };
 
-                // 构建消息文本
-                let message_text = format!("{} {}: {}", timestamp, prefix, text.content);
+                // 构建消息文本（图片显示尺寸，编辑过的消息加标记）
+                let mut body = msg.preview();
+                if let (ChatMessage::Image(image), false) = (msg, meta.deleted) {
+                    body = format!("{} {}×{}", body, image.width, image.height);
+                }
+                if meta.edited_at.is_some() && !meta.deleted {
+                    body.push_str(" (已编辑)");
+                }
+                let message_text = format!("{} {}: {}", timestamp, prefix, body);
 
                 // 根据发送者决定对齐方式
                 let alignment = if is_self {
@@ -351,9 +508,20 @@ impl<'a> ChatPanel<'a> {
                     height: line_height,
                 };
 
-                let paragraph = Paragraph::new(Line::from(vec![
-                    Span::styled(message_text, style),
-                ]))
+                let mut spans = vec![Span::styled(message_text, style)];
+                for (emoji, peers) in &meta.reactions {
+                    spans.push(Span::styled(
+                        format!(" {}{}", emoji, peers.len()),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                if let (true, Some(id)) = (is_self, msg.id()) {
+                    if let Some(status) = self.state.delivery_status(id) {
+                        spans.push(Self::status_tick(status));
+                    }
+                }
+
+                let paragraph = Paragraph::new(Line::from(spans))
                     .alignment(alignment)
                     .wrap(Wrap { trim: true });
 
@@ -363,6 +531,17 @@ impl<'a> ChatPanel<'a> {
         }
     }
 
+    /// 投递状态标记：○ 待发送，✓ 已发送，✓✓ 已送达，蓝色 ✓✓ 已读，✗ 投递失败
+    fn status_tick(status: DeliveryStatus) -> Span<'static> {
+        match status {
+            DeliveryStatus::Pending => Span::styled(" ○", Style::default().fg(Color::DarkGray)),
+            DeliveryStatus::Sent => Span::styled(" ✓", Style::default().fg(Color::Gray)),
+            DeliveryStatus::Delivered => Span::styled(" ✓✓", Style::default().fg(Color::Gray)),
+            DeliveryStatus::Read => Span::styled(" ✓✓", Style::default().fg(Color::Blue)),
+            DeliveryStatus::Failed => Span::styled(" ✗", Style::default().fg(Color::Red)),
+        }
+    }
+
     /// 渲染输入框
     fn render_input_box(&self, area: Rect, buf: &mut Buffer) {
         let input_text = self.state.input();
@@ -439,7 +618,7 @@ mod tests {
 
     #[test]
     fn test_chat_panel_state_creation() {
-        let state = ChatPanelState::new();
+        let state = ChatPanelState::new(PeerId::random());
         assert!(!state.has_active_chat());
         assert!(state.input().is_empty());
         assert_eq!(state.cursor_position, 0);
@@ -447,7 +626,7 @@ mod tests {
 
     #[test]
     fn test_handle_input_char() {
-        let mut state = ChatPanelState::new();
+        let mut state = ChatPanelState::new(PeerId::random());
         state.handle_input_char('H');
         state.handle_input_char('i');
         assert_eq!(state.input(), "Hi");
@@ -456,7 +635,7 @@ mod tests {
 
     #[test]
     fn test_handle_backspace() {
-        let mut state = ChatPanelState::new();
+        let mut state = ChatPanelState::new(PeerId::random());
         state.handle_input_char('H');
         state.handle_input_char('i');
         state.handle_backspace();
@@ -466,7 +645,7 @@ mod tests {
 
     #[test]
     fn test_take_input() {
-        let mut state = ChatPanelState::new();
+        let mut state = ChatPanelState::new(PeerId::random());
         state.handle_input_char('H');
         state.handle_input_char('i');
         let input = state.take_input();
@@ -477,7 +656,7 @@ mod tests {
 
     #[test]
     fn test_set_active_chats() {
-        let mut state = ChatPanelState::new();
+        let mut state = ChatPanelState::new(PeerId::random());
         let peer_id = PeerId::random();
         state.set_active_chats(vec![peer_id]);
         assert!(state.has_active_chat());
@@ -486,16 +665,107 @@ mod tests {
 
     #[test]
     fn test_scroll_operations() {
-        let mut state = ChatPanelState::new();
+        let mut state = ChatPanelState::new(PeerId::random());
         state.scroll_up();
         assert_eq!(state.scroll_offset, 0); // 不能向上滚动
         state.scroll_down();
         assert_eq!(state.scroll_offset, 1);
     }
 
+    #[test]
+    fn test_prepend_history() {
+        let local = PeerId::random();
+        let peer = PeerId::random();
+        let mut state = ChatPanelState::new(local);
+        state.set_active_chats(vec![peer]);
+
+        let newest = ChatMessage::text("new".to_string());
+        state.add_message(peer, newest.clone());
+
+        let older = ChatMessage::text("old".to_string());
+        let inserted = state.prepend_history(vec![(local, older.clone()), (peer, newest)]);
+        assert_eq!(inserted, 1);
+
+        let history = state.get_current_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].1, older);
+        assert_eq!(state.oldest_message_id().as_deref(), older.id());
+        assert_eq!(state.current_peer(), Some(peer));
+    }
+
+    #[test]
+    fn test_delivery_status_only_advances() {
+        let mut state = ChatPanelState::new(PeerId::random());
+        state.set_delivery_status("m1", DeliveryStatus::Pending);
+        state.set_delivery_status("m1", DeliveryStatus::Read);
+        // 乱序到达的较早状态不会覆盖
+        state.set_delivery_status("m1", DeliveryStatus::Delivered);
+        assert_eq!(state.delivery_status("m1"), Some(DeliveryStatus::Read));
+        assert_eq!(state.delivery_status("m2"), None);
+    }
+
+    #[test]
+    fn test_room_history_separated_from_direct_chat() {
+        let local = PeerId::random();
+        let alice = PeerId::random();
+        let bob = PeerId::random();
+        let mut state = ChatPanelState::new(local);
+
+        let room = ChatRoom::new("群聊".to_string(), local, &[alice, bob]);
+        state.upsert_room(room.clone());
+        assert_eq!(state.find_room_with_members(&[bob, alice]).map(|r| r.id.clone()), Some(room.id.clone()));
+        assert!(state.find_room_with_members(&[alice]).is_none());
+
+        state.add_message(alice, ChatMessage::text("私聊".to_string()));
+        state.add_message(alice, ChatMessage::room_text(room.id.clone(), "群聊".to_string()));
+
+        state.set_active_chats(vec![alice, bob]);
+        state.set_active_room(&room.id);
+        assert_eq!(state.current_peer(), None);
+        assert_eq!(state.get_current_history().len(), 1);
+
+        state.set_active_chats(vec![alice]);
+        assert!(state.active_room().is_none());
+        let history = state.get_current_history();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].1.room_id().is_none());
+    }
+
+    #[test]
+    fn test_updates_applied_to_panel_history() {
+        let local = PeerId::random();
+        let peer = PeerId::random();
+        let mut state = ChatPanelState::new(local);
+        state.set_active_chats(vec![peer]);
+
+        let mut own = ChatMessage::text("typo".to_string());
+        own.set_sender(local.to_string());
+        state.add_message(local, own.clone());
+        let mut theirs = ChatMessage::text("hi".to_string());
+        theirs.set_sender(peer.to_string());
+        state.add_message(peer, theirs.clone());
+
+        assert_eq!(state.last_own_message_id().as_deref(), own.id());
+        assert_eq!(state.last_peer_message_id().as_deref(), theirs.id());
+
+        let mut edit = ChatMessage::edit(own.id().unwrap().to_string(), "fixed".to_string());
+        edit.set_sender(local.to_string());
+        state.add_message(local, edit);
+
+        let mut delete = ChatMessage::delete(theirs.id().unwrap().to_string());
+        delete.set_sender(peer.to_string());
+        state.add_message(peer, delete);
+
+        let history = state.get_current_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].1.preview(), "fixed");
+        assert!(history[1].1.meta().unwrap().deleted);
+        assert_eq!(state.last_peer_message_id(), None);
+    }
+
     #[test]
     fn test_cursor_movement() {
-        let mut state = ChatPanelState::new();
+        let mut state = ChatPanelState::new(PeerId::random());
         state.handle_input_char('A');
         state.handle_input_char('B');
         state.handle_input_char('C');
@@ -1,19 +1,232 @@
 //! 文件选择组件
 //!
-//! 用于选择文件进行分享（占位实现）。
+//! 可导航的目录浏览器，用于选择要分享的文件（支持多选）。
 
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Rect},
-    style::{Color, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Widget},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
 };
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 
-/// 文件选择组件（占位）
+/// 目录项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    /// 显示名称
+    pub name: String,
+    /// 完整路径
+    pub path: PathBuf,
+    /// 是否为目录
+    pub is_dir: bool,
+    /// 文件大小（目录为 0）
+    pub size: u64,
+}
+
+/// 文件选择状态
+#[derive(Debug, Clone)]
+pub struct FilePickerState {
+    /// 当前目录
+    current_dir: PathBuf,
+    /// 当前目录下的条目（目录在前，按名称排序）
+    entries: Vec<FileEntry>,
+    /// 光标位置
+    cursor: usize,
+    /// 已标记的文件（可跨目录）
+    marked: BTreeSet<PathBuf>,
+    /// 读取目录时的错误
+    error: Option<String>,
+}
+
+impl Default for FilePickerState {
+    fn default() -> Self {
+        let dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self::new(dir)
+    }
+}
+
+impl FilePickerState {
+    /// 创建新的文件选择状态
+    pub fn new(dir: PathBuf) -> Self {
+        let mut state = Self {
+            current_dir: dir,
+            entries: Vec::new(),
+            cursor: 0,
+            marked: BTreeSet::new(),
+            error: None,
+        };
+        state.refresh();
+        state
+    }
+
+    /// 重新读取当前目录
+    pub fn refresh(&mut self) {
+        match read_entries(&self.current_dir) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(e) => {
+                self.entries.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+        if self.cursor >= self.entries.len() {
+            self.cursor = self.entries.len().saturating_sub(1);
+        }
+    }
+
+    /// 当前目录
+    pub fn current_dir(&self) -> &Path {
+        &self.current_dir
+    }
+
+    /// 当前目录下的条目
+    pub fn entries(&self) -> &[FileEntry] {
+        &self.entries
+    }
+
+    /// 光标位置
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// 读取目录时的错误
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// 获取当前光标项
+    pub fn get_current(&self) -> Option<&FileEntry> {
+        self.entries.get(self.cursor)
+    }
+
+    /// 移动光标向上
+    pub fn move_up(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+        }
+    }
+
+    /// 移动光标向下
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.entries.len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// 进入光标所在目录
+    ///
+    /// 返回是否切换了目录（光标在文件上时不做任何操作）。
+    pub fn enter(&mut self) -> bool {
+        match self.get_current() {
+            Some(entry) if entry.is_dir => {
+                self.current_dir = entry.path.clone();
+                self.cursor = 0;
+                self.refresh();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 返回上级目录，并将光标定位到原目录
+    pub fn go_parent(&mut self) -> bool {
+        let Some(parent) = self.current_dir.parent().map(Path::to_path_buf) else {
+            return false;
+        };
+
+        let previous = std::mem::replace(&mut self.current_dir, parent);
+        self.cursor = 0;
+        self.refresh();
+        if let Some(pos) = self.entries.iter().position(|e| e.path == previous) {
+            self.cursor = pos;
+        }
+        true
+    }
+
+    /// 切换光标所在文件的标记状态（目录不可标记），然后光标下移
+    pub fn toggle_mark(&mut self) {
+        if let Some(entry) = self.entries.get(self.cursor).filter(|e| !e.is_dir) {
+            if !self.marked.remove(&entry.path) {
+                self.marked.insert(entry.path.clone());
+            }
+        }
+        self.move_down();
+    }
+
+    /// 检查文件是否已标记
+    pub fn is_marked(&self, path: &Path) -> bool {
+        self.marked.contains(path)
+    }
+
+    /// 已标记文件数量
+    pub fn marked_count(&self) -> usize {
+        self.marked.len()
+    }
+
+    /// 取出所有标记的文件
+    ///
+    /// 没有标记任何文件时，返回光标所在的文件。
+    pub fn take_marked(&mut self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self
+                .get_current()
+                .filter(|e| !e.is_dir)
+                .map(|e| vec![e.path.clone()])
+                .unwrap_or_default();
+        }
+        std::mem::take(&mut self.marked).into_iter().collect()
+    }
+}
+
+/// 读取目录条目（隐藏文件除外），目录在前
+fn read_entries(dir: &Path) -> std::io::Result<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        // 跟随符号链接；无法访问的条目直接跳过
+        let Ok(metadata) = std::fs::metadata(entry.path()) else {
+            continue;
+        };
+        entries.push(FileEntry {
+            name,
+            path: entry.path(),
+            is_dir: metadata.is_dir(),
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+        });
+    }
+
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// 格式化字节数
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// 文件选择组件
 pub struct FilePickerComponent<'a> {
-    /// 消息内容
-    pub message: &'a str,
+    /// 选择状态
+    pub state: &'a FilePickerState,
     /// 标题
     pub title: String,
     /// 边框样式
@@ -22,20 +235,14 @@ pub struct FilePickerComponent<'a> {
 
 impl<'a> FilePickerComponent<'a> {
     /// 创建新的文件选择组件
-    pub fn new() -> Self {
+    pub fn new(state: &'a FilePickerState) -> Self {
         Self {
-            message: "文件选择功能开发中...\n\n敬请期待！",
+            state,
             title: "文件选择".to_string(),
             border_style: Style::default().fg(Color::Blue),
         }
     }
 
-    /// 设置消息
-    pub fn message(mut self, message: &'a str) -> Self {
-        self.message = message;
-        self
-    }
-
     /// 设置标题
     pub fn title(mut self, title: impl Into<String>) -> Self {
         self.title = title.into();
@@ -49,47 +256,147 @@ impl<'a> FilePickerComponent<'a> {
     }
 }
 
-impl<'a> Default for FilePickerComponent<'a> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl<'a> Widget for FilePickerComponent<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let text = vec![
-            Line::from(vec![
-                Span::styled(
-                    "📁 ",
-                    Style::default().fg(Color::Yellow),
-                ),
-                Span::styled(
-                    "文件选择",
-                    Style::default().fg(Color::White).add_modifier(ratatui::style::Modifier::BOLD),
-                ),
-            ]),
-            Line::from(""),
-            Line::from(self.message),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("提示: ", Style::default().fg(Color::Gray)),
-                Span::styled(
-                    "请先在左侧节点列表中选择要分享文件的设备",
-                    Style::default().fg(Color::DarkGray),
-                ),
-            ]),
-        ];
-
-        let paragraph = Paragraph::new(text)
-            .block(
-                Block::default()
-                    .title(self.title)
-                    .borders(Borders::ALL)
-                    .border_style(self.border_style),
-            )
+        let title = if self.state.marked_count() > 0 {
+            format!("{} (已标记 {})", self.title, self.state.marked_count())
+        } else {
+            self.title
+        };
+        let block = Block::default()
+            .title(title)
+            .title_bottom(Line::styled(
+                self.state.current_dir().display().to_string(),
+                Style::default().fg(Color::DarkGray),
+            ))
+            .borders(Borders::ALL)
+            .border_style(self.border_style);
+
+        if let Some(error) = self.state.error() {
+            Paragraph::new(vec![
+                Line::from("无法读取目录"),
+                Line::from(""),
+                Line::from(error.to_string()),
+                Line::from(""),
+                Line::from("按 Backspace 返回上级"),
+            ])
+            .block(block)
+            .style(Style::default().fg(Color::Red))
+            .alignment(Alignment::Center)
+            .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = self
+            .state
+            .entries()
+            .iter()
+            .map(|entry| {
+                if entry.is_dir {
+                    ListItem::new(format!("    📁 {}/", entry.name))
+                        .style(Style::default().fg(Color::Cyan))
+                } else {
+                    let checkbox = if self.state.is_marked(&entry.path) { "[✓]" } else { "[ ]" };
+                    ListItem::new(format!(
+                        "{} {} ({})",
+                        checkbox,
+                        entry.name,
+                        format_bytes(entry.size)
+                    ))
+                }
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
             .style(Style::default().fg(Color::White))
-            .alignment(Alignment::Center);
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        let mut list_state = ListState::default()
+            .with_selected(self.state.get_current().map(|_| self.state.cursor()));
+        StatefulWidget::render(list, area, buf, &mut list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_dir() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("b.txt"), b"hello").unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"hi").unwrap();
+        std::fs::write(dir.path().join(".hidden"), b"x").unwrap();
+        std::fs::write(dir.path().join("sub").join("c.bin"), b"abc").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_entries_sorted_dirs_first() {
+        let dir = sample_dir();
+        let state = FilePickerState::new(dir.path().to_path_buf());
+
+        let names: Vec<&str> = state.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["sub", "a.txt", "b.txt"]);
+        assert_eq!(state.entries()[2].size, 5);
+    }
+
+    #[test]
+    fn test_enter_and_go_parent() {
+        let dir = sample_dir();
+        let mut state = FilePickerState::new(dir.path().to_path_buf());
+
+        assert!(state.enter());
+        assert_eq!(state.current_dir(), dir.path().join("sub"));
+        assert_eq!(state.entries().len(), 1);
+
+        assert!(state.go_parent());
+        assert_eq!(state.current_dir(), dir.path());
+        assert_eq!(state.get_current().unwrap().name, "sub");
+
+        // 文件上按 Enter 不切换目录
+        state.move_down();
+        assert!(!state.enter());
+    }
+
+    #[test]
+    fn test_mark_multiple_files() {
+        let dir = sample_dir();
+        let mut state = FilePickerState::new(dir.path().to_path_buf());
+
+        // 目录不可标记
+        state.toggle_mark();
+        assert_eq!(state.marked_count(), 0);
+
+        state.toggle_mark(); // a.txt
+        state.toggle_mark(); // b.txt
+        assert_eq!(state.marked_count(), 2);
+
+        let marked = state.take_marked();
+        assert_eq!(marked, vec![dir.path().join("a.txt"), dir.path().join("b.txt")]);
+        assert_eq!(state.marked_count(), 0);
+    }
+
+    #[test]
+    fn test_take_marked_falls_back_to_cursor() {
+        let dir = sample_dir();
+        let mut state = FilePickerState::new(dir.path().to_path_buf());
+
+        assert!(state.take_marked().is_empty());
+        state.move_down();
+        assert_eq!(state.take_marked(), vec![dir.path().join("a.txt")]);
+    }
 
-        paragraph.render(area, buf);
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 + 512 * 1024), "5.5 MB");
     }
 }
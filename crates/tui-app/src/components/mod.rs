@@ -7,9 +7,11 @@ pub mod tabs;
 pub mod chat;
 pub mod file_picker;
 pub mod chat_panel;
+pub mod transfer_list;
 
 pub use node_list::{NodeList, NodeListState, NodeItem, NodeStatus};
 pub use tabs::{Tabs as AppTabs, AppTab};
 pub use chat::ChatComponent;
-pub use file_picker::FilePickerComponent;
+pub use file_picker::{FilePickerComponent, FilePickerState, FileEntry};
 pub use chat_panel::{ChatPanel, ChatPanelState};
+pub use transfer_list::{TransferList, TransferListState, TransferItem, TransferItemStatus};
@@ -3,6 +3,7 @@
 //! 显示已验证的节点列表，支持单选和多选。
 
 use libp2p::PeerId;
+use mdns::{Presence, TransportKind};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -18,7 +19,9 @@ pub struct NodeItem {
     pub display_name: String,
     pub device_name: String,
     pub status: NodeStatus,
+    pub presence: Presence,
     pub addresses: Vec<String>,
+    pub transport: Option<TransportKind>,
 }
 
 /// 节点状态
@@ -48,7 +51,7 @@ impl NodeStatus {
 }
 
 /// 节点列表状态
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct NodeListState {
     /// 节点列表
     pub items: Vec<NodeItem>,
@@ -58,16 +61,6 @@ pub struct NodeListState {
     pub cursor: usize,
 }
 
-impl Default for NodeListState {
-    fn default() -> Self {
-        Self {
-            items: Vec::new(),
-            selected: HashSet::new(),
-            cursor: 0,
-        }
-    }
-}
-
 impl NodeListState {
     /// 创建新的节点列表状态
     pub fn new(items: Vec<NodeItem>) -> Self {
@@ -81,10 +74,8 @@ impl NodeListState {
 
     /// 切换选中状态
     pub fn toggle_selection(&mut self) {
-        if self.cursor < self.items.len() {
-            if !self.selected.remove(&self.cursor) {
-                self.selected.insert(self.cursor);
-            }
+        if self.cursor < self.items.len() && !self.selected.remove(&self.cursor) {
+            self.selected.insert(self.cursor);
         }
     }
 
@@ -225,21 +216,24 @@ impl<'a> Widget for NodeList<'a> {
                 if self.detailed {
                     // 详细模式
                     ListItem::new(format!(
-                        "{} {} {}\n   状态: {}\n   地址: {}",
+                        "{} {} {}\n   状态: {} · {}\n   地址: {} ({})",
                         cursor,
                         checkbox,
                         node.display_name,
                         node.status.as_str(),
-                        node.addresses.first().map(|s| s.as_str()).unwrap_or("无")
+                        node.presence.label(),
+                        node.addresses.first().map(|s| s.as_str()).unwrap_or("无"),
+                        node.transport.map(|t| t.as_str()).unwrap_or("未连接")
                     ))
                 } else {
                     // 简洁模式
                     ListItem::new(format!(
-                        "{} {} {} ({})",
+                        "{} {} {} ({}) [{}]",
                         cursor,
                         checkbox,
                         node.display_name,
-                        node.device_name
+                        node.device_name,
+                        node.presence.label()
                     ))
                 }
             })
@@ -0,0 +1,483 @@
+//! 文件传输列表组件
+//!
+//! 显示发送队列和传输中的文件，包括进度、速度和剩余时间。
+
+use super::file_picker::format_bytes;
+use libp2p::PeerId;
+use mdns::{FileTransferEvent, TransferDirection};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Widget},
+};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// 同时进行的发送任务上限
+const MAX_ACTIVE_SENDS: usize = 2;
+
+/// 速度估算的平滑系数
+const SPEED_SMOOTHING: f64 = 0.3;
+
+/// 传输项状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferItemStatus {
+    /// 在本地队列中等待
+    Queued,
+    /// 已发出提议，等待对方接受
+    Offered,
+    /// 收到的提议，等待本地接受
+    Incoming,
+    /// 传输中
+    Active,
+    /// 中断，等待对方重新上线
+    Interrupted,
+    /// 已完成
+    Completed,
+    /// 失败、被拒绝或已取消
+    Failed(String),
+}
+
+impl TransferItemStatus {
+    /// 是否为终止状态
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed(_))
+    }
+}
+
+/// 传输项
+#[derive(Debug, Clone)]
+pub struct TransferItem {
+    /// 本地队列 ID
+    pub queue_id: u64,
+    /// 传输 ID（发出提议后才有）
+    pub transfer_id: Option<String>,
+    /// 对方的 Peer ID
+    pub peer_id: PeerId,
+    /// 文件名
+    pub file_name: String,
+    /// 本地路径（发送方为源文件）
+    pub path: PathBuf,
+    /// 传输方向
+    pub direction: TransferDirection,
+    /// 文件总字节数
+    pub total_bytes: u64,
+    /// 已传输字节数
+    pub transferred_bytes: u64,
+    /// 状态
+    pub status: TransferItemStatus,
+    /// 平滑后的速度（字节/秒）
+    speed: f64,
+    /// 上一次进度采样
+    last_sample: Option<(Instant, u64)>,
+}
+
+impl TransferItem {
+    /// 当前速度（字节/秒）
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// 进度百分比（0-100）
+    pub fn percent(&self) -> u16 {
+        if self.total_bytes == 0 {
+            return if self.status == TransferItemStatus::Completed { 100 } else { 0 };
+        }
+        (self.transferred_bytes.saturating_mul(100) / self.total_bytes).min(100) as u16
+    }
+
+    /// 预计剩余时间
+    pub fn eta(&self) -> Option<Duration> {
+        if self.status != TransferItemStatus::Active || self.speed < 1.0 {
+            return None;
+        }
+        let remaining = self.total_bytes.saturating_sub(self.transferred_bytes) as f64;
+        Some(Duration::from_secs_f64(remaining / self.speed))
+    }
+
+    /// 记录进度并更新速度
+    fn record_progress(&mut self, transferred_bytes: u64, now: Instant) {
+        if let Some((last_time, last_bytes)) = self.last_sample {
+            let elapsed = now.duration_since(last_time).as_secs_f64();
+            if elapsed > 0.0 {
+                let instant_speed = transferred_bytes.saturating_sub(last_bytes) as f64 / elapsed;
+                self.speed = if self.speed == 0.0 {
+                    instant_speed
+                } else {
+                    SPEED_SMOOTHING * instant_speed + (1.0 - SPEED_SMOOTHING) * self.speed
+                };
+            }
+        }
+        self.last_sample = Some((now, transferred_bytes));
+        self.transferred_bytes = transferred_bytes;
+    }
+}
+
+/// 传输列表状态
+#[derive(Debug, Clone, Default)]
+pub struct TransferListState {
+    /// 所有传输项（按加入顺序）
+    items: Vec<TransferItem>,
+    /// 下一个队列 ID
+    next_queue_id: u64,
+}
+
+impl TransferListState {
+    /// 创建新的传输列表状态
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 所有传输项
+    pub fn items(&self) -> &[TransferItem] {
+        &self.items
+    }
+
+    /// 将文件加入发送队列（每个目标节点一项）
+    pub fn enqueue(&mut self, files: &[PathBuf], peers: &[PeerId]) -> usize {
+        let mut count = 0;
+        for path in files {
+            let total_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string());
+
+            for peer_id in peers {
+                self.next_queue_id += 1;
+                self.items.push(TransferItem {
+                    queue_id: self.next_queue_id,
+                    transfer_id: None,
+                    peer_id: *peer_id,
+                    file_name: file_name.clone(),
+                    path: path.clone(),
+                    direction: TransferDirection::Outgoing,
+                    total_bytes,
+                    transferred_bytes: 0,
+                    status: TransferItemStatus::Queued,
+                    speed: 0.0,
+                    last_sample: None,
+                });
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// 取出可以开始发送的队列项
+    ///
+    /// 进行中的发送任务不超过 `MAX_ACTIVE_SENDS`，返回的项被标记为已提议。
+    pub fn start_queued(&mut self) -> Vec<(u64, PeerId, PathBuf)> {
+        let active = self
+            .items
+            .iter()
+            .filter(|i| {
+                i.direction == TransferDirection::Outgoing
+                    && matches!(i.status, TransferItemStatus::Offered | TransferItemStatus::Active)
+            })
+            .count();
+
+        self.items
+            .iter_mut()
+            .filter(|i| i.status == TransferItemStatus::Queued)
+            .take(MAX_ACTIVE_SENDS.saturating_sub(active))
+            .map(|item| {
+                item.status = TransferItemStatus::Offered;
+                (item.queue_id, item.peer_id, item.path.clone())
+            })
+            .collect()
+    }
+
+    /// 记录队列项发出提议的结果
+    pub fn set_started(&mut self, queue_id: u64, result: Result<String, String>) {
+        if let Some(item) = self.items.iter_mut().find(|i| i.queue_id == queue_id) {
+            match result {
+                Ok(transfer_id) => item.transfer_id = Some(transfer_id),
+                Err(error) => item.status = TransferItemStatus::Failed(error),
+            }
+        }
+    }
+
+    /// 最早一个等待本地接受的接收提议
+    pub fn pending_incoming(&self) -> Option<&TransferItem> {
+        self.items
+            .iter()
+            .find(|i| i.status == TransferItemStatus::Incoming)
+    }
+
+    /// 清除已结束的传输项
+    pub fn clear_finished(&mut self) {
+        self.items.retain(|i| !i.status.is_finished());
+    }
+
+    /// 根据文件传输事件更新状态
+    pub fn handle_event(&mut self, event: &FileTransferEvent) {
+        self.handle_event_at(event, Instant::now());
+    }
+
+    fn handle_event_at(&mut self, event: &FileTransferEvent, now: Instant) {
+        if let FileTransferEvent::OfferReceived { from, offer } = event {
+            self.next_queue_id += 1;
+            self.items.push(TransferItem {
+                queue_id: self.next_queue_id,
+                transfer_id: Some(offer.transfer_id.clone()),
+                peer_id: *from,
+                file_name: offer.file_name.clone(),
+                path: PathBuf::new(),
+                direction: TransferDirection::Incoming,
+                total_bytes: offer.file_size,
+                transferred_bytes: 0,
+                status: TransferItemStatus::Incoming,
+                speed: 0.0,
+                last_sample: None,
+            });
+            return;
+        }
+
+        let transfer_id = event.transfer_id();
+        let Some(item) = self
+            .items
+            .iter_mut()
+            .find(|i| i.transfer_id.as_deref() == Some(transfer_id))
+        else {
+            return;
+        };
+
+        match event {
+            FileTransferEvent::Accepted { .. } => {
+                item.status = TransferItemStatus::Active;
+                item.last_sample = Some((now, item.transferred_bytes));
+                item.speed = 0.0;
+            }
+            FileTransferEvent::Progress { transferred_bytes, total_bytes, .. } => {
+                item.status = TransferItemStatus::Active;
+                item.total_bytes = *total_bytes;
+                item.record_progress(*transferred_bytes, now);
+            }
+            FileTransferEvent::Completed { path, .. } => {
+                item.status = TransferItemStatus::Completed;
+                item.transferred_bytes = item.total_bytes;
+                item.path = path.clone();
+            }
+            FileTransferEvent::Interrupted { .. } => {
+                item.status = TransferItemStatus::Interrupted;
+                item.speed = 0.0;
+            }
+            FileTransferEvent::Rejected { reason, .. } => {
+                item.status = TransferItemStatus::Failed(format!("已拒绝: {}", reason));
+            }
+            FileTransferEvent::Failed { error, .. } => {
+                item.status = TransferItemStatus::Failed(error.clone());
+            }
+            FileTransferEvent::Cancelled { reason, .. } => {
+                item.status = TransferItemStatus::Failed(format!("已取消: {}", reason));
+            }
+            FileTransferEvent::OfferReceived { .. } => {}
+        }
+    }
+}
+
+/// 格式化剩余时间
+pub fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// 传输列表组件
+pub struct TransferList<'a> {
+    /// 列表状态
+    pub state: &'a TransferListState,
+    /// 标题
+    pub title: String,
+    /// 边框样式
+    pub border_style: Style,
+}
+
+impl<'a> TransferList<'a> {
+    /// 创建新的传输列表
+    pub fn new(state: &'a TransferListState) -> Self {
+        Self {
+            state,
+            title: "传输".to_string(),
+            border_style: Style::default().fg(Color::Blue),
+        }
+    }
+
+    /// 设置标题
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// 设置边框样式
+    pub fn border_style(mut self, style: Style) -> Self {
+        self.border_style = style;
+        self
+    }
+}
+
+impl<'a> Widget for TransferList<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // 最新的项显示在最上面
+        let items: Vec<ListItem> = self
+            .state
+            .items()
+            .iter()
+            .rev()
+            .map(|item| {
+                let arrow = match item.direction {
+                    TransferDirection::Outgoing => "↑",
+                    TransferDirection::Incoming => "↓",
+                };
+                let (status, color) = match &item.status {
+                    TransferItemStatus::Queued => ("排队中".to_string(), Color::Gray),
+                    TransferItemStatus::Offered => ("等待接受".to_string(), Color::Gray),
+                    TransferItemStatus::Incoming => ("[y] 接受 [n] 拒绝".to_string(), Color::Yellow),
+                    TransferItemStatus::Active => {
+                        let eta = item.eta().map(format_eta).unwrap_or_else(|| "--:--".to_string());
+                        (
+                            format!("{}/s 剩余 {}", format_bytes(item.speed() as u64), eta),
+                            Color::Cyan,
+                        )
+                    }
+                    TransferItemStatus::Interrupted => ("已中断，等待重连".to_string(), Color::Yellow),
+                    TransferItemStatus::Completed => ("完成".to_string(), Color::Green),
+                    TransferItemStatus::Failed(reason) => (reason.clone(), Color::Red),
+                };
+
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(format!("{} ", arrow), Style::default().fg(color)),
+                        Span::raw(item.file_name.clone()),
+                    ]),
+                    Line::from(vec![
+                        Span::styled(
+                            format!(
+                                "  {:>3}% {}/{} ",
+                                item.percent(),
+                                format_bytes(item.transferred_bytes),
+                                format_bytes(item.total_bytes)
+                            ),
+                            Style::default().fg(Color::White),
+                        ),
+                        Span::styled(status, Style::default().fg(color)),
+                    ]),
+                ])
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .title(self.title)
+                .borders(Borders::ALL)
+                .border_style(self.border_style),
+        );
+
+        list.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdns::FileOffer;
+
+    fn started(state: &mut TransferListState, files: &[&str]) -> Vec<String> {
+        let paths: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+        state.enqueue(&paths, &[PeerId::random()]);
+        state
+            .start_queued()
+            .into_iter()
+            .map(|(queue_id, _, _)| {
+                let transfer_id = format!("t{}", queue_id);
+                state.set_started(queue_id, Ok(transfer_id.clone()));
+                transfer_id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_queue_limits_active_sends() {
+        let mut state = TransferListState::new();
+        let ids = started(&mut state, &["a", "b", "c"]);
+        assert_eq!(ids.len(), MAX_ACTIVE_SENDS);
+        assert!(state.start_queued().is_empty());
+
+        // 一个完成后，队列中的下一个开始
+        state.handle_event(&FileTransferEvent::Completed {
+            peer_id: PeerId::random(),
+            transfer_id: ids[0].clone(),
+            direction: TransferDirection::Outgoing,
+            path: PathBuf::from("a"),
+        });
+        assert_eq!(state.start_queued().len(), 1);
+    }
+
+    #[test]
+    fn test_enqueue_per_peer() {
+        let mut state = TransferListState::new();
+        let count = state.enqueue(&[PathBuf::from("a")], &[PeerId::random(), PeerId::random()]);
+        assert_eq!(count, 2);
+        assert_eq!(state.items().len(), 2);
+    }
+
+    #[test]
+    fn test_progress_speed_and_eta() {
+        let mut state = TransferListState::new();
+        let ids = started(&mut state, &["a"]);
+        let peer_id = state.items()[0].peer_id;
+        let start = Instant::now();
+
+        state.handle_event_at(
+            &FileTransferEvent::Accepted { peer_id, transfer_id: ids[0].clone(), resume_from: 0 },
+            start,
+        );
+        state.handle_event_at(
+            &FileTransferEvent::Progress {
+                peer_id,
+                transfer_id: ids[0].clone(),
+                direction: TransferDirection::Outgoing,
+                transferred_bytes: 1000,
+                total_bytes: 4000,
+            },
+            start + Duration::from_secs(1),
+        );
+
+        let item = &state.items()[0];
+        assert_eq!(item.percent(), 25);
+        assert_eq!(item.speed() as u64, 1000);
+        assert_eq!(item.eta(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_incoming_offer() {
+        let mut state = TransferListState::new();
+        let offer = FileOffer::new("in.bin".to_string(), 10, 4);
+        state.handle_event(&FileTransferEvent::OfferReceived { from: PeerId::random(), offer: offer.clone() });
+
+        let pending = state.pending_incoming().unwrap();
+        assert_eq!(pending.transfer_id.as_deref(), Some(offer.transfer_id.as_str()));
+        assert_eq!(pending.direction, TransferDirection::Incoming);
+
+        state.handle_event(&FileTransferEvent::Rejected {
+            peer_id: pending.peer_id,
+            transfer_id: offer.transfer_id.clone(),
+            reason: "no".to_string(),
+        });
+        assert!(state.pending_incoming().is_none());
+        state.clear_finished();
+        assert!(state.items().is_empty());
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(Duration::from_secs(75)), "01:15");
+        assert_eq!(format_eta(Duration::from_secs(3725)), "1:02:05");
+    }
+}
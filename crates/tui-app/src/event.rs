@@ -4,7 +4,7 @@
 
 use crossterm::event::{KeyEvent, KeyEventKind};
 use futures::StreamExt;
-use mdns::{ManagedDiscoveryEvent, MdnsError, ChatEvent};
+use mdns::{ManagedDiscoveryEvent, MdnsError, ChatEvent, FileTransferEvent, TrustEvent};
 use std::time::Duration;
 
 /// 应用事件
@@ -18,6 +18,12 @@ pub enum Event {
     Discovery(ManagedDiscoveryEvent),
     /// 聊天事件
     Chat(ChatEvent),
+    /// 文件传输事件
+    FileTransfer(FileTransferEvent),
+    /// 信任事件（配对、信任状态变化）
+    Trust(TrustEvent),
+    /// 发送队列项已发出提议（队列 ID，传输 ID 或错误）
+    FileSendStarted(u64, Result<String, String>),
     /// 定时刷新事件
     Tick,
 }
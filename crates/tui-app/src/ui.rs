@@ -2,13 +2,13 @@
 //!
 //! 负责整个应用的 UI 渲染。
 
-use crate::components::{ChatPanel, FilePickerComponent, NodeList, AppTab};
+use crate::components::{ChatPanel, FilePickerComponent, NodeList, TransferList, AppTab};
 use crate::TuiApp;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
@@ -47,6 +47,52 @@ pub fn draw_ui(f: &mut Frame, app: &TuiApp) {
 
     // 绘制 Footer
     draw_footer(f, main_chunks[2], app);
+
+    // 添加节点对话框覆盖在最上层
+    draw_peer_dialog(f, size, app);
+}
+
+/// 绘制添加节点对话框
+fn draw_peer_dialog(f: &mut Frame, area: Rect, app: &TuiApp) {
+    let Some(dialog) = app.peer_dialog() else {
+        return;
+    };
+
+    let width = area.width.saturating_sub(4).min(72);
+    let height = 7.min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let mut lines = vec![
+        Line::from("输入节点地址，例如 /ip4/192.168.1.20/tcp/4001"),
+        Line::from(Span::styled(
+            format!("> {}", dialog.input),
+            Style::default().fg(Color::Yellow),
+        )),
+    ];
+    if let Some(ref error) = dialog.error {
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+    lines.push(Line::from(Span::styled(
+        "[Enter] 添加 [Esc] 取消",
+        Style::default().fg(Color::Gray),
+    )));
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("添加节点")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
 }
 
 /// 绘制 Header
@@ -63,6 +109,11 @@ fn draw_header(f: &mut Frame, area: Rect, app: &TuiApp) {
             format!("设备: {}", app.device_name()),
             Style::default().fg(Color::Green),
         ),
+        Span::raw(" | "),
+        Span::styled(
+            format!("状态: {}", app.presence().label()),
+            Style::default().fg(Color::Yellow),
+        ),
     ]);
 
     let header = Paragraph::new(title)
@@ -152,8 +203,12 @@ fn draw_panel_2_chat(f: &mut Frame, area: Rect, app: &TuiApp) {
     let has_focus = app.current_tab() == AppTab::Panel2;
 
     // 使用 ChatPanel 组件（包含消息列表和输入框）
+    let title = match app.chat_panel_state().active_room() {
+        Some(room) => format!("[2] 群聊: {}", room.name),
+        None => "[2] 聊天".to_string(),
+    };
     let chat_panel = ChatPanel::new(app.chat_panel_state(), app.local_peer_id())
-        .title(if has_focus { "[2] 聊天 *" } else { "[2] 聊天" })
+        .title(if has_focus { format!("{} *", title) } else { title })
         .border_style(if has_focus {
             Style::default().fg(Color::Green)
         } else {
@@ -164,9 +219,16 @@ fn draw_panel_2_chat(f: &mut Frame, area: Rect, app: &TuiApp) {
     f.render_widget(chat_panel, area);
 }
 
-/// 绘制面板 3：文件选择
+/// 绘制面板 3：文件选择（上部分目录浏览，下部分传输列表）
 fn draw_panel_3_file_picker(f: &mut Frame, area: Rect, app: &TuiApp) {
-    let selected_node = app.node_list_state().get_current();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(60),  // 目录浏览
+            Constraint::Percentage(40),  // 传输列表
+        ])
+        .split(area);
+
     let has_focus = app.current_tab() == AppTab::Panel3;
     let border_style = if has_focus {
         Style::default().fg(Color::Green)
@@ -174,40 +236,36 @@ fn draw_panel_3_file_picker(f: &mut Frame, area: Rect, app: &TuiApp) {
         Style::default().fg(Color::DarkGray)
     };
 
-    if selected_node.is_some() {
-        let node = selected_node.unwrap();
-        let msg = format!("选择文件分享给 {}", node.display_name);
-        let title = if has_focus { "[3] 文件选择 *" } else { "[3] 文件选择" };
-        let file_picker = FilePickerComponent::new().message(msg.as_str()).title(title).border_style(border_style);
-        f.render_widget(file_picker, area);
-    } else {
-        let hint_text = vec![
-            Line::from("文件选择"),
-            Line::from(""),
-            Line::from("请先在设备列表中选择要分享文件的设备"),
-        ];
+    // 标题显示发送目标（设备列表中选中的节点）
+    let target_count = app.node_list_state().get_selected_peer_ids().len();
+    let title = match (has_focus, target_count) {
+        (true, 0) => "[3] 文件选择 * (未选择设备)".to_string(),
+        (false, 0) => "[3] 文件选择".to_string(),
+        (true, n) => format!("[3] 发送给 {} 个设备 *", n),
+        (false, n) => format!("[3] 发送给 {} 个设备", n),
+    };
 
-        let title = if has_focus { "[3] 文件选择 *" } else { "[3] 文件选择" };
-        let hint_paragraph = Paragraph::new(hint_text)
-            .block(
-                Block::default()
-                    .title(title)
-                    .borders(Borders::ALL)
-                    .border_style(border_style),
-            )
-            .style(Style::default().fg(Color::White))
-            .alignment(Alignment::Center);
+    let file_picker = FilePickerComponent::new(app.file_picker_state())
+        .title(title)
+        .border_style(border_style);
+    f.render_widget(file_picker, chunks[0]);
 
-        f.render_widget(hint_paragraph, area);
-    }
+    let transfer_list = TransferList::new(app.transfer_list_state())
+        .title("传输")
+        .border_style(if has_focus {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        });
+    f.render_widget(transfer_list, chunks[1]);
 }
 
 /// 绘制 Footer
 fn draw_footer(f: &mut Frame, area: Rect, app: &TuiApp) {
     let (focus_indicator, help_keys) = match app.current_tab() {
-        AppTab::Panel1 => ("设备列表", "[↑↓] 选择 [Space/Enter] 选中"),
-        AppTab::Panel2 => ("聊天", "[输入文字] 打字 [Enter] 发送 [↑↓] 滚动"),
-        AppTab::Panel3 => ("文件选择", "[↑↓] 选择 [Enter] 打开"),
+        AppTab::Panel1 => ("设备列表", "[↑↓] 选择 [Space/Enter] 选中 [a] 添加节点 [p] 配对 [y/n] 确认验证码"),
+        AppTab::Panel2 => ("聊天", "[输入文字] 打字 [Enter] 发送 [↑↓] 滚动 /edit /delete /react /status"),
+        AppTab::Panel3 => ("文件选择", "[↑↓] 选择 [Enter] 打开 [Backspace] 上级 [Space] 标记 [s] 发送 [y/n] 接收"),
     };
     let help_text = format!(
         "[Tab] 切换焦点 | 当前焦点: {} | {} | [q] 退出",
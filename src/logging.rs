@@ -15,11 +15,12 @@ use std::path::PathBuf;
 const LOG_FILE_PREFIX: &str = "localp2p";
 
 /// 日志级别
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[allow(dead_code)]
 pub enum LogLevel {
     Trace,
     Debug,
+    #[default]
     Info,
     Warn,
     Error,
@@ -37,12 +38,6 @@ impl LogLevel {
     }
 }
 
-impl Default for LogLevel {
-    fn default() -> Self {
-        LogLevel::Info
-    }
-}
-
 /// 日志配置
 #[derive(Debug, Clone)]
 pub struct LoggingConfig {
@@ -198,7 +193,7 @@ mod tests {
 
         assert_eq!(config.log_dir, PathBuf::from("/tmp/logs"));
         assert_eq!(config.level, LogLevel::Debug);
-        assert_eq!(config.console_output, true);
-        assert_eq!(config.ansi, true);
+        assert!(config.console_output);
+        assert!(config.ansi);
     }
 }
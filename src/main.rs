@@ -1,6 +1,7 @@
 use mdns::{
     ManagedDiscovery, NodeManager, NodeManagerConfig, ManagedDiscoveryEvent,
-    HealthCheckConfig, UserInfo,
+    HealthCheckConfig, UserInfo, Presence, IdentityManager,
+    RendezvousConfig, RendezvousServer, RendezvousServerEvent,
 };
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,22 +13,54 @@ mod logging;
 struct CliArgs {
     device_name: String,
     tui_mode: bool,
+    encrypt_identity: bool,
+    rotate_identity: bool,
+    /// 手动添加的节点地址（`--peer`，可重复）
+    static_peers: Vec<String>,
+    /// 会合服务器地址（`--rendezvous`）
+    rendezvous: Option<String>,
+    /// 团队名称（`--team`，决定会合点命名空间）
+    team: String,
+    /// 作为中继节点（`--relay`）
+    relay_server: bool,
 }
 
+/// 未指定 `--team` 时的团队名称
+const DEFAULT_TEAM: &str = "localp2p";
+
+/// 带值的选项（解析设备名称时跳过它们的值）
+const VALUE_OPTIONS: [&str; 3] = ["--peer", "--rendezvous", "--team"];
+
 fn print_usage(program_name: &str) {
     println!("用法: {} <设备名称> [选项]", program_name);
+    println!("      {} rendezvous-server [--listen <地址>] [--identity <文件>]", program_name);
     println!();
     println!("参数:");
     println!("  设备名称    本设备的显示名称");
     println!();
     println!("选项:");
     println!("  --tui, -t   启用 TUI 图形界面模式");
+    println!("  --encrypt   使用口令加密密钥文件（TUI 模式）");
+    println!("  --rotate-identity  启动前轮换密钥，已信任本机的节点自动迁移（TUI 模式）");
+    println!("  --peer <地址>  手动添加节点地址（multiaddr，可重复；用于 mDNS 被屏蔽的网络）");
+    println!("  --rendezvous <地址>  会合服务器地址（带 /p2p/<peer_id>；用于跨子网发现节点）");
+    println!("  --team <名称>  团队名称，同一团队的节点在会合服务器上互相可见（默认 {}）", DEFAULT_TEAM);
+    println!("  --relay     作为中继节点，为无法直连的已验证节点转发聊天和文件传输（不限流量，建议配合网络密钥）");
     println!("  --help, -h  显示帮助信息");
     println!();
     println!("示例:");
     println!("  {} \"我的电脑\"              # 控制台模式", program_name);
     println!("  {} \"客厅电视\" --tui         # TUI 模式", program_name);
     println!("  {} \"卧室NAS\" -t            # TUI 模式（简写）", program_name);
+    println!("  {} \"办公电脑\" --peer /ip4/10.0.0.5/tcp/4001  # 手动添加节点", program_name);
+    println!("  {} rendezvous-server --listen /ip4/0.0.0.0/tcp/4100  # 运行会合服务器", program_name);
+    println!("  {} \"VPN网关\" --relay            # 中继模式", program_name);
+    println!("  {} \"办公电脑\" --rendezvous /ip4/10.0.0.2/tcp/4100/p2p/12D3... --team 研发部", program_name);
+}
+
+/// 选项的值（多次给出时取最后一个）
+fn option_value(args: &[String], option: &str) -> Option<String> {
+    args.windows(2).rev().find(|pair| pair[0] == option).map(|pair| pair[1].clone())
 }
 
 fn parse_args() -> CliArgs {
@@ -42,12 +75,33 @@ fn parse_args() -> CliArgs {
     // 检查是否启用 TUI 模式
     let tui_mode = args.iter().any(|a| a == "--tui" || a == "-t");
 
-    // 获取设备名称（第一个非选项参数）
+    // 检查是否加密密钥文件
+    let encrypt_identity = args.iter().any(|a| a == "--encrypt");
+
+    // 检查是否轮换密钥
+    let rotate_identity = args.iter().any(|a| a == "--rotate-identity");
+
+    // 收集手动添加的节点地址
+    let static_peers: Vec<String> = args
+        .windows(2)
+        .filter(|pair| pair[0] == "--peer")
+        .map(|pair| pair[1].clone())
+        .collect();
+
+    // 检查是否作为中继节点
+    let relay_server = args.iter().any(|a| a == "--relay");
+
+    // 会合服务器和团队名称
+    let rendezvous = option_value(&args, "--rendezvous");
+    let team = option_value(&args, "--team").unwrap_or_else(|| DEFAULT_TEAM.to_string());
+
+    // 获取设备名称（第一个非选项参数，跳过带值选项的值）
     let device_name = args
         .iter()
+        .enumerate()
         .skip(1)
-        .find(|a| !a.starts_with('-'))
-        .cloned()
+        .find(|(i, a)| !a.starts_with('-') && !VALUE_OPTIONS.contains(&args[i - 1].as_str()))
+        .map(|(_, a)| a.clone())
         .unwrap_or_else(|| {
         print_usage(&args[0]);
         std::process::exit(1);
@@ -56,18 +110,29 @@ fn parse_args() -> CliArgs {
     CliArgs {
         device_name,
         tui_mode,
+        encrypt_identity,
+        rotate_identity,
+        static_peers,
+        rendezvous,
+        team,
+        relay_server,
     }
 }
 
 /// 运行控制台模式
-async fn run_console_mode(device_name: String) -> Result<(), Box<dyn std::error::Error>> {
+async fn run_console_mode(
+    device_name: String,
+    static_peers: Vec<String>,
+    rendezvous: Option<RendezvousConfig>,
+    relay_server: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("Local P2P mDNS 节点管理示例（带用户信息交换）");
     println!("========================================");
     println!("设备名称: {}", device_name);
 
     // 创建用户信息（包含设备名称）
     let user_info = UserInfo::new(device_name.clone())
-        .with_status("在线".to_string());
+        .with_presence(Presence::Online);
 
     // 创建节点管理器配置
     let config = NodeManagerConfig::new()
@@ -75,7 +140,8 @@ async fn run_console_mode(device_name: String) -> Result<(), Box<dyn std::error:
         .with_agent_prefix(Some("localp2p-rust/".to_string()))
         .with_device_name(device_name.clone())
         .with_node_timeout(Duration::from_secs(300)) // 5分钟超时
-        .with_cleanup_interval(Duration::from_secs(60)); // 1分钟清理间隔
+        .with_cleanup_interval(Duration::from_secs(60)) // 1分钟清理间隔
+        .with_relay_server(relay_server);
 
     // 创建节点管理器
     let node_manager = Arc::new(NodeManager::new(config));
@@ -103,15 +169,30 @@ async fn run_console_mode(device_name: String) -> Result<(), Box<dyn std::error:
         health_config,
         user_info.clone(),  // ← 传入用户信息
         None,  // ← 控制台模式不使用持久化密钥对
+        None,  // ← 不使用私有网络密钥
     ).await?;
 
+    // 手动添加的节点（控制台模式不保存）
+    for addr in static_peers {
+        discovery.add_static_peer(addr.parse()?).await?;
+        println!("✓ 已添加静态节点: {}", addr);
+    }
+
+    // 会合点发现（跨子网）
+    if let Some(config) = rendezvous {
+        println!("✓ 会合服务器: {}（命名空间 {}）", config.server, config.namespace);
+        discovery.enable_rendezvous(config)?;
+    }
+
+    if discovery.is_relay_server() {
+        println!("✓ 中继模式：为无法直连的节点转发连接");
+    }
+
     println!("本地 Peer ID: {}", discovery.local_peer_id());
     println!("协议版本: {}", discovery.protocol_version());
     println!("代理版本: {}", discovery.agent_version());
     println!("本地设备名称: {}", user_info.device_name);
-    if let Some(ref status) = user_info.status {
-        println!("本地状态: {}", status);
-    }
+    println!("本地状态: {}", user_info.presence().label());
     println!();
     println!("开始扫描局域网内的对等节点...\n");
 
@@ -150,14 +231,34 @@ async fn run_console_mode(device_name: String) -> Result<(), Box<dyn std::error:
                 if let Some(ref avatar_url) = user_info.avatar_url {
                     println!("   头像: {}", avatar_url);
                 }
-                if let Some(ref status) = user_info.status {
-                    println!("   状态: {}", status);
+                if let Some(ref avatar_hash) = user_info.avatar_hash {
+                    println!("   头像哈希: {}", avatar_hash);
+                }
+                println!("   状态: {}", user_info.presence().label());
+                if let Some(message) = user_info.status_message() {
+                    println!("   状态说明: {}", message);
                 }
 
                 // 列出所有验证通过的节点
                 println!("\n当前验证通过的节点数: {}",
                     discovery.node_manager().node_count().await);
             }
+            ManagedDiscoveryEvent::UserInfoUpdated(peer_id, user_info) => {
+                println!("📝 {} 更新了用户信息: {}", peer_id, user_info.display_name());
+                println!("   状态: {}", user_info.presence().label());
+                if let Some(message) = user_info.status_message() {
+                    println!("   状态说明: {}", message);
+                }
+            }
+            ManagedDiscoveryEvent::AvatarReceived(peer_id, hash) => {
+                println!("🖼  已获取 {} 的头像: {}", peer_id, hash);
+            }
+            ManagedDiscoveryEvent::UserInfoRequestFailed(peer_id, reason) => {
+                println!("⚠️  获取 {} 的用户信息失败: {}", peer_id, reason);
+            }
+            ManagedDiscoveryEvent::PeerBanned(peer_id, reason) => {
+                println!("⛔ 节点 {} 因违反协议被封禁: {}", peer_id, reason);
+            }
             ManagedDiscoveryEvent::NodeRecovered(peer_id, rtt) => {
                 // 优先使用用户信息中的显示名称
                 let display_name = match discovery.get_user_info(&peer_id) {
@@ -198,10 +299,61 @@ async fn run_console_mode(device_name: String) -> Result<(), Box<dyn std::error:
     // _cleanup_handle.abort();
 }
 
+/// 运行会合服务器（`rendezvous-server` 子命令）
+///
+/// 密钥保存在 `--identity` 指定的文件中，重启后 Peer ID 不变，客户端配置的地址保持有效。
+async fn run_rendezvous_server(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let listen: Vec<String> = args
+        .windows(2)
+        .filter(|pair| pair[0] == "--listen")
+        .map(|pair| pair[1].clone())
+        .collect();
+    let listen_addresses = if listen.is_empty() {
+        vec!["/ip4/0.0.0.0/tcp/4100".parse()?]
+    } else {
+        listen.iter().map(|addr| addr.parse()).collect::<Result<Vec<_>, _>>()?
+    };
+    let identity_path = option_value(args, "--identity").unwrap_or_else(|| "rendezvous.key".to_string());
+    let keypair = IdentityManager::load_or_generate(std::path::Path::new(&identity_path))?;
+
+    let mut server = RendezvousServer::new(keypair, listen_addresses, None)?;
+    println!("Local P2P 会合服务器");
+    println!("========================================");
+    println!("Peer ID: {}", server.local_peer_id());
+    println!("密钥文件: {}", identity_path);
+    println!();
+
+    loop {
+        match server.run().await? {
+            RendezvousServerEvent::Listening(addr) => println!("监听地址: {}", addr),
+            RendezvousServerEvent::Registered(peer_id, namespace) => {
+                println!("[注册] {} @ {}", peer_id, namespace);
+            }
+            RendezvousServerEvent::Unregistered(peer_id, namespace) => {
+                println!("[取消注册] {} @ {}", peer_id, namespace);
+            }
+            RendezvousServerEvent::Discovered(peer_id, count) => {
+                tracing::debug!("{} 查询: {} 个节点", peer_id, count);
+            }
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 会合服务器子命令
+    let raw_args: Vec<String> = env::args().collect();
+    if raw_args.get(1).map(String::as_str) == Some("rendezvous-server") {
+        logging::init_logging_with_console(logging::LogLevel::Info)?;
+        return run_rendezvous_server(&raw_args[2..]).await;
+    }
+
     // 解析命令行参数
     let args = parse_args();
+    let rendezvous = match args.rendezvous {
+        Some(ref server) => Some(RendezvousConfig::new(server.parse()?, &args.team)),
+        None => None,
+    };
 
     // 初始化日志
     // TUI 模式：只输出到文件（避免干扰 TUI 界面）
@@ -215,10 +367,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 根据参数选择运行模式
     if args.tui_mode {
         // TUI 模式
-        tui_app::run_tui(args.device_name).await?;
+        let static_peers = args
+            .static_peers
+            .iter()
+            .map(|addr| addr.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+        tui_app::run_tui(args.device_name, args.encrypt_identity, args.rotate_identity, static_peers, rendezvous, args.relay_server).await?;
     } else {
         // 控制台模式（原有功能） 
-        run_console_mode(args.device_name).await?;
+        run_console_mode(args.device_name, args.static_peers, rendezvous, args.relay_server).await?;
     }
 
     Ok(())